@@ -12,8 +12,12 @@
 //! - **Service Errors**: Database or I/O failures from the service layer
 //! - **Not Found**: Resource not found (404)
 //! - **Invalid Input**: Validation failures (e.g., empty username/password)
-//! - **Existing User**: Attempting to create a duplicate user (409 Conflict)
+//! - **Existing User**: Attempting to create a duplicate user (402)
 //! - **Unauthorized**: Authentication failures (401)
+//! - **Forbidden**: Authenticated user is not allowed to perform the action (403)
+//! - **Precondition Failed**: A conditional request (e.g. `If-Match`) did not hold (412)
+//! - **Quota Exceeded**: The update would grow the user's storage past their quota (507)
+//! - **Payload Too Large**: The request body exceeds the configured limit (413)
 //! - **Runtime**: Unexpected errors
 //!
 //! # HTTP Status Code Mapping
@@ -27,8 +31,12 @@
 //! | Service | 500 Internal Server Error |
 //! | NotFound | 404 Not Found |
 //! | InvalidInput | 400 Bad Request |
-//! | ExistingUser | 402 Payment Required (keeps KOReader return code (?)) |
+//! | ExistingUser | 402 Payment Required (the status KOReader expects on registration) |
 //! | Unauthorized | 401 Unauthorized |
+//! | Forbidden | 403 Forbidden |
+//! | PreconditionFailed | 412 Precondition Failed |
+//! | QuotaExceeded | 507 Insufficient Storage |
+//! | PayloadTooLarge | 413 Payload Too Large |
 //! | Runtime | 500 Internal Server Error |
 //!
 //! # Error Response Format
@@ -42,6 +50,42 @@
 //! }
 //! ```
 //!
+//! Validation failures may also carry an `errors` array with one entry per offending field:
+//!
+//! ```json
+//! {
+//!   "code": "invalid_input",
+//!   "message": "Invalid input: Invalid progress payload",
+//!   "errors": [{ "field": "percentage", "message": "must be between 0 and 1" }]
+//! }
+//! ```
+//!
+//! # KOReader Compatibility
+//!
+//! The KOReader sync plugin sends `Accept: application/vnd.koreader.v1+json` and displays the
+//! `message` of error responses. For those clients, the
+//! [`koreader`](crate::api::middleware::koreader::koreader) middleware replaces the body above
+//! with the one of the reference kosync server, a numeric [`KoreaderCode`] with its status:
+//!
+//! | Error Type | Code | HTTP Status |
+//! |-----------|------|-------------|
+//! | PathRejection, JsonRejection | 2003 | 403 Forbidden |
+//! | InvalidInput on the `document` field | 2004 | 403 Forbidden |
+//! | InvalidInput, NotFound, Forbidden, PreconditionFailed, QuotaExceeded | 2003 | 403 Forbidden |
+//! | ExistingUser | 2002 | 402 Payment Required |
+//! | Unauthorized | 2001 | 401 Unauthorized |
+//! | Service | 1000 | 502 Bad Gateway |
+//! | Runtime | 2000 | 500 Internal Server Error |
+//!
+//! ```json
+//! {
+//!   "code": 2001,
+//!   "message": "Invalid credentials"
+//! }
+//! ```
+//!
+//! Messages are kept, except for server failures that get the reference message instead.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -50,25 +94,48 @@
 //! // Errors are automatically converted to HTTP responses
 //! fn example_handler() -> Result<(), ApiError> {
 //!     // This will become a 400 Bad Request response
-//!     return Err(ApiError::InvalidInput("Username cannot be empty".to_string()));
+//!     return Err(ApiError::invalid_input("Username cannot be empty"));
 //! }
 //! ```
 
 use axum::{
     Json,
     extract::rejection::{JsonRejection, PathRejection},
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 
 use crate::{model, service::error::ServiceError};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Body of every error response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ApiErrorPayload {
+    /// Machine-readable error code, e.g. `invalid_input`
     pub code: &'static str,
+    /// Human-readable description of the error
     pub message: String,
+    /// Offending fields, for validation failures
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// A validation failure on a single request field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -83,10 +150,10 @@ pub enum ApiError {
     Service(ServiceError),
 
     #[error("{0}")]
-    NotFound(ServiceError),
+    NotFound(String),
 
     #[error("Invalid input: {0}")]
-    InvalidInput(String),
+    InvalidInput(String, Vec<FieldError>),
 
     #[error("User '{0}' already exists")]
     ExistingUser(String),
@@ -94,6 +161,18 @@ pub enum ApiError {
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
     #[error(transparent)]
     Runtime(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -103,6 +182,8 @@ impl From<ServiceError> for ApiError {
         match value {
             all @ ServiceError::Io(_) => ApiError::Service(all),
             all @ ServiceError::DB(_) => ApiError::Service(all),
+            ServiceError::QuotaExceeded(limit) => ApiError::QuotaExceeded(limit),
+            ServiceError::Model(e) => e.into(),
         }
     }
 }
@@ -115,14 +196,98 @@ impl From<model::Error> for ApiError {
     }
 }
 
+/// Error codes of the reference kosync server, as displayed by KOReader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KoreaderCode {
+    UnknownServerError = 1000,
+    UnknownError = 2000,
+    Unauthorized = 2001,
+    UserExists = 2002,
+    InvalidRequest = 2003,
+    DocumentMissing = 2004,
+}
+
+impl KoreaderCode {
+    /// HTTP status the reference server answers with this code
+    pub fn status(self) -> StatusCode {
+        match self {
+            KoreaderCode::UnknownServerError => StatusCode::BAD_GATEWAY,
+            KoreaderCode::UnknownError => StatusCode::INTERNAL_SERVER_ERROR,
+            KoreaderCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            KoreaderCode::UserExists => StatusCode::PAYMENT_REQUIRED,
+            KoreaderCode::InvalidRequest | KoreaderCode::DocumentMissing => StatusCode::FORBIDDEN,
+        }
+    }
+
+    /// Message the reference server sends with this code
+    pub fn message(self) -> &'static str {
+        match self {
+            KoreaderCode::UnknownServerError => "Unknown server error.",
+            KoreaderCode::UnknownError => "Unknown error.",
+            KoreaderCode::Unauthorized => "Unauthorized",
+            KoreaderCode::UserExists => "Username is already registered.",
+            KoreaderCode::InvalidRequest => "Invalid request",
+            KoreaderCode::DocumentMissing => "Field 'document' not provided.",
+        }
+    }
+}
+
+impl Serialize for KoreaderCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(*self as u16)
+    }
+}
+
+/// Error body of the reference kosync server
+///
+/// Attached to the extensions of every error response, for the
+/// [`koreader`](crate::api::middleware::koreader::koreader) middleware to send instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct KoreaderError {
+    pub code: KoreaderCode,
+    pub message: String,
+}
+
+impl KoreaderError {
+    fn new(code: KoreaderCode, message: String) -> Self {
+        let message = match code {
+            KoreaderCode::UnknownServerError | KoreaderCode::UnknownError => {
+                code.message().to_string()
+            }
+            _ => message,
+        };
+        Self { code, message }
+    }
+
+    /// Sends this error in place of `original`, keeping its headers (e.g. `WWW-Authenticate`)
+    /// other than those describing the replaced body.
+    pub fn replace(self, original: Response) -> Response {
+        let mut response = self.into_response();
+        for (name, value) in original.headers() {
+            if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+                response.headers_mut().append(name, value.clone());
+            }
+        }
+        response
+    }
+}
+
+impl IntoResponse for KoreaderError {
+    fn into_response(self) -> Response {
+        (self.code.status(), Json(self)).into_response()
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let koreader_code = self.koreader_code();
         let (status, payload) = match self {
             ApiError::PathRejection(_) => (
                 StatusCode::BAD_REQUEST,
                 ApiErrorPayload {
                     code: "bad_request",
                     message: "Invalid path parameter".to_string(),
+                    errors: Vec::new(),
                 },
             ),
             ApiError::JsonRejection(ref e) => (
@@ -130,6 +295,7 @@ impl IntoResponse for ApiError {
                 ApiErrorPayload {
                     code: "bad_request",
                     message: e.to_string(),
+                    errors: Vec::new(),
                 },
             ),
             ApiError::Service(err) => (
@@ -137,20 +303,23 @@ impl IntoResponse for ApiError {
                 ApiErrorPayload {
                     code: "io_failure",
                     message: format!("{err}"),
+                    errors: Vec::new(),
                 },
             ),
             ApiError::NotFound(err) => (
                 StatusCode::NOT_FOUND,
                 ApiErrorPayload {
                     code: "not_found",
-                    message: format!("{err}"),
+                    message: err,
+                    errors: Vec::new(),
                 },
             ),
-            all @ ApiError::InvalidInput(_) => (
+            ApiError::InvalidInput(message, errors) => (
                 StatusCode::BAD_REQUEST,
                 ApiErrorPayload {
                     code: "invalid_input",
-                    message: all.to_string(),
+                    message: format!("Invalid input: {message}"),
+                    errors,
                 },
             ),
             all @ ApiError::ExistingUser(_) => (
@@ -158,6 +327,7 @@ impl IntoResponse for ApiError {
                 ApiErrorPayload {
                     code: "existing_user",
                     message: all.to_string(),
+                    errors: Vec::new(),
                 },
             ),
             ApiError::Unauthorized(err) => (
@@ -165,6 +335,39 @@ impl IntoResponse for ApiError {
                 ApiErrorPayload {
                     code: "unauthorized",
                     message: err.to_string(),
+                    errors: Vec::new(),
+                },
+            ),
+            ApiError::Forbidden(err) => (
+                StatusCode::FORBIDDEN,
+                ApiErrorPayload {
+                    code: "forbidden",
+                    message: err,
+                    errors: Vec::new(),
+                },
+            ),
+            ApiError::PreconditionFailed(err) => (
+                StatusCode::PRECONDITION_FAILED,
+                ApiErrorPayload {
+                    code: "precondition_failed",
+                    message: err,
+                    errors: Vec::new(),
+                },
+            ),
+            ApiError::QuotaExceeded(err) => (
+                StatusCode::INSUFFICIENT_STORAGE,
+                ApiErrorPayload {
+                    code: "quota_exceeded",
+                    message: err,
+                    errors: Vec::new(),
+                },
+            ),
+            ApiError::PayloadTooLarge(err) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ApiErrorPayload {
+                    code: "payload_too_large",
+                    message: err,
+                    errors: Vec::new(),
                 },
             ),
             ApiError::Runtime(err) => (
@@ -172,11 +375,15 @@ impl IntoResponse for ApiError {
                 ApiErrorPayload {
                     code: "runtime_error",
                     message: err.to_string(),
+                    errors: Vec::new(),
                 },
             ),
         };
 
-        (status, Json(payload)).into_response()
+        let koreader = KoreaderError::new(koreader_code, payload.message.clone());
+        let mut response = (status, Json(payload)).into_response();
+        response.extensions_mut().insert(koreader);
+        response
     }
 }
 
@@ -184,4 +391,217 @@ impl ApiError {
     pub fn runtime(e: impl std::error::Error + Send + Sync + 'static) -> Self {
         ApiError::Runtime(Box::new(e))
     }
+
+    /// Creates an [`ApiError::InvalidInput`] without field-level errors.
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        ApiError::InvalidInput(message.into(), Vec::new())
+    }
+
+    /// Code of the error on the reference kosync server
+    pub fn koreader_code(&self) -> KoreaderCode {
+        match self {
+            ApiError::InvalidInput(_, errors) if errors.iter().any(|e| e.field == "document") => {
+                KoreaderCode::DocumentMissing
+            }
+            ApiError::PathRejection(_)
+            | ApiError::JsonRejection(_)
+            | ApiError::InvalidInput(..)
+            | ApiError::NotFound(_)
+            | ApiError::Forbidden(_)
+            | ApiError::PreconditionFailed(_)
+            | ApiError::QuotaExceeded(_)
+            | ApiError::PayloadTooLarge(_) => KoreaderCode::InvalidRequest,
+            ApiError::ExistingUser(_) => KoreaderCode::UserExists,
+            ApiError::Unauthorized(_) => KoreaderCode::Unauthorized,
+            ApiError::Service(_) => KoreaderCode::UnknownServerError,
+            ApiError::Runtime(_) => KoreaderCode::UnknownError,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::rejection::{MissingJsonContentType, MissingPathParams};
+
+    use super::*;
+
+    fn error(error: ApiError) -> (StatusCode, KoreaderError) {
+        let response = error.into_response();
+        let koreader = response
+            .extensions()
+            .get::<KoreaderError>()
+            .cloned()
+            .expect("Missing KOReader error");
+        (response.status(), koreader)
+    }
+
+    #[test]
+    fn every_variant_maps_to_the_reference_protocol() {
+        let cases = [
+            (
+                ApiError::PathRejection(MissingPathParams::default().into()),
+                StatusCode::BAD_REQUEST,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::JsonRejection(MissingJsonContentType::default().into()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::Service(ServiceError::Io(std::io::Error::other("disk"))),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                KoreaderCode::UnknownServerError,
+            ),
+            (
+                ApiError::NotFound("missing".into()),
+                StatusCode::NOT_FOUND,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::invalid_input("Invalid user"),
+                StatusCode::BAD_REQUEST,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::InvalidInput(
+                    "Invalid progress payload".into(),
+                    vec![FieldError::new("document", "must not be empty")],
+                ),
+                StatusCode::BAD_REQUEST,
+                KoreaderCode::DocumentMissing,
+            ),
+            (
+                ApiError::ExistingUser("alice".into()),
+                StatusCode::PAYMENT_REQUIRED,
+                KoreaderCode::UserExists,
+            ),
+            (
+                ApiError::Unauthorized("Invalid credentials".into()),
+                StatusCode::UNAUTHORIZED,
+                KoreaderCode::Unauthorized,
+            ),
+            (
+                ApiError::Forbidden("Not the owner".into()),
+                StatusCode::FORBIDDEN,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::PreconditionFailed("Stale version".into()),
+                StatusCode::PRECONDITION_FAILED,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::QuotaExceeded("Document limit of 1 reached".into()),
+                StatusCode::INSUFFICIENT_STORAGE,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::PayloadTooLarge("Upload exceeds 1024 bytes".into()),
+                StatusCode::PAYLOAD_TOO_LARGE,
+                KoreaderCode::InvalidRequest,
+            ),
+            (
+                ApiError::runtime(std::io::Error::other("boom")),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                KoreaderCode::UnknownError,
+            ),
+        ];
+
+        for (api_error, status, code) in cases {
+            let name = format!("{api_error:?}");
+            let (actual_status, koreader) = error(api_error);
+            assert_eq!(status, actual_status, "Status of {name}");
+            assert_eq!(code, koreader.code, "KOReader code of {name}");
+        }
+    }
+
+    #[test]
+    fn reference_codes_have_reference_statuses() {
+        let cases = [
+            (
+                KoreaderCode::UnknownServerError,
+                1000,
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                KoreaderCode::UnknownError,
+                2000,
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (KoreaderCode::Unauthorized, 2001, StatusCode::UNAUTHORIZED),
+            (KoreaderCode::UserExists, 2002, StatusCode::PAYMENT_REQUIRED),
+            (KoreaderCode::InvalidRequest, 2003, StatusCode::FORBIDDEN),
+            (KoreaderCode::DocumentMissing, 2004, StatusCode::FORBIDDEN),
+        ];
+
+        for (code, number, status) in cases {
+            assert_eq!(number, code as u16);
+            assert_eq!(status, code.status(), "Status of {code:?}");
+            assert_eq!(
+                serde_json::json!(number),
+                serde_json::to_value(code).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn server_failures_hide_their_details() {
+        let (_, koreader) = error(ApiError::Service(ServiceError::Io(std::io::Error::other(
+            "/var/lib/korrosync/db.redb",
+        ))));
+        assert_eq!("Unknown server error.", koreader.message);
+
+        let (_, koreader) = error(ApiError::runtime(std::io::Error::other("boom")));
+        assert_eq!("Unknown error.", koreader.message);
+    }
+
+    #[test]
+    fn model_failures_are_not_storage_failures() {
+        let hashing = ServiceError::Model(model::Error::runtime(std::io::Error::other("hash")));
+        let (status, koreader) = error(hashing.into());
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status);
+        assert_eq!(KoreaderCode::UnknownError, koreader.code);
+    }
+
+    #[test]
+    fn client_errors_keep_their_message() {
+        let (_, koreader) = error(ApiError::QuotaExceeded(
+            "Document limit of 1 reached".into(),
+        ));
+        assert_eq!("Document limit of 1 reached", koreader.message);
+
+        let (_, koreader) = error(ApiError::ExistingUser("alice".into()));
+        assert_eq!("User 'alice' already exists", koreader.message);
+    }
+
+    #[test]
+    fn replacing_a_response_keeps_its_headers() {
+        let mut original = ApiError::Unauthorized("Missing credentials".into()).into_response();
+        original.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            header::HeaderValue::from_static("Basic realm=\"korrosync\""),
+        );
+        let koreader = original
+            .extensions()
+            .get::<KoreaderError>()
+            .cloned()
+            .expect("Missing KOReader error");
+
+        let response = koreader.replace(original);
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_eq!(
+            "Basic realm=\"korrosync\"",
+            response.headers()[header::WWW_AUTHENTICATE]
+        );
+        assert_eq!("application/json", response.headers()[header::CONTENT_TYPE]);
+        assert_eq!(
+            1,
+            response
+                .headers()
+                .get_all(header::CONTENT_TYPE)
+                .iter()
+                .count()
+        );
+    }
 }
@@ -0,0 +1,38 @@
+//! Custom request extractors.
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::request::Parts,
+};
+
+/// Source IP address of the request, if known.
+///
+/// The address is taken from the peer of the TCP connection, so it is only available when
+/// the router is served with `into_make_service_with_connect_info::<SocketAddr>()`. It is
+/// `None` otherwise (e.g. in tests driving the router directly).
+#[derive(Debug, Clone)]
+pub struct ClientIp(pub Option<String>);
+
+impl ClientIp {
+    /// Reads the client IP from the request extensions.
+    pub fn from_extensions(extensions: &axum::http::Extensions) -> Self {
+        ClientIp(
+            extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string()),
+        )
+    }
+}
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp::from_extensions(&parts.extensions))
+    }
+}
@@ -3,16 +3,33 @@ use axum::{
     middleware::Next,
     response::Response,
 };
+use chrono::Utc;
 use tracing::debug;
 
-use crate::api::{error::ApiError, state::AppState};
+#[cfg(feature = "statistics-sync")]
+use crate::model::koreader_key;
+use crate::{
+    api::{error::ApiError, extract::ClientIp, state::AppState},
+    model::{AuditEvent, AuditKind},
+};
 
 #[derive(Clone, Debug)]
 pub struct AuthenticatedUser(pub String, pub Option<i64>);
 
+/// Inactivity in milliseconds after which a successful authentication is audited as a new
+/// login
+const LOGIN_GAP_MS: i64 = 30 * 60 * 1000;
+
+/// Window in milliseconds during which rejected attempts for unknown usernames from the same
+/// IP are audited only once, so guessing usernames can't flood the audit log
+const UNKNOWN_USER_AUDIT_WINDOW_MS: i64 = 10 * 60 * 1000;
+
+const UNKNOWN_USER: &str = "unknown user";
+
 /// Authentication middleware for protected routes
 ///
-/// This middleware validates authentication creds.
+/// This middleware validates authentication creds. Logins and rejected attempts are recorded
+/// in the audit log together with the source IP, see [`authenticate`].
 #[tracing::instrument(level = tracing::Level::DEBUG, skip(state, request, next))]
 pub async fn auth(
     State(state): State<AppState>,
@@ -26,21 +43,120 @@ pub async fn auth(
     if let Some(username) = headers.get("x-auth-user").and_then(|v| v.to_str().ok())
         && let Some(key) = headers.get("x-auth-key").and_then(|v| v.to_str().ok())
     {
-        if let Some(mut user) = state.sync.get_user(username.into())? {
-            if !user.check(key)? {
-                return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
-            }
+        let ClientIp(ip) = ClientIp::from_extensions(request.extensions());
+        let user = authenticate(&state, username, key, ip)?;
+        request.extensions_mut().insert(user);
+        Ok(next.run(request).await)
+    } else {
+        Err(ApiError::Unauthorized("Missing credentials".to_string()))
+    }
+}
+
+/// HTTP Basic authentication middleware, for clients that can't send custom headers
+///
+/// The password is the one the user types in KOReader, which is turned into the same key
+/// the sync plugin sends. Rejections ask the client for credentials with a
+/// `WWW-Authenticate` challenge.
+#[cfg(feature = "statistics-sync")]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, request, next))]
+pub async fn basic_auth(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    use axum::{http::header, response::IntoResponse};
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    debug!("Basic auth middleware invoked");
 
-            user.touch();
-            let user = state.sync.create_or_update_user(user)?;
+    let credentials = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Basic "))
+        .and_then(|v| STANDARD.decode(v.trim()).ok())
+        .and_then(|v| String::from_utf8(v).ok());
 
-            let user = AuthenticatedUser(username.to_string(), user.last_activity());
+    let result = match credentials.as_deref().and_then(|c| c.split_once(':')) {
+        Some((username, password)) => {
+            let ClientIp(ip) = ClientIp::from_extensions(request.extensions());
+            authenticate(&state, username, &koreader_key(password), ip)
+        }
+        None => Err(ApiError::Unauthorized("Missing credentials".to_string())),
+    };
+
+    match result {
+        Ok(user) => {
             request.extensions_mut().insert(user);
-            Ok(next.run(request).await)
-        } else {
-            Err(ApiError::Unauthorized("Invalid credentials".to_string()))
+            next.run(request).await
+        }
+        Err(e) => {
+            let mut response = e.into_response();
+            if response.status() == axum::http::StatusCode::UNAUTHORIZED {
+                response.headers_mut().insert(
+                    header::WWW_AUTHENTICATE,
+                    header::HeaderValue::from_static("Basic realm=\"korrosync\""),
+                );
+            }
+            response
         }
+    }
+}
+
+/// Checks a user's key and records the activity.
+///
+/// The first successful authentication after [`LOGIN_GAP_MS`] of inactivity is audited as a
+/// login. Rejected attempts are audited as well, except repeated attempts for unknown
+/// usernames from the same IP within [`UNKNOWN_USER_AUDIT_WINDOW_MS`].
+fn authenticate(
+    state: &AppState,
+    username: &str,
+    key: &str,
+    ip: Option<String>,
+) -> Result<AuthenticatedUser, ApiError> {
+    if let Some(user) = state.sync.get_user(username.into())? {
+        if !user.check(key)? {
+            state.audit(
+                AuditEvent::new(AuditKind::AuthFailure, username)
+                    .with_ip(ip)
+                    .with_detail("invalid password"),
+            );
+            return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+        }
+        // Read before the authentication counts as activity
+        let previous_activity = user.last_activity();
+
+        // The user may have been deleted since the check
+        let Some(user) = state.sync.touch_user(username.into())? else {
+            return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+        };
+
+        let now = Utc::now().timestamp_millis();
+        if previous_activity.is_none_or(|at| now - at >= LOGIN_GAP_MS) {
+            state.audit(AuditEvent::new(AuditKind::Login, username).with_ip(ip));
+        }
+
+        Ok(AuthenticatedUser(
+            username.to_string(),
+            user.last_activity(),
+        ))
     } else {
-        Err(ApiError::Unauthorized("Missing credentials".to_string()))
+        let since = Utc::now().timestamp_millis() - UNKNOWN_USER_AUDIT_WINDOW_MS;
+        let audited = state
+            .sync
+            .list_events(None, Some(since))
+            .is_ok_and(|events| {
+                events.iter().any(|e| {
+                    e.kind == AuditKind::AuthFailure && e.detail == UNKNOWN_USER && e.ip == ip
+                })
+            });
+        if !audited {
+            state.audit(
+                AuditEvent::new(AuditKind::AuthFailure, username)
+                    .with_ip(ip)
+                    .with_detail(UNKNOWN_USER),
+            );
+        }
+        Err(ApiError::Unauthorized("Invalid credentials".to_string()))
     }
 }
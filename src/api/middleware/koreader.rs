@@ -0,0 +1,36 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{Level, debug};
+
+use crate::api::error::KoreaderError;
+
+/// Media type the KOReader sync plugin accepts
+pub const KOREADER_MEDIA_TYPE: &str = "application/vnd.koreader.v1+json";
+
+/// KOReader compatibility middleware
+///
+/// Error responses to clients that accept [`KOREADER_MEDIA_TYPE`] are replaced with the code,
+/// status and body of the reference kosync server, which the sync plugin displays, keeping
+/// the other headers of the response. Other clients keep the regular error payload.
+#[tracing::instrument(level = Level::DEBUG, skip(request, next))]
+pub async fn koreader(request: Request, next: Next) -> Response {
+    let compatible = accepts_koreader(request.headers());
+    let mut response = next.run(request).await;
+
+    if compatible && let Some(error) = response.extensions_mut().remove::<KoreaderError>() {
+        debug!("Sending KOReader error code {:?}", error.code);
+        return error.replace(response);
+    }
+    response
+}
+
+fn accepts_koreader(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(KOREADER_MEDIA_TYPE))
+}
@@ -1,3 +1,4 @@
 pub mod auth;
+pub mod koreader;
 pub mod public;
 pub mod ratelimiter;
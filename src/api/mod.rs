@@ -10,14 +10,54 @@
 //!
 //! - `POST /users/create` - User registration
 //! - `GET /robots.txt` - Robots exclusion file
+//! - `GET /share/{token}` - Read-only view of shared progress (JSON or HTML)
+//! - `GET /openapi.json` - OpenAPI description of the API (see [`openapi`])
+//! - `GET /docs` - Scalar UI to browse the OpenAPI description (`openapi-ui` feature)
+//! - `GET /livez` - Liveness probe
+//! - `GET /readyz` - Readiness probe, checking the storage
 //!
 //! ## Authenticated Endpoints (Require x-auth-user and x-auth-key Headers)
 //!
 //! - `GET /users/auth` - User authentication and profile
+//! - `PUT /users/password` - Change the authenticated user's password
+//! - `DELETE /users/me` - Delete the authenticated user's account and data
 //! - `PUT /syncs/progress` - Update reading progress
 //! - `GET /syncs/progress/{document}` - Retrieve reading progress for a document
+//! - `DELETE /syncs/progress/{document}` - Delete the reading progress of a document
+//! - `POST /syncs/progress/batch` - Retrieve reading progress for several documents
+//! - `PUT /syncs/progress/batch` - Update reading progress for several documents
+//! - `GET /syncs/progress/{document}/devices` - Last position of each device for a document
+//! - `GET /syncs/bookmarks/{document}`, `POST /syncs/bookmarks/{document}` - List or create bookmarks
+//! - `GET`, `PUT`, `DELETE /syncs/bookmarks/{document}/{id}` - Retrieve, update or delete a bookmark
+//! - `GET /aliases` - List the user's document aliases
+//! - `PUT /aliases/{alias}`, `DELETE /aliases/{alias}` - Link or unlink a document alias
+//! - `GET /documents` - List the user's documents with progress and metadata
+//! - `PUT /documents/{document}`, `GET /documents/{document}` - Set or get document metadata
+//! - `GET /annotations/{document}`, `POST /annotations/{document}` - List or merge a document's annotations
+//! - `DELETE /annotations/{document}/{id}` - Delete an annotation
+//! - `GET /annotations/{document}/export/{format}` - Export a document's annotations as Markdown or JSON
+//! - `GET /devices` - List the devices that have synchronized progress
+//! - `POST /groups`, `GET /groups` - Create and list reading groups
+//! - `GET /groups/{id}`, `DELETE /groups/{id}` - Show or delete a reading group
+//! - `POST /groups/join` - Join a reading group with an invite code
+//! - `POST /groups/{id}/invite` - Rotate the invite code of a group
+//! - `PUT /groups/{id}/settings` - Change what the user shares with a group
+//! - `POST /groups/{id}/leave` - Leave a reading group
+//! - `GET /groups/{id}/progress/{document}` - Progress of the group members on a document
+//! - `POST /shares`, `GET /shares` - Create and list public share links
+//! - `DELETE /shares/{token}` - Revoke a share link
+//! - `GET /stats` - Reading sessions, finished documents per month and average pace
+//! - `GET /goals`, `PUT /goals` - Reading goals, streak and goal completion
+//! - `GET /shelves` - Documents on the reading, finished and abandoned shelves
+//! - `PUT /shelves/{document}` - Move a document to a shelf
 //! - `GET /healthcheck` - Health check endpoint
 //!
+//! ## WebDAV Endpoints (HTTP Basic Authentication, `statistics-sync` Feature)
+//!
+//! - `PROPFIND /dav/` - List the user's statistics folder
+//! - `GET /dav/statistics.sqlite3` - Download the merged KOReader statistics database
+//! - `PUT /dav/statistics.sqlite3` - Upload a statistics database to merge into the stored one
+//!
 //! # Authentication
 //!
 //! Authenticated endpoints use HTTP Basic Authentication with custom headers:
@@ -32,6 +72,8 @@
 //! The API includes several middleware layers:
 //! - **Rate Limiting**: Prevents abuse by limiting requests per IP
 //! - **Authentication**: Validates credentials for protected routes
+//! - **KOReader Compatibility**: Sends the error codes of the reference kosync server to the
+//!   KOReader sync plugin (see [`error`])
 //! - **Tracing**: Logs HTTP requests and responses
 //! - **Error Handling**: Converts errors to appropriate HTTP responses
 //!
@@ -40,9 +82,15 @@
 //! - [`router`] - Application router configuration
 //! - [`state`] - Shared application state (database connection, etc.)
 //! - [`error`] - API-specific error types and HTTP error responses
+//! - [`extract`] - Custom request extractors (e.g. client IP)
+//! - [`validation`] - Field-level validation of request payloads
+//! - [`openapi`] - OpenAPI description generated from the route handlers
 //!
 pub mod error;
+pub mod extract;
 pub mod middleware;
+pub mod openapi;
 pub mod router;
 pub mod routes;
 pub mod state;
+pub mod validation;
@@ -0,0 +1,132 @@
+//! OpenAPI 3 description of the HTTP API, generated from the route handlers.
+//!
+//! Every route module documents its handlers with `#[utoipa::path]` and lists them in its
+//! own `ApiDoc`. [`openapi`] merges them into a single document, grouped like the router:
+//! operations of protected modules require the `x-auth-user` and `x-auth-key` headers and
+//! may answer 401 Unauthorized.
+//!
+//! The document is served at `GET /openapi.json`; with the `openapi-ui` feature, a Scalar
+//! UI to browse it is served at `/docs`.
+//!
+//! The WebDAV endpoints of the `statistics-sync` feature are not described, as OpenAPI has
+//! no notion of WebDAV methods such as `PROPFIND`.
+
+use utoipa::{
+    OpenApi,
+    openapi::{
+        self, ContentBuilder, Ref, ResponseBuilder,
+        security::{ApiKey, ApiKeyValue, SecurityRequirement, SecurityScheme},
+    },
+};
+
+use crate::api::routes;
+
+/// Security scheme of the `x-auth-user` header
+const AUTH_USER_SCHEME: &str = "auth_user";
+/// Security scheme of the `x-auth-key` header
+const AUTH_KEY_SCHEME: &str = "auth_key";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Korrosync",
+        description = "KOReader sync server. Besides the KOReader progress synchronization protocol, \
+            it synchronizes bookmarks and annotations and keeps reading statistics, goals and shelves."
+    ),
+    tags(
+        (name = "users", description = "Registration, authentication and account management"),
+        (name = "progress", description = "Reading progress synchronization (KOReader protocol)"),
+        (name = "bookmarks", description = "Named bookmarks"),
+        (name = "annotations", description = "Highlights and notes"),
+        (name = "documents", description = "Document metadata and aliases"),
+        (name = "devices", description = "Devices that synchronized progress"),
+        (name = "groups", description = "Reading groups with shared progress"),
+        (name = "shares", description = "Public share links"),
+        (name = "stats", description = "Reading statistics"),
+        (name = "goals", description = "Reading goals and streaks"),
+        (name = "shelves", description = "Reading, finished and abandoned shelves"),
+        (name = "misc", description = "Health checks, probes and crawler rules"),
+    )
+)]
+struct ApiDoc;
+
+/// Builds the OpenAPI document of every route served by [`app`](crate::api::router::app).
+pub fn openapi() -> openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    doc.merge(routes::robots::ApiDoc::openapi());
+    doc.merge(routes::share_view::ApiDoc::openapi());
+    doc.merge(routes::register::ApiDoc::openapi());
+    doc.merge(routes::probes::ApiDoc::openapi());
+
+    let mut protected = routes::users_auth::ApiDoc::openapi();
+    for api in [
+        routes::users_account::ApiDoc::openapi(),
+        routes::syncs_progress::ApiDoc::openapi(),
+        routes::bookmarks::ApiDoc::openapi(),
+        routes::devices::ApiDoc::openapi(),
+        routes::documents::ApiDoc::openapi(),
+        routes::aliases::ApiDoc::openapi(),
+        routes::annotations::ApiDoc::openapi(),
+        routes::groups::ApiDoc::openapi(),
+        routes::shares::ApiDoc::openapi(),
+        routes::stats::ApiDoc::openapi(),
+        routes::goals::ApiDoc::openapi(),
+        routes::shelves::ApiDoc::openapi(),
+        routes::healthcheck::ApiDoc::openapi(),
+    ] {
+        protected.merge(api);
+    }
+    require_auth(&mut protected);
+    doc.merge(protected);
+
+    doc
+}
+
+/// Makes every operation of `doc` require the KOReader authentication headers.
+fn require_auth(doc: &mut openapi::OpenApi) {
+    let components = doc.components.get_or_insert_with(Default::default);
+    components.add_security_scheme(
+        AUTH_USER_SCHEME,
+        SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+            "x-auth-user",
+            "Username",
+        ))),
+    );
+    components.add_security_scheme(
+        AUTH_KEY_SCHEME,
+        SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+            "x-auth-key",
+            "KOReader key of the password (its MD5 hex digest)",
+        ))),
+    );
+
+    let unauthorized = ResponseBuilder::new()
+        .description("Missing or invalid credentials")
+        .content(
+            "application/json",
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name("ApiErrorPayload")))
+                .build(),
+        )
+        .build();
+    for item in doc.paths.paths.values_mut() {
+        for operation in [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            operation.security = Some(vec![
+                SecurityRequirement::new(AUTH_USER_SCHEME, Vec::<String>::new())
+                    .add(AUTH_KEY_SCHEME, Vec::<String>::new()),
+            ]);
+            operation
+                .responses
+                .responses
+                .insert("401".to_string(), unauthorized.clone().into());
+        }
+    }
+}
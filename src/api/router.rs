@@ -11,22 +11,42 @@ use crate::api::{
 pub fn app(state: AppState) -> Router {
     let public_routes = Router::new()
         .merge(routes::robots::create_route())
+        .merge(routes::share_view::create_route())
         .merge(routes::register::create_route())
+        .merge(routes::openapi::create_route())
+        .merge(routes::probes::create_route())
         .layer(ServiceBuilder::new().layer(middleware::from_fn(api_middleware::public::public)));
 
     let auth_routes = Router::new()
         .merge(routes::users_auth::create_route())
+        .merge(routes::users_account::create_route())
         .merge(routes::syncs_progress::create_route())
+        .merge(routes::bookmarks::create_route())
+        .merge(routes::devices::create_route())
+        .merge(routes::documents::create_route())
+        .merge(routes::aliases::create_route())
+        .merge(routes::annotations::create_route())
+        .merge(routes::groups::create_route())
+        .merge(routes::shares::create_route())
+        .merge(routes::stats::create_route())
+        .merge(routes::goals::create_route())
+        .merge(routes::shelves::create_route())
         .merge(routes::healthcheck::create_route())
         .layer(ServiceBuilder::new().layer(middleware::from_fn_with_state(
             state.clone(),
             api_middleware::auth::auth,
         )));
 
-    Router::new()
-        .merge(public_routes)
-        .merge(auth_routes)
+    let router = Router::new().merge(public_routes).merge(auth_routes);
+
+    #[cfg(feature = "statistics-sync")]
+    let router = router.merge(routes::statistics_sync::create_route().layer(
+        middleware::from_fn_with_state(state.clone(), api_middleware::auth::basic_auth),
+    ));
+
+    router
         .fallback(routes::fallback::fallback)
         .with_state(state)
+        .layer(middleware::from_fn(api_middleware::koreader::koreader))
         .layer(TraceLayer::new_for_http())
 }
@@ -0,0 +1,147 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, put},
+};
+use axum_extra::extract::WithRejection;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::api::{
+    error::{ApiError, ApiErrorPayload},
+    middleware::auth::AuthenticatedUser,
+    state::AppState,
+    validation::Validator,
+};
+
+/// Create the document alias routes
+pub fn create_route() -> Router<AppState> {
+    Router::new().route("/aliases", get(list_aliases)).route(
+        "/aliases/{alias}",
+        put(link_document).delete(unlink_document),
+    )
+}
+
+/// OpenAPI description of the aliases routes
+#[derive(OpenApi)]
+#[openapi(paths(list_aliases, link_document, unlink_document))]
+pub struct ApiDoc;
+
+/// Request body for linking a document identifier to another document
+#[derive(Deserialize, Debug, ToSchema)]
+struct LinkDocumentRequest {
+    document: String,
+}
+
+impl LinkDocumentRequest {
+    fn validate(&self, state: &AppState, alias: &str) -> Result<(), ApiError> {
+        let max = state.validation.max_document_length;
+        let mut validator = Validator::new();
+        validator
+            .not_empty("alias", alias)
+            .max_length("alias", alias, max)
+            .not_empty("document", &self.document)
+            .max_length("document", &self.document, max);
+        validator.finish("Invalid document alias")
+    }
+}
+
+/// Response for a document alias
+#[derive(Serialize, ToSchema)]
+struct AliasResponse {
+    alias: String,
+    document: String,
+}
+
+/// Handler for GET /aliases
+///
+/// Lists the document aliases of the authenticated user
+#[utoipa::path(
+    get,
+    path = "/aliases",
+    tag = "documents",
+    responses((status = 200, description = "Aliases of the user", body = Vec<AliasResponse>))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_aliases(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<Vec<AliasResponse>>, ApiError> {
+    let aliases = state.sync.list_aliases(user)?;
+
+    Ok(Json(
+        aliases
+            .into_iter()
+            .map(|(alias, document)| AliasResponse { alias, document })
+            .collect(),
+    ))
+}
+
+/// Handler for PUT /aliases/{alias}
+///
+/// Makes `alias` resolve to the given document, so progress synchronized under either
+/// identifier is shared. Returns the canonical document both identifiers resolve to.
+#[utoipa::path(
+    put,
+    path = "/aliases/{alias}",
+    tag = "documents",
+    params(("alias" = String, Path, description = "Alternative document identifier")),
+    request_body = LinkDocumentRequest,
+    responses(
+        (status = 200, description = "The alias", body = AliasResponse),
+        (status = 400, description = "Invalid alias", body = ApiErrorPayload),
+        (status = 507, description = "The alias would exceed the user's storage quota", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn link_document(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(alias), _): WithRejection<Path<String>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<LinkDocumentRequest>, ApiError>,
+) -> Result<Json<AliasResponse>, ApiError> {
+    payload.validate(&state, &alias)?;
+    if payload.document == alias {
+        return Err(ApiError::invalid_input(
+            "A document cannot be an alias of itself",
+        ));
+    }
+
+    info!("Linking document alias");
+    let document = state.sync.link_document(
+        user,
+        alias.clone(),
+        payload.document,
+        state.quotas.default_quota(),
+    )?;
+
+    Ok(Json(AliasResponse { alias, document }))
+}
+
+/// Handler for DELETE /aliases/{alias}
+///
+/// Removes an alias; its progress stays with the canonical document
+#[utoipa::path(
+    delete,
+    path = "/aliases/{alias}",
+    tag = "documents",
+    params(("alias" = String, Path, description = "Alternative document identifier")),
+    responses(
+        (status = 204, description = "Alias removed"),
+        (status = 404, description = "The alias does not exist", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn unlink_document(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(alias), _): WithRejection<Path<String>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    if !state.sync.unlink_document(user, alias)? {
+        return Err(ApiError::NotFound("Alias not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,428 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use axum_extra::extract::WithRejection;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+        validation::Validator,
+    },
+    model::{Annotation, DocumentMetadata},
+};
+
+/// Create the annotation routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/annotations/{doc}",
+            get(list_annotations).post(merge_annotations),
+        )
+        .route("/annotations/{doc}/{id}", delete(delete_annotation))
+        .route(
+            "/annotations/{doc}/export/{format}",
+            get(export_annotations),
+        )
+}
+
+/// OpenAPI description of the annotations routes
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_annotations,
+    merge_annotations,
+    delete_annotation,
+    export_annotations
+))]
+pub struct ApiDoc;
+
+/// Request body for merging annotations
+#[derive(Deserialize, Debug, ToSchema)]
+struct MergeAnnotationsRequest {
+    annotations: Vec<AnnotationRequest>,
+}
+
+/// An annotation as reported by a device
+#[derive(Deserialize, Debug, ToSchema)]
+struct AnnotationRequest {
+    id: String,
+    text: Option<String>,
+    note: Option<String>,
+    #[serde(default)]
+    pos0: String,
+    #[serde(default)]
+    pos1: String,
+    chapter: Option<String>,
+    color: Option<String>,
+    /// Unix timestamp in milliseconds; defaults to `updated_at`
+    created_at: Option<u64>,
+    /// Unix timestamp in milliseconds of the change, as seen by the device
+    updated_at: u64,
+    #[serde(default)]
+    deleted: bool,
+}
+
+impl MergeAnnotationsRequest {
+    fn validate(&self, state: &AppState, doc: &str) -> Result<(), ApiError> {
+        let limits = &state.validation;
+        let mut validator = Validator::new();
+        check_document(&mut validator, state, doc);
+        validator.item_count(
+            "annotations",
+            self.annotations.len(),
+            limits.max_annotations,
+        );
+        for (i, annotation) in self.annotations.iter().enumerate() {
+            let field = |name: &str| format!("annotations[{i}].{name}");
+            validator
+                .not_empty(&field("id"), &annotation.id)
+                .max_length(&field("id"), &annotation.id, limits.max_field_length);
+            if annotation.deleted {
+                continue;
+            }
+            validator
+                .not_empty(&field("pos0"), &annotation.pos0)
+                .max_length(&field("pos0"), &annotation.pos0, limits.max_field_length)
+                .not_empty(&field("pos1"), &annotation.pos1)
+                .max_length(&field("pos1"), &annotation.pos1, limits.max_field_length);
+            for (name, value, max) in [
+                ("text", &annotation.text, limits.max_text_length),
+                ("note", &annotation.note, limits.max_text_length),
+                ("chapter", &annotation.chapter, limits.max_field_length),
+                ("color", &annotation.color, limits.max_field_length),
+            ] {
+                if let Some(value) = value {
+                    validator.max_length(&field(name), value, max);
+                }
+            }
+        }
+        validator.finish("Invalid annotations payload")
+    }
+}
+
+/// Adds the rules of the document identifier of a request path to `validator`
+fn check_document(validator: &mut Validator, state: &AppState, doc: &str) {
+    validator
+        .not_empty("doc", doc)
+        .max_length("doc", doc, state.validation.max_document_length);
+}
+
+/// Validates the document identifier, and the annotation id if any, of a request path
+fn validate_path(state: &AppState, doc: &str, id: Option<&str>) -> Result<(), ApiError> {
+    let mut validator = Validator::new();
+    check_document(&mut validator, state, doc);
+    if let Some(id) = id {
+        validator
+            .not_empty("id", id)
+            .max_length("id", id, state.validation.max_field_length);
+    }
+    validator.finish("Invalid annotation path")
+}
+
+/// An annotation as returned by the API
+#[derive(Serialize, ToSchema)]
+struct AnnotationResponse {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    pos0: String,
+    pos1: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chapter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    created_at: u64,
+    updated_at: u64,
+    deleted: bool,
+}
+
+/// Response for the annotations of a document
+#[derive(Serialize, ToSchema)]
+struct AnnotationsResponse {
+    document: String,
+    annotations: Vec<AnnotationResponse>,
+}
+
+/// Response for the JSON export of a document's annotations
+#[derive(Serialize, ToSchema)]
+struct AnnotationsExport {
+    document: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    annotations: Vec<AnnotationResponse>,
+}
+
+/// Handler for GET /annotations/{doc}
+///
+/// Returns every annotation of a document, including deleted ones (`deleted: true`) so
+/// devices can remove them too
+#[utoipa::path(
+    get,
+    path = "/annotations/{doc}",
+    tag = "annotations",
+    params(("doc" = String, Path, description = "Document identifier")),
+    responses(
+        (status = 200, description = "Annotations of the document, including deleted ones", body = AnnotationsResponse),
+        (status = 400, description = "Invalid document identifier", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_annotations(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+) -> Result<Json<AnnotationsResponse>, ApiError> {
+    validate_path(&state, &doc, None)?;
+
+    let annotations = state.sync.list_annotations(user, doc.clone())?;
+
+    Ok(Json(AnnotationsResponse {
+        document: doc,
+        annotations: annotations.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Handler for POST /annotations/{doc}
+///
+/// Merges the annotations of a device into the stored ones and returns the result, so a
+/// device can push its changes and pull everyone else's in a single request. The most
+/// recent change of each annotation wins; timestamps ahead of the server clock (beyond the
+/// allowed skew) are capped to it.
+#[utoipa::path(
+    post,
+    path = "/annotations/{doc}",
+    tag = "annotations",
+    params(("doc" = String, Path, description = "Document identifier")),
+    request_body = MergeAnnotationsRequest,
+    responses(
+        (status = 200, description = "Annotations of the document after the merge", body = AnnotationsResponse),
+        (status = 400, description = "Invalid annotations payload or too many annotations", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, payload))]
+async fn merge_annotations(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<MergeAnnotationsRequest>, ApiError>,
+) -> Result<Json<AnnotationsResponse>, ApiError> {
+    payload.validate(&state, &doc)?;
+
+    let latest = (Utc::now().timestamp_millis() as u64)
+        .saturating_add(state.validation.max_clock_skew_secs.saturating_mul(1000));
+    let annotations = payload
+        .annotations
+        .into_iter()
+        .map(|annotation| annotation.into_annotation(latest))
+        .collect();
+
+    info!("Merging annotations");
+    let annotations = state.sync.merge_annotations(
+        user,
+        doc.clone(),
+        annotations,
+        state.quotas.default_quota(),
+    )?;
+
+    Ok(Json(AnnotationsResponse {
+        document: doc,
+        annotations: annotations.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Handler for DELETE /annotations/{doc}/{id}
+///
+/// Deletes an annotation, leaving a tombstone so the deletion reaches every device
+#[utoipa::path(
+    delete,
+    path = "/annotations/{doc}/{id}",
+    tag = "annotations",
+    params(("doc" = String, Path, description = "Document identifier"), ("id" = String, Path, description = "Annotation id")),
+    responses(
+        (status = 204, description = "Annotation deleted"),
+        (status = 400, description = "Invalid document identifier or annotation id", body = ApiErrorPayload),
+        (status = 404, description = "The annotation does not exist or was deleted", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn delete_annotation(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path((doc, id)), _): WithRejection<Path<(String, String)>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    validate_path(&state, &doc, Some(&id))?;
+
+    let Some(annotation) = state
+        .sync
+        .list_annotations(user.clone(), doc.clone())?
+        .into_iter()
+        .find(|annotation| annotation.id == id && !annotation.deleted)
+    else {
+        return Err(ApiError::NotFound("Annotation not found".to_string()));
+    };
+
+    info!("Deleting annotation");
+    let tombstone = annotation.delete(Utc::now().timestamp_millis() as u64);
+    state
+        .sync
+        .merge_annotations(user, doc, vec![tombstone], state.quotas.default_quota())?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for GET /annotations/{doc}/export/{format}
+///
+/// Exports the annotations of a document, oldest first, as `markdown` or `json`. Deleted
+/// annotations are left out.
+#[utoipa::path(
+    get,
+    path = "/annotations/{doc}/export/{format}",
+    tag = "annotations",
+    params(("doc" = String, Path, description = "Document identifier"), ("format" = String, Path, description = "`markdown` or `json`")),
+    responses(
+        (status = 200, description = "Annotations of the document, oldest first", content(
+            (AnnotationsExport = "application/json"),
+            (String = "text/markdown"),
+        )),
+        (status = 400, description = "Unknown export format or invalid document identifier", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn export_annotations(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path((doc, format)), _): WithRejection<Path<(String, String)>, ApiError>,
+) -> Result<Response, ApiError> {
+    validate_path(&state, &doc, None)?;
+    if format != "markdown" && format != "json" {
+        return Err(ApiError::invalid_input(
+            "Export format must be 'markdown' or 'json'",
+        ));
+    }
+
+    let mut annotations: Vec<_> = state
+        .sync
+        .list_annotations(user.clone(), doc.clone())?
+        .into_iter()
+        .filter(|annotation| !annotation.deleted)
+        .collect();
+    annotations.sort_by_key(|annotation| annotation.created_at);
+    let metadata = state.sync.get_document(user, doc.clone())?;
+
+    info!("Exporting annotations as {format}");
+    if format == "json" {
+        let metadata = metadata.unwrap_or_default();
+        return Ok(Json(AnnotationsExport {
+            document: doc,
+            title: metadata.title,
+            author: metadata.author,
+            annotations: annotations.into_iter().map(Into::into).collect(),
+        })
+        .into_response());
+    }
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+        markdown(&doc, metadata.as_ref(), &annotations),
+    )
+        .into_response())
+}
+
+/// Renders annotations as a Markdown document, with a section per chapter
+fn markdown(
+    document: &str,
+    metadata: Option<&DocumentMetadata>,
+    annotations: &[Annotation],
+) -> String {
+    let title = metadata
+        .and_then(|m| m.title.as_deref())
+        .unwrap_or(document);
+    let mut out = format!("# {title}\n");
+    if let Some(author) = metadata.and_then(|m| m.author.as_deref()) {
+        out.push_str(&format!("\n*{author}*\n"));
+    }
+
+    let mut chapter = None;
+    for annotation in annotations {
+        if annotation.chapter.is_some() && annotation.chapter != chapter {
+            chapter = annotation.chapter.clone();
+            out.push_str(&format!(
+                "\n## {}\n",
+                chapter.as_deref().unwrap_or_default()
+            ));
+        }
+        out.push('\n');
+        if let Some(text) = &annotation.text {
+            for line in text.lines() {
+                out.push_str(&format!("> {line}\n"));
+            }
+        }
+        if let Some(note) = &annotation.note {
+            if annotation.text.is_some() {
+                out.push('\n');
+            }
+            out.push_str(&format!("{note}\n"));
+        }
+        let created = DateTime::from_timestamp_millis(annotation.created_at as i64)
+            .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default();
+        out.push_str(&format!("\n<sub>{created}</sub>\n"));
+    }
+
+    out
+}
+
+impl AnnotationRequest {
+    /// Converts the request into an annotation, capping its timestamps to `latest`
+    fn into_annotation(self, latest: u64) -> Annotation {
+        let updated_at = self.updated_at.min(latest);
+        let annotation = Annotation {
+            id: self.id,
+            text: self.text,
+            note: self.note,
+            pos0: self.pos0,
+            pos1: self.pos1,
+            chapter: self.chapter,
+            color: self.color,
+            created_at: self.created_at.unwrap_or(updated_at).min(latest),
+            updated_at,
+            deleted: false,
+        };
+
+        if self.deleted {
+            annotation.delete(updated_at)
+        } else {
+            annotation
+        }
+    }
+}
+
+impl From<Annotation> for AnnotationResponse {
+    fn from(value: Annotation) -> Self {
+        Self {
+            id: value.id,
+            text: value.text,
+            note: value.note,
+            pos0: value.pos0,
+            pos1: value.pos1,
+            chapter: value.chapter,
+            color: value.color,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            deleted: value.deleted,
+        }
+    }
+}
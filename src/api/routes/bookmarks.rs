@@ -0,0 +1,315 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+};
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+        validation::Validator,
+    },
+    model::Bookmark,
+};
+
+/// Create the bookmark synchronization routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/syncs/bookmarks/{doc}",
+            get(list_bookmarks).post(create_bookmark),
+        )
+        .route(
+            "/syncs/bookmarks/{doc}/{id}",
+            get(get_bookmark).put(put_bookmark).delete(delete_bookmark),
+        )
+}
+
+/// OpenAPI description of the bookmarks routes
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_bookmarks,
+    create_bookmark,
+    get_bookmark,
+    put_bookmark,
+    delete_bookmark
+))]
+pub struct ApiDoc;
+
+/// Request body for creating or updating a bookmark
+#[derive(Deserialize, Debug, ToSchema)]
+struct BookmarkRequest {
+    #[serde(default)]
+    label: String,
+    position: String,
+    /// Unix timestamp in milliseconds of the change, as seen by the device; defaults to the
+    /// time the server receives the request
+    timestamp: Option<u64>,
+}
+
+impl BookmarkRequest {
+    fn validate(&self, state: &AppState, doc: &str, id: Option<&str>) -> Result<(), ApiError> {
+        let limits = &state.validation;
+        let mut validator = Validator::new();
+        check_path(&mut validator, state, doc, id);
+        validator
+            .max_length("label", &self.label, limits.max_field_length)
+            .not_empty("position", &self.position)
+            .max_length("position", &self.position, limits.max_field_length);
+        validator.finish("Invalid bookmark payload")
+    }
+
+    /// Timestamp of the change, capped to the server clock plus the allowed skew so a
+    /// device with a fast clock can't make its changes win forever
+    fn timestamp(&self, state: &AppState) -> u64 {
+        let now = Utc::now().timestamp_millis() as u64;
+        let latest = now.saturating_add(state.validation.max_clock_skew_secs.saturating_mul(1000));
+        self.timestamp
+            .map_or(now, |timestamp| timestamp.min(latest))
+    }
+}
+
+/// Adds the rules of the document identifier, and the bookmark id if any, of a request
+/// path to `validator`
+fn check_path(validator: &mut Validator, state: &AppState, doc: &str, id: Option<&str>) {
+    let limits = &state.validation;
+    validator
+        .not_empty("doc", doc)
+        .max_length("doc", doc, limits.max_document_length);
+    if let Some(id) = id {
+        validator
+            .not_empty("id", id)
+            .max_length("id", id, limits.max_field_length);
+    }
+}
+
+/// Validates the document identifier, and the bookmark id if any, of a request path
+fn validate_path(state: &AppState, doc: &str, id: Option<&str>) -> Result<(), ApiError> {
+    let mut validator = Validator::new();
+    check_path(&mut validator, state, doc, id);
+    validator.finish("Invalid bookmark path")
+}
+
+/// A bookmark as returned by the API
+#[derive(Serialize, ToSchema)]
+struct BookmarkResponse {
+    id: String,
+    label: String,
+    position: String,
+    created_at: u64,
+    updated_at: u64,
+    deleted: bool,
+}
+
+/// Response for the bookmarks of a document
+#[derive(Serialize, ToSchema)]
+struct BookmarksResponse {
+    document: String,
+    bookmarks: Vec<BookmarkResponse>,
+}
+
+/// Handler for GET /syncs/bookmarks/{doc}
+///
+/// Returns every bookmark of a document, including deleted ones (`deleted: true`) so
+/// devices can remove them too
+#[utoipa::path(
+    get,
+    path = "/syncs/bookmarks/{doc}",
+    tag = "bookmarks",
+    params(("doc" = String, Path, description = "Document identifier")),
+    responses(
+        (status = 200, description = "Bookmarks of the document, including deleted ones", body = BookmarksResponse),
+        (status = 400, description = "Invalid document identifier", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_bookmarks(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+) -> Result<Json<BookmarksResponse>, ApiError> {
+    validate_path(&state, &doc, None)?;
+
+    let bookmarks = state.sync.list_bookmarks(user, doc.clone())?;
+
+    Ok(Json(BookmarksResponse {
+        document: doc,
+        bookmarks: bookmarks.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Handler for POST /syncs/bookmarks/{doc}
+///
+/// Creates a bookmark with a new id
+#[utoipa::path(
+    post,
+    path = "/syncs/bookmarks/{doc}",
+    tag = "bookmarks",
+    params(("doc" = String, Path, description = "Document identifier")),
+    request_body = BookmarkRequest,
+    responses(
+        (status = 201, description = "Bookmark created", body = BookmarkResponse),
+        (status = 400, description = "Invalid bookmark payload", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn create_bookmark(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<BookmarkRequest>, ApiError>,
+) -> Result<impl IntoResponse, ApiError> {
+    payload.validate(&state, &doc, None)?;
+
+    info!("Creating bookmark");
+    let timestamp = payload.timestamp(&state);
+    let bookmark = Bookmark::new(payload.label, payload.position, timestamp);
+    let bookmark = state
+        .sync
+        .put_bookmark(user, doc, bookmark, state.quotas.default_quota())?;
+
+    Ok((StatusCode::CREATED, Json(BookmarkResponse::from(bookmark))))
+}
+
+/// Handler for GET /syncs/bookmarks/{doc}/{id}
+#[utoipa::path(
+    get,
+    path = "/syncs/bookmarks/{doc}/{id}",
+    tag = "bookmarks",
+    params(("doc" = String, Path, description = "Document identifier"), ("id" = String, Path, description = "Bookmark id")),
+    responses(
+        (status = 200, description = "The bookmark", body = BookmarkResponse),
+        (status = 400, description = "Invalid document identifier or bookmark id", body = ApiErrorPayload),
+        (status = 404, description = "The bookmark does not exist or was deleted", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn get_bookmark(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path((doc, id)), _): WithRejection<Path<(String, String)>, ApiError>,
+) -> Result<Json<BookmarkResponse>, ApiError> {
+    validate_path(&state, &doc, Some(&id))?;
+
+    let bookmark = find_bookmark(&state, user, doc, &id)?;
+
+    Ok(Json(bookmark.into()))
+}
+
+/// Handler for PUT /syncs/bookmarks/{doc}/{id}
+///
+/// Creates or updates a bookmark with an id chosen by the client. The most recent change
+/// wins: if the stored bookmark changed after `timestamp`, it is kept and returned instead.
+#[utoipa::path(
+    put,
+    path = "/syncs/bookmarks/{doc}/{id}",
+    tag = "bookmarks",
+    params(("doc" = String, Path, description = "Document identifier"), ("id" = String, Path, description = "Bookmark id")),
+    request_body = BookmarkRequest,
+    responses(
+        (status = 200, description = "The stored bookmark, which is the existing one if it changed later", body = BookmarkResponse),
+        (status = 400, description = "Invalid bookmark payload", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn put_bookmark(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path((doc, id)), _): WithRejection<Path<(String, String)>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<BookmarkRequest>, ApiError>,
+) -> Result<Json<BookmarkResponse>, ApiError> {
+    payload.validate(&state, &doc, Some(&id))?;
+
+    let timestamp = payload.timestamp(&state);
+    let created_at = state
+        .sync
+        .list_bookmarks(user.clone(), doc.clone())?
+        .into_iter()
+        .find(|bookmark| bookmark.id == id)
+        .map_or(timestamp, |bookmark| bookmark.created_at);
+
+    info!("Storing bookmark");
+    let bookmark = Bookmark {
+        id,
+        label: payload.label,
+        position: payload.position,
+        created_at,
+        updated_at: timestamp,
+        deleted: false,
+    };
+    let bookmark = state
+        .sync
+        .put_bookmark(user, doc, bookmark, state.quotas.default_quota())?;
+
+    Ok(Json(bookmark.into()))
+}
+
+/// Handler for DELETE /syncs/bookmarks/{doc}/{id}
+///
+/// Deletes a bookmark, leaving a tombstone so the deletion reaches every device
+#[utoipa::path(
+    delete,
+    path = "/syncs/bookmarks/{doc}/{id}",
+    tag = "bookmarks",
+    params(("doc" = String, Path, description = "Document identifier"), ("id" = String, Path, description = "Bookmark id")),
+    responses(
+        (status = 204, description = "Bookmark deleted"),
+        (status = 400, description = "Invalid document identifier or bookmark id", body = ApiErrorPayload),
+        (status = 404, description = "The bookmark does not exist or was deleted", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn delete_bookmark(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path((doc, id)), _): WithRejection<Path<(String, String)>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    validate_path(&state, &doc, Some(&id))?;
+
+    let bookmark = find_bookmark(&state, user.clone(), doc.clone(), &id)?;
+
+    info!("Deleting bookmark");
+    let tombstone = bookmark.delete(Utc::now().timestamp_millis() as u64);
+    state
+        .sync
+        .put_bookmark(user, doc, tombstone, state.quotas.default_quota())?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Returns a bookmark of a document, unless it does not exist or was deleted
+fn find_bookmark(
+    state: &AppState,
+    user: String,
+    doc: String,
+    id: &str,
+) -> Result<Bookmark, ApiError> {
+    state
+        .sync
+        .list_bookmarks(user, doc)?
+        .into_iter()
+        .find(|bookmark| bookmark.id == id && !bookmark.deleted)
+        .ok_or_else(|| ApiError::NotFound("Bookmark not found".to_string()))
+}
+
+impl From<Bookmark> for BookmarkResponse {
+    fn from(value: Bookmark) -> Self {
+        Self {
+            id: value.id,
+            label: value.label,
+            position: value.position,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            deleted: value.deleted,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use axum::{Extension, Json, Router, extract::State, routing::get};
+use serde::Serialize;
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{error::ApiError, middleware::auth::AuthenticatedUser, state::AppState},
+    model::Device,
+};
+
+/// Create the device registry routes
+pub fn create_route() -> Router<AppState> {
+    Router::new().route("/devices", get(list_devices))
+}
+
+/// OpenAPI description of the devices routes
+#[derive(OpenApi)]
+#[openapi(paths(list_devices))]
+pub struct ApiDoc;
+
+/// Response item for a registered device
+#[derive(Serialize, ToSchema)]
+struct DeviceResponse {
+    device_id: String,
+    device: String,
+    name: String,
+    first_seen: u64,
+    last_seen: u64,
+    last_document: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_ip: Option<String>,
+}
+
+/// Handler for GET /devices
+///
+/// Returns the devices that have synchronized progress for the authenticated user
+#[utoipa::path(
+    get,
+    path = "/devices",
+    tag = "devices",
+    responses((status = 200, description = "Devices of the user", body = Vec<DeviceResponse>))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_devices(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<Vec<DeviceResponse>>, ApiError> {
+    info!("Listing devices");
+
+    let devices = state.sync.list_devices(user)?;
+
+    Ok(Json(devices.into_iter().map(Into::into).collect()))
+}
+
+impl From<Device> for DeviceResponse {
+    fn from(value: Device) -> Self {
+        Self {
+            name: value.display_name().to_string(),
+            device_id: value.device_id,
+            device: value.device,
+            first_seen: value.first_seen,
+            last_seen: value.last_seen,
+            last_document: value.last_document,
+            last_ip: value.last_ip,
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    routing::{get, put},
+};
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+        validation::Validator,
+    },
+    model::{DocumentMetadata, Progress},
+};
+
+/// Create the document metadata routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route("/documents", get(list_documents))
+        .route("/documents/{doc}", put(set_document).get(get_document))
+}
+
+/// OpenAPI description of the documents routes
+#[derive(OpenApi)]
+#[openapi(paths(list_documents, set_document, get_document))]
+pub struct ApiDoc;
+
+/// Request body for setting a document's metadata
+///
+/// Omitted or empty fields are cleared.
+#[derive(Deserialize, Debug, ToSchema)]
+struct SetDocumentRequest {
+    title: Option<String>,
+    author: Option<String>,
+    series: Option<String>,
+    language: Option<String>,
+    cover_url: Option<String>,
+}
+
+impl SetDocumentRequest {
+    fn validate(&self, state: &AppState, doc: &str) -> Result<(), ApiError> {
+        let limits = &state.validation;
+        let mut validator = Validator::new();
+        validator
+            .not_empty("doc", doc)
+            .max_length("doc", doc, limits.max_document_length);
+        let fields = [
+            ("title", &self.title),
+            ("author", &self.author),
+            ("series", &self.series),
+            ("language", &self.language),
+            ("cover_url", &self.cover_url),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value {
+                validator.max_length(field, value, limits.max_field_length);
+            }
+        }
+        validator.finish("Invalid document metadata")
+    }
+}
+
+/// Document metadata as returned by the API
+#[derive(Serialize, ToSchema)]
+struct DocumentMetadataResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    series: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cover_url: Option<String>,
+    updated_at: u64,
+}
+
+/// Response for a document
+#[derive(Serialize, ToSchema)]
+struct DocumentResponse {
+    document: String,
+    #[serde(flatten)]
+    metadata: DocumentMetadataResponse,
+}
+
+/// Response item for the document listing
+#[derive(Serialize, ToSchema)]
+struct DocumentListItem {
+    document: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<DocumentMetadataResponse>,
+    progress: DocumentProgressResponse,
+}
+
+/// Latest progress of a listed document
+#[derive(Serialize, ToSchema)]
+struct DocumentProgressResponse {
+    device_id: String,
+    device: String,
+    percentage: f32,
+    progress: String,
+    timestamp: u64,
+}
+
+/// Handler for GET /documents
+///
+/// Lists every document the authenticated user has progress for, with its latest
+/// progress and metadata (when known)
+#[utoipa::path(
+    get,
+    path = "/documents",
+    tag = "documents",
+    responses((status = 200, description = "Documents with progress, with their metadata", body = Vec<DocumentListItem>))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_documents(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<Vec<DocumentListItem>>, ApiError> {
+    info!("Listing documents");
+
+    let mut items = Vec::new();
+    for (document, progress) in state.sync.list_progress(user.clone())? {
+        let metadata = state.sync.get_document(user.clone(), document.clone())?;
+        items.push(DocumentListItem {
+            document,
+            metadata: metadata.map(Into::into),
+            progress: progress.into(),
+        });
+    }
+
+    Ok(Json(items))
+}
+
+/// Handler for PUT /documents/{doc}
+///
+/// Sets the authenticated user's metadata for a document. It takes precedence over the
+/// global metadata managed from the CLI.
+#[utoipa::path(
+    put,
+    path = "/documents/{doc}",
+    tag = "documents",
+    params(("doc" = String, Path, description = "Document identifier")),
+    request_body = SetDocumentRequest,
+    responses(
+        (status = 200, description = "The stored metadata", body = DocumentResponse),
+        (status = 400, description = "Invalid document metadata", body = ApiErrorPayload),
+        (status = 507, description = "The metadata would exceed the user's storage quota", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn set_document(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetDocumentRequest>, ApiError>,
+) -> Result<Json<DocumentResponse>, ApiError> {
+    payload.validate(&state, &doc)?;
+    let metadata = DocumentMetadata::try_from(payload)?;
+
+    info!("Setting document metadata");
+    let metadata = state.sync.set_document(
+        Some(user),
+        doc.clone(),
+        metadata,
+        state.quotas.default_quota(),
+    )?;
+
+    Ok(Json(DocumentResponse {
+        document: doc,
+        metadata: metadata.into(),
+    }))
+}
+
+/// Handler for GET /documents/{doc}
+///
+/// Returns the metadata of a document, preferring the authenticated user's own metadata
+/// over the global one
+#[utoipa::path(
+    get,
+    path = "/documents/{doc}",
+    tag = "documents",
+    params(("doc" = String, Path, description = "Document identifier")),
+    responses(
+        (status = 200, description = "Metadata of the document, the user's own or else the global one", body = DocumentResponse),
+        (status = 404, description = "The document has no metadata", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn get_document(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+) -> Result<Json<DocumentResponse>, ApiError> {
+    let Some(metadata) = state.sync.get_document(user, doc.clone())? else {
+        return Err(ApiError::NotFound("Document not found".to_string()));
+    };
+
+    Ok(Json(DocumentResponse {
+        document: doc,
+        metadata: metadata.into(),
+    }))
+}
+
+impl TryFrom<SetDocumentRequest> for DocumentMetadata {
+    type Error = ApiError;
+
+    fn try_from(value: SetDocumentRequest) -> Result<Self, Self::Error> {
+        let non_empty = |field: Option<String>| field.filter(|v| !v.trim().is_empty());
+
+        let cover_url = non_empty(value.cover_url);
+        if let Some(url) = &cover_url
+            && !(url.starts_with("http://") || url.starts_with("https://"))
+        {
+            return Err(ApiError::invalid_input(
+                "Cover URL must start with http:// or https://",
+            ));
+        }
+
+        Ok(Self {
+            title: non_empty(value.title),
+            author: non_empty(value.author),
+            series: non_empty(value.series),
+            language: non_empty(value.language),
+            cover_url,
+            updated_at: Utc::now().timestamp_millis() as u64,
+        })
+    }
+}
+
+impl From<DocumentMetadata> for DocumentMetadataResponse {
+    fn from(value: DocumentMetadata) -> Self {
+        Self {
+            title: value.title,
+            author: value.author,
+            series: value.series,
+            language: value.language,
+            cover_url: value.cover_url,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<Progress> for DocumentProgressResponse {
+    fn from(value: Progress) -> Self {
+        Self {
+            device_id: value.device_id,
+            device: value.device,
+            percentage: value.percentage,
+            progress: value.progress,
+            timestamp: value.timestamp,
+        }
+    }
+}
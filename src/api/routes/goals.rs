@@ -0,0 +1,159 @@
+use axum::{Extension, Json, Router, extract::State, routing::get};
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+        validation::Validator,
+    },
+    model::{GoalReport, Goals, ReadingStats},
+};
+
+/// Create the reading goals routes
+pub fn create_route() -> Router<AppState> {
+    Router::new().route("/goals", get(get_goals).put(set_goals))
+}
+
+/// OpenAPI description of the goals routes
+#[derive(OpenApi)]
+#[openapi(paths(get_goals, set_goals))]
+pub struct ApiDoc;
+
+/// Request body for setting the reading goals
+///
+/// Omitted goals are cleared.
+#[derive(Deserialize, Debug, ToSchema)]
+struct SetGoalsRequest {
+    books_per_year: Option<u32>,
+    minutes_per_day: Option<u32>,
+}
+
+/// Reading goals of the authenticated user and how far they are
+#[derive(Serialize, ToSchema)]
+struct GoalsResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    books_per_year: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minutes_per_day: Option<u32>,
+    streak: StreakResponse,
+    today: GoalProgressResponse,
+    year: GoalProgressResponse,
+}
+
+/// Consecutive days with reading
+#[derive(Serialize, ToSchema)]
+struct StreakResponse {
+    current: u32,
+    longest: u32,
+}
+
+/// Progress towards a goal over the current period (day or year)
+#[derive(Serialize, ToSchema)]
+struct GoalProgressResponse {
+    /// Minutes read today, or documents finished this year
+    done: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed: Option<bool>,
+}
+
+/// Handler for GET /goals
+///
+/// Returns the goals of the authenticated user, their current and longest reading streak,
+/// and how far today's reading time and this year's finished documents are from the goals
+#[utoipa::path(
+    get,
+    path = "/goals",
+    tag = "goals",
+    responses((status = 200, description = "Goals, streak and progress towards each goal", body = GoalsResponse))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn get_goals(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<GoalsResponse>, ApiError> {
+    info!("Getting reading goals");
+
+    let goals = state.sync.get_goals(user.clone())?.unwrap_or_default();
+    goals_response(&state, user, goals)
+}
+
+/// Handler for PUT /goals
+///
+/// Replaces the goals of the authenticated user and returns them evaluated
+#[utoipa::path(
+    put,
+    path = "/goals",
+    tag = "goals",
+    request_body = SetGoalsRequest,
+    responses(
+        (status = 200, description = "The goals, evaluated", body = GoalsResponse),
+        (status = 400, description = "Invalid goals payload", body = ApiErrorPayload),
+        (status = 507, description = "The goals would exceed the user's storage quota", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn set_goals(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetGoalsRequest>, ApiError>,
+) -> Result<Json<GoalsResponse>, ApiError> {
+    let mut validator = Validator::new();
+    if let Some(books) = payload.books_per_year {
+        validator.positive("books_per_year", books.into());
+    }
+    if let Some(minutes) = payload.minutes_per_day {
+        validator.positive("minutes_per_day", minutes.into());
+    }
+    validator.finish("Invalid goals payload")?;
+
+    info!("Setting reading goals");
+    let goals = state.sync.set_goals(
+        user.clone(),
+        Goals {
+            books_per_year: payload.books_per_year,
+            minutes_per_day: payload.minutes_per_day,
+            updated_at: Utc::now().timestamp_millis() as u64,
+        },
+        state.quotas.default_quota(),
+    )?;
+    goals_response(&state, user, goals)
+}
+
+fn goals_response(
+    state: &AppState,
+    user: String,
+    goals: Goals,
+) -> Result<Json<GoalsResponse>, ApiError> {
+    let history = state.sync.list_progress_history(user)?;
+    let report = GoalReport::evaluate(
+        &ReadingStats::from_history(&history),
+        Utc::now().timestamp_millis() as u64,
+    );
+
+    Ok(Json(GoalsResponse {
+        books_per_year: goals.books_per_year,
+        minutes_per_day: goals.minutes_per_day,
+        streak: StreakResponse {
+            current: report.current_streak,
+            longest: report.longest_streak,
+        },
+        today: GoalProgressResponse {
+            done: report.minutes_today,
+            target: goals.minutes_per_day,
+            completed: report.daily_goal_met(&goals),
+        },
+        year: GoalProgressResponse {
+            done: report.books_this_year as u64,
+            target: goals.books_per_year,
+            completed: report.yearly_goal_met(&goals),
+        },
+    }))
+}
@@ -0,0 +1,452 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post, put},
+};
+use axum_extra::extract::WithRejection;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+        validation::Validator,
+    },
+    model::{Group, GroupMember, Progress, Visibility},
+};
+
+/// Create the reading group routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route("/groups", post(create_group).get(list_groups))
+        .route("/groups/join", post(join_group))
+        .route("/groups/{id}", get(get_group).delete(delete_group))
+        .route("/groups/{id}/invite", post(rotate_invite))
+        .route("/groups/{id}/settings", put(update_settings))
+        .route("/groups/{id}/leave", post(leave_group))
+        .route("/groups/{id}/progress/{doc}", get(get_group_progress))
+}
+
+/// OpenAPI description of the groups routes
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_group,
+    list_groups,
+    get_group,
+    delete_group,
+    join_group,
+    rotate_invite,
+    update_settings,
+    leave_group,
+    get_group_progress
+))]
+pub struct ApiDoc;
+
+/// Request body for creating a group
+#[derive(Deserialize, Debug, ToSchema)]
+struct CreateGroupRequest {
+    name: String,
+}
+
+impl CreateGroupRequest {
+    fn validate(&self, state: &AppState) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        validator.not_empty("name", &self.name).max_length(
+            "name",
+            &self.name,
+            state.validation.max_field_length,
+        );
+        validator.finish("Invalid group payload")
+    }
+}
+
+/// Request body for joining a group
+#[derive(Deserialize, Debug, ToSchema)]
+struct JoinGroupRequest {
+    invite_code: String,
+}
+
+/// Request body for updating the authenticated user's membership settings
+#[derive(Deserialize, Debug, ToSchema)]
+struct GroupSettingsRequest {
+    visibility: String,
+}
+
+/// Response for a group
+///
+/// The invite code is only included for the owner of the group.
+#[derive(Serialize, ToSchema)]
+struct GroupResponse {
+    id: String,
+    name: String,
+    owner: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invite_code: Option<String>,
+    members: Vec<GroupMemberResponse>,
+    created_at: u64,
+}
+
+/// Response item for a group member
+#[derive(Serialize, ToSchema)]
+struct GroupMemberResponse {
+    username: String,
+    visibility: String,
+    joined_at: u64,
+}
+
+/// Response for a rotated invite code
+#[derive(Serialize, ToSchema)]
+struct InviteResponse {
+    invite_code: String,
+}
+
+/// Response for the progress of a group on a document
+#[derive(Serialize, ToSchema)]
+struct GroupProgressResponse {
+    document: String,
+    members: Vec<MemberProgressResponse>,
+}
+
+/// Progress of a single member, restricted to what their visibility setting allows
+#[derive(Serialize, ToSchema)]
+struct MemberProgressResponse {
+    username: String,
+    percentage: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    progress: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<String>,
+    timestamp: u64,
+}
+
+/// Handler for POST /groups
+///
+/// Creates a group owned by the authenticated user
+#[utoipa::path(
+    post,
+    path = "/groups",
+    tag = "groups",
+    request_body = CreateGroupRequest,
+    responses(
+        (status = 201, description = "Group created", body = GroupResponse),
+        (status = 400, description = "Empty or too long group name", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn create_group(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateGroupRequest>, ApiError>,
+) -> Result<impl IntoResponse, ApiError> {
+    payload.validate(&state)?;
+    let name = payload.name.trim();
+
+    info!("Creating group");
+    let group = state.sync.create_or_update_group(Group::new(name, &user))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(GroupResponse::for_user(group, &user)),
+    ))
+}
+
+/// Handler for GET /groups
+///
+/// Lists the groups the authenticated user belongs to
+#[utoipa::path(
+    get,
+    path = "/groups",
+    tag = "groups",
+    responses((status = 200, description = "Groups of the user", body = Vec<GroupResponse>))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_groups(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<Vec<GroupResponse>>, ApiError> {
+    let groups = state.sync.list_groups(Some(user.clone()))?;
+
+    Ok(Json(
+        groups
+            .into_iter()
+            .map(|group| GroupResponse::for_user(group, &user))
+            .collect(),
+    ))
+}
+
+/// Handler for GET /groups/{id}
+#[utoipa::path(
+    get,
+    path = "/groups/{id}",
+    tag = "groups",
+    params(("id" = String, Path, description = "Group id")),
+    responses(
+        (status = 200, description = "The group", body = GroupResponse),
+        (status = 404, description = "The group does not exist or the user is not a member", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn get_group(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(id), _): WithRejection<Path<String>, ApiError>,
+) -> Result<Json<GroupResponse>, ApiError> {
+    let group = find_member_group(&state, id, &user)?;
+
+    Ok(Json(GroupResponse::for_user(group, &user)))
+}
+
+/// Handler for DELETE /groups/{id}
+///
+/// Deletes the group. Only the owner can delete a group.
+#[utoipa::path(
+    delete,
+    path = "/groups/{id}",
+    tag = "groups",
+    params(("id" = String, Path, description = "Group id")),
+    responses(
+        (status = 204, description = "Group deleted"),
+        (status = 403, description = "The user does not own the group", body = ApiErrorPayload),
+        (status = 404, description = "The group does not exist or the user is not a member", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn delete_group(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(id), _): WithRejection<Path<String>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    let group = find_owned_group(&state, id, &user)?;
+
+    info!("Deleting group");
+    state.sync.delete_group(group.id)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for POST /groups/join
+///
+/// Adds the authenticated user to the group matching the invite code
+#[utoipa::path(
+    post,
+    path = "/groups/join",
+    tag = "groups",
+    request_body = JoinGroupRequest,
+    responses(
+        (status = 200, description = "The joined group", body = GroupResponse),
+        (status = 404, description = "No group has this invite code", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, payload))]
+async fn join_group(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Json(payload), _): WithRejection<Json<JoinGroupRequest>, ApiError>,
+) -> Result<Json<GroupResponse>, ApiError> {
+    let Some(group) = state.sync.join_group(payload.invite_code, user.clone())? else {
+        return Err(ApiError::NotFound("Invalid invite code".to_string()));
+    };
+
+    info!("Joined group {}", group.id);
+    Ok(Json(GroupResponse::for_user(group, &user)))
+}
+
+/// Handler for POST /groups/{id}/invite
+///
+/// Generates a new invite code, invalidating the previous one. Only the owner can
+/// rotate the invite code.
+#[utoipa::path(
+    post,
+    path = "/groups/{id}/invite",
+    tag = "groups",
+    params(("id" = String, Path, description = "Group id")),
+    responses(
+        (status = 200, description = "The new invite code", body = InviteResponse),
+        (status = 403, description = "The user does not own the group", body = ApiErrorPayload),
+        (status = 404, description = "The group does not exist or the user is not a member", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn rotate_invite(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(id), _): WithRejection<Path<String>, ApiError>,
+) -> Result<Json<InviteResponse>, ApiError> {
+    let group = find_owned_group(&state, id, &user)?;
+
+    let Some(invite_code) = state.sync.rotate_group_invite(group.id)? else {
+        return Err(group_not_found());
+    };
+
+    Ok(Json(InviteResponse { invite_code }))
+}
+
+/// Handler for PUT /groups/{id}/settings
+///
+/// Changes how much of the authenticated user's progress is visible to the group
+#[utoipa::path(
+    put,
+    path = "/groups/{id}/settings",
+    tag = "groups",
+    params(("id" = String, Path, description = "Group id")),
+    request_body = GroupSettingsRequest,
+    responses(
+        (status = 204, description = "Settings updated"),
+        (status = 400, description = "Unknown visibility", body = ApiErrorPayload),
+        (status = 404, description = "The group does not exist or the user is not a member", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn update_settings(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(id), _): WithRejection<Path<String>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<GroupSettingsRequest>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    let visibility: Visibility = payload
+        .visibility
+        .parse()
+        .map_err(ApiError::invalid_input)?;
+
+    if !state.sync.set_group_visibility(id, user, visibility)? {
+        return Err(group_not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for POST /groups/{id}/leave
+///
+/// Removes the authenticated user from the group. If the owner leaves, ownership passes
+/// to the longest-standing remaining member; the group is deleted with its last member.
+#[utoipa::path(
+    post,
+    path = "/groups/{id}/leave",
+    tag = "groups",
+    params(("id" = String, Path, description = "Group id")),
+    responses(
+        (status = 204, description = "Left the group"),
+        (status = 404, description = "The group does not exist or the user is not a member", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn leave_group(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(id), _): WithRejection<Path<String>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    if !state.sync.leave_group(id, user)? {
+        return Err(group_not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for GET /groups/{id}/progress/{doc}
+///
+/// Returns the latest progress of every group member on a document, restricted by each
+/// member's visibility setting. Hidden members and members without progress are omitted.
+#[utoipa::path(
+    get,
+    path = "/groups/{id}/progress/{doc}",
+    tag = "groups",
+    params(("id" = String, Path, description = "Group id"), ("doc" = String, Path, description = "Document identifier")),
+    responses(
+        (status = 200, description = "Progress of the visible members", body = GroupProgressResponse),
+        (status = 404, description = "The group does not exist or the user is not a member", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn get_group_progress(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path((id, doc)), _): WithRejection<Path<(String, String)>, ApiError>,
+) -> Result<Json<GroupProgressResponse>, ApiError> {
+    let group = find_member_group(&state, id, &user)?;
+
+    let mut members = Vec::new();
+    for member in group.members {
+        if member.visibility == Visibility::Hidden {
+            continue;
+        }
+        if let Some(progress) = state
+            .sync
+            .get_progress(member.username.clone(), doc.clone())?
+        {
+            members.push(MemberProgressResponse::new(member, progress));
+        }
+    }
+
+    Ok(Json(GroupProgressResponse {
+        document: doc,
+        members,
+    }))
+}
+
+/// Loads a group the user belongs to.
+///
+/// Groups the user is not a member of are reported as missing, so their existence is
+/// not disclosed.
+fn find_member_group(state: &AppState, id: String, user: &str) -> Result<Group, ApiError> {
+    match state.sync.get_group(id)? {
+        Some(group) if group.is_member(user) => Ok(group),
+        _ => Err(group_not_found()),
+    }
+}
+
+/// Loads a group the user owns.
+fn find_owned_group(state: &AppState, id: String, user: &str) -> Result<Group, ApiError> {
+    let group = find_member_group(state, id, user)?;
+    if group.owner != user {
+        return Err(ApiError::Forbidden(
+            "Only the group owner can perform this action".to_string(),
+        ));
+    }
+    Ok(group)
+}
+
+fn group_not_found() -> ApiError {
+    ApiError::NotFound("Group not found".to_string())
+}
+
+impl GroupResponse {
+    fn for_user(group: Group, user: &str) -> Self {
+        let invite_code = (group.owner == user).then_some(group.invite_code);
+        Self {
+            id: group.id,
+            name: group.name,
+            owner: group.owner,
+            invite_code,
+            members: group.members.into_iter().map(Into::into).collect(),
+            created_at: group.created_at,
+        }
+    }
+}
+
+impl From<GroupMember> for GroupMemberResponse {
+    fn from(value: GroupMember) -> Self {
+        Self {
+            username: value.username,
+            visibility: value.visibility.to_string(),
+            joined_at: value.joined_at,
+        }
+    }
+}
+
+impl MemberProgressResponse {
+    fn new(member: GroupMember, progress: Progress) -> Self {
+        let full = member.visibility == Visibility::Full;
+        Self {
+            username: member.username,
+            percentage: progress.percentage,
+            progress: full.then_some(progress.progress),
+            device: full.then_some(progress.device),
+            timestamp: progress.timestamp,
+        }
+    }
+}
@@ -1,13 +1,27 @@
 use axum::{Router, http::StatusCode, routing::get};
 use tracing::{Level, instrument};
+use utoipa::OpenApi;
 
 use crate::api::state::AppState;
 
-/// Health Check Router - contains one single GET health endpoint, meant to be used for probes
+/// Health Check Router - contains one single authenticated GET health endpoint, kept for
+/// KOReader compatibility. Container probes should use the public
+/// [`probes`](crate::api::routes::probes) routes instead.
 pub fn create_route() -> Router<AppState> {
     Router::new().route("/healthcheck", get(get_health_check))
 }
 
+/// OpenAPI description of the health check route
+#[derive(OpenApi)]
+#[openapi(paths(get_health_check))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/healthcheck",
+    tag = "misc",
+    responses((status = 200, description = "The server is up"))
+)]
 #[instrument(level = Level::DEBUG)]
 async fn get_health_check() -> StatusCode {
     StatusCode::OK
@@ -15,6 +15,18 @@
 //!   - Robots exclusion protocol file
 //!   - Instructs web crawlers not to index the API
 //!
+//! - **[`share_view`]** - `GET /share/{token}`
+//!   - Read-only view of the progress behind a share link, as JSON or a minimal HTML page
+//!   - Returns 404 for unknown, revoked or expired links
+//!
+//! - **[`openapi`]** - `GET /openapi.json`
+//!   - OpenAPI 3 description of the API, generated from the route handlers
+//!   - `GET /docs` serves a Scalar UI for it with the `openapi-ui` feature
+//!
+//! - **[`probes`]** - `GET /livez` and `GET /readyz`
+//!   - Liveness and readiness probes for Docker and Kubernetes
+//!   - Readiness reads the storage and fails with 503 once the server is shutting down
+//!
 //! - **[`fallback`]** - All unmatched routes
 //!   - Returns 404 Not Found for invalid endpoints
 //!
@@ -26,12 +38,80 @@
 //!   - User authentication retrieval
 //!   - Returns user information and last activity timestamp
 //!
+//! - **[`users_account`]** - Self-service account management
+//!   - `PUT /users/password` - Change the password (requires the current one)
+//!   - `DELETE /users/me` - Delete the account and all its data (requires confirmation)
+//!
 //! - **[`syncs_progress`]** - Progress synchronization endpoints
 //!   - `PUT /syncs/progress` - Update reading progress for a document
 //!   - `GET /syncs/progress/{document}` - Retrieve progress for a specific document
+//!   - `DELETE /syncs/progress/{document}` - Delete the progress and reading history of a document
+//!   - `POST /syncs/progress/batch` - Retrieve progress for several documents at once
+//!   - `PUT /syncs/progress/batch` - Update progress for several documents at once
+//!   - `GET /syncs/progress/{document}/devices` - Last position of each device for a document
+//!
+//! - **[`bookmarks`]** - Named bookmarks, kept apart from the reading position
+//!   - `GET /syncs/bookmarks/{document}` - List a document's bookmarks, including deletion tombstones
+//!   - `POST /syncs/bookmarks/{document}` - Create a bookmark
+//!   - `GET /syncs/bookmarks/{document}/{id}` - Retrieve a bookmark
+//!   - `PUT /syncs/bookmarks/{document}/{id}` - Create or update a bookmark; the most recent change wins
+//!   - `DELETE /syncs/bookmarks/{document}/{id}` - Delete a bookmark
+//!
+//! - **[`aliases`]** - Document aliases, so progress follows a book across digest changes
+//!   - `GET /aliases` - List the user's aliases
+//!   - `PUT /aliases/{alias}` - Make an identifier resolve to another document
+//!   - `DELETE /aliases/{alias}` - Remove an alias
+//!
+//! - **[`documents`]** - Document metadata
+//!   - `GET /documents` - List the user's documents with their latest progress and metadata
+//!   - `PUT /documents/{document}` - Set the user's title, author, series, language and cover
+//!   - `GET /documents/{document}` - Retrieve the metadata (user's own, else global)
+//!
+//! - **[`annotations`]** - Highlights and notes, merged across devices
+//!   - `GET /annotations/{document}` - List a document's annotations, including deletion tombstones
+//!   - `POST /annotations/{document}` - Merge a device's annotations and return the result
+//!   - `DELETE /annotations/{document}/{id}` - Delete an annotation
+//!   - `GET /annotations/{document}/export/{format}` - Export as `markdown` or `json`
+//!
+//! - **[`devices`]** - `GET /devices`
+//!   - Lists the devices that have synchronized progress, with first/last seen tracking
+//!
+//! - **[`groups`]** - Reading groups with shared progress
+//!   - `POST /groups` - Create a group owned by the authenticated user
+//!   - `GET /groups` - List the groups the authenticated user belongs to
+//!   - `GET /groups/{id}` / `DELETE /groups/{id}` - Show or delete (owner only) a group
+//!   - `POST /groups/join` - Join a group with its invite code
+//!   - `POST /groups/{id}/invite` - Rotate the invite code (owner only)
+//!   - `PUT /groups/{id}/settings` - Change what the user shares with the group
+//!   - `POST /groups/{id}/leave` - Leave the group
+//!   - `GET /groups/{id}/progress/{document}` - Progress of every visible member on a document
+//!
+//! - **[`shares`]** - Share link management
+//!   - `POST /shares` - Create a share link for a document, with optional expiry
+//!   - `GET /shares` - List the user's share links
+//!   - `DELETE /shares/{token}` - Revoke a share link
+//!
+//! - **[`stats`]** - `GET /stats`
+//!   - Reading sessions, documents finished per month and average pace, derived from the
+//!     history of progress updates
+//!
+//! - **[`goals`]** - Reading goals and streaks
+//!   - `GET /goals` - The user's goals, reading streak and progress towards each goal
+//!   - `PUT /goals` - Set the books-per-year and minutes-per-day goals
+//!
+//! - **[`shelves`]** - Reading, finished and abandoned shelves
+//!   - `GET /shelves` - The user's documents grouped by shelf, with each read-through
+//!   - `PUT /shelves/{document}` - Mark a document as reading, finished or abandoned
+//!
+//! - **`statistics_sync`** - WebDAV storage for KOReader's `statistics.sqlite3` (`statistics-sync` feature)
+//!   - Authenticated with HTTP Basic (the KOReader password) instead of the sync headers
+//!   - `PROPFIND /dav/` - List the user's folder
+//!   - `GET /dav/statistics.sqlite3` - Download the merged statistics database
+//!   - `PUT /dav/statistics.sqlite3` - Upload a statistics database, merged into the stored one
 //!
 //! - **[`healthcheck`]** - `GET /healthcheck`
-//!   - Simple health check endpoint for monitoring
+//!   - Authenticated health check, kept for KOReader compatibility; probes should use
+//!     [`probes`] instead
 //!
 //! # KOReader Compatibility
 //!
@@ -39,9 +119,25 @@
 //! KOReader's synchronization plugin. The API follows REST principles and uses JSON for
 //! request/response payloads.
 
+pub mod aliases;
+pub mod annotations;
+pub mod bookmarks;
+pub mod devices;
+pub mod documents;
 pub mod fallback;
+pub mod goals;
+pub mod groups;
 pub mod healthcheck;
+pub mod openapi;
+pub mod probes;
 pub mod register;
 pub mod robots;
+pub mod share_view;
+pub mod shares;
+pub mod shelves;
+#[cfg(feature = "statistics-sync")]
+pub mod statistics_sync;
+pub mod stats;
 pub mod syncs_progress;
+pub mod users_account;
 pub mod users_auth;
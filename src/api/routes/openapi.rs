@@ -0,0 +1,25 @@
+use axum::{Json, Router, routing::get};
+use tracing::{Level, instrument};
+
+use crate::api::{openapi::openapi, state::AppState};
+
+/// Create the OpenAPI document route, and the Scalar UI with the `openapi-ui` feature
+pub fn create_route() -> Router<AppState> {
+    let router = Router::new().route("/openapi.json", get(get_openapi));
+
+    #[cfg(feature = "openapi-ui")]
+    let router = {
+        use utoipa_scalar::{Scalar, Servable};
+        router.merge(Scalar::with_url("/docs", openapi()))
+    };
+
+    router
+}
+
+/// Handler for GET /openapi.json
+///
+/// Returns the OpenAPI 3 description of the API
+#[instrument(level = Level::DEBUG)]
+async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
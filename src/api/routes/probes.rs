@@ -0,0 +1,91 @@
+use std::time::Instant;
+
+use axum::{Json, Router, extract::State, http::StatusCode, routing::get};
+use serde::Serialize;
+use tracing::{Level, instrument, warn};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::api::state::AppState;
+
+/// Create the liveness and readiness probe routes, meant for container orchestrators
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route("/livez", get(get_liveness))
+        .route("/readyz", get(get_readiness))
+}
+
+/// OpenAPI description of the probe routes
+#[derive(OpenApi)]
+#[openapi(paths(get_liveness, get_readiness))]
+pub struct ApiDoc;
+
+/// Response body for the readiness probe
+#[derive(Debug, Serialize, ToSchema)]
+struct ReadinessResponse {
+    /// `ready`, `unavailable` when the storage can't be read, or `shutting_down`
+    status: &'static str,
+    /// Time taken to open a read transaction on the storage, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_latency_ms: Option<f64>,
+}
+
+/// Handler for GET /livez
+///
+/// Answers as long as the server process handles requests.
+#[utoipa::path(
+    get,
+    path = "/livez",
+    tag = "misc",
+    responses((status = 200, description = "The server is alive"))
+)]
+#[instrument(level = Level::DEBUG)]
+async fn get_liveness() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Handler for GET /readyz
+///
+/// Checks that the storage can be read and reports how long it took. Fails once the server
+/// starts shutting down, so that no new traffic is routed to it.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "misc",
+    responses(
+        (status = 200, description = "The server can serve requests", body = ReadinessResponse),
+        (status = 503, description = "The storage is unavailable or the server is shutting down", body = ReadinessResponse),
+    )
+)]
+#[instrument(level = Level::DEBUG, skip(state))]
+async fn get_readiness(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    if state.shutdown.is_cancelled() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ReadinessResponse {
+                status: "shutting_down",
+                storage_latency_ms: None,
+            }),
+        );
+    }
+
+    let start = Instant::now();
+    match state.sync.ping() {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ReadinessResponse {
+                status: "ready",
+                storage_latency_ms: Some(start.elapsed().as_secs_f64() * 1000.0),
+            }),
+        ),
+        Err(e) => {
+            warn!("Readiness check failed: {e}");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ReadinessResponse {
+                    status: "unavailable",
+                    storage_latency_ms: None,
+                }),
+            )
+        }
+    }
+}
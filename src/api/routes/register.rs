@@ -1,21 +1,45 @@
 use crate::{
-    api::{error::ApiError, state::AppState},
-    model::User,
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        extract::ClientIp,
+        state::AppState,
+    },
+    model::{AuditEvent, AuditKind, User},
 };
 use axum::{Json, Router, extract::State, http::StatusCode, response::IntoResponse, routing::post};
 use axum_extra::extract::WithRejection;
-use serde::Deserialize;
-use serde_json::json;
+use serde::{Deserialize, Serialize};
 use tracing::{Level, instrument};
+use utoipa::{OpenApi, ToSchema};
 
 /// Register Router - handles user registration
 pub fn create_route() -> Router<AppState> {
     Router::new().route("/users/create", post(register))
 }
 
+/// OpenAPI description of the registration route
+#[derive(OpenApi)]
+#[openapi(paths(register))]
+pub struct ApiDoc;
+
+/// Handler for POST /users/create
+///
+/// Registers a new user
+#[utoipa::path(
+    post,
+    path = "/users/create",
+    tag = "users",
+    request_body = RegisterUser,
+    responses(
+        (status = 201, description = "User created", body = RegisterResponse),
+        (status = 400, description = "Empty username or password", body = ApiErrorPayload),
+        (status = 402, description = "The username is taken (KOReader expects 402)", body = ApiErrorPayload),
+    )
+)]
 #[instrument(level = Level::DEBUG, skip(payload, state))]
 async fn register(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
     WithRejection(Json(payload), _): WithRejection<Json<RegisterUser>, ApiError>,
 ) -> Result<impl IntoResponse, ApiError> {
     payload.validate()?;
@@ -28,23 +52,35 @@ async fn register(
         User::new(&payload.username, &payload.password).map_err(ApiError::runtime)?,
     )?;
 
+    state.audit(AuditEvent::new(AuditKind::Registration, &payload.username).with_ip(ip));
+
     Ok((
         StatusCode::CREATED,
-        Json(json!({"username": payload.username})),
+        Json(RegisterResponse {
+            username: payload.username,
+        }),
     ))
 }
 
-#[derive(Deserialize, Debug)]
+/// Request body for registering a user
+#[derive(Deserialize, Debug, ToSchema)]
 struct RegisterUser {
     username: String,
+    /// KOReader key of the password (its MD5 hex digest)
     password: String,
 }
 
+/// Response for a registered user
+#[derive(Serialize, ToSchema)]
+struct RegisterResponse {
+    username: String,
+}
+
 impl RegisterUser {
     fn validate(&self) -> Result<(), ApiError> {
         if self.username.is_empty() || self.password.is_empty() {
-            return Err(ApiError::InvalidInput(
-                "Username and password cannot be empty".into(),
+            return Err(ApiError::invalid_input(
+                "Username and password cannot be empty",
             ));
         }
 
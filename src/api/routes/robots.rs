@@ -1,5 +1,6 @@
 use axum::{Router, routing::get};
 use tracing::{Level, debug};
+use utoipa::OpenApi;
 
 use crate::api::state::AppState;
 
@@ -8,9 +9,20 @@ pub fn create_route() -> Router<AppState> {
     Router::new().route("/robots.txt", get(get_robots))
 }
 
+/// OpenAPI description of the robots.txt route
+#[derive(OpenApi)]
+#[openapi(paths(get_robots))]
+pub struct ApiDoc;
+
 /// Handler for GET /robots.txt
 ///
 /// Returns a robots.txt that disallows all crawling.
+#[utoipa::path(
+    get,
+    path = "/robots.txt",
+    tag = "misc",
+    responses((status = 200, description = "Disallows all crawling", body = String, content_type = "text/plain"))
+)]
 #[tracing::instrument(level = Level::DEBUG)]
 async fn get_robots() -> &'static str {
     debug!("Robots.txt requested");
@@ -0,0 +1,152 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, header},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use serde::Serialize;
+use tracing::debug;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        state::AppState,
+    },
+    model::Progress,
+};
+
+/// Create the public share link route
+pub fn create_route() -> Router<AppState> {
+    Router::new().route("/share/{token}", get(view_share))
+}
+
+/// OpenAPI description of the share view routes
+#[derive(OpenApi)]
+#[openapi(paths(view_share))]
+pub struct ApiDoc;
+
+/// Public view of shared progress
+///
+/// The progress fields are omitted when the user has not synchronized the document yet.
+#[derive(Serialize, ToSchema)]
+struct SharedProgressResponse {
+    document: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percentage: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<u64>,
+}
+
+/// Handler for GET /share/{token}
+///
+/// Renders the current progress behind a share link, as a minimal HTML page when the
+/// client accepts `text/html` and as JSON otherwise. Unknown, revoked and expired links
+/// all return 404.
+#[utoipa::path(
+    get,
+    path = "/share/{token}",
+    tag = "shares",
+    params(("token" = String, Path, description = "Share token")),
+    responses(
+        (status = 200, description = "Progress behind the link", content(
+            (SharedProgressResponse = "application/json"),
+            (String = "text/html"),
+        )),
+        (status = 404, description = "The link is unknown, revoked or expired", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, headers))]
+async fn view_share(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    WithRejection(Path(token), _): WithRejection<Path<String>, ApiError>,
+) -> Result<Response, ApiError> {
+    let now = Utc::now().timestamp_millis() as u64;
+    let share = match state.sync.get_share(token)? {
+        Some(share) if !share.is_expired(now) => share,
+        _ => return Err(ApiError::NotFound("Share not found".to_string())),
+    };
+
+    debug!("Serving shared progress");
+    let progress = state
+        .sync
+        .get_progress(share.user, share.document.clone())?;
+    let response = SharedProgressResponse::new(share.document, progress);
+
+    if accepts_html(&headers) {
+        Ok(Html(response.to_html()).into_response())
+    } else {
+        Ok(Json(response).into_response())
+    }
+}
+
+fn accepts_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"))
+}
+
+impl SharedProgressResponse {
+    fn new(document: String, progress: Option<Progress>) -> Self {
+        match progress {
+            Some(progress) => Self {
+                document,
+                percentage: Some(progress.percentage),
+                device: Some(progress.device),
+                timestamp: Some(progress.timestamp),
+            },
+            None => Self {
+                document,
+                percentage: None,
+                device: None,
+                timestamp: None,
+            },
+        }
+    }
+
+    fn to_html(&self) -> String {
+        let document = escape_html(&self.document);
+        let body = match (self.percentage, &self.device, self.timestamp) {
+            (Some(percentage), Some(device), Some(timestamp)) => {
+                let updated = chrono::DateTime::from_timestamp_millis(timestamp as i64)
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_default();
+                format!(
+                    "<p><strong>{:.0}%</strong> read</p>\n<p>Device: {}</p>\n<p>Last update: {}</p>",
+                    percentage * 100.0,
+                    escape_html(device),
+                    updated
+                )
+            }
+            _ => "<p>No progress yet</p>".to_string(),
+        };
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"robots\" content=\"noindex\">\n<title>{document}</title>\n</head>\n\
+             <body>\n<h1>{document}</h1>\n{body}\n</body>\n</html>\n"
+        )
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,161 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, post},
+};
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+        validation::Validator,
+    },
+    model::Share,
+};
+
+/// Create the share link management routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route("/shares", post(create_share).get(list_shares))
+        .route("/shares/{token}", delete(revoke_share))
+}
+
+/// OpenAPI description of the shares routes
+#[derive(OpenApi)]
+#[openapi(paths(create_share, list_shares, revoke_share))]
+pub struct ApiDoc;
+
+/// Request body for creating a share link
+#[derive(Deserialize, Debug, ToSchema)]
+struct CreateShareRequest {
+    document: String,
+    /// Lifetime of the link in seconds; the link never expires when omitted
+    expires_in: Option<u64>,
+}
+
+impl CreateShareRequest {
+    fn validate(&self, state: &AppState) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        validator.not_empty("document", &self.document).max_length(
+            "document",
+            &self.document,
+            state.validation.max_document_length,
+        );
+        if let Some(expires_in) = self.expires_in {
+            validator.positive("expires_in", expires_in);
+        }
+        validator.finish("Invalid share payload")
+    }
+}
+
+/// Response for a share link
+#[derive(Serialize, ToSchema)]
+struct ShareResponse {
+    token: String,
+    document: String,
+    url: String,
+    created_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+}
+
+/// Handler for POST /shares
+///
+/// Creates a public, read-only link to the authenticated user's progress on a document
+#[utoipa::path(
+    post,
+    path = "/shares",
+    tag = "shares",
+    request_body = CreateShareRequest,
+    responses(
+        (status = 201, description = "Share link created", body = ShareResponse),
+        (status = 400, description = "Invalid document or zero expiry", body = ApiErrorPayload),
+        (status = 507, description = "The share would exceed the user's storage quota", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn create_share(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateShareRequest>, ApiError>,
+) -> Result<impl IntoResponse, ApiError> {
+    payload.validate(&state)?;
+
+    let expires_at = payload.expires_in.map(|secs| {
+        (Utc::now().timestamp_millis() as u64).saturating_add(secs.saturating_mul(1000))
+    });
+
+    info!("Creating share link");
+    let share = state.sync.create_share(
+        Share::new(user, payload.document, expires_at),
+        state.quotas.default_quota(),
+    )?;
+
+    Ok((StatusCode::CREATED, Json(ShareResponse::from(share))))
+}
+
+/// Handler for GET /shares
+///
+/// Lists the share links created by the authenticated user, including expired ones
+#[utoipa::path(
+    get,
+    path = "/shares",
+    tag = "shares",
+    responses((status = 200, description = "Share links of the user, including expired ones", body = Vec<ShareResponse>))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_shares(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<Vec<ShareResponse>>, ApiError> {
+    let shares = state.sync.list_shares(user)?;
+
+    Ok(Json(shares.into_iter().map(Into::into).collect()))
+}
+
+/// Handler for DELETE /shares/{token}
+///
+/// Revokes a share link; the public URL stops working immediately
+#[utoipa::path(
+    delete,
+    path = "/shares/{token}",
+    tag = "shares",
+    params(("token" = String, Path, description = "Share token")),
+    responses(
+        (status = 204, description = "Share link revoked"),
+        (status = 404, description = "The share link does not exist", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn revoke_share(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(token), _): WithRejection<Path<String>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    if !state.sync.revoke_share(user, token)? {
+        return Err(ApiError::NotFound("Share not found".to_string()));
+    }
+
+    info!("Share link revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+impl From<Share> for ShareResponse {
+    fn from(value: Share) -> Self {
+        Self {
+            url: format!("/share/{}", value.token),
+            token: value.token,
+            document: value.document,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+        }
+    }
+}
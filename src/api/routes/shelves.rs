@@ -0,0 +1,160 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    routing::{get, put},
+};
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+    },
+    model::{ReadThrough, Shelf, ShelfEntry},
+};
+
+/// Create the reading shelves routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route("/shelves", get(list_shelves))
+        .route("/shelves/{doc}", put(set_shelf))
+}
+
+/// OpenAPI description of the shelves routes
+#[derive(OpenApi)]
+#[openapi(paths(list_shelves, set_shelf))]
+pub struct ApiDoc;
+
+/// Request body for moving a document to a shelf
+#[derive(Deserialize, Debug, ToSchema)]
+struct SetShelfRequest {
+    /// `reading`, `finished` or `abandoned`
+    shelf: String,
+}
+
+/// Documents of the authenticated user, grouped by shelf
+#[derive(Serialize, Default, ToSchema)]
+struct ShelvesResponse {
+    reading: Vec<ShelfEntryResponse>,
+    finished: Vec<ShelfEntryResponse>,
+    abandoned: Vec<ShelfEntryResponse>,
+}
+
+/// A shelved document
+#[derive(Serialize, ToSchema)]
+struct ShelfEntryResponse {
+    document: String,
+    shelf: String,
+    /// When the document was last finished, even if it is being read again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished_at: Option<u64>,
+    times_finished: usize,
+    updated_at: u64,
+    /// Every read-through of the document, oldest first
+    reads: Vec<ReadThroughResponse>,
+}
+
+/// A read-through of a document
+#[derive(Serialize, ToSchema)]
+struct ReadThroughResponse {
+    shelf: String,
+    started_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ended_at: Option<u64>,
+}
+
+/// Handler for GET /shelves
+///
+/// Lists the documents of the authenticated user on the reading, finished and abandoned
+/// shelves, with each read-through of them
+#[utoipa::path(
+    get,
+    path = "/shelves",
+    tag = "shelves",
+    responses((status = 200, description = "Documents grouped by shelf", body = ShelvesResponse))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn list_shelves(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<ShelvesResponse>, ApiError> {
+    info!("Listing shelves");
+
+    let mut shelves = ShelvesResponse::default();
+    for (document, entry) in state.sync.list_shelves(user)? {
+        let shelf = match entry.shelf() {
+            Shelf::Reading => &mut shelves.reading,
+            Shelf::Finished => &mut shelves.finished,
+            Shelf::Abandoned => &mut shelves.abandoned,
+        };
+        shelf.push(ShelfEntryResponse::new(document, entry));
+    }
+
+    Ok(Json(shelves))
+}
+
+/// Handler for PUT /shelves/{doc}
+///
+/// Moves a document of the authenticated user to a shelf, e.g. to mark it finished before
+/// reaching the end or to abandon it. Moving a finished document elsewhere starts a new
+/// read-through, keeping the finished one.
+#[utoipa::path(
+    put,
+    path = "/shelves/{doc}",
+    tag = "shelves",
+    params(("doc" = String, Path, description = "Document identifier")),
+    request_body = SetShelfRequest,
+    responses(
+        (status = 200, description = "Shelf entry of the document", body = ShelfEntryResponse),
+        (status = 400, description = "Unknown shelf", body = ApiErrorPayload),
+        (status = 507, description = "The change would exceed the user's storage quota", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn set_shelf(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetShelfRequest>, ApiError>,
+) -> Result<Json<ShelfEntryResponse>, ApiError> {
+    let shelf: Shelf = payload.shelf.parse().map_err(ApiError::invalid_input)?;
+
+    info!("Moving doc {} to the {} shelf", doc, shelf);
+    let entry = state.sync.set_shelf(
+        user,
+        doc.clone(),
+        shelf,
+        Utc::now().timestamp_millis() as u64,
+        state.quotas.default_quota(),
+    )?;
+
+    Ok(Json(ShelfEntryResponse::new(doc, entry)))
+}
+
+impl ShelfEntryResponse {
+    fn new(document: String, entry: ShelfEntry) -> Self {
+        Self {
+            document,
+            shelf: entry.shelf().to_string(),
+            finished_at: entry.finished_at(),
+            times_finished: entry.times_finished(),
+            updated_at: entry.updated_at,
+            reads: entry.reads.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ReadThrough> for ReadThroughResponse {
+    fn from(value: ReadThrough) -> Self {
+        Self {
+            shelf: value.shelf.to_string(),
+            started_at: value.started_at,
+            ended_at: value.ended_at,
+        }
+    }
+}
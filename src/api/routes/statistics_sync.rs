@@ -0,0 +1,312 @@
+//! WebDAV endpoint for KOReader's statistics synchronization.
+//!
+//! KOReader's statistics plugin syncs `statistics.sqlite3` through a WebDAV server: it
+//! downloads the remote copy, merges it into its local database and uploads the result.
+//! This module exposes the minimal subset of WebDAV its client needs, under `/dav`, with
+//! one folder per user containing a single `statistics.sqlite3` file.
+//!
+//! Uploads are not stored blindly. When the client proves, with `If-Match`, that it merged
+//! against the latest stored copy, the upload replaces it (so books deleted on the device
+//! are deleted on the server too). Otherwise it is merged into the stored copy the same
+//! way KOReader does, so concurrent uploads from several devices never lose history.
+//! Uploads are received into a temporary file, up to the configured
+//! [`max_statistics_size`](crate::config::Validation::max_statistics_size), and stored
+//! within the user's quota.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use axum::{
+    Extension, Router,
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::any,
+};
+use axum_extra::extract::WithRejection;
+use http_body_util::BodyExt;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info};
+
+use crate::{
+    api::{error::ApiError, middleware::auth::AuthenticatedUser, state::AppState},
+    model::{Quota, StatisticsFile},
+    service::{
+        db::KorrosyncService,
+        statistics::{StatisticsError, Upload},
+    },
+};
+
+/// Name of the file KOReader's statistics plugin syncs
+pub const STATISTICS_FILE: &str = "statistics.sqlite3";
+
+const COLLECTION_ALLOW: &str = "OPTIONS, PROPFIND";
+const FILE_ALLOW: &str = "OPTIONS, PROPFIND, GET, HEAD, PUT";
+
+/// Upload lock of each user, so two devices merging at the same time don't overwrite each
+/// other while the uploads of different users run in parallel
+static UPLOAD_LOCKS: LazyLock<Mutex<HashMap<String, Arc<Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+/// Create the statistics sync routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route("/dav", any(collection))
+        .route("/dav/", any(collection))
+        .route("/dav/{file}", any(file))
+}
+
+/// Handler for the `/dav` folder
+///
+/// Answers `OPTIONS` and `PROPFIND`, listing the statistics file when it exists.
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, headers))]
+async fn collection(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    match method.as_str() {
+        "OPTIONS" => Ok(options(COLLECTION_ALLOW)),
+        "PROPFIND" => {
+            let mut entries = vec![collection_entry()];
+            if headers.get("depth").is_none_or(|depth| depth != "0")
+                && let Some(stored) = state.sync.get_statistics_file(user)?
+            {
+                entries.push(file_entry(&stored));
+            }
+            Ok(multistatus(&entries))
+        }
+        _ => Ok(method_not_allowed(COLLECTION_ALLOW)),
+    }
+}
+
+/// Handler for `/dav/{file}`
+///
+/// Only `statistics.sqlite3` exists. It can be downloaded (`GET`), described
+/// (`PROPFIND`) and uploaded (`PUT`), in which case it is merged into the stored copy.
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, headers, body))]
+async fn file(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    method: Method,
+    WithRejection(Path(file), _): WithRejection<Path<String>, ApiError>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, ApiError> {
+    if file != STATISTICS_FILE {
+        return Err(ApiError::NotFound("File not found".to_string()));
+    }
+
+    match method.as_str() {
+        "OPTIONS" => Ok(options(FILE_ALLOW)),
+        "GET" => {
+            info!("Downloading statistics");
+            let (stored, data) = state
+                .sync
+                .get_statistics(user)?
+                .ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+
+            Ok((file_headers(&stored), Body::from(data)).into_response())
+        }
+        "HEAD" => {
+            let stored = state
+                .sync
+                .get_statistics_file(user)?
+                .ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+
+            Ok((
+                file_headers(&stored),
+                [(header::CONTENT_LENGTH, HeaderValue::from(stored.size))],
+            )
+                .into_response())
+        }
+        "PROPFIND" => {
+            let stored = state
+                .sync
+                .get_statistics_file(user)?
+                .ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+            Ok(multistatus(&[file_entry(&stored)]))
+        }
+        "PUT" => {
+            info!("Uploading statistics");
+            let if_match = headers
+                .get(header::IF_MATCH)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            let upload =
+                receive_upload(&headers, body, state.validation.max_statistics_size).await?;
+
+            let sync = state.sync.clone();
+            let quota = state.quotas.default_quota();
+            let lock = upload_lock(&user);
+            let (status, stored) = tokio::task::spawn_blocking(move || {
+                let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+                store_upload(sync.as_ref(), user, upload, if_match, quota)
+            })
+            .await
+            .map_err(ApiError::runtime)??;
+
+            Ok((status, [(header::ETAG, etag_header(&stored))]).into_response())
+        }
+        _ => Ok(method_not_allowed(FILE_ALLOW)),
+    }
+}
+
+/// Returns the upload lock of a user, dropping the locks no upload holds anymore
+fn upload_lock(user: &str) -> Arc<Mutex<()>> {
+    let mut locks = UPLOAD_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+    locks.entry(user.to_string()).or_default().clone()
+}
+
+/// Receives the body of an upload into a temporary file, rejecting it as soon as it is
+/// larger than `limit` bytes
+async fn receive_upload(headers: &HeaderMap, body: Body, limit: usize) -> Result<Upload, ApiError> {
+    let too_large =
+        || ApiError::PayloadTooLarge(format!("Statistics database exceeds {limit} bytes"));
+    let announced = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if announced.is_some_and(|length| length > limit) {
+        return Err(too_large());
+    }
+
+    let upload = Upload::new().map_err(ApiError::runtime)?;
+    let mut file = tokio::fs::File::create(upload.path())
+        .await
+        .map_err(ApiError::runtime)?;
+    let mut body = body;
+    let mut size = 0;
+    while let Some(frame) = body.frame().await {
+        let Ok(chunk) = frame.map_err(ApiError::runtime)?.into_data() else {
+            continue;
+        };
+        size += chunk.len();
+        if size > limit {
+            return Err(too_large());
+        }
+        file.write_all(&chunk).await.map_err(ApiError::runtime)?;
+    }
+    file.flush().await.map_err(ApiError::runtime)?;
+
+    Ok(upload)
+}
+
+/// Validates an upload and stores it, merged into the current copy unless `If-Match`
+/// shows the client already merged against it, within the user's quota (falling back to
+/// `default_quota`). Returns the status and the stored file.
+fn store_upload(
+    sync: &(dyn KorrosyncService + Send + Sync),
+    user: String,
+    upload: Upload,
+    if_match: Option<String>,
+    default_quota: Quota,
+) -> Result<(StatusCode, StatisticsFile), ApiError> {
+    let (status, data) = match sync.get_statistics_file(user.clone())? {
+        None => {
+            upload.validate().map_err(statistics_error)?;
+            (
+                StatusCode::CREATED,
+                upload.read().map_err(ApiError::runtime)?,
+            )
+        }
+        Some(current) if if_match.as_deref() == Some(etag(&current).as_str()) => {
+            debug!("Upload is based on the stored copy, replacing it");
+            upload.validate().map_err(statistics_error)?;
+            (
+                StatusCode::NO_CONTENT,
+                upload.read().map_err(ApiError::runtime)?,
+            )
+        }
+        Some(_) => {
+            debug!("Merging upload into the stored copy");
+            let (_, current) = sync
+                .get_statistics(user.clone())?
+                .ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+            let merged = upload.merge_into(&current).map_err(statistics_error)?;
+            (StatusCode::NO_CONTENT, merged)
+        }
+    };
+
+    Ok((status, sync.put_statistics(user, data, default_quota)?))
+}
+
+fn statistics_error(e: StatisticsError) -> ApiError {
+    match e {
+        StatisticsError::Invalid(message) => ApiError::invalid_input(message),
+        e => ApiError::runtime(e),
+    }
+}
+
+/// Strong entity tag of a stored file, from the digest of its content
+fn etag(stored: &StatisticsFile) -> String {
+    format!("\"{}\"", stored.md5)
+}
+
+fn etag_header(stored: &StatisticsFile) -> HeaderValue {
+    HeaderValue::from_str(&etag(stored)).expect("Hex digests are valid header values")
+}
+
+fn file_headers(stored: &StatisticsFile) -> [(header::HeaderName, HeaderValue); 2] {
+    [
+        (
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-sqlite3"),
+        ),
+        (header::ETAG, etag_header(stored)),
+    ]
+}
+
+fn options(allow: &'static str) -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::ALLOW, allow),
+            (header::HeaderName::from_static("dav"), "1"),
+        ],
+    )
+        .into_response()
+}
+
+fn method_not_allowed(allow: &'static str) -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, allow)]).into_response()
+}
+
+fn collection_entry() -> String {
+    "<d:response><d:href>/dav/</d:href><d:propstat><d:prop>\
+     <d:displayname></d:displayname><d:resourcetype><d:collection/></d:resourcetype>\
+     </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"
+        .to_string()
+}
+
+fn file_entry(stored: &StatisticsFile) -> String {
+    format!(
+        "<d:response><d:href>/dav/{STATISTICS_FILE}</d:href><d:propstat><d:prop>\
+         <d:displayname>{STATISTICS_FILE}</d:displayname><d:resourcetype/>\
+         <d:getcontenttype>application/x-sqlite3</d:getcontenttype>\
+         <d:getcontentlength>{}</d:getcontentlength><d:getetag>{}</d:getetag>\
+         </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        stored.size,
+        etag(stored).replace('"', "&quot;"),
+    )
+}
+
+fn multistatus(entries: &[String]) -> Response {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <d:multistatus xmlns:d=\"DAV:\">{}</d:multistatus>",
+        entries.concat()
+    );
+
+    (
+        StatusCode::MULTI_STATUS,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
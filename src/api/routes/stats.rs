@@ -0,0 +1,94 @@
+use axum::{Extension, Json, Router, extract::State, routing::get};
+use serde::Serialize;
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{error::ApiError, middleware::auth::AuthenticatedUser, state::AppState},
+    model::{ReadingSession, ReadingStats},
+};
+
+/// Create the reading statistics routes
+pub fn create_route() -> Router<AppState> {
+    Router::new().route("/stats", get(get_stats))
+}
+
+/// OpenAPI description of the stats routes
+#[derive(OpenApi)]
+#[openapi(paths(get_stats))]
+pub struct ApiDoc;
+
+/// Reading statistics of the authenticated user
+#[derive(Serialize, ToSchema)]
+struct StatsResponse {
+    total_reading_secs: u64,
+    /// Average percentage points read per hour
+    average_pace: Option<f32>,
+    finished_per_month: Vec<MonthlyFinishedResponse>,
+    sessions: Vec<SessionResponse>,
+}
+
+/// Number of documents finished in a month
+#[derive(Serialize, ToSchema)]
+struct MonthlyFinishedResponse {
+    month: String,
+    count: usize,
+}
+
+/// A reading session
+#[derive(Serialize, ToSchema)]
+struct SessionResponse {
+    document: String,
+    device_id: String,
+    device: String,
+    start: u64,
+    end: u64,
+    duration_secs: u64,
+    percentage_points: f32,
+}
+
+/// Handler for GET /stats
+///
+/// Returns the reading sessions, finished documents per month and average pace of the
+/// authenticated user, derived from the history of their progress updates
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "stats",
+    responses((status = 200, description = "Reading statistics of the user", body = StatsResponse))
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn get_stats(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+) -> Result<Json<StatsResponse>, ApiError> {
+    info!("Getting reading statistics");
+
+    let history = state.sync.list_progress_history(user)?;
+    let stats = ReadingStats::from_history(&history);
+
+    Ok(Json(StatsResponse {
+        total_reading_secs: stats.total_reading_ms() / 1000,
+        average_pace: stats.average_pace(),
+        finished_per_month: stats
+            .finished_per_month()
+            .into_iter()
+            .map(|(month, count)| MonthlyFinishedResponse { month, count })
+            .collect(),
+        sessions: stats.sessions.into_iter().map(Into::into).collect(),
+    }))
+}
+
+impl From<ReadingSession> for SessionResponse {
+    fn from(value: ReadingSession) -> Self {
+        Self {
+            duration_secs: value.duration_ms() / 1000,
+            percentage_points: value.percentage_points(),
+            document: value.document,
+            device_id: value.device_id,
+            device: value.device,
+            start: value.start,
+            end: value.end,
+        }
+    }
+}
@@ -1,38 +1,122 @@
 use axum::{
     Extension, Json, Router,
     extract::{Path, State},
-    response::IntoResponse,
-    routing::{get, put},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{AppendHeaders, IntoResponse, Response},
+    routing::{get, post, put},
 };
 use axum_extra::extract::WithRejection;
+use chrono::DateTime;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use tracing::{debug, info};
+use utoipa::{OpenApi, ToSchema};
 
 use crate::{
-    api::{error::ApiError, middleware::auth::AuthenticatedUser, state::AppState},
+    api::{
+        error::{ApiError, ApiErrorPayload, FieldError},
+        extract::ClientIp,
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+        validation::Validator,
+    },
+    config::Validation,
     model::Progress,
+    service::db::SyncContext,
 };
 
+/// Header a client may use to report when a position was reached, in Unix milliseconds
+const CLIENT_TIMESTAMP_HEADER: &str = "x-client-timestamp";
+
 /// Create the syncs progress routes
 pub fn create_route() -> Router<AppState> {
     Router::new()
         .route("/syncs/progress", put(update_progress))
-        .route("/syncs/progress/{doc}", get(get_progress))
+        .route(
+            "/syncs/progress/batch",
+            post(get_progress_batch).put(update_progress_batch),
+        )
+        .route(
+            "/syncs/progress/{doc}",
+            get(get_progress).delete(delete_progress),
+        )
+        .route("/syncs/progress/{doc}/devices", get(get_device_progress))
 }
 
+/// OpenAPI description of the syncs progress routes
+#[derive(OpenApi)]
+#[openapi(paths(
+    update_progress,
+    get_progress,
+    delete_progress,
+    get_progress_batch,
+    update_progress_batch,
+    get_device_progress
+))]
+pub struct ApiDoc;
+
 /// Request body for updating sync progress
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone, ToSchema)]
 struct UpdateProgressRequest {
     pub device_id: String,
     pub device: String,
     pub document: String,
     pub percentage: f32,
     pub progress: String,
+    /// Unix timestamp in milliseconds when the client reached this position
+    pub timestamp: Option<u64>,
+}
+
+impl UpdateProgressRequest {
+    fn validate(&self, state: &AppState) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        self.check(&mut validator, &state.validation, "");
+        validator.finish("Invalid progress payload")
+    }
+
+    /// Adds the rules of a progress update to `validator`, prefixing field names with `prefix`
+    fn check(&self, validator: &mut Validator, limits: &Validation, prefix: &str) {
+        let field = |name: &str| format!("{prefix}{name}");
+        validator
+            .not_empty(&field("document"), &self.document)
+            .max_length(
+                &field("document"),
+                &self.document,
+                limits.max_document_length,
+            )
+            .not_empty(&field("device_id"), &self.device_id)
+            .max_length(
+                &field("device_id"),
+                &self.device_id,
+                limits.max_field_length,
+            )
+            .max_length(&field("device"), &self.device, limits.max_field_length)
+            .max_length(&field("progress"), &self.progress, limits.max_field_length)
+            .percentage(&field("percentage"), self.percentage);
+    }
+}
+
+/// Request body for retrieving the progress of several documents
+#[derive(Debug, Deserialize, ToSchema)]
+struct BatchProgressRequest {
+    pub documents: Vec<String>,
+}
+
+/// Request body for updating the progress of several documents, e.g. when a device
+/// catches up after reading offline
+#[derive(Debug, Deserialize, ToSchema)]
+struct BatchUpdateProgressRequest {
+    pub progress: Vec<UpdateProgressRequest>,
+}
+
+/// Response item for a stored progress update
+#[derive(Serialize, ToSchema)]
+struct UpdatedProgressResponse {
+    pub document: String,
+    pub timestamp: u64,
 }
 
 /// Response for sync progress
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ProgressResponse {
     pub device_id: String,
     pub device: String,
@@ -40,54 +124,349 @@ struct ProgressResponse {
     pub percentage: f32,
     pub progress: String,
     pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_timestamp: Option<u64>,
+}
+
+/// Response item for the last position of a device
+#[derive(Serialize, ToSchema)]
+struct DevicePositionResponse {
+    pub device_id: String,
+    pub device: String,
+    pub name: String,
+    pub percentage: f32,
+    pub progress: String,
+    pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_timestamp: Option<u64>,
 }
 
 /// Handler for PUT /syncs/progress
 ///
-/// Updates the synchronization progress for a document
-#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+/// Updates the synchronization progress for a document, records the reporting device in
+/// the user's device registry and moves the document between reading shelves (see
+/// [`sync_context`]), all in one transaction. Payloads that break the configured validation
+/// limits are rejected with the list of offending fields.
+///
+/// Clients may report when the position was reached, either with the `timestamp` field
+/// or the `X-Client-Timestamp` header. It is stored next to the time the server received
+/// the update, but only when it is within the configured clock skew.
+///
+/// Updates that would grow the user's storage past their quota are rejected with 507
+/// Insufficient Storage (see [`sync_context`]).
+///
+/// With `If-Match`, the update is only stored if the current progress still has one of the
+/// given entity tags (or exists at all, for `*`); otherwise 412 Precondition Failed is
+/// returned. The response carries the `ETag` of the stored progress.
+#[utoipa::path(
+    put,
+    path = "/syncs/progress",
+    tag = "progress",
+    params(
+        ("If-Match" = Option<String>, Header, description = "Only store the update if the current progress has one of these entity tags"),
+        ("X-Client-Timestamp" = Option<u64>, Header, description = "When the position was reached, in Unix milliseconds"),
+    ),
+    request_body = UpdateProgressRequest,
+    responses(
+        (status = 200, description = "Progress stored", body = UpdatedProgressResponse,
+            headers(("ETag" = String, description = "Entity tag of the stored progress"))),
+        (status = 400, description = "Invalid progress payload", body = ApiErrorPayload),
+        (status = 412, description = "The progress changed since it was retrieved", body = ApiErrorPayload),
+        (status = 507, description = "The update would exceed the user's storage quota", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, headers))]
 async fn update_progress(
     State(state): State<AppState>,
     Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     WithRejection(Json(payload), _): WithRejection<Json<UpdateProgressRequest>, ApiError>,
 ) -> Result<impl IntoResponse, ApiError> {
     debug!("Updating sync progress");
 
+    payload.validate(&state)?;
+    let reported = match payload.timestamp {
+        Some(timestamp) => Some(timestamp),
+        None => header_timestamp(&headers)?,
+    };
+
+    let mut progress: Progress = payload.clone().into();
+    progress.client_timestamp = trusted_timestamp(reported, progress.timestamp, &state.validation);
+
+    let tag = etag(&progress);
+    let if_match = header_value(&headers, header::IF_MATCH);
+    let condition = |current: Option<&Progress>| {
+        if_match.as_ref().is_none_or(|if_match| {
+            current.is_some_and(|current| etag_matches(if_match, &etag(current), false))
+        })
+    };
     let (doc, ts) = state
         .sync
-        .update_progress(user, payload.document.clone(), payload.into())?;
+        .sync_progress(
+            user,
+            vec![(payload.document, progress)],
+            &sync_context(&state, ip),
+            Some(&condition),
+        )?
+        .and_then(|stored| stored.into_iter().next())
+        .ok_or_else(|| {
+            ApiError::PreconditionFailed("Progress changed since it was retrieved".to_string())
+        })?;
 
-    Ok(Json(json!({
-        "document": doc,
-        "timestamp": ts,
-    }))
-    .into_response())
+    Ok((
+        [(header::ETAG, to_header(tag))],
+        Json(UpdatedProgressResponse {
+            document: doc,
+            timestamp: ts,
+        }),
+    )
+        .into_response())
 }
 
 /// Handler for GET /syncs/progress/{doc}
 ///
-/// Returns the synchronization progress for a specific document
-#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+/// Returns the synchronization progress for a specific document, with `ETag` and
+/// `Last-Modified` headers derived from when it was stored. Conditional requests with
+/// `If-None-Match` or `If-Modified-Since` get 304 Not Modified when nothing changed, so
+/// polling clients don't need to download the progress again.
+#[utoipa::path(
+    get,
+    path = "/syncs/progress/{doc}",
+    tag = "progress",
+    params(
+        ("doc" = String, Path, description = "Document identifier"),
+        ("If-None-Match" = Option<String>, Header, description = "Entity tags of a progress the client already has"),
+        ("If-Modified-Since" = Option<String>, Header, description = "HTTP date of a progress the client already has"),
+    ),
+    responses(
+        (status = 200, description = "Progress of the document, or an empty object if there is none", body = ProgressResponse,
+            headers(
+                ("ETag" = String, description = "Entity tag of the progress"),
+                ("Last-Modified" = String, description = "When the progress was stored"),
+            )),
+        (status = 304, description = "The progress did not change"),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, headers))]
 async fn get_progress(
     State(state): State<AppState>,
     Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    headers: HeaderMap,
     WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
-) -> Result<impl IntoResponse, ApiError> {
+) -> Result<Response, ApiError> {
     info!("Getting sync progress for doc: {}", doc);
 
     let progress = state.sync.get_progress(user, doc.clone());
 
     match progress {
-        Ok(Some(progress)) => Ok(Json(ProgressResponse {
-            document: doc,
-            ..progress.into()
-        })
-        .into_response()),
-        Ok(None) => Ok(Json(json!({})).into_response()),
+        Ok(Some(progress)) => {
+            let mut validators = vec![(header::ETAG, to_header(etag(&progress)))];
+            if let Some(modified) = http_date(progress.timestamp) {
+                validators.push((header::LAST_MODIFIED, to_header(modified)));
+            }
+            if is_not_modified(&headers, &progress) {
+                return Ok((StatusCode::NOT_MODIFIED, AppendHeaders(validators)).into_response());
+            }
+
+            Ok((
+                AppendHeaders(validators),
+                Json(ProgressResponse {
+                    document: doc,
+                    ..progress.into()
+                }),
+            )
+                .into_response())
+        }
+        Ok(None) => Ok(Json(serde_json::json!({})).into_response()),
         Err(e) => Err(e.into()),
     }
 }
 
+/// Handler for DELETE /syncs/progress/{doc}
+///
+/// Deletes the progress of a document, including the position of each device and its
+/// reading history
+#[utoipa::path(
+    delete,
+    path = "/syncs/progress/{doc}",
+    tag = "progress",
+    params(("doc" = String, Path, description = "Document identifier")),
+    responses(
+        (status = 204, description = "Progress deleted"),
+        (status = 404, description = "The document has no progress", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn delete_progress(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    info!("Deleting sync progress for doc: {}", doc);
+
+    if state.sync.delete_progress(user, doc)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound("Progress not found".to_string()))
+    }
+}
+
+/// Handler for POST /syncs/progress/batch
+///
+/// Returns the progress of every requested document that has some, in request order, so a
+/// device can refresh its whole library in a single request. Documents without progress
+/// are left out.
+#[utoipa::path(
+    post,
+    path = "/syncs/progress/batch",
+    tag = "progress",
+    request_body = BatchProgressRequest,
+    responses(
+        (status = 200, description = "Progress of the documents that have some", body = Vec<ProgressResponse>),
+        (status = 400, description = "Invalid progress batch payload", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, payload))]
+async fn get_progress_batch(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Json(payload), _): WithRejection<Json<BatchProgressRequest>, ApiError>,
+) -> Result<Json<Vec<ProgressResponse>>, ApiError> {
+    let limits = &state.validation;
+    let mut validator = Validator::new();
+    validator.item_count("documents", payload.documents.len(), limits.max_batch_size);
+    for (i, document) in payload.documents.iter().enumerate() {
+        validator.max_length(
+            &format!("documents[{i}]"),
+            document,
+            limits.max_document_length,
+        );
+    }
+    validator.finish("Invalid progress batch payload")?;
+
+    info!("Getting sync progress for {} docs", payload.documents.len());
+    let progress = state.sync.get_progress_batch(user, payload.documents)?;
+
+    Ok(Json(
+        progress
+            .into_iter()
+            .map(|(document, progress)| ProgressResponse {
+                document,
+                ..progress.into()
+            })
+            .collect(),
+    ))
+}
+
+/// Handler for PUT /syncs/progress/batch
+///
+/// Stores several progress updates at once, all or nothing. Updates are applied oldest
+/// first, going by their trusted `timestamp` (see [`update_progress`]), so the most recent
+/// position of each document ends up as its progress. Returns the stored updates in the
+/// order they were applied. The whole batch is rejected if it would exceed the user's quota.
+#[utoipa::path(
+    put,
+    path = "/syncs/progress/batch",
+    tag = "progress",
+    request_body = BatchUpdateProgressRequest,
+    responses(
+        (status = 200, description = "Stored updates, in the order they were applied", body = Vec<UpdatedProgressResponse>),
+        (status = 400, description = "Invalid progress batch payload", body = ApiErrorPayload),
+        (status = 507, description = "The updates would exceed the user's storage quota", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, payload))]
+async fn update_progress_batch(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    ClientIp(ip): ClientIp,
+    WithRejection(Json(payload), _): WithRejection<Json<BatchUpdateProgressRequest>, ApiError>,
+) -> Result<Json<Vec<UpdatedProgressResponse>>, ApiError> {
+    let limits = &state.validation;
+    let mut validator = Validator::new();
+    validator.item_count("progress", payload.progress.len(), limits.max_batch_size);
+    for (i, update) in payload.progress.iter().enumerate() {
+        update.check(&mut validator, limits, &format!("progress[{i}]."));
+    }
+    validator.finish("Invalid progress batch payload")?;
+
+    let mut updates: Vec<(String, Progress)> = payload
+        .progress
+        .into_iter()
+        .map(|update| {
+            let reported = update.timestamp;
+            let document = update.document.clone();
+            let mut progress: Progress = update.into();
+            progress.client_timestamp = trusted_timestamp(reported, progress.timestamp, limits);
+            (document, progress)
+        })
+        .collect();
+    updates.sort_by_key(|(_, progress)| progress.reported_at());
+
+    info!("Updating sync progress for {} docs", updates.len());
+    let stored = state
+        .sync
+        .sync_progress(user, updates, &sync_context(&state, ip), None)?
+        .unwrap_or_default();
+
+    Ok(Json(
+        stored
+            .into_iter()
+            .map(|(document, timestamp)| UpdatedProgressResponse {
+                document,
+                timestamp,
+            })
+            .collect(),
+    ))
+}
+
+/// Handler for GET /syncs/progress/{doc}/devices
+///
+/// Returns the last position reported by each device for a specific document, most
+/// recent first, so the user can pick which one to jump to
+#[utoipa::path(
+    get,
+    path = "/syncs/progress/{doc}/devices",
+    tag = "progress",
+    params(("doc" = String, Path, description = "Document identifier")),
+    responses(
+        (status = 200, description = "Last position of each device, most recent first", body = Vec<DevicePositionResponse>),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state))]
+async fn get_device_progress(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(user, _)): Extension<AuthenticatedUser>,
+    WithRejection(Path(doc), _): WithRejection<Path<String>, ApiError>,
+) -> Result<Json<Vec<DevicePositionResponse>>, ApiError> {
+    info!("Getting device positions for doc: {}", doc);
+
+    let positions = state.sync.list_device_progress(user.clone(), doc)?;
+    let devices = state.sync.list_devices(user)?;
+
+    Ok(Json(
+        positions
+            .into_iter()
+            .map(|progress| {
+                let name = devices
+                    .iter()
+                    .find(|d| d.device_id == progress.device_id)
+                    .map_or_else(|| progress.device.clone(), |d| d.display_name().to_string());
+                DevicePositionResponse {
+                    device_id: progress.device_id,
+                    device: progress.device,
+                    name,
+                    percentage: progress.percentage,
+                    progress: progress.progress,
+                    timestamp: progress.timestamp,
+                    client_timestamp: progress.client_timestamp,
+                }
+            })
+            .collect(),
+    ))
+}
+
 impl From<UpdateProgressRequest> for Progress {
     fn from(value: UpdateProgressRequest) -> Self {
         Self {
@@ -96,6 +475,7 @@ impl From<UpdateProgressRequest> for Progress {
             percentage: value.percentage,
             progress: value.progress,
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            client_timestamp: None,
         }
     }
 }
@@ -109,6 +489,108 @@ impl From<Progress> for ProgressResponse {
             percentage: value.percentage,
             progress: value.progress,
             timestamp: value.timestamp,
+            client_timestamp: value.client_timestamp,
+        }
+    }
+}
+
+/// Settings of the progress updates of a user, coming from `ip`.
+///
+/// The configured default quota applies to users without one of their own; only updates
+/// growing the document count or the stored bytes past a limit are rejected, see
+/// [`Quota::check`](crate::model::Quota::check). The address is recorded for the reporting
+/// devices. Reaching the configured finished threshold marks a document finished; going
+/// below it again starts a new read-through.
+fn sync_context(state: &AppState, ip: Option<String>) -> SyncContext {
+    SyncContext {
+        default_quota: state.quotas.default_quota(),
+        ip,
+        finished_threshold: Some(state.shelves.finished_threshold),
+    }
+}
+
+/// Strong entity tag of a progress entry, derived from when it was stored
+fn etag(progress: &Progress) -> String {
+    format!("\"{}\"", progress.timestamp)
+}
+
+fn to_header(value: String) -> HeaderValue {
+    HeaderValue::from_str(&value).expect("Entity tags and dates are valid header values")
+}
+
+/// Formats a Unix timestamp in milliseconds as an HTTP date
+fn http_date(timestamp: u64) -> Option<String> {
+    DateTime::from_timestamp_millis(timestamp as i64)
+        .map(|date| date.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+}
+
+fn header_value(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Whether an `If-Match`/`If-None-Match` header value lists `etag`, or is `*`.
+///
+/// Weak comparison ignores the `W/` prefix; strong comparison never matches weak tags.
+fn etag_matches(header: &str, etag: &str, weak: bool) -> bool {
+    header.split(',').map(str::trim).any(|candidate| {
+        if candidate == "*" {
+            return true;
+        }
+        match candidate.strip_prefix("W/") {
+            Some(candidate) => weak && candidate == etag,
+            None => candidate == etag,
         }
+    })
+}
+
+/// Whether a conditional GET can be answered with 304 Not Modified.
+///
+/// `If-None-Match` takes precedence over `If-Modified-Since`, which only has a precision of
+/// seconds.
+fn is_not_modified(headers: &HeaderMap, progress: &Progress) -> bool {
+    if let Some(if_none_match) = header_value(headers, header::IF_NONE_MATCH) {
+        return etag_matches(&if_none_match, &etag(progress), true);
     }
+
+    header_value(headers, header::IF_MODIFIED_SINCE)
+        .and_then(|since| DateTime::parse_from_rfc2822(&since).ok())
+        .is_some_and(|since| (progress.timestamp / 1000) as i64 <= since.timestamp())
+}
+
+/// Parses the client timestamp header, if present.
+fn header_timestamp(headers: &HeaderMap) -> Result<Option<u64>, ApiError> {
+    headers
+        .get(CLIENT_TIMESTAMP_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .ok_or_else(|| {
+                    ApiError::InvalidInput(
+                        "Invalid progress payload".to_string(),
+                        vec![FieldError::new(
+                            CLIENT_TIMESTAMP_HEADER,
+                            "must be a Unix timestamp in milliseconds",
+                        )],
+                    )
+                })
+        })
+        .transpose()
+}
+
+/// Returns the client-reported timestamp if it is within the allowed clock skew of the
+/// time the server received the update.
+fn trusted_timestamp(reported: Option<u64>, received: u64, limits: &Validation) -> Option<u64> {
+    let max_skew = limits.max_clock_skew_secs.saturating_mul(1000);
+    reported.filter(|reported| {
+        let trusted = max_skew > 0 && reported.abs_diff(received) <= max_skew;
+        if !trusted {
+            debug!("Ignoring client timestamp {reported}, server time is {received}");
+        }
+        trusted
+    })
 }
@@ -0,0 +1,145 @@
+use axum::{
+    Extension, Json, Router,
+    extract::State,
+    http::StatusCode,
+    routing::{delete, put},
+};
+use axum_extra::extract::WithRejection;
+use serde::Deserialize;
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiErrorPayload},
+        extract::ClientIp,
+        middleware::auth::AuthenticatedUser,
+        state::AppState,
+    },
+    model::{AuditEvent, AuditKind},
+};
+
+/// Create the self-service account routes
+pub fn create_route() -> Router<AppState> {
+    Router::new()
+        .route("/users/password", put(change_password))
+        .route("/users/me", delete(delete_account))
+}
+
+/// OpenAPI description of the users account routes
+#[derive(OpenApi)]
+#[openapi(paths(change_password, delete_account))]
+pub struct ApiDoc;
+
+/// Request body for changing the authenticated user's password
+///
+/// Both values are the keys as sent by the client in `x-auth-key` (KOReader sends the MD5
+/// hex digest of the password).
+#[derive(Deserialize, Debug, ToSchema)]
+struct ChangePasswordRequest {
+    current_password: String,
+    new_password: String,
+}
+
+/// Request body for deleting the authenticated user's account
+///
+/// `confirm` must repeat the username of the account being deleted.
+#[derive(Deserialize, Debug, ToSchema)]
+struct DeleteAccountRequest {
+    confirm: String,
+}
+
+/// Handler for PUT /users/password
+///
+/// Changes the password of the authenticated user after verifying the current one.
+#[utoipa::path(
+    put,
+    path = "/users/password",
+    tag = "users",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 204, description = "Password changed"),
+        (status = 400, description = "Empty new password", body = ApiErrorPayload),
+        (status = 401, description = "The current password does not match", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, payload))]
+async fn change_password(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(username, _)): Extension<AuthenticatedUser>,
+    ClientIp(ip): ClientIp,
+    WithRejection(Json(payload), _): WithRejection<Json<ChangePasswordRequest>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    if payload.new_password.is_empty() {
+        return Err(ApiError::invalid_input("New password cannot be empty"));
+    }
+
+    let Some(user) = state.sync.get_user(username.clone())? else {
+        return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+    };
+
+    if !user.check(&payload.current_password)? {
+        state.audit(
+            AuditEvent::new(AuditKind::AuthFailure, &username)
+                .with_ip(ip)
+                .with_detail("invalid current password on password change"),
+        );
+        return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    if !state
+        .sync
+        .change_password(username.clone(), payload.new_password)?
+    {
+        return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    state.audit(
+        AuditEvent::new(AuditKind::PasswordChange, &username)
+            .with_ip(ip)
+            .with_detail("self-service"),
+    );
+
+    info!("Password changed for user {username}");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for DELETE /users/me
+///
+/// Deletes the authenticated user and all of their synchronized data.
+#[utoipa::path(
+    delete,
+    path = "/users/me",
+    tag = "users",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 204, description = "Account and all its data deleted"),
+        (status = 400, description = "The confirmation does not match the username", body = ApiErrorPayload),
+    )
+)]
+#[tracing::instrument(level = tracing::Level::DEBUG, skip(state, payload))]
+async fn delete_account(
+    State(state): State<AppState>,
+    Extension(AuthenticatedUser(username, _)): Extension<AuthenticatedUser>,
+    ClientIp(ip): ClientIp,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteAccountRequest>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    if payload.confirm != username {
+        return Err(ApiError::invalid_input(
+            "Confirmation does not match the username",
+        ));
+    }
+
+    state.sync.delete_user(username.clone())?;
+
+    state.audit(
+        AuditEvent::new(AuditKind::UserDeleted, &username)
+            .with_ip(ip)
+            .with_detail("self-service"),
+    );
+
+    info!("Account deleted for user {username}");
+
+    Ok(StatusCode::NO_CONTENT)
+}
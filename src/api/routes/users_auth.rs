@@ -1,6 +1,7 @@
 use axum::{Extension, Json, Router, http::StatusCode, routing::get};
 use serde::Serialize;
 use tracing::info;
+use utoipa::{OpenApi, ToSchema};
 
 use crate::api::{middleware::auth::AuthenticatedUser, state::AppState};
 
@@ -9,11 +10,18 @@ pub fn create_route() -> Router<AppState> {
     Router::new().route("/users/auth", get(get_auth_user))
 }
 
+/// OpenAPI description of the user authentication route
+#[derive(OpenApi)]
+#[openapi(paths(get_auth_user))]
+pub struct ApiDoc;
+
 /// Response for authenticated user information
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct AuthResponse {
+    /// Always `OK`
     authorized: String,
     username: String,
+    /// Unix timestamp in milliseconds of the user's previous activity
     #[serde(skip_serializing_if = "Option::is_none")]
     last_activity: Option<i64>,
 }
@@ -21,6 +29,12 @@ struct AuthResponse {
 /// Handler for GET /users/auth
 ///
 /// Returns authentication status
+#[utoipa::path(
+    get,
+    path = "/users/auth",
+    tag = "users",
+    responses((status = 200, description = "The credentials are valid", body = AuthResponse))
+)]
 #[tracing::instrument(
     skip_all,
     fields(
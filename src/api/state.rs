@@ -1,9 +1,35 @@
 use std::sync::Arc;
 
-use crate::service::db::KorrosyncService;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{Quotas, Shelves, Validation},
+    model::AuditEvent,
+    service::db::KorrosyncService,
+};
 
 /// Application state shared across all routes
 #[derive(Clone)]
 pub struct AppState {
     pub sync: Arc<dyn KorrosyncService + Send + Sync>,
+    /// Limits applied when validating request payloads
+    pub validation: Validation,
+    /// When documents move between reading shelves
+    pub shelves: Shelves,
+    /// Default storage quota of users
+    pub quotas: Quotas,
+    /// Cancelled when the server starts shutting down, to fail readiness probes
+    pub shutdown: CancellationToken,
+}
+
+impl AppState {
+    /// Records an audit event.
+    ///
+    /// Failing to write the audit log must not fail the request that triggered it, so
+    /// errors are logged and otherwise ignored.
+    pub fn audit(&self, event: AuditEvent) {
+        if let Err(e) = self.sync.record_event(event) {
+            tracing::warn!("Failed to record audit event: {e}");
+        }
+    }
 }
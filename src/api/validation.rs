@@ -0,0 +1,93 @@
+//! Request payload validation.
+//!
+//! [`Validator`] collects every rule violation of a payload instead of stopping at the
+//! first one, so clients get a complete list of offending fields in a single
+//! [`ApiError::InvalidInput`] response.
+//!
+//! # Example
+//!
+//! ```
+//! use korrosync::api::validation::Validator;
+//!
+//! let mut validator = Validator::new();
+//! validator
+//!     .not_empty("document", "")
+//!     .percentage("percentage", f32::NAN);
+//!
+//! let err = validator.finish("Invalid progress payload").unwrap_err();
+//! assert_eq!(err.to_string(), "Invalid input: Invalid progress payload");
+//! ```
+
+use crate::api::error::{ApiError, FieldError};
+
+/// Accumulates field-level validation errors
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires the value to contain at least one non-whitespace character.
+    pub fn not_empty(&mut self, field: &str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.errors
+                .push(FieldError::new(field, "must not be empty"));
+        }
+        self
+    }
+
+    /// Requires the value to be at most `max` bytes long.
+    pub fn max_length(&mut self, field: &str, value: &str, max: usize) -> &mut Self {
+        if value.len() > max {
+            self.errors.push(FieldError::new(
+                field,
+                format!("must be at most {max} bytes long"),
+            ));
+        }
+        self
+    }
+
+    /// Requires the value to be a finite number between 0 and 1 (inclusive).
+    pub fn percentage(&mut self, field: &str, value: f32) -> &mut Self {
+        if !value.is_finite() || !(0.0..=1.0).contains(&value) {
+            self.errors.push(FieldError::new(
+                field,
+                "must be a finite number between 0 and 1",
+            ));
+        }
+        self
+    }
+
+    /// Requires the value to be greater than zero.
+    pub fn positive(&mut self, field: &str, value: u64) -> &mut Self {
+        if value == 0 {
+            self.errors
+                .push(FieldError::new(field, "must be greater than 0"));
+        }
+        self
+    }
+
+    /// Requires a list to hold between 1 and `max` items.
+    pub fn item_count(&mut self, field: &str, len: usize, max: usize) -> &mut Self {
+        if len == 0 || len > max {
+            self.errors.push(FieldError::new(
+                field,
+                format!("must contain between 1 and {max} items"),
+            ));
+        }
+        self
+    }
+
+    /// Returns an [`ApiError::InvalidInput`] listing every collected error, if any.
+    pub fn finish(self, message: &str) -> Result<(), ApiError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::InvalidInput(message.to_string(), self.errors))
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 
+use crate::model::Shelf;
+
 #[derive(Parser)]
 #[command(name = "korrosync", version, about = "KOReader synchronization server")]
 pub struct Cli {
@@ -18,20 +20,52 @@ pub enum Commands {
     /// User management commands
     #[command(subcommand)]
     User(UserCommands),
+    /// Device registry commands
+    #[command(subcommand)]
+    Device(DeviceCommands),
+    /// Reading progress commands
+    #[command(subcommand)]
+    Progress(ProgressCommands),
+    /// Show the reading statistics of a user
+    Stats {
+        #[arg(short, long)]
+        user: String,
+    },
+    /// Reading goal commands
+    #[command(subcommand)]
+    Goals(GoalCommands),
+    /// Reading shelf commands
+    #[command(subcommand)]
+    Shelf(ShelfCommands),
+    /// Document metadata commands
+    #[command(subcommand)]
+    Document(DocumentCommands),
+    /// Reading group commands
+    #[command(subcommand)]
+    Group(GroupCommands),
     /// Database maintenance commands
     #[command(subcommand)]
     Db(DbCommands),
+    /// Security audit log commands
+    #[command(subcommand)]
+    Audit(AuditCommands),
 }
 
 #[derive(Subcommand)]
 pub enum UserCommands {
     /// Create a new user
+    ///
+    /// The password is stored as KOReader sends it (MD5 hex digest), so the user can log in
+    /// from KOReader with the same password.
     Create {
         #[arg(short, long)]
         username: String,
         /// Password (use '-' to read from stdin)
         #[arg(short, long)]
         password: String,
+        /// Store the password as given instead of deriving the KOReader key from it
+        #[arg(long)]
+        raw_key: bool,
     },
     /// List all users
     List,
@@ -41,12 +75,202 @@ pub enum UserCommands {
         username: String,
     },
     /// Reset a user's password
+    ///
+    /// Like `create`, the KOReader key is derived from the password unless `--raw-key` is set.
     ResetPassword {
         #[arg(short, long)]
         username: String,
         /// Password (use '-' to read from stdin)
         #[arg(short, long)]
         password: String,
+        /// Store the password as given instead of deriving the KOReader key from it
+        #[arg(long)]
+        raw_key: bool,
+    },
+    /// Show or change a user's storage quota
+    ///
+    /// Without options, prints the user's quota and usage. Limits that are not given keep
+    /// their current value; `0` makes a limit unlimited.
+    Quota {
+        #[arg(short, long)]
+        username: String,
+        /// Maximum number of documents with progress
+        #[arg(long)]
+        max_documents: Option<u64>,
+        /// Maximum total size in bytes of the user's data
+        #[arg(long)]
+        max_bytes: Option<u64>,
+        /// Go back to the server-wide default quota
+        #[arg(long, conflicts_with_all = ["max_documents", "max_bytes"])]
+        reset: bool,
+    },
+    /// Check a password against a stored user
+    Verify {
+        #[arg(short, long)]
+        username: String,
+        /// Password (use '-' to read from stdin)
+        #[arg(short, long)]
+        password: String,
+        /// Check the password as given instead of deriving the KOReader key from it
+        #[arg(long)]
+        raw_key: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DeviceCommands {
+    /// List the devices of a user
+    List {
+        #[arg(short, long)]
+        user: String,
+    },
+    /// Set a friendly name for a device
+    Rename {
+        #[arg(short, long)]
+        user: String,
+        #[arg(short, long)]
+        device_id: String,
+        /// New friendly name (omit to reset to the reported device model)
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// Remove a device from the registry
+    Forget {
+        #[arg(short, long)]
+        user: String,
+        #[arg(short, long)]
+        device_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProgressCommands {
+    /// List the progress of a user, with document metadata when known
+    List {
+        #[arg(short, long)]
+        user: String,
+    },
+    /// Delete the progress of a document, including its reading history
+    Delete {
+        #[arg(short, long)]
+        user: String,
+        /// Document identifier (as reported by KOReader)
+        #[arg(short, long)]
+        document: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GoalCommands {
+    /// Show the goals of a user, their reading streak and how far each goal is
+    Show {
+        #[arg(short, long)]
+        user: String,
+    },
+    /// Set the goals of a user, replacing the previous ones
+    Set {
+        #[arg(short, long)]
+        user: String,
+        /// Documents to finish each year (omit to clear)
+        #[arg(short, long)]
+        books_per_year: Option<u32>,
+        /// Minutes to read each day (omit to clear)
+        #[arg(short, long)]
+        minutes_per_day: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ShelfCommands {
+    /// List the shelved documents of a user, with when they were finished
+    List {
+        #[arg(short, long)]
+        user: String,
+        /// Only show this shelf (reading, finished or abandoned)
+        #[arg(short, long)]
+        shelf: Option<Shelf>,
+    },
+    /// Move a document to a shelf
+    ///
+    /// Moving a finished document elsewhere starts a new read-through.
+    Set {
+        #[arg(short, long)]
+        user: String,
+        /// Document identifier (as reported by KOReader)
+        #[arg(short, long)]
+        document: String,
+        /// Shelf to move the document to (reading, finished or abandoned)
+        #[arg(short, long)]
+        shelf: Shelf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DocumentCommands {
+    /// Set the metadata of a document, replacing any previous metadata
+    Set {
+        /// Document identifier (as reported by KOReader)
+        #[arg(short, long)]
+        document: String,
+        /// Only set the metadata for this username (defaults to global metadata)
+        #[arg(short, long)]
+        user: Option<String>,
+        #[arg(short, long)]
+        title: Option<String>,
+        #[arg(short, long)]
+        author: Option<String>,
+        #[arg(short, long)]
+        series: Option<String>,
+        #[arg(short, long)]
+        language: Option<String>,
+        #[arg(short, long)]
+        cover_url: Option<String>,
+    },
+    /// Make a document identifier an alias of another document, sharing its progress
+    Link {
+        #[arg(short, long)]
+        user: String,
+        /// Document identifier to redirect
+        #[arg(short, long)]
+        alias: String,
+        /// Document identifier to redirect to
+        #[arg(short, long)]
+        document: String,
+    },
+    /// Remove a document alias
+    Unlink {
+        #[arg(short, long)]
+        user: String,
+        #[arg(short, long)]
+        alias: String,
+    },
+    /// List the document aliases of a user
+    Aliases {
+        #[arg(short, long)]
+        user: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GroupCommands {
+    /// Create a reading group owned by an existing user
+    Create {
+        #[arg(short, long)]
+        name: String,
+        /// Username of the owner, who becomes the first member
+        #[arg(short, long)]
+        owner: String,
+    },
+    /// List reading groups
+    List {
+        /// Only show the groups this username belongs to
+        #[arg(short, long)]
+        user: Option<String>,
+    },
+    /// Delete a reading group
+    Delete {
+        #[arg(short, long)]
+        id: String,
     },
 }
 
@@ -60,4 +284,24 @@ pub enum DbCommands {
         #[arg(short, long)]
         output: String,
     },
+    /// Delete stale progress and flag or remove inactive users, following the retention
+    /// rules configured in the environment
+    Gc {
+        /// Only report what would be removed or flagged
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// List audit events, oldest first
+    List {
+        /// Only show events for this username
+        #[arg(short, long)]
+        user: Option<String>,
+        /// Only show events since this date (YYYY-MM-DD or RFC 3339, UTC)
+        #[arg(short, long)]
+        since: Option<String>,
+    },
 }
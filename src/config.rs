@@ -20,11 +20,49 @@
 //! ## Rate Limiting
 //! - `KORROSYNC_RATE_LIMIT_PER_SECOND` - Rate limit replenishment rate per second (default: `2`)
 //! - `KORROSYNC_RATE_LIMIT_BURST_SIZE` - Maximum burst size before rate limiting (default: `5`)
+//!
+//! ## Audit Log
+//! - `KORROSYNC_AUDIT_RETENTION_DAYS` - Days to keep audit events, `0` keeps them forever (default: `90`)
+//!
+//! ## Data Retention
+//! - `KORROSYNC_PROGRESS_RETENTION_DAYS` - Days after which progress that was not updated is deleted,
+//!   `0` keeps it forever (default: `0`)
+//! - `KORROSYNC_INACTIVE_USER_DAYS` - Days without activity after which a user is flagged as inactive,
+//!   `0` never flags users (default: `0`)
+//! - `KORROSYNC_INACTIVE_USER_GRACE_DAYS` - Days a user stays flagged as inactive before being removed;
+//!   when unset, inactive users are only flagged (default: unset)
+//!
+//! ## Request Validation
+//! - `KORROSYNC_MAX_DOCUMENT_LENGTH` - Maximum length in bytes of a document identifier (default: `512`)
+//! - `KORROSYNC_MAX_FIELD_LENGTH` - Maximum length in bytes of other string fields (default: `1024`)
+//! - `KORROSYNC_MAX_TEXT_LENGTH` - Maximum length in bytes of free text, such as highlighted text and
+//!   notes (default: `65536`)
+//! - `KORROSYNC_MAX_BATCH_SIZE` - Maximum number of documents in a batch progress request (default: `100`)
+//! - `KORROSYNC_MAX_ANNOTATIONS` - Maximum number of annotations merged in a single request (default: `1000`)
+//! - `KORROSYNC_MAX_STATISTICS_SIZE` - Maximum size in bytes of an uploaded statistics database
+//!   (default: `67108864`, 64 MiB)
+//! - `KORROSYNC_MAX_CLOCK_SKEW_SECS` - How far, in seconds, a client-reported progress timestamp may be
+//!   from the server clock to be trusted, `0` never trusts them (default: `0`)
+//!
+//! ## Shelves
+//! - `KORROSYNC_FINISHED_THRESHOLD` - Progress percentage, between 0 and 1, from which a document is
+//!   moved to the finished shelf (default: `0.99`)
+//!
+//! ## Storage Quotas
+//! - `KORROSYNC_QUOTA_MAX_DOCUMENTS` - Maximum number of documents a user may have progress for,
+//!   `0` is unlimited (default: `0`)
+//! - `KORROSYNC_QUOTA_MAX_BYTES` - Maximum total size in bytes of a user's data (progress, devices,
+//!   annotations, bookmarks, statistics, shelves, goals, share links, document aliases and
+//!   document metadata), `0` is unlimited (default: `0`)
+//!
+//! Both can be overridden per user with `korrosync user quota`.
 
 use std::env;
 
 use serde::{Deserialize, Serialize};
 
+use crate::model::Quota;
+
 const DEFAULT_DB_PATH: &str = "data/db.redb";
 const DEFAULT_SERVER_ADDRESS: &str = "0.0.0.0:3000";
 #[cfg(feature = "tls")]
@@ -33,6 +71,15 @@ const DEFAULT_TLS_CERT: &str = "tls/cert.pem";
 const DEFAULT_TLS_PRIVKEY: &str = "tls/key.pem";
 const DEFAULT_RATE_LIMIT_PER_SECOND: u64 = 2;
 const DEFAULT_RATE_LIMIT_BURST_SIZE: u32 = 5;
+const DEFAULT_AUDIT_RETENTION_DAYS: u64 = 90;
+const DEFAULT_MAX_DOCUMENT_LENGTH: usize = 512;
+const DEFAULT_MAX_FIELD_LENGTH: usize = 1024;
+const DEFAULT_MAX_TEXT_LENGTH: usize = 64 * 1024;
+const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+const DEFAULT_MAX_ANNOTATIONS: usize = 1000;
+const DEFAULT_MAX_STATISTICS_SIZE: usize = 64 * 1024 * 1024;
+const DEFAULT_MAX_CLOCK_SKEW_SECS: u64 = 0;
+const DEFAULT_FINISHED_THRESHOLD: f32 = 0.99;
 
 /// Main configuration structure for Korrosync
 ///
@@ -45,6 +92,16 @@ pub struct Config {
     pub server: Server,
     /// Rate limiting configuration
     pub rate_limit: RateLimit,
+    /// Audit log configuration
+    pub audit: Audit,
+    /// Retention of stale progress and inactive accounts
+    pub retention: Retention,
+    /// Request validation limits
+    pub validation: Validation,
+    /// Reading shelves configuration
+    pub shelves: Shelves,
+    /// Default storage quota of users
+    pub quotas: Quotas,
 }
 
 /// Database configuration
@@ -80,6 +137,11 @@ impl Config {
             db: Db::from_env(),
             server: Server::from_env(),
             rate_limit: RateLimit::from_env(),
+            audit: Audit::from_env(),
+            retention: Retention::from_env(),
+            validation: Validation::from_env(),
+            shelves: Shelves::from_env(),
+            quotas: Quotas::from_env(),
         }
     }
 }
@@ -174,6 +236,222 @@ impl RateLimit {
     }
 }
 
+/// Audit log configuration
+#[derive(Serialize, Deserialize)]
+pub struct Audit {
+    /// Number of days audit events are kept before being pruned (`0` disables pruning)
+    pub retention_days: u64,
+}
+
+impl Audit {
+    pub fn from_env() -> Self {
+        let retention_days = env::var("KORROSYNC_AUDIT_RETENTION_DAYS")
+            .map(|v| {
+                v.parse::<u64>().unwrap_or_else(|_| {
+                    panic!(
+                        "Invalid value for KORROSYNC_AUDIT_RETENTION_DAYS: '{}'. Expected a non-negative integer",
+                        v
+                    )
+                })
+            })
+            .unwrap_or(DEFAULT_AUDIT_RETENTION_DAYS);
+
+        Self { retention_days }
+    }
+}
+
+/// Retention rules for stale progress and inactive accounts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Retention {
+    /// Days after which progress that was not updated is deleted (`0` keeps it forever)
+    pub progress_days: u64,
+    /// Days without activity after which a user is flagged as inactive (`0` never flags users)
+    pub inactive_user_days: u64,
+    /// Days a flagged user may stay inactive before being removed (`None` only flags them)
+    pub inactive_user_grace_days: Option<u64>,
+}
+
+impl Retention {
+    pub fn from_env() -> Self {
+        let parse = |name: &str| {
+            env::var(name).ok().map(|v| {
+                v.parse::<u64>().unwrap_or_else(|_| {
+                    panic!(
+                        "Invalid value for {}: '{}'. Expected a non-negative integer",
+                        name, v
+                    )
+                })
+            })
+        };
+
+        Self {
+            progress_days: parse("KORROSYNC_PROGRESS_RETENTION_DAYS").unwrap_or_default(),
+            inactive_user_days: parse("KORROSYNC_INACTIVE_USER_DAYS").unwrap_or_default(),
+            inactive_user_grace_days: parse("KORROSYNC_INACTIVE_USER_GRACE_DAYS"),
+        }
+    }
+
+    /// Whether any retention rule is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.progress_days > 0 || self.inactive_user_days > 0
+    }
+}
+
+/// Request validation limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Validation {
+    /// Maximum length in bytes of a document identifier
+    pub max_document_length: usize,
+    /// Maximum length in bytes of any other string field (device, device id, progress, ...)
+    pub max_field_length: usize,
+    /// Maximum length in bytes of free text (highlighted text, notes)
+    pub max_text_length: usize,
+    /// Maximum number of documents in a batch progress request
+    pub max_batch_size: usize,
+    /// Maximum number of annotations merged in a single request
+    pub max_annotations: usize,
+    /// Maximum size in bytes of an uploaded statistics database
+    pub max_statistics_size: usize,
+    /// Maximum distance in seconds between a client-reported timestamp and the server clock
+    /// for the client timestamp to be trusted (`0` never trusts it)
+    pub max_clock_skew_secs: u64,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Self {
+            max_document_length: DEFAULT_MAX_DOCUMENT_LENGTH,
+            max_field_length: DEFAULT_MAX_FIELD_LENGTH,
+            max_text_length: DEFAULT_MAX_TEXT_LENGTH,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            max_annotations: DEFAULT_MAX_ANNOTATIONS,
+            max_statistics_size: DEFAULT_MAX_STATISTICS_SIZE,
+            max_clock_skew_secs: DEFAULT_MAX_CLOCK_SKEW_SECS,
+        }
+    }
+}
+
+impl Validation {
+    pub fn from_env() -> Self {
+        let parse = |name: &str, default: usize| {
+            let value = env::var(name)
+                .map(|v| {
+                    v.parse::<usize>().unwrap_or_else(|_| {
+                        panic!(
+                            "Invalid value for {}: '{}'. Expected a positive integer",
+                            name, v
+                        )
+                    })
+                })
+                .unwrap_or(default);
+
+            assert!(value > 0, "{} must be greater than 0", name);
+            value
+        };
+
+        let max_clock_skew_secs = env::var("KORROSYNC_MAX_CLOCK_SKEW_SECS")
+            .map(|v| {
+                v.parse::<u64>().unwrap_or_else(|_| {
+                    panic!(
+                        "Invalid value for KORROSYNC_MAX_CLOCK_SKEW_SECS: '{}'. Expected a non-negative integer",
+                        v
+                    )
+                })
+            })
+            .unwrap_or(DEFAULT_MAX_CLOCK_SKEW_SECS);
+
+        Self {
+            max_document_length: parse(
+                "KORROSYNC_MAX_DOCUMENT_LENGTH",
+                DEFAULT_MAX_DOCUMENT_LENGTH,
+            ),
+            max_field_length: parse("KORROSYNC_MAX_FIELD_LENGTH", DEFAULT_MAX_FIELD_LENGTH),
+            max_text_length: parse("KORROSYNC_MAX_TEXT_LENGTH", DEFAULT_MAX_TEXT_LENGTH),
+            max_batch_size: parse("KORROSYNC_MAX_BATCH_SIZE", DEFAULT_MAX_BATCH_SIZE),
+            max_annotations: parse("KORROSYNC_MAX_ANNOTATIONS", DEFAULT_MAX_ANNOTATIONS),
+            max_statistics_size: parse(
+                "KORROSYNC_MAX_STATISTICS_SIZE",
+                DEFAULT_MAX_STATISTICS_SIZE,
+            ),
+            max_clock_skew_secs,
+        }
+    }
+}
+
+/// Reading shelves configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Shelves {
+    /// Progress percentage (between 0 and 1) from which a document counts as finished
+    pub finished_threshold: f32,
+}
+
+impl Default for Shelves {
+    fn default() -> Self {
+        Self {
+            finished_threshold: DEFAULT_FINISHED_THRESHOLD,
+        }
+    }
+}
+
+impl Shelves {
+    pub fn from_env() -> Self {
+        let finished_threshold = env::var("KORROSYNC_FINISHED_THRESHOLD")
+            .map(|v| {
+                v.parse::<f32>()
+                    .ok()
+                    .filter(|threshold| *threshold > 0.0 && *threshold <= 1.0)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Invalid value for KORROSYNC_FINISHED_THRESHOLD: '{}'. Expected a number greater than 0 and at most 1",
+                            v
+                        )
+                    })
+            })
+            .unwrap_or(DEFAULT_FINISHED_THRESHOLD);
+
+        Self { finished_threshold }
+    }
+}
+
+/// Default storage quota of users, unless overridden for a user
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Quotas {
+    /// Maximum number of documents a user may have progress for (`0` is unlimited)
+    pub max_documents: u64,
+    /// Maximum total size in bytes of a user's data (`0` is unlimited)
+    pub max_bytes: u64,
+}
+
+impl Quotas {
+    pub fn from_env() -> Self {
+        let parse = |name: &str| {
+            env::var(name)
+                .map(|v| {
+                    v.parse::<u64>().unwrap_or_else(|_| {
+                        panic!(
+                            "Invalid value for {}: '{}'. Expected a non-negative integer",
+                            name, v
+                        )
+                    })
+                })
+                .unwrap_or_default()
+        };
+
+        Self {
+            max_documents: parse("KORROSYNC_QUOTA_MAX_DOCUMENTS"),
+            max_bytes: parse("KORROSYNC_QUOTA_MAX_BYTES"),
+        }
+    }
+
+    /// The quota of users without one of their own
+    pub fn default_quota(&self) -> Quota {
+        Quota {
+            max_documents: (self.max_documents > 0).then_some(self.max_documents),
+            max_bytes: (self.max_bytes > 0).then_some(self.max_bytes),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,4 +523,199 @@ mod tests {
             RateLimit::from_env();
         });
     }
+
+    #[test]
+    fn audit_defaults() {
+        temp_env::with_var_unset("KORROSYNC_AUDIT_RETENTION_DAYS", || {
+            assert_eq!(Audit::from_env().retention_days, 90);
+        });
+    }
+
+    #[test]
+    fn audit_custom_retention() {
+        temp_env::with_var("KORROSYNC_AUDIT_RETENTION_DAYS", Some("0"), || {
+            assert_eq!(Audit::from_env().retention_days, 0);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for KORROSYNC_AUDIT_RETENTION_DAYS")]
+    fn audit_invalid_retention() {
+        temp_env::with_var("KORROSYNC_AUDIT_RETENTION_DAYS", Some("-1"), || {
+            Audit::from_env();
+        });
+    }
+
+    #[test]
+    fn retention_defaults() {
+        temp_env::with_vars_unset(
+            vec![
+                "KORROSYNC_PROGRESS_RETENTION_DAYS",
+                "KORROSYNC_INACTIVE_USER_DAYS",
+                "KORROSYNC_INACTIVE_USER_GRACE_DAYS",
+            ],
+            || {
+                let retention = Retention::from_env();
+                assert_eq!(retention.progress_days, 0);
+                assert_eq!(retention.inactive_user_days, 0);
+                assert_eq!(retention.inactive_user_grace_days, None);
+                assert!(!retention.is_enabled());
+            },
+        );
+    }
+
+    #[test]
+    fn retention_custom_values() {
+        temp_env::with_vars(
+            vec![
+                ("KORROSYNC_PROGRESS_RETENTION_DAYS", Some("365")),
+                ("KORROSYNC_INACTIVE_USER_DAYS", Some("180")),
+                ("KORROSYNC_INACTIVE_USER_GRACE_DAYS", Some("0")),
+            ],
+            || {
+                let retention = Retention::from_env();
+                assert_eq!(retention.progress_days, 365);
+                assert_eq!(retention.inactive_user_days, 180);
+                assert_eq!(retention.inactive_user_grace_days, Some(0));
+                assert!(retention.is_enabled());
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for KORROSYNC_INACTIVE_USER_GRACE_DAYS")]
+    fn retention_invalid_grace_days() {
+        temp_env::with_var("KORROSYNC_INACTIVE_USER_GRACE_DAYS", Some("soon"), || {
+            Retention::from_env();
+        });
+    }
+
+    #[test]
+    fn validation_defaults() {
+        temp_env::with_vars_unset(
+            vec![
+                "KORROSYNC_MAX_DOCUMENT_LENGTH",
+                "KORROSYNC_MAX_FIELD_LENGTH",
+                "KORROSYNC_MAX_TEXT_LENGTH",
+                "KORROSYNC_MAX_BATCH_SIZE",
+                "KORROSYNC_MAX_ANNOTATIONS",
+                "KORROSYNC_MAX_STATISTICS_SIZE",
+                "KORROSYNC_MAX_CLOCK_SKEW_SECS",
+            ],
+            || {
+                let validation = Validation::from_env();
+                assert_eq!(validation.max_document_length, 512);
+                assert_eq!(validation.max_field_length, 1024);
+                assert_eq!(validation.max_text_length, 65536);
+                assert_eq!(validation.max_batch_size, 100);
+                assert_eq!(validation.max_annotations, 1000);
+                assert_eq!(validation.max_statistics_size, 64 * 1024 * 1024);
+                assert_eq!(validation.max_clock_skew_secs, 0);
+            },
+        );
+    }
+
+    #[test]
+    fn validation_custom_values() {
+        temp_env::with_vars(
+            vec![
+                ("KORROSYNC_MAX_DOCUMENT_LENGTH", Some("64")),
+                ("KORROSYNC_MAX_FIELD_LENGTH", Some("128")),
+                ("KORROSYNC_MAX_TEXT_LENGTH", Some("4096")),
+                ("KORROSYNC_MAX_BATCH_SIZE", Some("10")),
+                ("KORROSYNC_MAX_ANNOTATIONS", Some("50")),
+                ("KORROSYNC_MAX_STATISTICS_SIZE", Some("1048576")),
+                ("KORROSYNC_MAX_CLOCK_SKEW_SECS", Some("86400")),
+            ],
+            || {
+                let validation = Validation::from_env();
+                assert_eq!(validation.max_document_length, 64);
+                assert_eq!(validation.max_field_length, 128);
+                assert_eq!(validation.max_text_length, 4096);
+                assert_eq!(validation.max_batch_size, 10);
+                assert_eq!(validation.max_annotations, 50);
+                assert_eq!(validation.max_statistics_size, 1048576);
+                assert_eq!(validation.max_clock_skew_secs, 86400);
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for KORROSYNC_MAX_FIELD_LENGTH")]
+    fn validation_invalid_field_length() {
+        temp_env::with_var("KORROSYNC_MAX_FIELD_LENGTH", Some("long"), || {
+            Validation::from_env();
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "KORROSYNC_MAX_DOCUMENT_LENGTH must be greater than 0")]
+    fn validation_zero_document_length() {
+        temp_env::with_var("KORROSYNC_MAX_DOCUMENT_LENGTH", Some("0"), || {
+            Validation::from_env();
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for KORROSYNC_MAX_CLOCK_SKEW_SECS")]
+    fn validation_invalid_clock_skew() {
+        temp_env::with_var("KORROSYNC_MAX_CLOCK_SKEW_SECS", Some("-5"), || {
+            Validation::from_env();
+        });
+    }
+
+    #[test]
+    fn shelves_defaults() {
+        temp_env::with_var_unset("KORROSYNC_FINISHED_THRESHOLD", || {
+            assert_eq!(Shelves::from_env().finished_threshold, 0.99);
+        });
+    }
+
+    #[test]
+    fn shelves_custom_threshold() {
+        temp_env::with_var("KORROSYNC_FINISHED_THRESHOLD", Some("0.95"), || {
+            assert_eq!(Shelves::from_env().finished_threshold, 0.95);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for KORROSYNC_FINISHED_THRESHOLD")]
+    fn shelves_invalid_threshold() {
+        temp_env::with_var("KORROSYNC_FINISHED_THRESHOLD", Some("95"), || {
+            Shelves::from_env();
+        });
+    }
+
+    #[test]
+    fn quotas_defaults() {
+        temp_env::with_vars_unset(
+            vec!["KORROSYNC_QUOTA_MAX_DOCUMENTS", "KORROSYNC_QUOTA_MAX_BYTES"],
+            || {
+                assert!(Quotas::from_env().default_quota().is_unlimited());
+            },
+        );
+    }
+
+    #[test]
+    fn quotas_custom_values() {
+        temp_env::with_vars(
+            vec![
+                ("KORROSYNC_QUOTA_MAX_DOCUMENTS", Some("500")),
+                ("KORROSYNC_QUOTA_MAX_BYTES", Some("0")),
+            ],
+            || {
+                let quota = Quotas::from_env().default_quota();
+                assert_eq!(quota.max_documents, Some(500));
+                assert_eq!(quota.max_bytes, None);
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for KORROSYNC_QUOTA_MAX_BYTES")]
+    fn quotas_invalid_bytes() {
+        temp_env::with_var("KORROSYNC_QUOTA_MAX_BYTES", Some("1GB"), || {
+            Quotas::from_env();
+        });
+    }
 }
@@ -61,6 +61,30 @@
 //! - `KORROSYNC_RATE_LIMIT_PER_SECOND` - Rate limit replenishment rate per second (default: 2)
 //! - `KORROSYNC_RATE_LIMIT_BURST_SIZE` - Maximum burst size before rate limiting (default: 5)
 //!
+//! Audit log:
+//! - `KORROSYNC_AUDIT_RETENTION_DAYS` - Days to keep audit events, 0 keeps them forever (default: 90)
+//!
+//! Data retention:
+//! - `KORROSYNC_PROGRESS_RETENTION_DAYS` - Days after which progress that was not updated is deleted, 0 keeps it forever (default: 0)
+//! - `KORROSYNC_INACTIVE_USER_DAYS` - Days without activity after which a user is flagged as inactive, 0 never flags users (default: 0)
+//! - `KORROSYNC_INACTIVE_USER_GRACE_DAYS` - Days a user stays flagged as inactive before being removed (default: unset, only flag)
+//!
+//! Validation:
+//! - `KORROSYNC_MAX_DOCUMENT_LENGTH` - Maximum length of a document identifier in bytes (default: 512)
+//! - `KORROSYNC_MAX_FIELD_LENGTH` - Maximum length of other request string fields in bytes (default: 1024)
+//! - `KORROSYNC_MAX_TEXT_LENGTH` - Maximum length of highlighted text and notes in bytes (default: 65536)
+//! - `KORROSYNC_MAX_BATCH_SIZE` - Maximum number of documents in a batch progress request (default: 100)
+//! - `KORROSYNC_MAX_ANNOTATIONS` - Maximum number of annotations merged in a single request (default: 1000)
+//! - `KORROSYNC_MAX_STATISTICS_SIZE` - Maximum size of an uploaded statistics database in bytes (default: 67108864)
+//! - `KORROSYNC_MAX_CLOCK_SKEW_SECS` - Maximum skew in seconds to trust client progress timestamps, 0 never trusts them (default: 0)
+//!
+//! Shelves:
+//! - `KORROSYNC_FINISHED_THRESHOLD` - Progress percentage, between 0 and 1, from which a document is finished (default: 0.99)
+//!
+//! Storage quotas:
+//! - `KORROSYNC_QUOTA_MAX_DOCUMENTS` - Maximum number of documents a user may have progress for, 0 is unlimited (default: 0)
+//! - `KORROSYNC_QUOTA_MAX_BYTES` - Maximum total size in bytes of a user's data, 0 is unlimited (default: 0)
+//!
 //! # Features
 //!
 //! This crate supports the following optional cargo features:
@@ -84,6 +108,25 @@
 //! **Note:** Without this feature, the server only supports HTTP. You can still use HTTPS
 //! by deploying behind a reverse proxy like Nginx or Caddy.
 //!
+//! ## `statistics-sync`
+//!
+//! Serves KOReader's statistics synchronization over WebDAV under `/dav`, merging the uploaded
+//! `statistics.sqlite3` databases with a bundled SQLite.
+//!
+//! **Compile-time enablement:**
+//! ```bash
+//! cargo build --release --features statistics-sync
+//! ```
+//!
+//! ## `openapi-ui`
+//!
+//! Serves a Scalar UI at `/docs` to browse the OpenAPI description of the API.
+//!
+//! **Compile-time enablement:**
+//! ```bash
+//! cargo build --release --features openapi-ui
+//! ```
+//!
 //! # KOReader Compatibility
 //!
 //! This server implements the KOReader synchronization API, allowing you to:
@@ -108,7 +151,10 @@ use tracing::{info, instrument};
 use crate::{
     api::{middleware::ratelimiter::rate_limiter_layer, router::app, state::AppState},
     config::Config,
-    service::db::KorrosyncServiceRedb,
+    service::{
+        db::KorrosyncServiceRedb,
+        retention::{audit_retention_task, garbage_collection_task},
+    },
 };
 
 use crate::logging::init_logging;
@@ -133,18 +179,36 @@ pub async fn run_server(cfg: Config) -> eyre::Result<()> {
 
     let state = AppState {
         sync: Arc::new(KorrosyncServiceRedb::new(cfg.db.path).context("DB Init Error")?),
+        validation: cfg.validation,
+        shelves: cfg.shelves,
+        quotas: cfg.quotas,
+        shutdown: CancellationToken::new(),
     };
 
+    let state_shutdown_token = state.shutdown.clone();
     let shutdown_token_cleanup = CancellationToken::new();
     let (rate_limiter, cleanup_task) =
         rate_limiter_layer(shutdown_token_cleanup.clone(), &cfg.rate_limit);
+    let audit_task = audit_retention_task(
+        state.sync.clone(),
+        &cfg.audit,
+        shutdown_token_cleanup.clone(),
+    );
+    let gc_task = garbage_collection_task(
+        state.sync.clone(),
+        &cfg.retention,
+        shutdown_token_cleanup.clone(),
+    );
 
     let app = app(state)
         .layer(rate_limiter)
         .into_make_service_with_connect_info::<SocketAddr>();
 
     let shutdown_handle = Handle::new();
-    tokio::spawn(shutdown_signal(shutdown_handle.clone()));
+    tokio::spawn(shutdown_signal(
+        shutdown_handle.clone(),
+        state_shutdown_token,
+    ));
 
     #[cfg(feature = "tls")]
     {
@@ -185,12 +249,20 @@ pub async fn run_server(cfg: Config) -> eyre::Result<()> {
             .context("Failed to start server")?;
     }
 
-    // Cancel the rate limiter cleanup task and wait for it to finish
+    // Cancel the background tasks and wait for them to finish
     shutdown_token_cleanup.cancel();
     cleanup_task.await.map_err(|e| {
         tracing::error!("Rate limiter cleanup task failed: {}", e);
         e
     })?;
+    audit_task.await.map_err(|e| {
+        tracing::error!("Audit retention task failed: {}", e);
+        e
+    })?;
+    gc_task.await.map_err(|e| {
+        tracing::error!("Garbage collection task failed: {}", e);
+        e
+    })?;
 
     info!("Server shutdown complete");
 
@@ -200,10 +272,10 @@ pub async fn run_server(cfg: Config) -> eyre::Result<()> {
 /// Handle graceful shutdown signals
 ///
 /// A background task is spawned to listen for shutdown signals (Ctrl-C, SIGINT, SIGTERM).
-/// Then call the handle's `graceful_shutdown` method to initiate a graceful shutdown of the
-/// server.
-#[instrument(fields(graceful_shutdown), skip(handle))]
-async fn shutdown_signal<A: Address>(handle: Handle<A>) {
+/// Then cancel `ready` so readiness probes fail, and call the handle's `graceful_shutdown`
+/// method to initiate a graceful shutdown of the server.
+#[instrument(fields(graceful_shutdown), skip(handle, ready))]
+async fn shutdown_signal<A: Address>(handle: Handle<A>, ready: CancellationToken) {
     let ctrl_c = async {
         signal::ctrl_c()
             .await
@@ -240,6 +312,8 @@ async fn shutdown_signal<A: Address>(handle: Handle<A>) {
 
     info!("Server is shutting down...");
 
+    ready.cancel();
+
     handle.graceful_shutdown(Some(Duration::from_secs(SHUTDOWN_DURATION_SECS)));
 
     for remaining in (1..=SHUTDOWN_DURATION_SECS).rev() {
@@ -2,10 +2,17 @@ use std::fs;
 
 use clap::Parser;
 use color_eyre::eyre::{self, Context};
-use korrosync::cli::{Cli, Commands, DbCommands, UserCommands};
-use korrosync::config::Config;
-use korrosync::model::User;
+use korrosync::cli::{
+    AuditCommands, Cli, Commands, DbCommands, DeviceCommands, DocumentCommands, GoalCommands,
+    GroupCommands, ProgressCommands, ShelfCommands, UserCommands,
+};
+use korrosync::config::{Config, Quotas, Retention};
+use korrosync::model::{
+    AuditEvent, AuditKind, DocumentMetadata, GoalReport, Goals, Group, Quota, QuotaUsage,
+    ReadingStats, User, koreader_key,
+};
 use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+use korrosync::service::retention::collect_garbage;
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
@@ -26,32 +33,59 @@ async fn main() -> eyre::Result<()> {
             let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
 
             match cmd {
-                UserCommands::Create { username, password } => {
-                    let password = resolve_password(password)?;
-                    let user = User::new(&username, &password)
+                UserCommands::Create {
+                    username,
+                    password,
+                    raw_key,
+                } => {
+                    let key = resolve_key(resolve_password(password)?, raw_key);
+                    let user = User::new(&username, &key)
                         .map_err(|e| eyre::eyre!("Failed to create user: {}", e))?;
                     service
                         .create_or_update_user(user)
                         .context("Failed to save user")?;
+                    service
+                        .record_event(
+                            AuditEvent::new(AuditKind::AdminAction, &username)
+                                .with_detail("user created via CLI"),
+                        )
+                        .context("Failed to record audit event")?;
                     println!("User '{}' created successfully", username);
                 }
                 UserCommands::List => {
                     let users = service.list_users().context("Failed to list users")?;
+                    let inactive = service
+                        .list_inactive_users()
+                        .context("Failed to list inactive users")?;
+                    let quotas = Quotas::from_env();
                     if users.is_empty() {
                         println!("No users found");
                     } else {
-                        println!("{:<20} LAST ACTIVITY", "USERNAME");
-                        println!("{}", "-".repeat(40));
+                        println!(
+                            "{:<20} {:<24} {:<16} {:<24} FLAGGED INACTIVE",
+                            "USERNAME", "LAST ACTIVITY", "DOCUMENTS", "BYTES"
+                        );
+                        println!("{}", "-".repeat(110));
                         for user in &users {
                             let activity = user
                                 .last_activity()
-                                .map(|ts| {
-                                    chrono::DateTime::from_timestamp_millis(ts)
-                                        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-                                        .unwrap_or_else(|| ts.to_string())
-                                })
+                                .map(format_timestamp)
                                 .unwrap_or_else(|| "never".to_string());
-                            println!("{:<20} {}", user.username(), activity);
+                            let (quota, _) = user_quota(&service, &quotas, user.username())?;
+                            let usage = user_usage(&service, user.username())?;
+                            let flagged = inactive
+                                .iter()
+                                .find(|(name, _)| name == user.username())
+                                .map(|(_, at)| format_timestamp(*at))
+                                .unwrap_or_else(|| "-".to_string());
+                            println!(
+                                "{:<20} {:<24} {:<16} {:<24} {}",
+                                user.username(),
+                                activity,
+                                format_usage(usage.documents, quota.max_documents),
+                                format_usage(usage.bytes, quota.max_bytes),
+                                flagged
+                            );
                         }
                         println!("\nTotal: {} user(s)", users.len());
                     }
@@ -61,26 +95,562 @@ async fn main() -> eyre::Result<()> {
                         .delete_user(username.clone())
                         .context("Failed to delete user")?;
                     if deleted {
+                        service
+                            .record_event(
+                                AuditEvent::new(AuditKind::UserDeleted, &username)
+                                    .with_detail("removed via CLI"),
+                            )
+                            .context("Failed to record audit event")?;
                         println!("User '{}' removed successfully", username);
                     } else {
                         println!("User '{}' not found", username);
                     }
                 }
-                UserCommands::ResetPassword { username, password } => {
-                    let password = resolve_password(password)?;
-                    let existing = service
-                        .get_user(username.clone())
-                        .context("Failed to query user")?;
-                    if existing.is_none() {
+                UserCommands::ResetPassword {
+                    username,
+                    password,
+                    raw_key,
+                } => {
+                    let key = resolve_key(resolve_password(password)?, raw_key);
+                    let changed = service
+                        .change_password(username.clone(), key)
+                        .context("Failed to update user")?;
+                    if !changed {
                         eyre::bail!("User '{}' not found", username);
                     }
-                    let user = User::new(&username, &password)
-                        .map_err(|e| eyre::eyre!("Failed to hash password: {}", e))?;
                     service
-                        .create_or_update_user(user)
-                        .context("Failed to update user")?;
+                        .record_event(
+                            AuditEvent::new(AuditKind::PasswordReset, &username)
+                                .with_detail("reset via CLI"),
+                        )
+                        .context("Failed to record audit event")?;
                     println!("Password for user '{}' reset successfully", username);
                 }
+                UserCommands::Quota {
+                    username,
+                    max_documents,
+                    max_bytes,
+                    reset,
+                } => {
+                    if service
+                        .get_user(username.clone())
+                        .context("Failed to query user")?
+                        .is_none()
+                    {
+                        eyre::bail!("User '{}' not found", username);
+                    }
+                    let quotas = Quotas::from_env();
+                    if reset {
+                        service
+                            .set_quota(username.clone(), None)
+                            .context("Failed to reset quota")?;
+                        println!("Quota of user '{}' reset to the default", username);
+                    } else if max_documents.is_some() || max_bytes.is_some() {
+                        let (current, _) = user_quota(&service, &quotas, &username)?;
+                        let limit = |value: Option<u64>, current: Option<u64>| match value {
+                            Some(0) => None,
+                            Some(value) => Some(value),
+                            None => current,
+                        };
+                        let quota = Quota {
+                            max_documents: limit(max_documents, current.max_documents),
+                            max_bytes: limit(max_bytes, current.max_bytes),
+                        };
+                        service
+                            .set_quota(username.clone(), Some(quota))
+                            .context("Failed to set quota")?;
+                        service
+                            .record_event(
+                                AuditEvent::new(AuditKind::AdminAction, &username)
+                                    .with_detail("quota changed via CLI"),
+                            )
+                            .context("Failed to record audit event")?;
+                        println!("Quota of user '{}' updated", username);
+                    }
+
+                    let (quota, custom) = user_quota(&service, &quotas, &username)?;
+                    let usage = user_usage(&service, &username)?;
+                    println!("Quota: {}", if custom { "custom" } else { "default" });
+                    println!(
+                        "Documents: {}",
+                        format_usage(usage.documents, quota.max_documents)
+                    );
+                    println!("Bytes: {}", format_usage(usage.bytes, quota.max_bytes));
+                }
+                UserCommands::Verify {
+                    username,
+                    password,
+                    raw_key,
+                } => {
+                    let key = resolve_key(resolve_password(password)?, raw_key);
+                    let Some(user) = service
+                        .get_user(username.clone())
+                        .context("Failed to query user")?
+                    else {
+                        eyre::bail!("User '{}' not found", username);
+                    };
+                    let valid = user
+                        .check(&key)
+                        .map_err(|e| eyre::eyre!("Failed to verify password: {}", e))?;
+                    if !valid {
+                        eyre::bail!("Password does not match for user '{}'", username);
+                    }
+                    println!("Password matches for user '{}'", username);
+                }
+            }
+            Ok(())
+        }
+        Commands::Device(cmd) => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            match cmd {
+                DeviceCommands::List { user } => {
+                    let devices = service
+                        .list_devices(user.clone())
+                        .context("Failed to list devices")?;
+                    if devices.is_empty() {
+                        println!("No devices found for user '{}'", user);
+                    } else {
+                        println!(
+                            "{:<36} {:<24} {:<24} {:<24} LAST DOCUMENT",
+                            "DEVICE ID", "NAME", "FIRST SEEN", "LAST SEEN"
+                        );
+                        println!("{}", "-".repeat(130));
+                        for device in &devices {
+                            println!(
+                                "{:<36} {:<24} {:<24} {:<24} {}",
+                                device.device_id,
+                                device.display_name(),
+                                format_timestamp(device.first_seen as i64),
+                                format_timestamp(device.last_seen as i64),
+                                device.last_document
+                            );
+                        }
+                        println!("\nTotal: {} device(s)", devices.len());
+                    }
+                }
+                DeviceCommands::Rename {
+                    user,
+                    device_id,
+                    name,
+                } => {
+                    let renamed = service
+                        .rename_device(user.clone(), device_id.clone(), name)
+                        .context("Failed to rename device")?;
+                    if !renamed {
+                        eyre::bail!("Device '{}' not found for user '{}'", device_id, user);
+                    }
+                    println!("Device '{}' renamed successfully", device_id);
+                }
+                DeviceCommands::Forget { user, device_id } => {
+                    let forgotten = service
+                        .forget_device(user.clone(), device_id.clone())
+                        .context("Failed to forget device")?;
+                    if forgotten {
+                        println!("Device '{}' forgotten successfully", device_id);
+                    } else {
+                        println!("Device '{}' not found for user '{}'", device_id, user);
+                    }
+                }
+            }
+            Ok(())
+        }
+        Commands::Progress(cmd) => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            match cmd {
+                ProgressCommands::List { user } => {
+                    let progress = service
+                        .list_progress(user.clone())
+                        .context("Failed to list progress")?;
+                    if progress.is_empty() {
+                        println!("No progress found for user '{}'", user);
+                    } else {
+                        println!(
+                            "{:<34} {:<30} {:<20} {:>8}  {:<20} UPDATED",
+                            "DOCUMENT", "TITLE", "AUTHOR", "PROGRESS", "DEVICE"
+                        );
+                        println!("{}", "-".repeat(140));
+                        for (document, entry) in &progress {
+                            let metadata = service
+                                .get_document(user.clone(), document.clone())
+                                .context("Failed to get document metadata")?
+                                .unwrap_or_default();
+                            println!(
+                                "{:<34} {:<30} {:<20} {:>7.1}%  {:<20} {}",
+                                document,
+                                metadata.title.as_deref().unwrap_or("-"),
+                                metadata.author.as_deref().unwrap_or("-"),
+                                entry.percentage * 100.0,
+                                entry.device,
+                                format_timestamp(entry.timestamp as i64)
+                            );
+                        }
+                        println!("\nTotal: {} document(s)", progress.len());
+                    }
+                }
+                ProgressCommands::Delete { user, document } => {
+                    let deleted = service
+                        .delete_progress(user.clone(), document.clone())
+                        .context("Failed to delete progress")?;
+                    if deleted {
+                        println!("Progress of '{}' deleted successfully", document);
+                    } else {
+                        println!("No progress found for '{}' and user '{}'", document, user);
+                    }
+                }
+            }
+            Ok(())
+        }
+        Commands::Stats { user } => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            let history = service
+                .list_progress_history(user.clone())
+                .context("Failed to list progress history")?;
+            if history.is_empty() {
+                println!("No reading history found for user '{}'", user);
+                return Ok(());
+            }
+            let stats = ReadingStats::from_history(&history);
+
+            println!(
+                "{:<24} {:<34} {:<20} {:>9} {:>8}",
+                "STARTED", "DOCUMENT", "DEVICE", "DURATION", "READ"
+            );
+            println!("{}", "-".repeat(99));
+            for session in &stats.sessions {
+                println!(
+                    "{:<24} {:<34} {:<20} {:>9} {:>7.1}%",
+                    format_timestamp(session.start as i64),
+                    session.document,
+                    session.device,
+                    format_duration(session.duration_ms()),
+                    session.percentage_points()
+                );
+            }
+            println!("\nTotal: {} session(s)", stats.sessions.len());
+
+            println!(
+                "\nTime spent reading: {}",
+                format_duration(stats.total_reading_ms())
+            );
+            match stats.average_pace() {
+                Some(pace) => println!("Average pace: {:.1}% per hour", pace),
+                None => println!("Average pace: -"),
+            }
+
+            let months = stats.finished_per_month();
+            if months.is_empty() {
+                println!("Finished documents: none");
+            } else {
+                println!("Finished documents:");
+                for (month, count) in months {
+                    println!("  {:<8} {}", month, count);
+                }
+            }
+            Ok(())
+        }
+        Commands::Goals(cmd) => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            match cmd {
+                GoalCommands::Show { user } => {
+                    let goals = service
+                        .get_goals(user.clone())
+                        .context("Failed to get goals")?
+                        .unwrap_or_default();
+                    let history = service
+                        .list_progress_history(user.clone())
+                        .context("Failed to list progress history")?;
+                    let report = GoalReport::evaluate(
+                        &ReadingStats::from_history(&history),
+                        chrono::Utc::now().timestamp_millis() as u64,
+                    );
+
+                    println!("Goals of user '{}'", user);
+                    println!(
+                        "Reading streak: {} day(s) (longest: {})",
+                        report.current_streak, report.longest_streak
+                    );
+                    match goals.minutes_per_day {
+                        Some(target) => println!(
+                            "Today: {}/{} minutes{}",
+                            report.minutes_today,
+                            target,
+                            goal_status(report.daily_goal_met(&goals))
+                        ),
+                        None => println!("Today: {} minutes (no daily goal)", report.minutes_today),
+                    }
+                    match goals.books_per_year {
+                        Some(target) => println!(
+                            "This year: {}/{} books{}",
+                            report.books_this_year,
+                            target,
+                            goal_status(report.yearly_goal_met(&goals))
+                        ),
+                        None => println!(
+                            "This year: {} books (no yearly goal)",
+                            report.books_this_year
+                        ),
+                    }
+                }
+                GoalCommands::Set {
+                    user,
+                    books_per_year,
+                    minutes_per_day,
+                } => {
+                    if service
+                        .get_user(user.clone())
+                        .context("Failed to look up user")?
+                        .is_none()
+                    {
+                        eyre::bail!("User '{}' not found", user);
+                    }
+                    if books_per_year == Some(0) || minutes_per_day == Some(0) {
+                        eyre::bail!("Goals must be greater than 0");
+                    }
+                    service
+                        .set_goals(
+                            user.clone(),
+                            Goals {
+                                books_per_year,
+                                minutes_per_day,
+                                updated_at: chrono::Utc::now().timestamp_millis() as u64,
+                            },
+                            Quotas::from_env().default_quota(),
+                        )
+                        .context("Failed to set goals")?;
+                    println!("Goals of user '{}' updated successfully", user);
+                }
+            }
+            Ok(())
+        }
+        Commands::Shelf(cmd) => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            match cmd {
+                ShelfCommands::List { user, shelf } => {
+                    let entries: Vec<_> = service
+                        .list_shelves(user.clone())
+                        .context("Failed to list shelves")?
+                        .into_iter()
+                        .filter(|(_, entry)| shelf.is_none_or(|shelf| entry.shelf() == shelf))
+                        .collect();
+                    if entries.is_empty() {
+                        println!("No shelved documents found for user '{}'", user);
+                    } else {
+                        println!(
+                            "{:<34} {:<30} {:<10} {:<20} {:<20} READS",
+                            "DOCUMENT", "TITLE", "SHELF", "STARTED", "FINISHED"
+                        );
+                        println!("{}", "-".repeat(125));
+                        for (document, entry) in &entries {
+                            let metadata = service
+                                .get_document(user.clone(), document.clone())
+                                .context("Failed to get document metadata")?
+                                .unwrap_or_default();
+                            println!(
+                                "{:<34} {:<30} {:<10} {:<20} {:<20} {}",
+                                document,
+                                metadata.title.as_deref().unwrap_or("-"),
+                                entry.shelf().to_string(),
+                                entry
+                                    .current()
+                                    .map(|read| format_timestamp(read.started_at as i64))
+                                    .unwrap_or_else(|| "-".to_string()),
+                                entry
+                                    .finished_at()
+                                    .map(|at| format_timestamp(at as i64))
+                                    .unwrap_or_else(|| "-".to_string()),
+                                entry.reads.len()
+                            );
+                        }
+                        println!("\nTotal: {} document(s)", entries.len());
+                    }
+                }
+                ShelfCommands::Set {
+                    user,
+                    document,
+                    shelf,
+                } => {
+                    if service
+                        .get_user(user.clone())
+                        .context("Failed to look up user")?
+                        .is_none()
+                    {
+                        eyre::bail!("User '{}' not found", user);
+                    }
+                    service
+                        .set_shelf(
+                            user,
+                            document.clone(),
+                            shelf,
+                            chrono::Utc::now().timestamp_millis() as u64,
+                            Quotas::from_env().default_quota(),
+                        )
+                        .context("Failed to set shelf")?;
+                    println!("Document '{}' moved to the {} shelf", document, shelf);
+                }
+            }
+            Ok(())
+        }
+        Commands::Document(cmd) => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            match cmd {
+                DocumentCommands::Set {
+                    document,
+                    user,
+                    title,
+                    author,
+                    series,
+                    language,
+                    cover_url,
+                } => {
+                    let metadata = DocumentMetadata {
+                        title,
+                        author,
+                        series,
+                        language,
+                        cover_url,
+                        updated_at: chrono::Utc::now().timestamp_millis() as u64,
+                    };
+                    service
+                        .set_document(
+                            user.clone(),
+                            document.clone(),
+                            metadata,
+                            Quotas::from_env().default_quota(),
+                        )
+                        .context("Failed to save document metadata")?;
+                    match user {
+                        Some(user) => println!(
+                            "Metadata for document '{}' set for user '{}'",
+                            document, user
+                        ),
+                        None => println!("Global metadata for document '{}' set", document),
+                    }
+                }
+                DocumentCommands::Link {
+                    user,
+                    alias,
+                    document,
+                } => {
+                    if alias == document {
+                        eyre::bail!("A document cannot be an alias of itself");
+                    }
+                    let canonical = service
+                        .link_document(
+                            user,
+                            alias.clone(),
+                            document,
+                            Quotas::from_env().default_quota(),
+                        )
+                        .context("Failed to link document")?;
+                    println!("Document '{}' now resolves to '{}'", alias, canonical);
+                }
+                DocumentCommands::Unlink { user, alias } => {
+                    let unlinked = service
+                        .unlink_document(user.clone(), alias.clone())
+                        .context("Failed to unlink document")?;
+                    if unlinked {
+                        println!("Alias '{}' removed successfully", alias);
+                    } else {
+                        println!("Document '{}' is not an alias for user '{}'", alias, user);
+                    }
+                }
+                DocumentCommands::Aliases { user } => {
+                    let aliases = service
+                        .list_aliases(user.clone())
+                        .context("Failed to list aliases")?;
+                    if aliases.is_empty() {
+                        println!("No aliases found for user '{}'", user);
+                    } else {
+                        println!("{:<40} DOCUMENT", "ALIAS");
+                        println!("{}", "-".repeat(80));
+                        for (alias, document) in &aliases {
+                            println!("{:<40} {}", alias, document);
+                        }
+                        println!("\nTotal: {} alias(es)", aliases.len());
+                    }
+                }
+            }
+            Ok(())
+        }
+        Commands::Group(cmd) => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            match cmd {
+                GroupCommands::Create { name, owner } => {
+                    if service
+                        .get_user(owner.clone())
+                        .context("Failed to look up owner")?
+                        .is_none()
+                    {
+                        eyre::bail!("User '{}' not found", owner);
+                    }
+                    let group = service
+                        .create_or_update_group(Group::new(&name, &owner))
+                        .context("Failed to save group")?;
+                    service
+                        .record_event(
+                            AuditEvent::new(AuditKind::AdminAction, &owner)
+                                .with_detail(format!("group '{}' created via CLI", group.id)),
+                        )
+                        .context("Failed to record audit event")?;
+                    println!("Group '{}' created successfully", name);
+                    println!("ID: {}", group.id);
+                    println!("Invite code: {}", group.invite_code);
+                }
+                GroupCommands::List { user } => {
+                    let groups = service.list_groups(user).context("Failed to list groups")?;
+                    if groups.is_empty() {
+                        println!("No groups found");
+                    } else {
+                        println!(
+                            "{:<36} {:<24} {:<20} {:<8} CREATED",
+                            "ID", "NAME", "OWNER", "MEMBERS"
+                        );
+                        println!("{}", "-".repeat(115));
+                        for group in &groups {
+                            println!(
+                                "{:<36} {:<24} {:<20} {:<8} {}",
+                                group.id,
+                                group.name,
+                                group.owner,
+                                group.members.len(),
+                                format_timestamp(group.created_at as i64)
+                            );
+                        }
+                        println!("\nTotal: {} group(s)", groups.len());
+                    }
+                }
+                GroupCommands::Delete { id } => {
+                    let Some(group) = service
+                        .get_group(id.clone())
+                        .context("Failed to get group")?
+                    else {
+                        eyre::bail!("Group '{}' not found", id);
+                    };
+                    service
+                        .delete_group(id.clone())
+                        .context("Failed to delete group")?;
+                    service
+                        .record_event(
+                            AuditEvent::new(AuditKind::AdminAction, &group.owner)
+                                .with_detail(format!("group '{}' deleted via CLI", id)),
+                        )
+                        .context("Failed to record audit event")?;
+                    println!("Group '{}' deleted successfully", id);
+                }
             }
             Ok(())
         }
@@ -108,16 +678,186 @@ async fn main() -> eyre::Result<()> {
                     fs::copy(&db_path, &output).context("Failed to backup database")?;
                     println!("Database backed up to '{}'", output);
                 }
+                DbCommands::Gc { dry_run } => {
+                    let config = Retention::from_env();
+                    if !config.is_enabled() {
+                        println!(
+                            "No retention rules configured (see KORROSYNC_PROGRESS_RETENTION_DAYS and KORROSYNC_INACTIVE_USER_DAYS)"
+                        );
+                        return Ok(());
+                    }
+
+                    let service =
+                        KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+                    let report = collect_garbage(
+                        &service,
+                        &config,
+                        chrono::Utc::now().timestamp_millis(),
+                        dry_run,
+                    )
+                    .context("Failed to collect garbage")?;
+
+                    let (removed, flagged) = if dry_run {
+                        ("Would remove", "Would flag")
+                    } else {
+                        ("Removed", "Flagged")
+                    };
+                    println!(
+                        "{} {} stale progress entries",
+                        removed,
+                        report.stale_progress.len()
+                    );
+                    for (user, document, timestamp) in &report.stale_progress {
+                        println!(
+                            "  {:<20} {:<34} last updated {}",
+                            user,
+                            document,
+                            format_timestamp(*timestamp as i64)
+                        );
+                    }
+                    println!(
+                        "{} {} inactive user(s)",
+                        flagged,
+                        report.flagged_users.len()
+                    );
+                    for (user, last_activity) in &report.flagged_users {
+                        println!(
+                            "  {:<20} last active {}",
+                            user,
+                            format_timestamp(*last_activity)
+                        );
+                    }
+                    println!(
+                        "{} {} inactive user(s)",
+                        removed,
+                        report.removed_users.len()
+                    );
+                    for (user, last_activity) in &report.removed_users {
+                        println!(
+                            "  {:<20} last active {}",
+                            user,
+                            format_timestamp(*last_activity)
+                        );
+                    }
+                    if !report.reactivated_users.is_empty() {
+                        println!("Active again: {}", report.reactivated_users.join(", "));
+                    }
+                }
+            }
+            Ok(())
+        }
+        Commands::Audit(cmd) => {
+            let db_path = resolve_db_path(cli.db_path);
+            let service = KorrosyncServiceRedb::new(&db_path).context("Failed to open database")?;
+
+            match cmd {
+                AuditCommands::List { user, since } => {
+                    let since = since.as_deref().map(parse_since).transpose()?;
+                    let events = service
+                        .list_events(user, since)
+                        .context("Failed to list audit events")?;
+                    if events.is_empty() {
+                        println!("No audit events found");
+                    } else {
+                        println!(
+                            "{:<24} {:<16} {:<20} {:<40} DETAIL",
+                            "TIMESTAMP", "EVENT", "USERNAME", "IP"
+                        );
+                        println!("{}", "-".repeat(110));
+                        for event in &events {
+                            println!(
+                                "{:<24} {:<16} {:<20} {:<40} {}",
+                                format_timestamp(event.timestamp),
+                                event.kind.to_string(),
+                                event.username,
+                                event.ip.as_deref().unwrap_or("-"),
+                                event.detail
+                            );
+                        }
+                        println!("\nTotal: {} event(s)", events.len());
+                    }
+                }
             }
             Ok(())
         }
     }
 }
 
+/// The quota of a user, and whether it was set for them rather than the default
+fn user_quota(
+    service: &KorrosyncServiceRedb,
+    quotas: &Quotas,
+    username: &str,
+) -> eyre::Result<(Quota, bool)> {
+    let quota = service
+        .get_quota(username.to_string())
+        .context("Failed to query quota")?;
+    Ok(match quota {
+        Some(quota) => (quota, true),
+        None => (quotas.default_quota(), false),
+    })
+}
+
+fn user_usage(service: &KorrosyncServiceRedb, username: &str) -> eyre::Result<QuotaUsage> {
+    service
+        .get_usage(username.to_string())
+        .context("Failed to query usage")
+}
+
+fn format_usage(used: u64, max: Option<u64>) -> String {
+    match max {
+        Some(max) => format!("{used}/{max}"),
+        None => format!("{used}/unlimited"),
+    }
+}
+
+fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ts)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+/// Formats a duration in milliseconds as hours and minutes (e.g. `1h 05m`).
+fn format_duration(ms: u64) -> String {
+    let minutes = ms / 60_000;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Suffix describing whether a goal is met.
+fn goal_status(met: Option<bool>) -> &'static str {
+    match met {
+        Some(true) => " (goal met)",
+        _ => "",
+    }
+}
+
+/// Parses a `--since` argument (`YYYY-MM-DD` or RFC 3339) into milliseconds since the epoch.
+fn parse_since(since: &str) -> eyre::Result<i64> {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(since) {
+        return Ok(dt.timestamp_millis());
+    }
+    let date = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}', expected YYYY-MM-DD or RFC 3339", since))?;
+    Ok(date
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc()
+        .timestamp_millis())
+}
+
 fn resolve_db_path(cli_override: Option<String>) -> String {
     cli_override.unwrap_or_else(|| Config::from_env().db.path)
 }
 
+/// Returns the key to store for a password: the KOReader key (MD5 hex digest) unless the
+/// raw password was explicitly requested.
+fn resolve_key(password: String, raw_key: bool) -> String {
+    if raw_key {
+        password
+    } else {
+        koreader_key(password)
+    }
+}
+
 fn resolve_password(password: String) -> eyre::Result<String> {
     if password != "-" {
         return Ok(password);
@@ -0,0 +1,144 @@
+//! Highlights and notes attached to a document.
+//!
+//! KOReader keeps annotations on the device, so they are lost whenever a device is reset.
+//! This module defines [`Annotation`], the synchronized copy of one of them. Annotations
+//! are identified by an id chosen by the client and merged across devices with a
+//! last-writer-wins rule; deleting one leaves a tombstone behind so the deletion reaches
+//! the other devices instead of the annotation being resurrected by them.
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// A highlight, with an optional note, in a document.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::Annotation;
+///
+/// let kobo = Annotation {
+///     id: "a1".to_string(),
+///     text: Some("It was a pleasure to burn.".to_string()),
+///     updated_at: 1_000,
+///     ..Default::default()
+/// };
+/// let phone = Annotation {
+///     note: Some("Opening line".to_string()),
+///     updated_at: 2_000,
+///     ..kobo.clone()
+/// };
+///
+/// // The most recent edit wins, whatever the order they are merged in
+/// assert_eq!(kobo.clone().merge(phone.clone()), phone);
+/// assert_eq!(phone.clone().merge(kobo), phone);
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Annotation {
+    /// Identifier chosen by the client, unique within the document
+    pub id: String,
+    /// Highlighted text
+    pub text: Option<String>,
+    /// Note attached to the highlight
+    pub note: Option<String>,
+    /// Start of the highlighted range (KOReader xpointer or page)
+    pub pos0: String,
+    /// End of the highlighted range (KOReader xpointer or page)
+    pub pos1: String,
+    /// Chapter the highlight belongs to
+    pub chapter: Option<String>,
+    /// Highlight color (e.g. `yellow`)
+    pub color: Option<String>,
+    /// Unix timestamp in milliseconds when the annotation was created
+    pub created_at: u64,
+    /// Unix timestamp in milliseconds of the last change, including deletion
+    pub updated_at: u64,
+    /// Whether the annotation was deleted; deleted annotations are kept as tombstones
+    pub deleted: bool,
+}
+
+impl Annotation {
+    /// Merges two versions of the same annotation, keeping the most recently updated one.
+    ///
+    /// On a tie, a deletion wins over an edit, and otherwise the comparison falls back to
+    /// the content, so every device converges to the same version regardless of the order
+    /// in which updates are merged.
+    pub fn merge(self, other: Annotation) -> Annotation {
+        let rank = |a: &Annotation| {
+            (
+                a.updated_at,
+                a.deleted,
+                a.note.clone(),
+                a.text.clone(),
+                a.color.clone(),
+            )
+        };
+        if rank(&other) > rank(&self) {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// Turns the annotation into a tombstone deleted at the given Unix timestamp (milliseconds).
+    ///
+    /// The content is dropped; only what is needed to propagate the deletion is kept.
+    pub fn delete(self, at: u64) -> Annotation {
+        Annotation {
+            id: self.id,
+            pos0: self.pos0,
+            pos1: self.pos1,
+            created_at: self.created_at,
+            updated_at: at.max(self.updated_at),
+            deleted: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(updated_at: u64, note: &str) -> Annotation {
+        Annotation {
+            id: "a1".to_string(),
+            text: Some("text".to_string()),
+            note: Some(note.to_string()),
+            pos0: "/body/DocFragment[3]/body/p[1]/text().0".to_string(),
+            pos1: "/body/DocFragment[3]/body/p[1]/text().42".to_string(),
+            created_at: 1,
+            updated_at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_latest_update() {
+        let old = annotation(10, "old");
+        let new = annotation(20, "new");
+
+        assert_eq!(old.clone().merge(new.clone()).note.as_deref(), Some("new"));
+        assert_eq!(new.merge(old).note.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_merge_ties_are_deterministic() {
+        let a = annotation(10, "a");
+        let b = annotation(10, "b");
+        let deleted = annotation(10, "c").delete(10);
+
+        assert_eq!(a.clone().merge(b.clone()), b.clone().merge(a.clone()));
+        assert!(a.merge(deleted.clone()).deleted);
+        assert!(deleted.merge(b).deleted);
+    }
+
+    #[test]
+    fn test_delete_leaves_tombstone() {
+        let tombstone = annotation(10, "note").delete(5);
+
+        assert!(tombstone.deleted);
+        assert_eq!(tombstone.id, "a1");
+        assert_eq!(tombstone.updated_at, 10);
+        assert_eq!(tombstone.note, None);
+        assert_eq!(tombstone.text, None);
+    }
+}
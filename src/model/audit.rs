@@ -0,0 +1,99 @@
+//! Security audit events.
+//!
+//! This module defines the [`AuditEvent`] struct, a persistent record of an authentication
+//! or account related action (registrations, logins, failed logins, password changes,
+//! deletions and administrative actions). Audit events are append-only: they are never modified once
+//! recorded and are only removed by the retention policy.
+
+use std::fmt;
+
+use chrono::Utc;
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Kind of action recorded by an [`AuditEvent`].
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AuditKind {
+    /// A new account was registered through the API
+    #[default]
+    Registration,
+    /// An authentication attempt was rejected
+    AuthFailure,
+    /// A user changed their own password
+    PasswordChange,
+    /// A password was reset by an administrator
+    PasswordReset,
+    /// An account was deleted, either by its owner or by an administrator
+    UserDeleted,
+    /// Any other administrative action (e.g. creating a user from the CLI)
+    AdminAction,
+    /// A user authenticated after being inactive for a while (see the auth middleware)
+    Login,
+}
+
+impl fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditKind::Registration => "registration",
+            AuditKind::AuthFailure => "auth_failure",
+            AuditKind::PasswordChange => "password_change",
+            AuditKind::PasswordReset => "password_reset",
+            AuditKind::UserDeleted => "user_deleted",
+            AuditKind::AdminAction => "admin_action",
+            AuditKind::Login => "login",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A single entry of the security audit log.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::{AuditEvent, AuditKind};
+///
+/// let event = AuditEvent::new(AuditKind::AuthFailure, "alice")
+///     .with_ip(Some("192.168.1.10".to_string()))
+///     .with_detail("invalid password");
+///
+/// assert_eq!(event.username, "alice");
+/// assert_eq!(event.kind, AuditKind::AuthFailure);
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone)]
+pub struct AuditEvent {
+    /// Unix timestamp in milliseconds when the event happened
+    pub timestamp: i64,
+    /// What happened
+    pub kind: AuditKind,
+    /// Username the event refers to (may not exist, e.g. failed logins)
+    pub username: String,
+    /// Source IP address of the request, when the event originated from the API
+    pub ip: Option<String>,
+    /// Free-form details about the event
+    pub detail: String,
+}
+
+impl AuditEvent {
+    /// Creates a new event of the given kind for a user, timestamped now.
+    pub fn new(kind: AuditKind, username: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now().timestamp_millis(),
+            kind,
+            username: username.into(),
+            ip: None,
+            detail: String::new(),
+        }
+    }
+
+    /// Sets the source IP address of the event.
+    pub fn with_ip(mut self, ip: Option<String>) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    /// Sets the free-form details of the event.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+}
@@ -0,0 +1,76 @@
+//! Named bookmarks in a document.
+//!
+//! Unlike [`Progress`](crate::model::Progress), which only keeps where a document was last
+//! read, a user can keep any number of [`Bookmark`]s per document. They are merged across
+//! devices the same way as [`Annotation`](crate::model::Annotation)s: the most recent
+//! change wins, and deleted bookmarks are kept as tombstones.
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// A labelled position in a document.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::Bookmark;
+///
+/// let bookmark = Bookmark::new("Chapter 3", "/body/DocFragment[7].0", 1_000);
+/// assert_eq!(bookmark.id.len(), 32);
+///
+/// let tombstone = bookmark.clone().delete(2_000);
+/// assert!(tombstone.deleted);
+/// assert_eq!(bookmark.merge(tombstone.clone()), tombstone);
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Bookmark {
+    /// Identifier of the bookmark, unique within the document
+    pub id: String,
+    /// Label chosen by the user
+    pub label: String,
+    /// Bookmarked position (KOReader xpointer or page number)
+    pub position: String,
+    /// Unix timestamp in milliseconds when the bookmark was created
+    pub created_at: u64,
+    /// Unix timestamp in milliseconds of the last change, including deletion
+    pub updated_at: u64,
+    /// Whether the bookmark was deleted; deleted bookmarks are kept as tombstones
+    pub deleted: bool,
+}
+
+impl Bookmark {
+    /// Creates a bookmark with a new random id, created at the given Unix timestamp (milliseconds).
+    pub fn new(label: impl Into<String>, position: impl Into<String>, at: u64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            label: label.into(),
+            position: position.into(),
+            created_at: at,
+            updated_at: at,
+            deleted: false,
+        }
+    }
+
+    /// Merges two versions of the same bookmark, keeping the most recently updated one.
+    ///
+    /// On a tie, a deletion wins over an edit, and otherwise the comparison falls back to
+    /// the content, so the result does not depend on the order of the merges.
+    pub fn merge(self, other: Bookmark) -> Bookmark {
+        let rank = |b: &Bookmark| (b.updated_at, b.deleted, b.label.clone(), b.position.clone());
+        if rank(&other) > rank(&self) {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// Turns the bookmark into a tombstone deleted at the given Unix timestamp (milliseconds).
+    pub fn delete(self, at: u64) -> Bookmark {
+        Bookmark {
+            id: self.id,
+            created_at: self.created_at,
+            updated_at: at.max(self.updated_at),
+            deleted: true,
+            ..Default::default()
+        }
+    }
+}
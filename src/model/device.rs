@@ -0,0 +1,75 @@
+//! Devices known to belong to a user.
+//!
+//! This module defines the [`Device`] struct, which tracks a KOReader device that has
+//! synchronized progress for a user: when it was first and last seen, the last document it
+//! reported and the address it connected from. Users can give devices a friendly name to
+//! tell identical models apart.
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::model::Progress;
+
+/// A device that has reported reading progress for a user.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::Device;
+///
+/// let mut device = Device {
+///     device_id: "kindle-123".to_string(),
+///     device: "Kindle Paperwhite".to_string(),
+///     name: None,
+///     first_seen: 1704067200000,
+///     last_seen: 1704067200000,
+///     last_document: "book.epub".to_string(),
+///     last_ip: Some("192.168.1.10".to_string()),
+/// };
+/// assert_eq!(device.display_name(), "Kindle Paperwhite");
+///
+/// device.name = Some("Bedroom Kindle".to_string());
+/// assert_eq!(device.display_name(), "Bedroom Kindle");
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone)]
+pub struct Device {
+    /// Unique identifier reported by the device
+    pub device_id: String,
+    /// Device model as reported by the device
+    pub device: String,
+    /// Optional friendly name chosen by the user
+    pub name: Option<String>,
+    /// Unix timestamp in milliseconds when the device was first seen
+    pub first_seen: u64,
+    /// Unix timestamp in milliseconds when the device was last seen
+    pub last_seen: u64,
+    /// Last document the device reported progress for
+    pub last_document: String,
+    /// IP address the device last connected from, if known
+    pub last_ip: Option<String>,
+}
+
+impl Device {
+    /// The device that reported a progress update, seen for the first time when it did.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - The reported progress
+    /// * `document` - The document the progress was reported for
+    /// * `ip` - IP address the update came from, if known
+    pub fn reporting(progress: &Progress, document: String, ip: Option<String>) -> Self {
+        Self {
+            device_id: progress.device_id.clone(),
+            device: progress.device.clone(),
+            name: None,
+            first_seen: progress.timestamp,
+            last_seen: progress.timestamp,
+            last_document: document,
+            last_ip: ip,
+        }
+    }
+
+    /// Returns the friendly name if set, otherwise the reported device model.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.device)
+    }
+}
@@ -0,0 +1,38 @@
+//! Human-readable metadata for documents.
+//!
+//! KOReader identifies documents by an opaque digest (usually a partial MD5 of the file),
+//! which is what progress is stored against. This module defines [`DocumentMetadata`], the
+//! title, author and other details attached to such an identifier so listings can show
+//! what is actually being read.
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Descriptive metadata of a document.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::DocumentMetadata;
+///
+/// let metadata = DocumentMetadata {
+///     title: Some("Dune".to_string()),
+///     author: Some("Frank Herbert".to_string()),
+///     ..Default::default()
+/// };
+/// assert_eq!(metadata.title.as_deref(), Some("Dune"));
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct DocumentMetadata {
+    /// Title of the document
+    pub title: Option<String>,
+    /// Author(s) of the document
+    pub author: Option<String>,
+    /// Series the document belongs to
+    pub series: Option<String>,
+    /// Language of the document (e.g. `en`)
+    pub language: Option<String>,
+    /// URL of a cover image
+    pub cover_url: Option<String>,
+    /// Unix timestamp in milliseconds when the metadata was last updated
+    pub updated_at: u64,
+}
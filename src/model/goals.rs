@@ -0,0 +1,216 @@
+//! Reading goals and streaks.
+//!
+//! A user can set [`Goals`] (books per year, minutes per day). They are evaluated against
+//! the [`ReadingStats`] derived from the progress history, producing a [`GoalReport`] with
+//! the reading streak and how far each goal is. Days are calendar days in UTC.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Datelike, NaiveDate};
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::model::ReadingStats;
+
+/// Reading goals of a user.
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Goals {
+    /// Number of documents to finish each calendar year
+    pub books_per_year: Option<u32>,
+    /// Minutes to read each day
+    pub minutes_per_day: Option<u32>,
+    /// Unix timestamp in milliseconds when the goals were last updated
+    pub updated_at: u64,
+}
+
+/// Reading streak and goal progress of a user at a point in time.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::{GoalReport, Goals, Progress, ReadingStats};
+///
+/// const DAY: u64 = 24 * 60 * 60 * 1000;
+/// let at = |timestamp| ("book.epub".to_string(), Progress {
+///     device_id: "kobo-1".to_string(),
+///     timestamp,
+///     ..Default::default()
+/// });
+/// // Read on three days in a row, the last one being today
+/// let today = 20_000 * DAY;
+/// let history = vec![at(today - 2 * DAY), at(today - DAY), at(today)];
+/// let goals = Goals { minutes_per_day: Some(20), ..Default::default() };
+///
+/// let report = GoalReport::evaluate(&ReadingStats::from_history(&history), today);
+/// assert_eq!(report.current_streak, 3);
+/// assert_eq!(report.daily_goal_met(&goals), Some(false));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GoalReport {
+    /// Consecutive days with reading, ending today (or yesterday, if nothing was read yet today)
+    pub current_streak: u32,
+    /// Longest run of consecutive days with reading
+    pub longest_streak: u32,
+    /// Minutes read today
+    pub minutes_today: u64,
+    /// Documents finished in the current year
+    pub books_this_year: usize,
+}
+
+impl GoalReport {
+    /// Evaluates the reading statistics at the given Unix timestamp (milliseconds).
+    ///
+    /// A day counts towards a streak as soon as some progress was reported on it. Reading
+    /// time is attributed to the day each session started.
+    pub fn evaluate(stats: &ReadingStats, now: u64) -> Self {
+        let today = date(now);
+
+        let mut days = BTreeSet::new();
+        let mut minutes: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+        for session in &stats.sessions {
+            let (start, end) = (date(session.start), date(session.end));
+            days.extend(start.iter_days().take_while(|day| *day <= end));
+            *minutes.entry(start).or_default() += session.duration_ms() / 60_000;
+        }
+
+        let mut longest_streak = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for day in &days {
+            run = match previous {
+                Some(previous) if previous.succ_opt() == Some(*day) => run + 1,
+                _ => 1,
+            };
+            longest_streak = longest_streak.max(run);
+            previous = Some(*day);
+        }
+
+        let mut current_streak = 0;
+        let mut day = if days.contains(&today) {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        while let Some(d) = day.filter(|d| days.contains(d)) {
+            current_streak += 1;
+            day = d.pred_opt();
+        }
+
+        Self {
+            current_streak,
+            longest_streak,
+            minutes_today: minutes.get(&today).copied().unwrap_or_default(),
+            books_this_year: stats
+                .finished
+                .iter()
+                .filter(|finished| date(finished.finished_at).year() == today.year())
+                .count(),
+        }
+    }
+
+    /// Whether today's reading time reached the daily goal, or `None` without a daily goal.
+    pub fn daily_goal_met(&self, goals: &Goals) -> Option<bool> {
+        goals
+            .minutes_per_day
+            .map(|target| self.minutes_today >= u64::from(target))
+    }
+
+    /// Whether this year's finished documents reached the yearly goal, or `None` without a
+    /// yearly goal.
+    pub fn yearly_goal_met(&self, goals: &Goals) -> Option<bool> {
+        goals
+            .books_per_year
+            .map(|target| self.books_this_year >= target as usize)
+    }
+}
+
+/// UTC calendar day of a Unix timestamp in milliseconds
+fn date(timestamp: u64) -> NaiveDate {
+    DateTime::from_timestamp_millis(timestamp as i64)
+        .map(|date| date.date_naive())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Progress;
+
+    const MINUTE: u64 = 60 * 1000;
+    const DAY: u64 = 24 * 60 * MINUTE;
+    /// 2024-03-10T00:00:00Z
+    const TODAY: u64 = 1_710_028_800_000;
+
+    fn update(document: &str, timestamp: u64, percentage: f32) -> (String, Progress) {
+        (
+            document.to_string(),
+            Progress {
+                device_id: "kobo".to_string(),
+                percentage,
+                timestamp,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn report(history: &[(String, Progress)], now: u64) -> GoalReport {
+        GoalReport::evaluate(&ReadingStats::from_history(history), now)
+    }
+
+    #[test]
+    fn test_streaks() {
+        let history = vec![
+            // A two day run a week ago
+            update("a", TODAY - 7 * DAY, 0.1),
+            update("a", TODAY - 6 * DAY, 0.2),
+            // A three day run ending yesterday
+            update("a", TODAY - 3 * DAY, 0.3),
+            update("a", TODAY - 2 * DAY, 0.4),
+            update("a", TODAY - DAY, 0.5),
+        ];
+
+        // Nothing read yet today: the streak is still alive
+        let result = report(&history, TODAY + 10 * MINUTE);
+        assert_eq!(result.current_streak, 3);
+        assert_eq!(result.longest_streak, 3);
+
+        // A day without reading breaks it
+        let result = report(&history, TODAY + DAY);
+        assert_eq!(result.current_streak, 0);
+        assert_eq!(result.longest_streak, 3);
+    }
+
+    #[test]
+    fn test_session_across_midnight_counts_for_both_days() {
+        let history = vec![
+            update("a", TODAY - 10 * MINUTE, 0.1),
+            update("a", TODAY + 10 * MINUTE, 0.2),
+        ];
+
+        let result = report(&history, TODAY + 60 * MINUTE);
+        assert_eq!(result.current_streak, 2);
+        // Reading time belongs to the day the session started
+        assert_eq!(result.minutes_today, 0);
+    }
+
+    #[test]
+    fn test_goal_completion() {
+        let history = vec![
+            update("a", TODAY + 60 * MINUTE, 0.5),
+            update("a", TODAY + 90 * MINUTE, 1.0),
+            // Finished last year
+            update("b", TODAY - 100 * DAY, 1.0),
+        ];
+        let goals = Goals {
+            books_per_year: Some(2),
+            minutes_per_day: Some(30),
+            ..Default::default()
+        };
+
+        let result = report(&history, TODAY + 120 * MINUTE);
+        assert_eq!(result.minutes_today, 30);
+        assert_eq!(result.books_this_year, 1);
+        assert_eq!(result.daily_goal_met(&goals), Some(true));
+        assert_eq!(result.yearly_goal_met(&goals), Some(false));
+        assert_eq!(result.daily_goal_met(&Goals::default()), None);
+    }
+}
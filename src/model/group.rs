@@ -0,0 +1,225 @@
+//! Reading groups with shared progress visibility.
+//!
+//! This module defines the [`Group`] struct: a named set of users (e.g. a book club) that
+//! can see each other's progress on a document. Users join a group with its invite code,
+//! and each member decides how much of their progress is visible to the rest of the group
+//! through their [`Visibility`] setting.
+
+use std::{fmt, str::FromStr};
+
+use chrono::Utc;
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// How much of a member's progress is shared with the rest of the group.
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Percentage, position, device and timestamp are shared
+    #[default]
+    Full,
+    /// Only the percentage and timestamp are shared
+    Percentage,
+    /// Nothing is shared (opt-out)
+    Hidden,
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Visibility::Full => "full",
+            Visibility::Percentage => "percentage",
+            Visibility::Hidden => "hidden",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Visibility::Full),
+            "percentage" => Ok(Visibility::Percentage),
+            "hidden" => Ok(Visibility::Hidden),
+            other => Err(format!(
+                "Invalid visibility '{other}'. Expected: full, percentage or hidden"
+            )),
+        }
+    }
+}
+
+/// A member of a [`Group`].
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone)]
+pub struct GroupMember {
+    /// Username of the member
+    pub username: String,
+    /// What the member shares with the group
+    pub visibility: Visibility,
+    /// Unix timestamp in milliseconds when the member joined
+    pub joined_at: u64,
+}
+
+/// A reading group.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::Group;
+///
+/// let mut group = Group::new("Book club", "alice");
+/// assert_eq!(group.owner, "alice");
+/// assert!(group.is_member("alice"));
+///
+/// group.add_member("bob");
+/// assert!(group.is_member("bob"));
+///
+/// // When the owner leaves, ownership passes to the next member
+/// group.remove_member("alice");
+/// assert_eq!(group.owner, "bob");
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone)]
+pub struct Group {
+    /// Unique identifier of the group
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Username of the owner, who can manage invites and delete the group
+    pub owner: String,
+    /// Secret code other users need to join the group
+    pub invite_code: String,
+    /// Members of the group, including the owner, in joining order
+    pub members: Vec<GroupMember>,
+    /// Unix timestamp in milliseconds when the group was created
+    pub created_at: u64,
+}
+
+impl Group {
+    /// Creates a new group owned by `owner`, who becomes its first member.
+    ///
+    /// A random identifier and invite code are generated.
+    pub fn new(name: impl Into<String>, owner: impl Into<String>) -> Self {
+        let owner = owner.into();
+        let now = Utc::now().timestamp_millis() as u64;
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.into(),
+            owner: owner.clone(),
+            invite_code: new_invite_code(),
+            members: vec![GroupMember {
+                username: owner,
+                visibility: Visibility::default(),
+                joined_at: now,
+            }],
+            created_at: now,
+        }
+    }
+
+    /// Returns the membership of `username`, if they belong to the group.
+    pub fn member(&self, username: &str) -> Option<&GroupMember> {
+        self.members.iter().find(|m| m.username == username)
+    }
+
+    /// Returns `true` if `username` belongs to the group.
+    pub fn is_member(&self, username: &str) -> bool {
+        self.member(username).is_some()
+    }
+
+    /// Adds `username` to the group with the default visibility.
+    ///
+    /// Returns `false` if the user was already a member.
+    pub fn add_member(&mut self, username: impl Into<String>) -> bool {
+        let username = username.into();
+        if self.is_member(&username) {
+            return false;
+        }
+
+        self.members.push(GroupMember {
+            username,
+            visibility: Visibility::default(),
+            joined_at: Utc::now().timestamp_millis() as u64,
+        });
+        true
+    }
+
+    /// Removes `username` from the group.
+    ///
+    /// If the owner is removed, ownership passes to the longest-standing remaining member.
+    /// Returns `false` if the user was not a member.
+    pub fn remove_member(&mut self, username: &str) -> bool {
+        let before = self.members.len();
+        self.members.retain(|m| m.username != username);
+        if self.members.len() == before {
+            return false;
+        }
+
+        if self.owner == username
+            && let Some(next) = self.members.first()
+        {
+            self.owner = next.username.clone();
+        }
+        true
+    }
+
+    /// Generates a new invite code, invalidating the previous one.
+    pub fn rotate_invite_code(&mut self) {
+        self.invite_code = new_invite_code();
+    }
+}
+
+fn new_invite_code() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_group_has_owner_as_member() {
+        let group = Group::new("Book club", "alice");
+
+        assert_eq!(group.members.len(), 1);
+        assert_eq!(group.members[0].username, "alice");
+        assert_eq!(group.members[0].visibility, Visibility::Full);
+        assert!(!group.invite_code.is_empty());
+    }
+
+    #[test]
+    fn test_add_member_is_idempotent() {
+        let mut group = Group::new("Book club", "alice");
+
+        assert!(group.add_member("bob"));
+        assert!(!group.add_member("bob"));
+        assert_eq!(group.members.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_owner_transfers_ownership() {
+        let mut group = Group::new("Book club", "alice");
+        group.add_member("bob");
+        group.add_member("carol");
+
+        assert!(group.remove_member("alice"));
+        assert_eq!(group.owner, "bob");
+
+        assert!(!group.remove_member("alice"));
+    }
+
+    #[test]
+    fn test_rotate_invite_code() {
+        let mut group = Group::new("Book club", "alice");
+        let old = group.invite_code.clone();
+
+        group.rotate_invite_code();
+        assert_ne!(old, group.invite_code);
+    }
+
+    #[test]
+    fn test_visibility_round_trip() {
+        for visibility in [Visibility::Full, Visibility::Percentage, Visibility::Hidden] {
+            assert_eq!(visibility.to_string().parse::<Visibility>(), Ok(visibility));
+        }
+        assert!("public".parse::<Visibility>().is_err());
+    }
+}
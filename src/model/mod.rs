@@ -13,6 +13,63 @@
 //! Represents reading progress for a specific document on a specific device.
 //! Tracks the current position, percentage complete, device information, and timestamp.
 //!
+//! ## [`ReadingStats`]
+//!
+//! Reading sessions, finished documents and pace, derived from the history of progress
+//! updates.
+//!
+//! ## [`Goals`]
+//!
+//! Books-per-year and minutes-per-day goals of a user, evaluated against the reading
+//! statistics into a [`GoalReport`] with the current reading streak.
+//!
+//! ## [`ShelfEntry`]
+//!
+//! The [`Shelf`] a document sits on (reading, finished or abandoned), with each
+//! [`ReadThrough`] of it and when it was finished.
+//!
+//! ## [`Quota`]
+//!
+//! Limits on the number of documents and stored bytes of a user, checked against their
+//! [`QuotaUsage`] whenever they store progress, annotations, bookmarks or statistics.
+//!
+//! ## [`StatisticsFile`]
+//!
+//! Size and digest of the KOReader statistics database a user synchronizes, kept apart
+//! from the database itself.
+//!
+//! ## [`DocumentMetadata`]
+//!
+//! Title, author, series, language and cover of a document, attached to the opaque
+//! identifier KOReader uses for it.
+//!
+//! ## [`Annotation`]
+//!
+//! A highlight or note in a document, merged across devices with deletion tombstones.
+//!
+//! ## [`Bookmark`]
+//!
+//! A named position in a document, kept alongside the last-read [`Progress`].
+//!
+//! ## [`Device`]
+//!
+//! A device that has synchronized progress for a user, with first/last seen tracking and
+//! an optional friendly name.
+//!
+//! ## [`Group`]
+//!
+//! A reading group whose members can see each other's progress, according to each
+//! member's [`Visibility`] setting.
+//!
+//! ## [`Share`]
+//!
+//! A revocable, optionally expiring public link to a user's progress on one document.
+//!
+//! ## [`AuditEvent`]
+//!
+//! An entry of the security audit log (registrations, failed logins, password changes,
+//! account deletions and administrative actions).
+//!
 //! ## [`Error`]
 //!
 //! Model-specific errors that can occur during user or progress operations.
@@ -33,13 +90,38 @@
 //!     percentage: 42.5,
 //!     progress: "Page 85 of 200".to_string(),
 //!     timestamp: 1704067200000,
+//!     client_timestamp: None,
 //! };
 //! ```
 
+mod annotation;
+mod audit;
+mod bookmark;
+mod device;
+mod document;
 mod error;
+mod goals;
+mod group;
 mod progress;
+mod quota;
+mod share;
+mod shelf;
+mod statistics_file;
+mod stats;
 mod user;
 
+pub use annotation::Annotation;
+pub use audit::{AuditEvent, AuditKind};
+pub use bookmark::Bookmark;
+pub use device::Device;
+pub use document::DocumentMetadata;
 pub use error::Error;
+pub use goals::{GoalReport, Goals};
+pub use group::{Group, GroupMember, Visibility};
 pub use progress::Progress;
-pub use user::User;
+pub use quota::{Quota, QuotaUsage, StoredSize};
+pub use share::Share;
+pub use shelf::{ReadThrough, Shelf, ShelfEntry};
+pub use statistics_file::StatisticsFile;
+pub use stats::{FINISHED_PERCENTAGE, FinishedDocument, ReadingSession, ReadingStats};
+pub use user::{User, koreader_key};
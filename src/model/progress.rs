@@ -3,6 +3,10 @@
 //! This module defines the [`Progress`] struct which represents a user's reading position
 //! and metadata for a specific document. Progress is synchronized across devices and
 //! includes information about the device, position, and timestamp.
+//!
+//! Besides the time the server received an update, a progress record may carry the time
+//! the client says the position was reached, e.g. when it was recorded offline and pushed
+//! later.
 
 use rkyv::{Archive, Deserialize, Serialize};
 
@@ -22,7 +26,10 @@ use rkyv::{Archive, Deserialize, Serialize};
 ///     percentage: 67.5,
 ///     progress: "Page 135 of 200".to_string(),
 ///     timestamp: 1609459200000,
+///     client_timestamp: None,
 /// };
+///
+/// assert_eq!(progress.reported_at(), 1609459200000);
 /// ```
 #[derive(Debug, Archive, Serialize, Deserialize, Default, Clone)]
 pub struct Progress {
@@ -34,6 +41,16 @@ pub struct Progress {
     pub percentage: f32,
     /// Textual representation of progress
     pub progress: String,
-    /// Unix timestamp in milliseconds when progress was last updated
+    /// Unix timestamp in milliseconds when the server received the update
     pub timestamp: u64,
+    /// Unix timestamp in milliseconds reported by the client, if it was trusted
+    pub client_timestamp: Option<u64>,
+}
+
+impl Progress {
+    /// Returns when the position was reached: the client-reported time if there is one,
+    /// the server-received time otherwise.
+    pub fn reported_at(&self) -> u64 {
+        self.client_timestamp.unwrap_or(self.timestamp)
+    }
 }
@@ -0,0 +1,264 @@
+//! Per-user storage quotas.
+//!
+//! A [`Quota`] limits how many documents a user may have progress for and how many bytes
+//! their data may take: progress, devices, annotations, bookmarks, the statistics database,
+//! shelves, reading goals, share links, document aliases and document metadata.
+//! [`QuotaUsage`] measures what a user currently stores, so an update can be
+//! rejected when it would grow past a limit.
+//!
+//! The history of progress updates only grows, so it is not counted: a user at their limit
+//! can keep syncing the documents they have.
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::model::{
+    Annotation, Bookmark, Device, DocumentMetadata, Goals, Progress, Share, ShelfEntry,
+};
+
+/// Bytes taken by the fixed-size fields of a progress entry (percentage and timestamps)
+const PROGRESS_FIXED_BYTES: u64 = 4 + 8 + 9;
+/// Bytes taken by the fixed-size fields of a device (first and last seen)
+const DEVICE_FIXED_BYTES: u64 = 8 + 8;
+/// Bytes taken by the fixed-size fields of an annotation or bookmark (timestamps and deletion flag)
+const MARK_FIXED_BYTES: u64 = 8 + 8 + 1;
+/// Bytes taken by a read-through of a shelf entry (shelf and timestamps)
+const READ_THROUGH_BYTES: u64 = 1 + 8 + 9;
+/// Bytes taken by reading goals (both goals and the update timestamp)
+const GOALS_BYTES: u64 = 5 + 5 + 8;
+/// Bytes taken by the fixed-size fields of a share link (timestamps)
+const SHARE_FIXED_BYTES: u64 = 8 + 9;
+
+/// Storage limits of a user; `None` means unlimited.
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Maximum number of documents with progress
+    pub max_documents: Option<u64>,
+    /// Maximum total size in bytes of the user's data
+    pub max_bytes: Option<u64>,
+}
+
+impl Quota {
+    /// Whether neither the document count nor the stored bytes are limited.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_documents.is_none() && self.max_bytes.is_none()
+    }
+
+    /// Checks the usage an update would lead to against the limits.
+    ///
+    /// Only a limit the update grows past is enforced, so a user already over a lowered
+    /// limit can keep syncing the documents they have as long as they don't grow. Returns a
+    /// description of the exceeded limit.
+    pub fn check(&self, before: &QuotaUsage, after: &QuotaUsage) -> Result<(), String> {
+        if let Some(max) = self.max_documents
+            && after.documents > max
+            && after.documents > before.documents
+        {
+            return Err(format!("Document limit of {max} reached"));
+        }
+        if let Some(max) = self.max_bytes
+            && after.bytes > max
+            && after.bytes > before.bytes
+        {
+            return Err(format!("Storage limit of {max} bytes reached"));
+        }
+        Ok(())
+    }
+}
+
+/// Storage used by a user.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::{Progress, Quota, QuotaUsage, StoredSize};
+///
+/// let progress = Progress {
+///     device_id: "kobo-1".to_string(),
+///     ..Default::default()
+/// };
+/// let before = QuotaUsage::default();
+/// let mut after = before;
+/// after.documents += 1;
+/// after.store(None, progress.stored_size());
+///
+/// let quota = Quota { max_documents: Some(1), max_bytes: None };
+/// assert!(quota.check(&before, &after).is_ok());
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaUsage {
+    /// Number of documents with progress
+    pub documents: u64,
+    /// Total size in bytes of the user's data, see [`StoredSize`]
+    pub bytes: u64,
+}
+
+impl QuotaUsage {
+    /// Accounts for a record of `bytes` stored in place of one of `replaced` bytes, if any.
+    pub fn store(&mut self, replaced: Option<u64>, bytes: u64) {
+        if let Some(replaced) = replaced {
+            self.remove(replaced);
+        }
+        self.bytes += bytes;
+    }
+
+    /// Accounts for a removed record of `bytes`.
+    pub fn remove(&mut self, bytes: u64) {
+        self.bytes = self.bytes.saturating_sub(bytes);
+    }
+}
+
+/// Approximate number of bytes a record takes in storage: its text fields and its
+/// fixed-size fields.
+///
+/// Records stored per document are also charged the length of the document identifier.
+pub trait StoredSize {
+    /// Bytes the record takes.
+    fn stored_size(&self) -> u64;
+}
+
+impl StoredSize for Progress {
+    fn stored_size(&self) -> u64 {
+        (self.device_id.len() + self.device.len() + self.progress.len()) as u64
+            + PROGRESS_FIXED_BYTES
+    }
+}
+
+impl StoredSize for Device {
+    fn stored_size(&self) -> u64 {
+        (self.device_id.len()
+            + self.device.len()
+            + self.name.as_ref().map_or(0, String::len)
+            + self.last_document.len()
+            + self.last_ip.as_ref().map_or(0, String::len)) as u64
+            + DEVICE_FIXED_BYTES
+    }
+}
+
+impl StoredSize for Annotation {
+    fn stored_size(&self) -> u64 {
+        let optional = [&self.text, &self.note, &self.chapter, &self.color]
+            .into_iter()
+            .flatten()
+            .map(String::len)
+            .sum::<usize>();
+        (self.id.len() + self.pos0.len() + self.pos1.len() + optional) as u64 + MARK_FIXED_BYTES
+    }
+}
+
+impl StoredSize for Bookmark {
+    fn stored_size(&self) -> u64 {
+        (self.id.len() + self.label.len() + self.position.len()) as u64 + MARK_FIXED_BYTES
+    }
+}
+
+impl StoredSize for ShelfEntry {
+    fn stored_size(&self) -> u64 {
+        self.reads.len() as u64 * READ_THROUGH_BYTES + 8
+    }
+}
+
+impl StoredSize for Goals {
+    fn stored_size(&self) -> u64 {
+        GOALS_BYTES
+    }
+}
+
+impl StoredSize for Share {
+    fn stored_size(&self) -> u64 {
+        (self.token.len() + self.document.len()) as u64 + SHARE_FIXED_BYTES
+    }
+}
+
+impl StoredSize for DocumentMetadata {
+    fn stored_size(&self) -> u64 {
+        [
+            &self.title,
+            &self.author,
+            &self.series,
+            &self.language,
+            &self.cover_url,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|field| field.len() as u64)
+        .sum::<u64>()
+            + 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(position: &str) -> Progress {
+        Progress {
+            device_id: "kobo".to_string(),
+            device: "Kobo".to_string(),
+            progress: position.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stored_size() {
+        assert_eq!(
+            progress("12").stored_size(),
+            4 + 4 + 2 + PROGRESS_FIXED_BYTES
+        );
+
+        let annotation = Annotation {
+            id: "a1".to_string(),
+            note: Some("note".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(annotation.stored_size(), 2 + 4 + MARK_FIXED_BYTES);
+
+        let mut entry = ShelfEntry::default();
+        entry.record_progress(1.0, 1000, 0.99);
+        assert_eq!(entry.stored_size(), READ_THROUGH_BYTES + 8);
+
+        let metadata = DocumentMetadata {
+            title: Some("Dune".to_string()),
+            cover_url: Some("https://c".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(metadata.stored_size(), 4 + 9 + 8);
+    }
+
+    #[test]
+    fn test_usage_store_and_remove() {
+        let mut usage = QuotaUsage::default();
+        usage.store(None, 100);
+        usage.store(Some(100), 60);
+        assert_eq!(usage.bytes, 60);
+
+        usage.remove(80);
+        assert_eq!(usage.bytes, 0);
+    }
+
+    #[test]
+    fn test_check_only_enforces_growth() {
+        let quota = Quota {
+            max_documents: Some(2),
+            max_bytes: Some(100),
+        };
+        let usage = |documents, bytes| QuotaUsage { documents, bytes };
+
+        assert!(quota.check(&usage(1, 50), &usage(2, 100)).is_ok());
+        assert_eq!(
+            quota.check(&usage(2, 50), &usage(3, 60)),
+            Err("Document limit of 2 reached".to_string())
+        );
+        assert_eq!(
+            quota.check(&usage(2, 90), &usage(2, 101)),
+            Err("Storage limit of 100 bytes reached".to_string())
+        );
+        // Already over the limit, but not growing
+        assert!(quota.check(&usage(3, 120), &usage(3, 110)).is_ok());
+        assert!(
+            Quota::default()
+                .check(&usage(0, 0), &usage(1000, 1000))
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Public share links for a document's progress.
+//!
+//! This module defines the [`Share`] struct: an unguessable token that lets anyone holding
+//! the link see a user's current progress on one document, without authenticating. Shares
+//! can be revoked at any time and may expire.
+
+use chrono::Utc;
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// A read-only share link for a user's progress on a document.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::Share;
+///
+/// let share = Share::new("alice", "book.epub", Some(1_000));
+///
+/// assert_eq!(share.token.len(), 32);
+/// assert!(!share.is_expired(999));
+/// assert!(share.is_expired(1_000));
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone)]
+pub struct Share {
+    /// Random token identifying the share in its public URL
+    pub token: String,
+    /// Username whose progress is shared
+    pub user: String,
+    /// Document whose progress is shared
+    pub document: String,
+    /// Unix timestamp in milliseconds when the share was created
+    pub created_at: u64,
+    /// Unix timestamp in milliseconds after which the share stops working, if any
+    pub expires_at: Option<u64>,
+}
+
+impl Share {
+    /// Creates a share for a user's document with a new random token.
+    pub fn new(
+        user: impl Into<String>,
+        document: impl Into<String>,
+        expires_at: Option<u64>,
+    ) -> Self {
+        Self {
+            token: uuid::Uuid::new_v4().simple().to_string(),
+            user: user.into(),
+            document: document.into(),
+            created_at: Utc::now().timestamp_millis() as u64,
+            expires_at,
+        }
+    }
+
+    /// Returns `true` if the share has expired at the given Unix timestamp (milliseconds).
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
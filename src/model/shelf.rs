@@ -0,0 +1,285 @@
+//! Reading shelves of a user's documents.
+//!
+//! Every document a user has progress for sits on one [`Shelf`]: being read, finished or
+//! abandoned. A [`ShelfEntry`] keeps the [`ReadThrough`]s of a document, so reading a
+//! finished book again starts a new read-through instead of overwriting when it was
+//! finished.
+
+use std::{fmt, str::FromStr};
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Shelf a document sits on.
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Shelf {
+    /// The document is being read
+    #[default]
+    Reading,
+    /// The document was read to the end
+    Finished,
+    /// The reader gave up on the document
+    Abandoned,
+}
+
+impl fmt::Display for Shelf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Shelf::Reading => "reading",
+            Shelf::Finished => "finished",
+            Shelf::Abandoned => "abandoned",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Shelf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reading" => Ok(Shelf::Reading),
+            "finished" => Ok(Shelf::Finished),
+            "abandoned" => Ok(Shelf::Abandoned),
+            other => Err(format!(
+                "Invalid shelf '{other}'. Expected: reading, finished or abandoned"
+            )),
+        }
+    }
+}
+
+/// One reading of a document, from when it was started until it was finished or abandoned.
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ReadThrough {
+    /// Where the read-through currently stands
+    pub shelf: Shelf,
+    /// Unix timestamp in milliseconds when the read-through started
+    pub started_at: u64,
+    /// Unix timestamp in milliseconds when it was finished or abandoned; `None` while reading
+    pub ended_at: Option<u64>,
+}
+
+/// Shelf state of a document, with every read-through of it.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::{Shelf, ShelfEntry};
+///
+/// let mut entry = ShelfEntry::default();
+/// entry.record_progress(0.5, 1000, 0.99);
+/// assert_eq!(entry.shelf(), Shelf::Reading);
+///
+/// // Crossing the threshold finishes the book
+/// entry.record_progress(1.0, 2000, 0.99);
+/// assert_eq!(entry.finished_at(), Some(2000));
+///
+/// // Going back to the beginning starts a second read-through
+/// entry.record_progress(0.01, 3000, 0.99);
+/// assert_eq!(entry.shelf(), Shelf::Reading);
+/// assert_eq!(entry.reads.len(), 2);
+/// assert_eq!(entry.times_finished(), 1);
+/// ```
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ShelfEntry {
+    /// Read-throughs of the document, oldest first; the last one is the current one
+    pub reads: Vec<ReadThrough>,
+    /// Unix timestamp in milliseconds of the last change
+    pub updated_at: u64,
+}
+
+impl ShelfEntry {
+    /// Shelf the document currently sits on.
+    pub fn shelf(&self) -> Shelf {
+        self.reads.last().map(|read| read.shelf).unwrap_or_default()
+    }
+
+    /// The current read-through, if the document was ever shelved.
+    pub fn current(&self) -> Option<&ReadThrough> {
+        self.reads.last()
+    }
+
+    /// When the document was last finished, even if it is being read again.
+    pub fn finished_at(&self) -> Option<u64> {
+        self.reads
+            .iter()
+            .rev()
+            .find(|read| read.shelf == Shelf::Finished)
+            .and_then(|read| read.ended_at)
+    }
+
+    /// Number of read-throughs that reached the end.
+    pub fn times_finished(&self) -> usize {
+        self.reads
+            .iter()
+            .filter(|read| read.shelf == Shelf::Finished)
+            .count()
+    }
+
+    /// Moves the document according to a progress update at `percentage`.
+    ///
+    /// Reaching `threshold` finishes the current read-through. Progress below it on a
+    /// finished document starts a new read-through, while an abandoned document is picked
+    /// up again where it was left. Returns whether the entry changed.
+    pub fn record_progress(&mut self, percentage: f32, at: u64, threshold: f32) -> bool {
+        let target = if percentage >= threshold {
+            Shelf::Finished
+        } else {
+            Shelf::Reading
+        };
+        if self.reads.is_empty() && target == Shelf::Finished {
+            // Finished without any earlier progress: the read-through starts here as well
+            self.start(Shelf::Reading, at);
+        }
+        self.set_shelf(target, at)
+    }
+
+    /// Moves the document to `shelf`, e.g. when the reader marks it explicitly.
+    ///
+    /// Leaving the finished shelf starts a new read-through, keeping the finished one.
+    /// Returns whether the entry changed.
+    pub fn set_shelf(&mut self, shelf: Shelf, at: u64) -> bool {
+        let ended_at = (shelf != Shelf::Reading).then_some(at);
+        match self.reads.last_mut() {
+            Some(current) if current.shelf == shelf => return false,
+            Some(current) if current.shelf != Shelf::Finished => {
+                current.shelf = shelf;
+                current.ended_at = ended_at;
+            }
+            _ => self.start(shelf, at),
+        }
+        self.updated_at = at;
+        true
+    }
+
+    /// Combines the entries of two identifiers of the same document, e.g. when linking them.
+    ///
+    /// Every finished read-through of both is kept, while the current read-through is the
+    /// one of the most recently updated entry.
+    pub fn merge(self, other: ShelfEntry) -> ShelfEntry {
+        let (mut newer, older) = if other.updated_at > self.updated_at {
+            (other, self)
+        } else {
+            (self, other)
+        };
+        let current = newer.reads.pop();
+
+        // Only the last read-through can be unfinished, the newer one supersedes it
+        let mut reads: Vec<ReadThrough> = older
+            .reads
+            .into_iter()
+            .filter(|read| read.shelf == Shelf::Finished)
+            .chain(newer.reads)
+            .collect();
+        reads.sort_by_key(|read| read.started_at);
+        reads.extend(current);
+
+        ShelfEntry {
+            reads,
+            updated_at: newer.updated_at,
+        }
+    }
+
+    fn start(&mut self, shelf: Shelf, at: u64) {
+        self.reads.push(ReadThrough {
+            shelf,
+            started_at: at,
+            ended_at: (shelf != Shelf::Reading).then_some(at),
+        });
+        self.updated_at = at;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: f32 = 0.99;
+
+    #[test]
+    fn test_progress_finishes_at_threshold() {
+        let mut entry = ShelfEntry::default();
+        assert!(entry.record_progress(0.2, 1000, THRESHOLD));
+        assert!(!entry.record_progress(0.5, 2000, THRESHOLD));
+        assert!(entry.record_progress(0.995, 3000, THRESHOLD));
+
+        assert_eq!(
+            entry.reads,
+            vec![ReadThrough {
+                shelf: Shelf::Finished,
+                started_at: 1000,
+                ended_at: Some(3000),
+            }]
+        );
+        // Further progress past the threshold keeps the original finish date
+        assert!(!entry.record_progress(1.0, 4000, THRESHOLD));
+        assert_eq!(entry.finished_at(), Some(3000));
+    }
+
+    #[test]
+    fn test_first_progress_past_threshold() {
+        let mut entry = ShelfEntry::default();
+        entry.record_progress(1.0, 1000, THRESHOLD);
+
+        assert_eq!(entry.reads.len(), 1);
+        assert_eq!(entry.shelf(), Shelf::Finished);
+        assert_eq!(entry.reads[0].started_at, 1000);
+        assert_eq!(entry.finished_at(), Some(1000));
+    }
+
+    #[test]
+    fn test_reopening_finished_starts_new_read_through() {
+        let mut entry = ShelfEntry::default();
+        entry.record_progress(0.5, 1000, THRESHOLD);
+        entry.set_shelf(Shelf::Finished, 2000);
+        entry.record_progress(0.1, 3000, THRESHOLD);
+        entry.record_progress(1.0, 4000, THRESHOLD);
+
+        assert_eq!(entry.times_finished(), 2);
+        assert_eq!(entry.reads[0].ended_at, Some(2000));
+        assert_eq!(entry.reads[1].started_at, 3000);
+        assert_eq!(entry.finished_at(), Some(4000));
+    }
+
+    #[test]
+    fn test_abandoned_is_resumed() {
+        let mut entry = ShelfEntry::default();
+        entry.record_progress(0.3, 1000, THRESHOLD);
+        assert!(entry.set_shelf(Shelf::Abandoned, 2000));
+        assert_eq!(entry.current().and_then(|read| read.ended_at), Some(2000));
+
+        entry.record_progress(0.4, 3000, THRESHOLD);
+        assert_eq!(entry.reads.len(), 1);
+        assert_eq!(entry.shelf(), Shelf::Reading);
+        assert_eq!(entry.current().and_then(|read| read.ended_at), None);
+        assert_eq!(entry.updated_at, 3000);
+    }
+
+    #[test]
+    fn test_merge_keeps_finished_read_throughs() {
+        let mut finished = ShelfEntry::default();
+        finished.record_progress(0.5, 1000, THRESHOLD);
+        finished.record_progress(1.0, 2000, THRESHOLD);
+        finished.record_progress(0.1, 3000, THRESHOLD);
+
+        let mut reading = ShelfEntry::default();
+        reading.record_progress(0.2, 2500, THRESHOLD);
+        reading.set_shelf(Shelf::Abandoned, 4000);
+
+        let merged = finished.clone().merge(reading.clone());
+        assert_eq!(merged, reading.merge(finished));
+        assert_eq!(merged.times_finished(), 1);
+        assert_eq!(merged.finished_at(), Some(2000));
+        assert_eq!(merged.reads.len(), 2);
+        assert_eq!(merged.shelf(), Shelf::Abandoned);
+        assert_eq!(merged.current().map(|read| read.started_at), Some(2500));
+        assert_eq!(merged.updated_at, 4000);
+    }
+
+    #[test]
+    fn test_shelf_parsing() {
+        for shelf in [Shelf::Reading, Shelf::Finished, Shelf::Abandoned] {
+            assert_eq!(shelf.to_string().parse::<Shelf>(), Ok(shelf));
+        }
+        assert!("wishlist".parse::<Shelf>().is_err());
+    }
+}
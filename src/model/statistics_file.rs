@@ -0,0 +1,36 @@
+//! Metadata of a stored KOReader statistics database.
+//!
+//! The database itself can be large, so its size and digest are computed once when it is
+//! stored, and kept apart to describe it without reading it back.
+
+use md5::{Digest, Md5};
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Size and digest of a user's `statistics.sqlite3`.
+#[derive(Debug, Archive, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct StatisticsFile {
+    /// Size of the database in bytes
+    pub size: u64,
+    /// MD5 hex digest of the database
+    pub md5: String,
+}
+
+impl StatisticsFile {
+    /// Describes the given database content.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use korrosync::model::StatisticsFile;
+    ///
+    /// let file = StatisticsFile::of(b"");
+    /// assert_eq!(0, file.size);
+    /// assert_eq!("d41d8cd98f00b204e9800998ecf8427e", file.md5);
+    /// ```
+    pub fn of(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            md5: format!("{:x}", Md5::digest(data)),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Reading statistics derived from the progress history.
+//!
+//! KOReader pushes its position every few page turns while reading, so the history of
+//! progress updates already tells when a user was reading. This module groups those
+//! updates into [`ReadingSession`]s and derives [`ReadingStats`] (time spent, finished
+//! documents, pace) from them, without requiring anything new from clients.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::DateTime;
+
+use crate::model::Progress;
+
+/// Maximum gap in milliseconds between two updates of the same session
+pub const SESSION_GAP_MS: u64 = 30 * 60 * 1000;
+
+/// Percentage from which a document counts as finished
+pub const FINISHED_PERCENTAGE: f32 = 0.99;
+
+/// A stretch of continuous reading of one document on one device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadingSession {
+    /// Document being read
+    pub document: String,
+    /// Device the session happened on
+    pub device_id: String,
+    /// Human-readable device name
+    pub device: String,
+    /// Unix timestamp in milliseconds of the first update of the session
+    pub start: u64,
+    /// Unix timestamp in milliseconds of the last update of the session
+    pub end: u64,
+    /// Position before the session started
+    pub start_percentage: f32,
+    /// Position at the end of the session
+    pub end_percentage: f32,
+}
+
+impl ReadingSession {
+    /// Length of the session in milliseconds.
+    pub fn duration_ms(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Percentage points read during the session; negative if the reader went back.
+    pub fn percentage_points(&self) -> f32 {
+        (self.end_percentage - self.start_percentage) * 100.0
+    }
+}
+
+/// A document that reached [`FINISHED_PERCENTAGE`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishedDocument {
+    pub document: String,
+    /// Unix timestamp in milliseconds of the first update past the finish line
+    pub finished_at: u64,
+}
+
+/// Statistics of a user's reading.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::{Progress, ReadingStats};
+///
+/// let at = |timestamp, percentage| Progress {
+///     device_id: "kobo-1".to_string(),
+///     percentage,
+///     timestamp,
+///     ..Default::default()
+/// };
+/// let history = vec![
+///     ("book.epub".to_string(), at(0, 0.25)),
+///     ("book.epub".to_string(), at(30 * 60 * 1000, 0.5)),
+/// ];
+///
+/// let stats = ReadingStats::from_history(&history);
+/// assert_eq!(stats.sessions.len(), 1);
+/// // 25 percentage points in half an hour
+/// assert_eq!(stats.average_pace(), Some(50.0));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReadingStats {
+    /// Reading sessions, oldest first
+    pub sessions: Vec<ReadingSession>,
+    /// Finished documents, in the order they were finished
+    pub finished: Vec<FinishedDocument>,
+}
+
+impl ReadingStats {
+    /// Derives the statistics from a user's progress history.
+    ///
+    /// Consecutive updates of the same document on the same device belong to the same
+    /// session as long as they are at most [`SESSION_GAP_MS`] apart.
+    pub fn from_history(history: &[(String, Progress)]) -> Self {
+        let mut updates: Vec<_> = history.iter().collect();
+        updates.sort_by_key(|(_, progress)| progress.reported_at());
+
+        let mut stats = Self::default();
+        let mut positions: HashMap<&str, f32> = HashMap::new();
+        let mut finished: HashSet<&str> = HashSet::new();
+        let mut current: Option<ReadingSession> = None;
+
+        for (document, progress) in updates {
+            let timestamp = progress.reported_at();
+
+            let continues = current.as_ref().is_some_and(|session| {
+                session.document == *document
+                    && session.device_id == progress.device_id
+                    && timestamp - session.end <= SESSION_GAP_MS
+            });
+            if continues {
+                if let Some(session) = current.as_mut() {
+                    session.end = timestamp;
+                    session.end_percentage = progress.percentage;
+                }
+            } else {
+                stats.sessions.extend(current.take());
+                current = Some(ReadingSession {
+                    document: document.clone(),
+                    device_id: progress.device_id.clone(),
+                    device: progress.device.clone(),
+                    start: timestamp,
+                    end: timestamp,
+                    start_percentage: positions
+                        .get(document.as_str())
+                        .copied()
+                        .unwrap_or(progress.percentage),
+                    end_percentage: progress.percentage,
+                });
+            }
+            positions.insert(document, progress.percentage);
+
+            if progress.percentage >= FINISHED_PERCENTAGE && finished.insert(document) {
+                stats.finished.push(FinishedDocument {
+                    document: document.clone(),
+                    finished_at: timestamp,
+                });
+            }
+        }
+        stats.sessions.extend(current);
+
+        stats
+    }
+
+    /// Total time spent reading, in milliseconds.
+    pub fn total_reading_ms(&self) -> u64 {
+        self.sessions.iter().map(ReadingSession::duration_ms).sum()
+    }
+
+    /// Number of documents finished in each month (`YYYY-MM`, UTC), oldest first.
+    pub fn finished_per_month(&self) -> Vec<(String, usize)> {
+        let mut months: Vec<(String, usize)> = Vec::new();
+        for document in &self.finished {
+            let month = DateTime::from_timestamp_millis(document.finished_at as i64)
+                .map(|date| date.format("%Y-%m").to_string())
+                .unwrap_or_default();
+            match months.iter_mut().find(|(m, _)| *m == month) {
+                Some((_, count)) => *count += 1,
+                None => months.push((month, 1)),
+            }
+        }
+        months.sort();
+        months
+    }
+
+    /// Average percentage points read per hour, over the sessions that lasted some time.
+    ///
+    /// Returns `None` if no session has a measurable duration.
+    pub fn average_pace(&self) -> Option<f32> {
+        let timed = self.sessions.iter().filter(|s| s.duration_ms() > 0);
+        let (points, ms) = timed.fold((0.0, 0), |(points, ms), session| {
+            (
+                points + session.percentage_points().max(0.0),
+                ms + session.duration_ms(),
+            )
+        });
+
+        (ms > 0).then(|| points / (ms as f32 / 3_600_000.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60 * 1000;
+
+    fn update(document: &str, device_id: &str, minute: u64, percentage: f32) -> (String, Progress) {
+        (
+            document.to_string(),
+            Progress {
+                device_id: device_id.to_string(),
+                device: format!("{device_id}-name"),
+                percentage,
+                progress: String::new(),
+                timestamp: minute * MINUTE,
+                client_timestamp: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_sessions_split_on_gap_device_and_document() {
+        let history = vec![
+            update("a", "kobo", 0, 0.1),
+            update("a", "kobo", 10, 0.2),
+            update("a", "kobo", 20, 0.3),
+            // Too long since the last update
+            update("a", "kobo", 100, 0.35),
+            // Another device
+            update("a", "phone", 105, 0.4),
+            // Another document
+            update("b", "phone", 110, 0.05),
+        ];
+
+        let stats = ReadingStats::from_history(&history);
+        let sessions: Vec<_> = stats
+            .sessions
+            .iter()
+            .map(|s| (s.document.as_str(), s.device_id.as_str(), s.start, s.end))
+            .collect();
+        assert_eq!(
+            sessions,
+            vec![
+                ("a", "kobo", 0, 20 * MINUTE),
+                ("a", "kobo", 100 * MINUTE, 100 * MINUTE),
+                ("a", "phone", 105 * MINUTE, 105 * MINUTE),
+                ("b", "phone", 110 * MINUTE, 110 * MINUTE),
+            ]
+        );
+        assert_eq!(stats.total_reading_ms(), 20 * MINUTE);
+    }
+
+    #[test]
+    fn test_session_starts_from_previous_position() {
+        let history = vec![
+            update("a", "kobo", 0, 0.1),
+            update("a", "phone", 60, 0.3),
+            update("a", "phone", 90, 0.4),
+        ];
+
+        let stats = ReadingStats::from_history(&history);
+        let last = stats.sessions.last().expect("Expected a session");
+        assert_eq!(last.start_percentage, 0.1);
+        assert_eq!(last.end_percentage, 0.4);
+        assert!((last.percentage_points() - 30.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_history_is_ordered_by_time() {
+        let history = vec![update("a", "kobo", 10, 0.2), update("a", "kobo", 0, 0.1)];
+
+        let stats = ReadingStats::from_history(&history);
+        assert_eq!(stats.sessions.len(), 1);
+        assert_eq!(stats.sessions[0].start_percentage, 0.1);
+        assert_eq!(stats.sessions[0].end_percentage, 0.2);
+    }
+
+    #[test]
+    fn test_finished_documents_are_counted_once_per_month() {
+        let january = 1_704_067_200_000 / MINUTE;
+        let february = 1_706_745_600_000 / MINUTE;
+        let history = vec![
+            update("a", "kobo", january, 0.995),
+            update("a", "kobo", january + 5, 1.0),
+            update("b", "kobo", january + 60, 1.0),
+            update("c", "kobo", february, 0.99),
+            update("d", "kobo", february + 60, 0.5),
+        ];
+
+        let stats = ReadingStats::from_history(&history);
+        assert_eq!(stats.finished.len(), 3);
+        assert_eq!(
+            stats.finished_per_month(),
+            vec![("2024-01".to_string(), 2), ("2024-02".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_average_pace() {
+        let history = vec![
+            update("a", "kobo", 0, 0.1),
+            update("a", "kobo", 30, 0.2),
+            update("b", "kobo", 120, 0.5),
+            update("b", "kobo", 150, 0.6),
+        ];
+        let stats = ReadingStats::from_history(&history);
+        let pace = stats.average_pace().expect("Expected a pace");
+        assert!((pace - 20.0).abs() < 1e-3, "Unexpected pace {pace}");
+
+        let stats = ReadingStats::from_history(&[update("a", "kobo", 0, 0.1)]);
+        assert_eq!(stats.average_pace(), None);
+    }
+}
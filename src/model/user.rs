@@ -9,6 +9,12 @@
 //! Passwords are hashed using Argon2 (the winner of the Password Hashing Competition)
 //! with randomly generated salts. Plain-text passwords are never stored.
 //!
+//! # KOReader Keys
+//!
+//! KOReader never sends the plain password: the `x-auth-key` header carries the MD5 hex
+//! digest of it. Users created outside of KOReader (e.g. from the CLI) must therefore be
+//! created from [`koreader_key`] of their password to be able to log in from a device.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -29,6 +35,7 @@ use argon2::{
     },
 };
 use chrono::Utc;
+use md5::{Digest, Md5};
 use rkyv::{Archive, Deserialize, Serialize};
 
 use crate::model::error::Error;
@@ -86,14 +93,8 @@ impl User {
         username: impl Into<String>,
         password: impl Into<String>,
     ) -> Result<Self, password_hash::Error> {
-        let password = password.into();
         let username = username.into();
-
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)?
-            .to_string();
+        let password_hash = hash_password(password.into())?;
 
         Ok(Self {
             username,
@@ -102,6 +103,35 @@ impl User {
         })
     }
 
+    /// Replaces the stored password hash with a hash of the given plain password.
+    ///
+    /// A fresh salt is generated, so the resulting hash differs from the previous one even
+    /// when the same password is set again. The last activity timestamp is preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `password` - The new plain-text password to hash and store
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use korrosync::model::User;
+    ///
+    /// let mut user = User::new("alice", "old_password")?;
+    /// user.set_password("new_password")?;
+    ///
+    /// assert!(user.check("new_password")?);
+    /// assert!(!user.check("old_password")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_password(
+        &mut self,
+        password: impl Into<String>,
+    ) -> Result<(), password_hash::Error> {
+        self.password_hash = hash_password(password.into())?;
+        Ok(())
+    }
+
     /// Returns the username associated with this user.
     ///
     /// # Returns
@@ -228,6 +258,33 @@ impl User {
     }
 }
 
+/// Derives the key KOReader sends in `x-auth-key` for a given plain password.
+///
+/// KOReader's sync plugin authenticates with the lowercase MD5 hex digest of the password,
+/// so this is the value that has to be stored (hashed) for a user to be able to log in
+/// from a device.
+///
+/// # Example
+///
+/// ```
+/// use korrosync::model::koreader_key;
+///
+/// assert_eq!(koreader_key("secret"), "5ebe2294ecd0e0f08eab7690d2a6ee69");
+/// ```
+pub fn koreader_key(password: impl AsRef<str>) -> String {
+    format!("{:x}", Md5::digest(password.as_ref().as_bytes()))
+}
+
+/// Hashes a plain password with Argon2 and a randomly generated salt.
+fn hash_password(password: String) -> Result<String, password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    let argon2 = Argon2::default();
+
+    Ok(argon2
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -293,6 +350,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_set_password_replaces_hash() {
+        let mut user = User::new("alice", "old_password").expect("Failed to create user");
+        user.set_last_activity(1609459200000);
+        let old_hash = user.password_hash.clone();
+
+        user.set_password("new_password")
+            .expect("Failed to set password");
+
+        assert_ne!(old_hash, user.password_hash, "Hash should be rotated");
+        assert!(
+            user.check("new_password")
+                .expect("Failed to check password")
+        );
+        assert!(
+            !user
+                .check("old_password")
+                .expect("Failed to check password")
+        );
+        assert_eq!(
+            user.last_activity(),
+            Some(1609459200000),
+            "Changing the password should keep the last activity"
+        );
+    }
+
+    #[test]
+    fn test_koreader_key_is_md5_hex() {
+        assert_eq!(koreader_key(""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(koreader_key("test"), "098f6bcd4621d373cade4e832627b4f6");
+    }
+
+    #[test]
+    fn test_user_created_from_koreader_key() {
+        let user = User::new("alice", koreader_key("secret")).expect("Failed to create user");
+
+        assert!(
+            user.check("5ebe2294ecd0e0f08eab7690d2a6ee69")
+                .expect("Failed to check password"),
+            "The MD5 key sent by KOReader should be accepted"
+        );
+        assert!(
+            !user.check("secret").expect("Failed to check password"),
+            "The raw password is not what KOReader sends"
+        );
+    }
+
     #[test]
     fn test_last_activity_initial() {
         let user = User::new("alice", "password").expect("Failed to create user");
@@ -11,13 +11,33 @@
 //!
 
 use crate::{
-    model::{Progress, User},
+    model::{
+        Annotation, AuditEvent, Bookmark, Device, DocumentMetadata, Goals, Group, Progress, Quota,
+        QuotaUsage, Share, Shelf, ShelfEntry, StatisticsFile, User, Visibility,
+    },
     service::error::ServiceError,
 };
 
 pub mod redb;
 pub use self::redb::KorrosyncServiceRedb;
 
+/// Check of the stored progress of a document, if any, that a conditional update depends on
+pub type ProgressCondition<'a> = dyn Fn(Option<&Progress>) -> bool + 'a;
+
+/// Settings and request details applied when storing progress reported by a client.
+///
+/// See [`KorrosyncService::sync_progress`].
+#[derive(Debug, Clone, Default)]
+pub struct SyncContext {
+    /// Quota of users without one of their own (see [`KorrosyncService::get_quota`])
+    pub default_quota: Quota,
+    /// IP address the updates came from, if known
+    pub ip: Option<String>,
+    /// Percentage from which a document counts as finished, or `None` to leave the shelves
+    /// untouched (see [`ShelfEntry::record_progress`])
+    pub finished_threshold: Option<f32>,
+}
+
 /// Trait defining the core database operations for KoReader synchronization.
 ///
 /// This trait provides a database-agnostic interface for managing users and reading progress.
@@ -61,9 +81,52 @@ pub trait KorrosyncService {
     /// - `Err(...)` - unexpected database error occurred
     fn create_or_update_user(&self, user: User) -> Result<User, ServiceError>;
 
+    /// Replaces the password of an existing user.
+    ///
+    /// Only the password hash changes; reading and writing the user happen in the same
+    /// transaction, so concurrent updates of other fields are not lost and a deleted user
+    /// is never brought back.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The username of the user
+    /// * `key` - The new password (the key as sent by the client)
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The password was changed
+    /// - `Ok(false)` - No user exists with the given username
+    /// - `Err(...)` - Unexpected database error occurred
+    fn change_password(&self, name: String, key: String) -> Result<bool, ServiceError>;
+
+    /// Records activity of an existing user now.
+    ///
+    /// Like [`Self::change_password`], only the last activity timestamp changes and a
+    /// deleted user is never brought back.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(user))` - The user, with the updated activity
+    /// - `Ok(None)` - No user exists with the given username
+    /// - `Err(...)` - Unexpected database error occurred
+    fn touch_user(&self, name: String) -> Result<Option<User>, ServiceError>;
+
     /// Updates or creates reading progress for a user's document.
     ///
     /// If progress already exists for this user/document combination, it will be overwritten.
+    /// The progress is also kept as the last position of the reporting device (see
+    /// [`Self::list_device_progress`]). The document is resolved through the user's aliases
+    /// (see [`Self::link_document`]), so the progress is stored against the canonical
+    /// document.
+    ///
+    /// This is [`Self::sync_progress`] for a single update, without a condition, a default
+    /// quota or an IP address: the reporting device is recorded, the document moves between
+    /// shelves at [`FINISHED_PERCENTAGE`](crate::model::FINISHED_PERCENTAGE) and the quota
+    /// set for the user, if any, is enforced.
     ///
     /// # Arguments
     ///
@@ -79,7 +142,8 @@ pub trait KorrosyncService {
     ///
     /// # Errors
     ///
-    /// Returns an error if an unexpected database error occurs.
+    /// Returns `ServiceError::QuotaExceeded` if the update would exceed the user's quota, or
+    /// an error if an unexpected database error occurs.
     fn update_progress(
         &self,
         user: String,
@@ -87,8 +151,49 @@ pub trait KorrosyncService {
         progress: Progress,
     ) -> Result<(String, u64), ServiceError>;
 
+    /// Stores progress updates reported by a client.
+    ///
+    /// The updates are applied in order, each resolved through the user's aliases, in a
+    /// single transaction that first checks `condition` against the stored progress of each
+    /// document and, once they are stored, the user's storage quota. The quota set for the
+    /// user takes precedence over the default of `context`; only updates growing the
+    /// document count or the stored bytes past a limit are rejected, see [`Quota::check`].
+    /// The stored bytes include the device positions and the devices the updates add, but
+    /// not the history (see [`Self::get_usage`]).
+    /// No other update can slip in between the check of `condition` and the updates, which
+    /// enables optimistic concurrency, e.g. HTTP `If-Match`.
+    ///
+    /// The same transaction records each reporting device, see [`Device::reporting`] (a known
+    /// device keeps its `first_seen` timestamp and friendly `name`), and moves each document
+    /// between shelves, see [`ShelfEntry::record_progress`].
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `updates` - The documents and the progress to store for each of them
+    /// * `context` - Server-wide settings of the update
+    /// * `condition` - If given, called with the stored progress (if any) of each document;
+    ///   the updates only happen when it returns `true` for all of them
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(Vec<(document, timestamp)>))` - The document and timestamp of each update
+    /// - `Ok(None)` - The condition did not hold and nothing was stored
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The updates would exceed the user's quota
+    ///   and nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn sync_progress(
+        &self,
+        user: String,
+        updates: Vec<(String, Progress)>,
+        context: &SyncContext,
+        condition: Option<&ProgressCondition<'_>>,
+    ) -> Result<Option<Vec<(String, u64)>>, ServiceError>;
+
     /// Retrieves reading progress for a specific user and document.
     ///
+    /// Like [`Self::update_progress`], the document is resolved through the user's aliases.
+    ///
     /// # Arguments
     ///
     /// * `user` - The username of the user
@@ -105,6 +210,380 @@ pub trait KorrosyncService {
         document: String,
     ) -> Result<Option<Progress>, ServiceError>;
 
+    /// Retrieves reading progress for several of a user's documents at once.
+    ///
+    /// All documents are read from a single consistent snapshot. Like [`Self::get_progress`],
+    /// each document is resolved through the user's aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `documents` - The document identifiers to look up
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<(document, progress)>)` - The progress of each requested document that has
+    ///   some, in request order and keyed by the requested identifier
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_progress_batch(
+        &self,
+        user: String,
+        documents: Vec<String>,
+    ) -> Result<Vec<(String, Progress)>, ServiceError>;
+
+    /// Deletes the reading progress of a user's document.
+    ///
+    /// The progress, the last position of each device, the progress history and the shelf
+    /// entry of the document are removed, so it no longer shows up in the reading
+    /// statistics or shelves either.
+    /// Like [`Self::get_progress`], the document is resolved through the user's aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The document had progress and it was deleted
+    /// - `Ok(false)` - No progress exists for the document
+    /// - `Err(...)` - Unexpected database error occurred
+    fn delete_progress(&self, user: String, document: String) -> Result<bool, ServiceError>;
+
+    /// Deletes the reading progress of a user's document, provided it was last updated
+    /// before a given time.
+    ///
+    /// Unlike [`Self::delete_progress`], only the progress and the last position of each
+    /// device are removed: the progress history and the shelf entry are kept, so the
+    /// document still counts in the reading statistics and shelves. The check and the
+    /// deletion happen in the same transaction, so progress updated meanwhile is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier
+    /// * `before` - Unix timestamp in milliseconds; only progress received earlier is deleted
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The progress was older and it was deleted
+    /// - `Ok(false)` - No progress exists for the document, or it is recent
+    /// - `Err(...)` - Unexpected database error occurred
+    fn delete_progress_if_older(
+        &self,
+        user: String,
+        document: String,
+        before: u64,
+    ) -> Result<bool, ServiceError>;
+
+    /// Lists the last position reported by each device for a user's document.
+    ///
+    /// Like [`Self::get_progress`], the document is resolved through the user's aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier to look up
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<Progress>)` - One entry per device, most recent first
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_device_progress(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Vec<Progress>, ServiceError>;
+
+    /// Lists every reading progress entry of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<(document, progress)>)` - All progress of the user, ordered by document
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_progress(&self, user: String) -> Result<Vec<(String, Progress)>, ServiceError>;
+
+    /// Lists every progress update ever recorded for a user.
+    ///
+    /// Unlike [`Self::list_progress`], which only keeps the latest position of each document,
+    /// the history keeps one entry per update so reading statistics can be derived from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<(document, progress)>)` - All updates of the user, oldest first
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_progress_history(&self, user: String) -> Result<Vec<(String, Progress)>, ServiceError>;
+
+    /// Retrieves the KOReader statistics database of a user, with its metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some((file, bytes)))` - The SQLite database file, as last stored
+    /// - `Ok(None)` - The user never uploaded statistics
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_statistics(
+        &self,
+        user: String,
+    ) -> Result<Option<(StatisticsFile, Vec<u8>)>, ServiceError>;
+
+    /// Retrieves the size and digest of a user's KOReader statistics database, without
+    /// reading the database itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(file))` - The metadata of the stored database
+    /// - `Ok(None)` - The user never uploaded statistics
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_statistics_file(&self, user: String) -> Result<Option<StatisticsFile>, ServiceError>;
+
+    /// Stores the KOReader statistics database of a user, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `data` - The SQLite database file
+    /// * `default_quota` - Quota of users without one of their own
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(file)` - The size and digest of the stored database
+    /// - `Err(ServiceError::QuotaExceeded(_))` - A larger database would exceed the user's
+    ///   quota and nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn put_statistics(
+        &self,
+        user: String,
+        data: Vec<u8>,
+        default_quota: Quota,
+    ) -> Result<StatisticsFile, ServiceError>;
+
+    /// Lists the annotations of a user's document, ordered by annotation id.
+    ///
+    /// Deleted annotations are included as tombstones, so clients can propagate deletions.
+    /// Like [`Self::get_progress`], the document is resolved through the user's aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<Annotation>)` - All annotations of the document, including tombstones
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_annotations(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Vec<Annotation>, ServiceError>;
+
+    /// Merges annotations reported by a device into the stored ones of a user's document.
+    ///
+    /// Each annotation is merged with the stored version with the same id (see
+    /// [`Annotation::merge`]), so replaying an older copy never overwrites a newer edit or
+    /// resurrects a deleted annotation. The document is resolved through the user's aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier
+    /// * `annotations` - The annotations to merge
+    /// * `default_quota` - Quota of users without one of their own
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<Annotation>)` - All annotations of the document after the merge, as
+    ///   returned by [`Self::list_annotations`]
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The merge would exceed the user's quota and
+    ///   nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn merge_annotations(
+        &self,
+        user: String,
+        document: String,
+        annotations: Vec<Annotation>,
+        default_quota: Quota,
+    ) -> Result<Vec<Annotation>, ServiceError>;
+
+    /// Lists the bookmarks of a user's document, ordered by bookmark id.
+    ///
+    /// Deleted bookmarks are included as tombstones. Like [`Self::get_progress`], the
+    /// document is resolved through the user's aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<Bookmark>)` - All bookmarks of the document, including tombstones
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_bookmarks(&self, user: String, document: String)
+    -> Result<Vec<Bookmark>, ServiceError>;
+
+    /// Stores a bookmark of a user's document, merged with the stored version with the same id.
+    ///
+    /// The most recent version wins (see [`Bookmark::merge`]), so an outdated edit never
+    /// overwrites a newer one or resurrects a deleted bookmark. Deleting a bookmark is storing
+    /// its tombstone (see [`Bookmark::delete`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier
+    /// * `bookmark` - The bookmark to store
+    /// * `default_quota` - Quota of users without one of their own
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Bookmark)` - The bookmark as stored after the merge
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The bookmark would exceed the user's quota
+    ///   and nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn put_bookmark(
+        &self,
+        user: String,
+        document: String,
+        bookmark: Bookmark,
+        default_quota: Quota,
+    ) -> Result<Bookmark, ServiceError>;
+
+    /// Retrieves the reading goals of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(goals))` - The goals last set by the user
+    /// - `Ok(None)` - The user never set goals
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_goals(&self, user: String) -> Result<Option<Goals>, ServiceError>;
+
+    /// Sets the reading goals of a user, replacing the previous ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `goals` - The goals to store
+    /// * `default_quota` - Quota of users without one of their own
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Goals)` - The goals as stored
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The goals would exceed the user's quota and
+    ///   nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn set_goals(
+        &self,
+        user: String,
+        goals: Goals,
+        default_quota: Quota,
+    ) -> Result<Goals, ServiceError>;
+
+    /// Explicitly moves a user's document to a shelf.
+    ///
+    /// See [`ShelfEntry::set_shelf`]. Like [`Self::get_progress`], the document is resolved
+    /// through the user's aliases.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier
+    /// * `shelf` - The shelf to move the document to
+    /// * `at` - Unix timestamp in milliseconds of the change
+    /// * `default_quota` - Quota of users without one of their own
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ShelfEntry)` - The shelf entry of the document after the change
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The change would exceed the user's quota and
+    ///   nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn set_shelf(
+        &self,
+        user: String,
+        document: String,
+        shelf: Shelf,
+        at: u64,
+        default_quota: Quota,
+    ) -> Result<ShelfEntry, ServiceError>;
+
+    /// Lists the shelf entries of a user's documents, ordered by document.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<(document, entry)>)` - Every shelved document of the user
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_shelves(&self, user: String) -> Result<Vec<(String, ShelfEntry)>, ServiceError>;
+
+    /// Retrieves the storage quota set specifically for a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(quota))` - The quota overriding the server-wide default
+    /// - `Ok(None)` - The user has the server-wide default quota
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_quota(&self, user: String) -> Result<Option<Quota>, ServiceError>;
+
+    /// Sets or clears the storage quota of a user, overriding the server-wide default.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `quota` - The quota to store, or `None` to go back to the default
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The user had a quota of their own before
+    /// - `Ok(false)` - The user had the default quota before
+    /// - `Err(...)` - Unexpected database error occurred
+    fn set_quota(&self, user: String, quota: Option<Quota>) -> Result<bool, ServiceError>;
+
+    /// Retrieves the storage used by a user, as checked against their quota.
+    ///
+    /// Documents are those with progress. Bytes cover the progress, the position of each
+    /// device, the devices, annotations and bookmarks (tombstones included), the statistics
+    /// database, shelves, reading goals, share links, document aliases and the user's own
+    /// document metadata, see [`StoredSize`](crate::model::StoredSize). The history of
+    /// progress updates only grows and is left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(usage)` - What the user stores; nothing for unknown users
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_usage(&self, user: String) -> Result<QuotaUsage, ServiceError>;
+
     /// Lists all users in the database.
     ///
     /// # Returns
@@ -113,7 +592,14 @@ pub trait KorrosyncService {
     /// - `Err(...)` - Unexpected database error occurred
     fn list_users(&self) -> Result<Vec<User>, ServiceError>;
 
-    /// Deletes a user by username.
+    /// Deletes a user by username, together with all the data associated with them.
+    ///
+    /// The user record, every reading progress entry, every annotation and bookmark, the
+    /// reading goals and shelves, the storage quota and usage, every device, every share
+    /// link, the document aliases, the document metadata owned by the user and their
+    /// inactivity flag are removed in a single transaction, so a user re-created with the
+    /// same name starts fresh. The user also leaves every group they belong to (see
+    /// [`Self::leave_group`]).
     ///
     /// # Arguments
     ///
@@ -125,4 +611,429 @@ pub trait KorrosyncService {
     /// - `Ok(false)` - No user with that username existed
     /// - `Err(...)` - Unexpected database error occurred
     fn delete_user(&self, name: String) -> Result<bool, ServiceError>;
+
+    /// Deletes a user, as [`Self::delete_user`] does, provided their last activity is older
+    /// than a given time.
+    ///
+    /// The check and the deletion happen in the same transaction, so a user that becomes
+    /// active meanwhile is kept. Users that never logged in are never deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The username to delete
+    /// * `before` - Unix timestamp in milliseconds; only users last active earlier are deleted
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The user was inactive and was deleted
+    /// - `Ok(false)` - No such user exists, or they were active since
+    /// - `Err(...)` - Unexpected database error occurred
+    fn delete_user_if_inactive(&self, name: String, before: i64) -> Result<bool, ServiceError>;
+
+    /// Flags a user as inactive, keeping the original date if they were already flagged.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The username to flag
+    /// * `at` - Unix timestamp in milliseconds when the user was found inactive
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The user was not flagged before
+    /// - `Ok(false)` - The user was already flagged
+    /// - `Err(...)` - Unexpected database error occurred
+    fn flag_inactive_user(&self, name: String, at: i64) -> Result<bool, ServiceError>;
+
+    /// Clears the inactivity flag of a user, e.g. after they became active again.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The user was flagged
+    /// - `Ok(false)` - The user was not flagged
+    /// - `Err(...)` - Unexpected database error occurred
+    fn unflag_inactive_user(&self, name: String) -> Result<bool, ServiceError>;
+
+    /// Lists the users flagged as inactive, with when they were flagged.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<(username, flagged_at)>)` - Every flagged user, ordered by username
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_inactive_users(&self) -> Result<Vec<(String, i64)>, ServiceError>;
+
+    /// Lists the devices known for a user, ordered by device identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the devices
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<Device>)` - All devices of the user
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_devices(&self, user: String) -> Result<Vec<Device>, ServiceError>;
+
+    /// Sets or clears the friendly name of a user's device.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the device
+    /// * `device_id` - The device identifier
+    /// * `name` - The new friendly name, or `None` to fall back to the reported model
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The device existed and was renamed
+    /// - `Ok(false)` - No such device exists for the user
+    /// - `Err(...)` - Unexpected database error occurred
+    fn rename_device(
+        &self,
+        user: String,
+        device_id: String,
+        name: Option<String>,
+    ) -> Result<bool, ServiceError>;
+
+    /// Removes a device from a user's registry.
+    ///
+    /// The device is registered again the next time it synchronizes progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the device
+    /// * `device_id` - The device identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The device existed and was removed
+    /// - `Ok(false)` - No such device exists for the user
+    /// - `Err(...)` - Unexpected database error occurred
+    fn forget_device(&self, user: String, device_id: String) -> Result<bool, ServiceError>;
+
+    /// Appends an event to the security audit log.
+    ///
+    /// The audit log is append-only: events are never modified and are kept when the
+    /// user they refer to is deleted. They are only removed by [`Self::prune_events`].
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to record
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an unexpected database error occurs.
+    fn record_event(&self, event: AuditEvent) -> Result<(), ServiceError>;
+
+    /// Lists audit events in chronological order.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - If set, only events referring to this username are returned
+    /// * `since` - If set, only events at or after this Unix timestamp (milliseconds) are returned
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<AuditEvent>)` - The matching events, oldest first
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_events(
+        &self,
+        user: Option<String>,
+        since: Option<i64>,
+    ) -> Result<Vec<AuditEvent>, ServiceError>;
+
+    /// Removes audit events older than the given timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - Unix timestamp in milliseconds; strictly older events are removed
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(n)` - Number of events removed
+    /// - `Err(...)` - Unexpected database error occurred
+    fn prune_events(&self, before: i64) -> Result<usize, ServiceError>;
+
+    /// Creates a new group or updates an existing one.
+    ///
+    /// If a group with the same identifier already exists, it will be overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to add or update
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Group)` - Group was successfully created or updated
+    /// - `Err(...)` - Unexpected database error occurred
+    fn create_or_update_group(&self, group: Group) -> Result<Group, ServiceError>;
+
+    /// Retrieves a group by identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The group identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(group))` - Group found with the given identifier
+    /// - `Ok(None)` - No group exists with the given identifier
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_group(&self, id: String) -> Result<Option<Group>, ServiceError>;
+
+    /// Lists groups, ordered by identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - If set, only the groups this username is a member of are returned
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<Group>)` - The matching groups
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_groups(&self, user: Option<String>) -> Result<Vec<Group>, ServiceError>;
+
+    /// Deletes a group and all its memberships.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The group identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The group existed and was deleted
+    /// - `Ok(false)` - No group with that identifier existed
+    /// - `Err(...)` - Unexpected database error occurred
+    fn delete_group(&self, id: String) -> Result<bool, ServiceError>;
+
+    /// Adds a user to the group matching an invite code.
+    ///
+    /// Joining a group the user already belongs to leaves their membership untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `invite_code` - The invite code of the group
+    /// * `user` - The username joining the group
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(group))` - The group joined, including the new member
+    /// - `Ok(None)` - No group matches the invite code
+    /// - `Err(...)` - Unexpected database error occurred
+    fn join_group(&self, invite_code: String, user: String) -> Result<Option<Group>, ServiceError>;
+
+    /// Removes a user from a group.
+    ///
+    /// If the user owns the group, ownership passes to the longest-standing remaining
+    /// member. The group is deleted when its last member leaves.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The group identifier
+    /// * `user` - The username leaving the group
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The user was a member and left the group
+    /// - `Ok(false)` - No such group exists or the user was not a member
+    /// - `Err(...)` - Unexpected database error occurred
+    fn leave_group(&self, id: String, user: String) -> Result<bool, ServiceError>;
+
+    /// Changes how much of a member's progress is visible to the rest of a group.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The group identifier
+    /// * `user` - The username of the member
+    /// * `visibility` - The new visibility setting
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The setting was updated
+    /// - `Ok(false)` - No such group exists or the user is not a member
+    /// - `Err(...)` - Unexpected database error occurred
+    fn set_group_visibility(
+        &self,
+        id: String,
+        user: String,
+        visibility: Visibility,
+    ) -> Result<bool, ServiceError>;
+
+    /// Generates a new invite code for a group, invalidating the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The group identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(code))` - The new invite code
+    /// - `Ok(None)` - No group exists with the given identifier
+    /// - `Err(...)` - Unexpected database error occurred
+    fn rotate_group_invite(&self, id: String) -> Result<Option<String>, ServiceError>;
+
+    /// Stores a new share link.
+    ///
+    /// # Arguments
+    ///
+    /// * `share` - The share to store, counted against the quota of its owner
+    /// * `default_quota` - Quota of users without one of their own
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Share)` - The share as stored
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The share would exceed the user's quota and
+    ///   nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn create_share(&self, share: Share, default_quota: Quota) -> Result<Share, ServiceError>;
+
+    /// Retrieves a share link by token.
+    ///
+    /// Expired shares are returned as well; callers decide how to treat them.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The share token
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(share))` - Share found with the given token
+    /// - `Ok(None)` - No share exists with the given token
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_share(&self, token: String) -> Result<Option<Share>, ServiceError>;
+
+    /// Lists the share links created by a user, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the shares
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<Share>)` - All shares of the user, including expired ones
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_shares(&self, user: String) -> Result<Vec<Share>, ServiceError>;
+
+    /// Revokes a user's share link.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the share
+    /// * `token` - The share token
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The share existed, belonged to the user and was removed
+    /// - `Ok(false)` - No such share exists for the user
+    /// - `Err(...)` - Unexpected database error occurred
+    fn revoke_share(&self, user: String, token: String) -> Result<bool, ServiceError>;
+
+    /// Sets the metadata of a document, replacing any previous metadata in the same scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username the metadata belongs to, or `None` for global metadata
+    ///   shared by every user
+    /// * `document` - The document identifier
+    /// * `metadata` - The metadata to store
+    /// * `default_quota` - Quota of users without one of their own; global metadata is not
+    ///   counted against any quota
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(DocumentMetadata)` - The metadata as stored
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The metadata would exceed the user's quota and
+    ///   nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn set_document(
+        &self,
+        user: Option<String>,
+        document: String,
+        metadata: DocumentMetadata,
+        default_quota: Quota,
+    ) -> Result<DocumentMetadata, ServiceError>;
+
+    /// Retrieves the metadata of a document as seen by a user.
+    ///
+    /// The user's own metadata takes precedence over the global metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username looking up the document
+    /// * `document` - The document identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(metadata))` - Metadata found for the user or globally
+    /// - `Ok(None)` - The document has no metadata
+    /// - `Err(...)` - Unexpected database error occurred
+    fn get_document(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Option<DocumentMetadata>, ServiceError>;
+
+    /// Makes a document identifier an alias of another document for a user.
+    ///
+    /// Both identifiers are resolved first, so linking to an alias links to its canonical
+    /// document, and the aliases of `alias` follow it to the new canonical document. The
+    /// most recent progress of the two documents is kept under the canonical one, and their
+    /// annotations, bookmarks and shelf entries are merged (see [`ShelfEntry::merge`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the aliases
+    /// * `alias` - The document identifier to redirect
+    /// * `document` - The document identifier to redirect to
+    /// * `default_quota` - Quota of users without one of their own
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(canonical)` - The canonical document both identifiers now resolve to
+    /// - `Err(ServiceError::QuotaExceeded(_))` - The alias would exceed the user's quota and
+    ///   nothing was stored
+    /// - `Err(...)` - Unexpected database error occurred
+    fn link_document(
+        &self,
+        user: String,
+        alias: String,
+        document: String,
+        default_quota: Quota,
+    ) -> Result<String, ServiceError>;
+
+    /// Removes a document alias of a user.
+    ///
+    /// The progress stays with the canonical document; the alias starts with no progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the alias
+    /// * `alias` - The aliased document identifier
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(true)` - The alias existed and was removed
+    /// - `Ok(false)` - The identifier was not an alias
+    /// - `Err(...)` - Unexpected database error occurred
+    fn unlink_document(&self, user: String, alias: String) -> Result<bool, ServiceError>;
+
+    /// Lists the document aliases of a user, ordered by alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username owning the aliases
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<(alias, canonical)>)` - All aliases of the user
+    /// - `Err(...)` - Unexpected database error occurred
+    fn list_aliases(&self, user: String) -> Result<Vec<(String, String)>, ServiceError>;
+
+    /// Checks that the storage can serve reads, by opening a read transaction.
+    ///
+    /// Used by the readiness probe.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` - The storage is available
+    /// - `Err(...)` - The storage could not be read
+    fn ping(&self) -> Result<(), ServiceError>;
 }
@@ -5,10 +5,40 @@
 //!
 //! # Database Schema
 //!
-//! The implementation maintains two tables:
+//! The implementation maintains the following tables:
 //!
 //! - **users-v2**: Stores user credentials with username as key and [`User`] as value
-//! - **progress-v2**: Stores reading progress with composite key (document, user) and [`Progress`] as value
+//! - **progress-v4**: Stores reading progress with composite key (user, document) and [`Progress`] as value
+//! - **device-progress-v2**: Stores the last position reported by each device with composite key
+//!   (user, document, device_id) and [`Progress`] as value
+//! - **progress-history-v1**: Append-only log of every progress update with composite key
+//!   (user, timestamp, document, device_id) and [`Progress`] as value
+//! - **annotations-v1**: Stores highlights and notes, including deletion tombstones, with composite key
+//!   (user, document, id) and [`Annotation`] as value
+//! - **bookmarks-v1**: Stores named bookmarks, including deletion tombstones, with composite key
+//!   (user, document, id) and [`Bookmark`] as value
+//! - **statistics-v1**: Stores the KOReader statistics database (SQLite file) of each user, with username as key
+//! - **statistics-files-v1**: Stores the size and digest of each statistics database with username as key and
+//!   [`StatisticsFile`] as value
+//! - **goals-v1**: Stores the reading goals of each user with username as key and [`Goals`] as value
+//! - **shelves-v1**: Stores the shelf of each document, with its read-throughs, with composite key
+//!   (user, document) and [`ShelfEntry`] as value
+//! - **quotas-v1**: Stores the storage quota overriding the default of a user with username as key and
+//!   [`Quota`] as value
+//! - **usage-v2**: Stores the storage used by each user with username as key and [`QuotaUsage`] as value,
+//!   updated in the same transaction as the data it measures
+//! - **inactive-users-v1**: Stores the users flagged as inactive with username as key and the Unix timestamp
+//!   (milliseconds) when they were flagged as value
+//! - **devices-v1**: Stores the devices of each user with composite key (user, device_id) and [`Device`] as value
+//! - **audit-v1**: Append-only security audit log with composite key (timestamp, id) and [`AuditEvent`] as value
+//! - **groups-v1**: Stores reading groups, including their members, with the group id as key and [`Group`] as value
+//! - **shares-v1**: Stores public share links with the share token as key and [`Share`] as value
+//! - **aliases-v1**: Maps alternative document identifiers of a user to the canonical one, with composite key
+//!   (user, alias) and the canonical document as value
+//! - **documents-v1**: Stores document metadata with composite key (user, document) and [`DocumentMetadata`] as value;
+//!   global metadata has no user
+//!
+//! Tables from older schema versions are migrated when the database is opened (see `migration`).
 //!
 //! # Example
 //!
@@ -31,6 +61,7 @@
 //!     percentage: 45.5,
 //!     progress: "Chapter 5".to_string(),
 //!     timestamp: 1609459200000,
+//!     client_timestamp: None,
 //! };
 //! service.update_progress("alice".into(), "book.epub".into(), progress)?;
 //! # Ok(())
@@ -40,18 +71,52 @@
 use rkyv::{Archive, Deserialize, Serialize};
 use std::{fs::create_dir_all, path::Path};
 
-use redb::{Database, ReadableDatabase, ReadableTable, TableDefinition};
+use redb::{Database, ReadableDatabase, ReadableTable, TableDefinition, WriteTransaction};
 
 use crate::{
-    model::{Progress, User},
-    service::{db::KorrosyncService, error::ServiceError, serialization::Rkyv},
+    model::{
+        self, Annotation, AuditEvent, Bookmark, Device, DocumentMetadata, Goals, Group, Progress,
+        Quota, QuotaUsage, Share, Shelf, ShelfEntry, StatisticsFile, StoredSize, User, Visibility,
+    },
+    service::{
+        db::{KorrosyncService, ProgressCondition, SyncContext},
+        error::ServiceError,
+        serialization::Rkyv,
+    },
 };
 
-// Table definitions with versioning for future migration support
-// TODO: implement migrations for table definitions. So far we don't need it but it could be useful in the future
+mod migration;
+
+// Table definitions are versioned; bump the version and add a migration when a stored layout changes
 const USERS_TABLE: TableDefinition<&str, Rkyv<User>> = TableDefinition::new("users-v2");
 const PROGRESS_TABLE: TableDefinition<Rkyv<ProgressKey>, Rkyv<Progress>> =
-    TableDefinition::new("progress-v2");
+    TableDefinition::new("progress-v4");
+const DEVICE_PROGRESS_TABLE: TableDefinition<Rkyv<DeviceProgressKey>, Rkyv<Progress>> =
+    TableDefinition::new("device-progress-v2");
+const HISTORY_TABLE: TableDefinition<Rkyv<HistoryKey>, Rkyv<Progress>> =
+    TableDefinition::new("progress-history-v1");
+const ANNOTATIONS_TABLE: TableDefinition<Rkyv<AnnotationKey>, Rkyv<Annotation>> =
+    TableDefinition::new("annotations-v1");
+const BOOKMARKS_TABLE: TableDefinition<Rkyv<BookmarkKey>, Rkyv<Bookmark>> =
+    TableDefinition::new("bookmarks-v1");
+const STATISTICS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("statistics-v1");
+const STATISTICS_FILES_TABLE: TableDefinition<&str, Rkyv<StatisticsFile>> =
+    TableDefinition::new("statistics-files-v1");
+const GOALS_TABLE: TableDefinition<&str, Rkyv<Goals>> = TableDefinition::new("goals-v1");
+const SHELVES_TABLE: TableDefinition<Rkyv<ShelfKey>, Rkyv<ShelfEntry>> =
+    TableDefinition::new("shelves-v1");
+const QUOTAS_TABLE: TableDefinition<&str, Rkyv<Quota>> = TableDefinition::new("quotas-v1");
+const USAGE_TABLE: TableDefinition<&str, Rkyv<QuotaUsage>> = TableDefinition::new("usage-v2");
+const INACTIVE_USERS_TABLE: TableDefinition<&str, i64> = TableDefinition::new("inactive-users-v1");
+const DEVICES_TABLE: TableDefinition<Rkyv<DeviceKey>, Rkyv<Device>> =
+    TableDefinition::new("devices-v1");
+const AUDIT_TABLE: TableDefinition<Rkyv<AuditKey>, Rkyv<AuditEvent>> =
+    TableDefinition::new("audit-v1");
+const GROUPS_TABLE: TableDefinition<&str, Rkyv<Group>> = TableDefinition::new("groups-v1");
+const SHARES_TABLE: TableDefinition<&str, Rkyv<Share>> = TableDefinition::new("shares-v1");
+const ALIASES_TABLE: TableDefinition<Rkyv<AliasKey>, &str> = TableDefinition::new("aliases-v1");
+const DOCUMENTS_TABLE: TableDefinition<Rkyv<DocumentKey>, Rkyv<DocumentMetadata>> =
+    TableDefinition::new("documents-v1");
 
 /// Redb-based implementation of KoReader synchronization service.
 ///
@@ -64,12 +129,105 @@ pub struct KorrosyncServiceRedb {
 
 /// Composite key for the progress table.
 ///
-/// Combines document identifier and username to uniquely identify
-/// a user's progress in a specific document.
+/// Combines username and document identifier to uniquely identify a user's progress in a
+/// specific document. Keys are ordered by user, so the progress of a user can be read with
+/// a range scan.
 #[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
 struct ProgressKey {
+    user: String,
+    document: String,
+}
+
+/// Composite key for the per-device progress table.
+///
+/// Keys are ordered by user and document, so the positions of every device for a
+/// document are stored contiguously.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct DeviceProgressKey {
+    user: String,
+    document: String,
+    device_id: String,
+}
+
+/// Composite key for the progress history table.
+///
+/// Keys are ordered by user and then by time, so the history of a user is stored
+/// contiguously and in chronological order.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct HistoryKey {
+    user: String,
+    timestamp: u64,
+    document: String,
+    device_id: String,
+}
+
+/// Composite key for the annotations table.
+///
+/// Keys are ordered by user and document, so the annotations of a document are stored
+/// contiguously.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct AnnotationKey {
+    user: String,
+    document: String,
+    id: String,
+}
+
+/// Composite key for the bookmarks table.
+///
+/// Keys are ordered by user and document, so the bookmarks of a document are stored
+/// contiguously.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct BookmarkKey {
+    user: String,
+    document: String,
+    id: String,
+}
+
+/// Composite key for the shelves table.
+///
+/// Keys are ordered by user first, so all the shelved documents of a user are stored
+/// contiguously.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct ShelfKey {
+    user: String,
     document: String,
+}
+
+/// Composite key for the devices table.
+///
+/// Keys are ordered by user first, so all the devices of a user are stored contiguously.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct DeviceKey {
+    user: String,
+    device_id: String,
+}
+
+/// Composite key for the audit table.
+///
+/// Events are ordered by timestamp; the random id disambiguates events
+/// recorded within the same millisecond.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct AuditKey {
+    timestamp: i64,
+    id: u64,
+}
+
+/// Composite key for the aliases table.
+///
+/// Keys are ordered by user first, so all the aliases of a user are stored contiguously.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct AliasKey {
     user: String,
+    alias: String,
+}
+
+/// Composite key for the documents table.
+///
+/// Global metadata has no user; per-user metadata overrides it for that user.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct DocumentKey {
+    user: Option<String>,
+    document: String,
 }
 
 impl KorrosyncServiceRedb {
@@ -120,857 +278,4432 @@ impl KorrosyncServiceRedb {
 
         let db = Database::create(path).map_err(ServiceError::db)?;
 
-        // create tables if not exist
+        // migrate tables from older versions, then create tables if not exist
         let write_txn = db.begin_write().map_err(ServiceError::db)?;
+        migration::migrate(&write_txn)?;
         write_txn
             .open_table(USERS_TABLE)
             .map_err(ServiceError::db)?;
         write_txn
             .open_table(PROGRESS_TABLE)
             .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(DEVICE_PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(HISTORY_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(ANNOTATIONS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(BOOKMARKS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(STATISTICS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(STATISTICS_FILES_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(GOALS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(SHELVES_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(QUOTAS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(USAGE_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(INACTIVE_USERS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(DEVICES_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(AUDIT_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(GROUPS_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(SHARES_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        write_txn
+            .open_table(DOCUMENTS_TABLE)
+            .map_err(ServiceError::db)?;
         write_txn.commit().map_err(ServiceError::db)?;
 
         Ok(Self { db })
     }
-}
-
-impl KorrosyncService for KorrosyncServiceRedb {
-    /// Retrieves a user by username from the database.
-    ///
-    /// # Arguments
-    ///
-    /// * `name` - The username to look up
-    ///
-    /// # Returns
-    ///
-    /// - `Ok(Some(user))` - User found with the given username
-    /// - `Ok(None)` - No user exists with the given username
-    ///
-    /// # Example
-    ///
-    /// ```no_run
-    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
-    ///
-    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
-    ///
-    /// match service.get_user("alice".into())? {
-    ///     Some(user) => println!("Found user: {}", user.username()),
-    ///     None => println!("User not found"),
-    /// }
-    /// # Ok::<(), Box<dyn std::error::Error>>(())
-    /// ```
-    fn get_user(&self, name: String) -> Result<Option<User>, ServiceError> {
-        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
-        let table = read_txn.open_table(USERS_TABLE).map_err(ServiceError::db)?;
-
-        let user = table
-            .get(&*name)
-            .map_err(ServiceError::db)?
-            .map(|hash| hash.value());
-
-        Ok(user)
-    }
 
-    /// Adds a new user or updates an existing user in the database.
-    ///
-    /// # Arguments
-    ///
-    /// * `user` - Reference to the user to add or update
+    /// Applies `f` to a stored group and saves the result, all in one write transaction.
     ///
-    /// # Example
-    ///
-    /// ```no_run
-    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
-    /// use korrosync::model::User;
-    ///
-    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
-    /// let user = User::new("alice", "secure_password")?;
-    ///
-    /// service.create_or_update_user(user)?;
-    /// println!("User added successfully");
-    /// # Ok::<(), Box<dyn std::error::Error>>(())
-    /// ```
-    fn create_or_update_user(&self, user: User) -> Result<User, ServiceError> {
+    /// Returns `None` without writing anything if the group does not exist.
+    fn modify_group<R>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Group) -> R,
+    ) -> Result<Option<R>, ServiceError> {
         let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
-        {
+        let result = {
             let mut table = write_txn
-                .open_table(USERS_TABLE)
-                .map_err(ServiceError::db)?;
-            table
-                .insert(user.username(), &user)
+                .open_table(GROUPS_TABLE)
                 .map_err(ServiceError::db)?;
-        }
+            let existing = table
+                .get(id)
+                .map_err(ServiceError::db)?
+                .map(|group| group.value());
+            match existing {
+                Some(mut group) => {
+                    let result = f(&mut group);
+                    if group.members.is_empty() {
+                        table.remove(id).map_err(ServiceError::db)?;
+                    } else {
+                        table.insert(id, &group).map_err(ServiceError::db)?;
+                    }
+                    Some(result)
+                }
+                None => None,
+            }
+        };
         write_txn.commit().map_err(ServiceError::db)?;
 
-        Ok(user)
+        Ok(result)
     }
 
-    /// Updates or creates reading progress for a user's document.
-    ///
-    /// This method stores the reading progress for a specific user and document combination.
-    /// If progress already exists for this combination, it will be overwritten with the new data.
-    /// The operation is atomic and transactional.
-    ///
-    /// # Arguments
-    ///
-    /// * `user` - The username of the user
-    /// * `document` - The document identifier (typically filename or path)
-    /// * `progress` - The progress information to store
-    ///
-    /// # Returns
-    ///
-    /// Returns a tuple containing:
-    /// - The document identifier (echoed back)
-    /// - The timestamp from the progress record
-    ///
-    /// # Example
-    ///
-    /// ```no_run
-    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
-    /// use korrosync::model::Progress;
-    ///
-    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
-    ///
-    /// let progress = Progress {
-    ///     device_id: "device-123".to_string(),
-    ///     device: "Kindle".to_string(),
-    ///     percentage: 45.5,
-    ///     progress: "Page 91 of 200".to_string(),
-    ///     timestamp: 1609459200000,
-    /// };
+    /// Applies `f` to the shelf entry of a user's document and saves it if it changed, all
+    /// in one write transaction and within the user's quota.
     ///
-    /// let (doc, ts) = service.update_progress("alice".into(), "book.epub".into(), progress)?;
-    /// println!("Updated progress for {} at timestamp {}", doc, ts);
-    /// # Ok::<(), Box<dyn std::error::Error>>(())
-    /// ```
-    fn update_progress(
+    /// The document is resolved through the user's aliases first; documents that were never
+    /// shelved start from an empty entry.
+    fn modify_shelf(
         &self,
         user: String,
         document: String,
-        progress: Progress,
-    ) -> Result<(String, u64), ServiceError> {
-        let key = ProgressKey { document, user };
-
+        default_quota: Quota,
+        f: impl FnOnce(&mut ShelfEntry) -> bool,
+    ) -> Result<ShelfEntry, ServiceError> {
         let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
-        {
-            let mut table = write_txn
-                .open_table(PROGRESS_TABLE)
-                .map_err(ServiceError::db)?;
-            table.insert(&key, &progress).map_err(ServiceError::db)?;
-        }
+        let entry = Self::account_usage(&write_txn, &user, Some(default_quota), |usage| {
+            Self::write_shelf(&write_txn, user.clone(), document, usage, f)
+        })?;
         write_txn.commit().map_err(ServiceError::db)?;
 
-        Ok((key.document, progress.timestamp))
+        Ok(entry)
     }
 
-    /// Retrieves reading progress for a specific user and document.
-    ///
-    /// # Arguments
-    ///
-    /// * `user` - The username of the user
-    /// * `document` - The document identifier to look up
-    ///
-    /// # Returns
-    ///
-    /// Returns the `Progress` information if found.
-    ///
-    /// # Example
-    ///
-    /// ```no_run
-    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
-    ///
-    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
-    ///
-    /// match service.get_progress("alice".to_string(), "book.epub".to_string()) {
-    ///     Ok(Some(progress)) => {
-    ///         println!("Progress: {}% on device {}",
-    ///                  progress.percentage, progress.device);
-    ///     }
-    ///     Ok(None) => println!("No progress found"),
-    ///     Err(e) => println!("Unexpected error: {}", e),
-    /// }
-    /// # Ok::<(), Box<dyn std::error::Error>>(())
-    /// ```
-    fn get_progress(
-        &self,
+    /// Like [`Self::modify_shelf`], within an open write transaction.
+    fn write_shelf(
+        write_txn: &WriteTransaction,
         user: String,
         document: String,
-    ) -> Result<Option<Progress>, ServiceError> {
-        let key = ProgressKey { document, user };
-
-        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
-        let table = read_txn
-            .open_table(PROGRESS_TABLE)
+        usage: &mut QuotaUsage,
+        f: impl FnOnce(&mut ShelfEntry) -> bool,
+    ) -> Result<ShelfEntry, ServiceError> {
+        let aliases = write_txn
+            .open_table(ALIASES_TABLE)
             .map_err(ServiceError::db)?;
+        let key = ShelfKey {
+            document: resolve_alias(&aliases, &user, document)?,
+            user,
+        };
 
-        if let Some(progress) = table.get(&key).map_err(ServiceError::db)? {
-            Ok(Some(progress.value()))
-        } else {
-            Ok(None)
+        let mut table = write_txn
+            .open_table(SHELVES_TABLE)
+            .map_err(ServiceError::db)?;
+        let mut entry = table
+            .get(&key)
+            .map_err(ServiceError::db)?
+            .map(|entry| entry.value())
+            .unwrap_or_default();
+        if f(&mut entry) {
+            let replaced = table
+                .insert(&key, &entry)
+                .map_err(ServiceError::db)?
+                .map(|replaced| row_size(&key.document, &replaced.value()));
+            usage.store(replaced, row_size(&key.document, &entry));
         }
-    }
-
-    fn list_users(&self) -> Result<Vec<User>, ServiceError> {
-        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
-        let table = read_txn.open_table(USERS_TABLE).map_err(ServiceError::db)?;
 
-        let mut users = Vec::new();
-        for entry in table.iter().map_err(ServiceError::db)? {
-            let (_key, value) = entry.map_err(ServiceError::db)?;
-            users.push(value.value());
-        }
-        Ok(users)
+        Ok(entry)
     }
 
-    fn delete_user(&self, name: String) -> Result<bool, ServiceError> {
-        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+    /// Removes a user and all their data within an open write transaction.
+    ///
+    /// See [`KorrosyncService::delete_user`]. Returns whether the user existed.
+    fn remove_user(write_txn: &WriteTransaction, name: &str) -> Result<bool, ServiceError> {
         let existed = {
             let mut table = write_txn
                 .open_table(USERS_TABLE)
                 .map_err(ServiceError::db)?;
-            table.remove(&*name).map_err(ServiceError::db)?.is_some()
+            table.remove(name).map_err(ServiceError::db)?.is_some()
         };
-        write_txn.commit().map_err(ServiceError::db)?;
-        Ok(existed)
-    }
-}
+        {
+            let mut table = write_txn
+                .open_table(PROGRESS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(DEVICE_PROGRESS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(HISTORY_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(ANNOTATIONS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(BOOKMARKS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(STATISTICS_TABLE)
+                .map_err(ServiceError::db)?;
+            table.remove(name).map_err(ServiceError::db)?;
+
+            let mut table = write_txn
+                .open_table(STATISTICS_FILES_TABLE)
+                .map_err(ServiceError::db)?;
+            table.remove(name).map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(GOALS_TABLE)
+                .map_err(ServiceError::db)?;
+            table.remove(name).map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(SHELVES_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(QUOTAS_TABLE)
+                .map_err(ServiceError::db)?;
+            table.remove(name).map_err(ServiceError::db)?;
+
+            let mut table = write_txn
+                .open_table(USAGE_TABLE)
+                .map_err(ServiceError::db)?;
+            table.remove(name).map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(INACTIVE_USERS_TABLE)
+                .map_err(ServiceError::db)?;
+            table.remove(name).map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(DEVICES_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(SHARES_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|_, share| share.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(DOCUMENTS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user.as_deref() != Some(name))
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(ALIASES_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .retain(|key, _| key.user != name)
+                .map_err(ServiceError::db)?;
+        }
+        {
+            let mut table = write_txn
+                .open_table(GROUPS_TABLE)
+                .map_err(ServiceError::db)?;
+            let mut groups = Vec::new();
+            for entry in table.iter().map_err(ServiceError::db)? {
+                let (_key, value) = entry.map_err(ServiceError::db)?;
+                let group = value.value();
+                if group.is_member(name) {
+                    groups.push(group);
+                }
+            }
+            for mut group in groups {
+                group.remove_member(name);
+                if group.members.is_empty() {
+                    table.remove(&*group.id).map_err(ServiceError::db)?;
+                } else {
+                    table.insert(&*group.id, &group).map_err(ServiceError::db)?;
+                }
+            }
+        }
+        Ok(existed)
+    }
+
+    /// Records a device seen for a user within an open write transaction.
+    ///
+    /// A known device keeps its `first_seen` timestamp and friendly `name`.
+    fn write_device(
+        write_txn: &WriteTransaction,
+        user: String,
+        mut device: Device,
+        usage: &mut QuotaUsage,
+    ) -> Result<Device, ServiceError> {
+        let key = DeviceKey {
+            user,
+            device_id: device.device_id.clone(),
+        };
+
+        let mut table = write_txn
+            .open_table(DEVICES_TABLE)
+            .map_err(ServiceError::db)?;
+        if let Some(existing) = table.get(&key).map_err(ServiceError::db)? {
+            let existing = existing.value();
+            device.first_seen = existing.first_seen;
+            device.name = existing.name;
+        }
+        let replaced = table
+            .insert(&key, &device)
+            .map_err(ServiceError::db)?
+            .map(|replaced| replaced.value().stored_size());
+        usage.store(replaced, device.stored_size());
+
+        Ok(device)
+    }
+
+    /// Whether the stored progress of each of a user's documents satisfies `condition`,
+    /// within an open write transaction.
+    ///
+    /// Documents are resolved through the user's aliases first.
+    fn progress_satisfies<'a>(
+        write_txn: &WriteTransaction,
+        user: &str,
+        documents: impl IntoIterator<Item = &'a String>,
+        condition: &ProgressCondition<'_>,
+    ) -> Result<bool, ServiceError> {
+        let aliases = write_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        let table = write_txn
+            .open_table(PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+        for document in documents {
+            let key = ProgressKey {
+                user: user.to_string(),
+                document: resolve_alias(&aliases, user, document.clone())?,
+            };
+            let current = table
+                .get(&key)
+                .map_err(ServiceError::db)?
+                .map(|progress| progress.value());
+            if !condition(current.as_ref()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Applies `f` to the storage used by a user and saves the result, within an open write
+    /// transaction.
+    ///
+    /// If a quota is given, the usage `f` leads to is checked against the user's quota,
+    /// falling back to the given one if they have none of their own (see [`Quota::check`]).
+    /// Dropping the transaction without committing discards whatever `f` stored.
+    fn account_usage<R>(
+        write_txn: &WriteTransaction,
+        user: &str,
+        default_quota: Option<Quota>,
+        f: impl FnOnce(&mut QuotaUsage) -> Result<R, ServiceError>,
+    ) -> Result<R, ServiceError> {
+        let before = write_txn
+            .open_table(USAGE_TABLE)
+            .map_err(ServiceError::db)?
+            .get(user)
+            .map_err(ServiceError::db)?
+            .map(|usage| usage.value())
+            .unwrap_or_default();
+
+        let mut usage = before;
+        let result = f(&mut usage)?;
+
+        if let Some(default_quota) = default_quota {
+            let quota = write_txn
+                .open_table(QUOTAS_TABLE)
+                .map_err(ServiceError::db)?
+                .get(user)
+                .map_err(ServiceError::db)?
+                .map_or(default_quota, |quota| quota.value());
+            quota
+                .check(&before, &usage)
+                .map_err(ServiceError::QuotaExceeded)?;
+        }
+        if usage != before {
+            write_txn
+                .open_table(USAGE_TABLE)
+                .map_err(ServiceError::db)?
+                .insert(user, &usage)
+                .map_err(ServiceError::db)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Stores a user's progress for a document within an open write transaction.
+    ///
+    /// The document is resolved through the user's aliases first, so progress is always
+    /// stored against the canonical document. The progress becomes both the latest position
+    /// of the document and the last position of the reporting device.
+    fn write_progress(
+        write_txn: &WriteTransaction,
+        user: String,
+        document: String,
+        progress: &Progress,
+        usage: &mut QuotaUsage,
+    ) -> Result<(), ServiceError> {
+        let aliases = write_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        let document = resolve_alias(&aliases, &user, document)?;
+
+        // History only grows, so it is left out of the quota: counting it would eventually
+        // reject every update, even of documents that don't grow
+        let mut table = write_txn
+            .open_table(HISTORY_TABLE)
+            .map_err(ServiceError::db)?;
+        let key = HistoryKey {
+            user: user.clone(),
+            timestamp: progress.reported_at(),
+            document: document.clone(),
+            device_id: progress.device_id.clone(),
+        };
+        table.insert(&key, progress).map_err(ServiceError::db)?;
+        let size = row_size(&key.document, progress);
+
+        let mut table = write_txn
+            .open_table(DEVICE_PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+        let key = DeviceProgressKey {
+            user,
+            document,
+            device_id: progress.device_id.clone(),
+        };
+        let replaced = table
+            .insert(&key, progress)
+            .map_err(ServiceError::db)?
+            .map(|replaced| row_size(&key.document, &replaced.value()));
+        usage.store(replaced, size);
+
+        let mut table = write_txn
+            .open_table(PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+        let key = ProgressKey {
+            user: key.user,
+            document: key.document,
+        };
+        let replaced = table
+            .insert(&key, progress)
+            .map_err(ServiceError::db)?
+            .map(|replaced| row_size(&key.document, &replaced.value()));
+        if replaced.is_none() {
+            usage.documents += 1;
+        }
+        usage.store(replaced, size);
+
+        Ok(())
+    }
+}
+
+/// Bytes a record stored per document takes, including the document identifier.
+fn row_size(document: &str, record: &impl StoredSize) -> u64 {
+    document.len() as u64 + record.stored_size()
+}
+
+/// Bytes an alias takes: the alias and the document it resolves to.
+fn alias_size(alias: &str, document: &str) -> u64 {
+    (alias.len() + document.len()) as u64
+}
+
+/// Collects the progress of every document of a user, ordered by document.
+fn user_progress(
+    table: &impl ReadableTable<Rkyv<ProgressKey>, Rkyv<Progress>>,
+    user: &str,
+) -> Result<Vec<(String, Progress)>, ServiceError> {
+    let start = ProgressKey {
+        user: user.to_string(),
+        ..Default::default()
+    };
+
+    let mut progress = Vec::new();
+    for entry in table.range(start..).map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        if key.user != user {
+            break;
+        }
+        progress.push((key.document, value.value()));
+    }
+    Ok(progress)
+}
+
+/// Collects the per-device positions of a user's document.
+fn device_positions(
+    table: &impl ReadableTable<Rkyv<DeviceProgressKey>, Rkyv<Progress>>,
+    user: &str,
+    document: &str,
+) -> Result<Vec<Progress>, ServiceError> {
+    let start = DeviceProgressKey {
+        user: user.to_string(),
+        document: document.to_string(),
+        device_id: String::new(),
+    };
+
+    let mut positions = Vec::new();
+    for entry in table.range(start..).map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        if key.user != user || key.document != document {
+            break;
+        }
+        positions.push(value.value());
+    }
+    Ok(positions)
+}
+
+/// Removes the per-device positions of a user's document.
+fn remove_device_positions(
+    table: &mut redb::Table<Rkyv<DeviceProgressKey>, Rkyv<Progress>>,
+    user: &str,
+    document: &str,
+    usage: &mut QuotaUsage,
+) -> Result<(), ServiceError> {
+    for position in device_positions(table, user, document)? {
+        usage.remove(row_size(document, &position));
+        table
+            .remove(&DeviceProgressKey {
+                user: user.to_string(),
+                document: document.to_string(),
+                device_id: position.device_id,
+            })
+            .map_err(ServiceError::db)?;
+    }
+    Ok(())
+}
+
+/// Collects the keys of the progress history of a user's document, oldest first.
+///
+/// History keys are ordered by user and then by time, so this scans the history of the
+/// user only.
+fn document_history_keys(
+    table: &impl ReadableTable<Rkyv<HistoryKey>, Rkyv<Progress>>,
+    user: &str,
+    document: &str,
+) -> Result<Vec<HistoryKey>, ServiceError> {
+    let start = HistoryKey {
+        user: user.to_string(),
+        ..Default::default()
+    };
+
+    let mut keys = Vec::new();
+    for entry in table.range(start..).map_err(ServiceError::db)? {
+        let (key, _) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        if key.user != user {
+            break;
+        }
+        if key.document == document {
+            keys.push(key);
+        }
+    }
+    Ok(keys)
+}
+
+/// Collects the annotations of a user's document, ordered by id.
+fn document_annotations(
+    table: &impl ReadableTable<Rkyv<AnnotationKey>, Rkyv<Annotation>>,
+    user: &str,
+    document: &str,
+) -> Result<Vec<Annotation>, ServiceError> {
+    let start = AnnotationKey {
+        user: user.to_string(),
+        document: document.to_string(),
+        id: String::new(),
+    };
+
+    let mut annotations = Vec::new();
+    for entry in table.range(start..).map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        if key.user != user || key.document != document {
+            break;
+        }
+        annotations.push(value.value());
+    }
+    Ok(annotations)
+}
+
+/// Merges annotations into the stored ones of a user's (canonical) document.
+fn write_annotations(
+    table: &mut redb::Table<Rkyv<AnnotationKey>, Rkyv<Annotation>>,
+    user: &str,
+    document: &str,
+    annotations: Vec<Annotation>,
+    usage: &mut QuotaUsage,
+) -> Result<(), ServiceError> {
+    for annotation in annotations {
+        let key = AnnotationKey {
+            user: user.to_string(),
+            document: document.to_string(),
+            id: annotation.id.clone(),
+        };
+        let merged = match table.get(&key).map_err(ServiceError::db)? {
+            Some(current) => current.value().merge(annotation),
+            None => annotation,
+        };
+        let replaced = table
+            .insert(&key, &merged)
+            .map_err(ServiceError::db)?
+            .map(|replaced| row_size(document, &replaced.value()));
+        usage.store(replaced, row_size(document, &merged));
+    }
+    Ok(())
+}
+
+/// Collects the bookmarks of a user's document, ordered by id.
+fn document_bookmarks(
+    table: &impl ReadableTable<Rkyv<BookmarkKey>, Rkyv<Bookmark>>,
+    user: &str,
+    document: &str,
+) -> Result<Vec<Bookmark>, ServiceError> {
+    let start = BookmarkKey {
+        user: user.to_string(),
+        document: document.to_string(),
+        id: String::new(),
+    };
+
+    let mut bookmarks = Vec::new();
+    for entry in table.range(start..).map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        if key.user != user || key.document != document {
+            break;
+        }
+        bookmarks.push(value.value());
+    }
+    Ok(bookmarks)
+}
+
+/// Merges a bookmark into the stored version of a user's (canonical) document and returns the result.
+fn write_bookmark(
+    table: &mut redb::Table<Rkyv<BookmarkKey>, Rkyv<Bookmark>>,
+    user: &str,
+    document: &str,
+    bookmark: Bookmark,
+    usage: &mut QuotaUsage,
+) -> Result<Bookmark, ServiceError> {
+    let key = BookmarkKey {
+        user: user.to_string(),
+        document: document.to_string(),
+        id: bookmark.id.clone(),
+    };
+    let merged = match table.get(&key).map_err(ServiceError::db)? {
+        Some(current) => current.value().merge(bookmark),
+        None => bookmark,
+    };
+    let replaced = table
+        .insert(&key, &merged)
+        .map_err(ServiceError::db)?
+        .map(|replaced| row_size(document, &replaced.value()));
+    usage.store(replaced, row_size(document, &merged));
+    Ok(merged)
+}
+
+/// Returns the canonical document for one of a user's document identifiers.
+///
+/// Identifiers that are not an alias are canonical themselves. Aliases always point to a
+/// canonical document, so a single lookup is enough.
+fn resolve_alias(
+    table: &impl ReadableTable<Rkyv<AliasKey>, &'static str>,
+    user: &str,
+    document: String,
+) -> Result<String, ServiceError> {
+    let key = AliasKey {
+        user: user.to_string(),
+        alias: document,
+    };
+    let canonical = table
+        .get(&key)
+        .map_err(ServiceError::db)?
+        .map(|canonical| canonical.value().to_string());
+
+    Ok(canonical.unwrap_or(key.alias))
+}
+
+impl KorrosyncService for KorrosyncServiceRedb {
+    /// Retrieves a user by username from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The username to look up
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(user))` - User found with the given username
+    /// - `Ok(None)` - No user exists with the given username
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+    ///
+    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
+    ///
+    /// match service.get_user("alice".into())? {
+    ///     Some(user) => println!("Found user: {}", user.username()),
+    ///     None => println!("User not found"),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_user(&self, name: String) -> Result<Option<User>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn.open_table(USERS_TABLE).map_err(ServiceError::db)?;
+
+        let user = table
+            .get(&*name)
+            .map_err(ServiceError::db)?
+            .map(|hash| hash.value());
+
+        Ok(user)
+    }
+
+    /// Adds a new user or updates an existing user in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - Reference to the user to add or update
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+    /// use korrosync::model::User;
+    ///
+    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
+    /// let user = User::new("alice", "secure_password")?;
+    ///
+    /// service.create_or_update_user(user)?;
+    /// println!("User added successfully");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn create_or_update_user(&self, user: User) -> Result<User, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        {
+            let mut table = write_txn
+                .open_table(USERS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .insert(user.username(), &user)
+                .map_err(ServiceError::db)?;
+        }
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(user)
+    }
+
+    fn change_password(&self, name: String, key: String) -> Result<bool, ServiceError> {
+        // Hash before locking the database, it is the slow part
+        let mut updated = User::new(name.as_str(), key).map_err(model::Error::runtime)?;
+
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = {
+            let mut table = write_txn
+                .open_table(USERS_TABLE)
+                .map_err(ServiceError::db)?;
+            let current = table
+                .get(name.as_str())
+                .map_err(ServiceError::db)?
+                .map(|user| user.value());
+            match current {
+                Some(current) => {
+                    if let Some(at) = current.last_activity() {
+                        updated.set_last_activity(at);
+                    }
+                    table
+                        .insert(name.as_str(), &updated)
+                        .map_err(ServiceError::db)?;
+                    true
+                }
+                None => false,
+            }
+        };
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn touch_user(&self, name: String) -> Result<Option<User>, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let user = {
+            let mut table = write_txn
+                .open_table(USERS_TABLE)
+                .map_err(ServiceError::db)?;
+            let current = table
+                .get(name.as_str())
+                .map_err(ServiceError::db)?
+                .map(|user| user.value());
+            match current {
+                Some(mut user) => {
+                    user.touch();
+                    table
+                        .insert(name.as_str(), &user)
+                        .map_err(ServiceError::db)?;
+                    Some(user)
+                }
+                None => None,
+            }
+        };
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(user)
+    }
+
+    /// Updates or creates reading progress for a user's document.
+    ///
+    /// This method stores the reading progress for a specific user and document combination.
+    /// If progress already exists for this combination, it will be overwritten with the new data.
+    /// The update goes through [`KorrosyncService::sync_progress`] without a default quota or
+    /// an IP address, and moves the document between shelves at [`model::FINISHED_PERCENTAGE`].
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier (typically filename or path)
+    /// * `progress` - The progress information to store
+    ///
+    /// # Returns
+    ///
+    /// Returns a tuple containing:
+    /// - The document identifier (echoed back)
+    /// - The timestamp from the progress record
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+    /// use korrosync::model::Progress;
+    ///
+    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
+    ///
+    /// let progress = Progress {
+    ///     device_id: "device-123".to_string(),
+    ///     device: "Kindle".to_string(),
+    ///     percentage: 45.5,
+    ///     progress: "Page 91 of 200".to_string(),
+    ///     timestamp: 1609459200000,
+    ///     client_timestamp: None,
+    /// };
+    ///
+    /// let (doc, ts) = service.update_progress("alice".into(), "book.epub".into(), progress)?;
+    /// println!("Updated progress for {} at timestamp {}", doc, ts);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn update_progress(
+        &self,
+        user: String,
+        document: String,
+        progress: Progress,
+    ) -> Result<(String, u64), ServiceError> {
+        let context = SyncContext {
+            finished_threshold: Some(model::FINISHED_PERCENTAGE),
+            ..Default::default()
+        };
+        let timestamp = progress.timestamp;
+        self.sync_progress(user, vec![(document.clone(), progress)], &context, None)?;
+
+        Ok((document, timestamp))
+    }
+
+    fn sync_progress(
+        &self,
+        user: String,
+        updates: Vec<(String, Progress)>,
+        context: &SyncContext,
+        condition: Option<&ProgressCondition<'_>>,
+    ) -> Result<Option<Vec<(String, u64)>>, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        if let Some(condition) = condition
+            && !Self::progress_satisfies(
+                &write_txn,
+                &user,
+                updates.iter().map(|(document, _)| document),
+                condition,
+            )?
+        {
+            return Ok(None);
+        }
+
+        // Dropping the transaction without committing discards the updates
+        let results =
+            Self::account_usage(&write_txn, &user, Some(context.default_quota), |usage| {
+                let mut results = Vec::with_capacity(updates.len());
+                for (document, progress) in updates {
+                    Self::write_progress(
+                        &write_txn,
+                        user.clone(),
+                        document.clone(),
+                        &progress,
+                        usage,
+                    )?;
+                    let device = Device::reporting(&progress, document.clone(), context.ip.clone());
+                    Self::write_device(&write_txn, user.clone(), device, usage)?;
+                    if let Some(finished_threshold) = context.finished_threshold {
+                        let (percentage, at) = (progress.percentage, progress.reported_at());
+                        Self::write_shelf(
+                            &write_txn,
+                            user.clone(),
+                            document.clone(),
+                            usage,
+                            |entry| entry.record_progress(percentage, at, finished_threshold),
+                        )?;
+                    }
+                    results.push((document, progress.timestamp));
+                }
+                Ok(results)
+            })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(Some(results))
+    }
+
+    /// Retrieves reading progress for a specific user and document.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The username of the user
+    /// * `document` - The document identifier to look up
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Progress` information if found.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+    ///
+    /// let service = KorrosyncServiceRedb::new("korrosync.db")?;
+    ///
+    /// match service.get_progress("alice".to_string(), "book.epub".to_string()) {
+    ///     Ok(Some(progress)) => {
+    ///         println!("Progress: {}% on device {}",
+    ///                  progress.percentage, progress.device);
+    ///     }
+    ///     Ok(None) => println!("No progress found"),
+    ///     Err(e) => println!("Unexpected error: {}", e),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_progress(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Option<Progress>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let aliases = read_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        let document = resolve_alias(&aliases, &user, document)?;
+        let key = ProgressKey { user, document };
+
+        let table = read_txn
+            .open_table(PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        if let Some(progress) = table.get(&key).map_err(ServiceError::db)? {
+            Ok(Some(progress.value()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_progress_batch(
+        &self,
+        user: String,
+        documents: Vec<String>,
+    ) -> Result<Vec<(String, Progress)>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let aliases = read_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let mut progress = Vec::new();
+        for requested in documents {
+            let key = ProgressKey {
+                user: user.clone(),
+                document: resolve_alias(&aliases, &user, requested.clone())?,
+            };
+            if let Some(value) = table.get(&key).map_err(ServiceError::db)? {
+                progress.push((requested, value.value()));
+            }
+        }
+        Ok(progress)
+    }
+
+    fn delete_progress(&self, user: String, document: String) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = Self::account_usage(&write_txn, &user, None, |usage| {
+            let aliases = write_txn
+                .open_table(ALIASES_TABLE)
+                .map_err(ServiceError::db)?;
+            let document = resolve_alias(&aliases, &user, document)?;
+
+            let mut devices = write_txn
+                .open_table(DEVICE_PROGRESS_TABLE)
+                .map_err(ServiceError::db)?;
+            remove_device_positions(&mut devices, &user, &document, usage)?;
+
+            let mut history = write_txn
+                .open_table(HISTORY_TABLE)
+                .map_err(ServiceError::db)?;
+            for key in document_history_keys(&history, &user, &document)? {
+                history.remove(&key).map_err(ServiceError::db)?;
+            }
+
+            let shelf = write_txn
+                .open_table(SHELVES_TABLE)
+                .map_err(ServiceError::db)?
+                .remove(&ShelfKey {
+                    user: user.clone(),
+                    document: document.clone(),
+                })
+                .map_err(ServiceError::db)?
+                .map(|entry| row_size(&document, &entry.value()));
+            if let Some(shelf) = shelf {
+                usage.remove(shelf);
+            }
+
+            let mut table = write_txn
+                .open_table(PROGRESS_TABLE)
+                .map_err(ServiceError::db)?;
+            let removed = table
+                .remove(&ProgressKey {
+                    user: user.clone(),
+                    document: document.clone(),
+                })
+                .map_err(ServiceError::db)?
+                .map(|removed| row_size(&document, &removed.value()));
+            if let Some(removed) = removed {
+                usage.documents = usage.documents.saturating_sub(1);
+                usage.remove(removed);
+            }
+            Ok(removed.is_some())
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn delete_progress_if_older(
+        &self,
+        user: String,
+        document: String,
+        before: u64,
+    ) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let stale = Self::account_usage(&write_txn, &user, None, |usage| {
+            let aliases = write_txn
+                .open_table(ALIASES_TABLE)
+                .map_err(ServiceError::db)?;
+            let key = ProgressKey {
+                document: resolve_alias(&aliases, &user, document)?,
+                user: user.clone(),
+            };
+
+            let mut table = write_txn
+                .open_table(PROGRESS_TABLE)
+                .map_err(ServiceError::db)?;
+            let stale = table
+                .get(&key)
+                .map_err(ServiceError::db)?
+                .map(|progress| progress.value())
+                .filter(|progress| progress.timestamp < before);
+            let Some(stale) = stale else {
+                return Ok(false);
+            };
+            table.remove(&key).map_err(ServiceError::db)?;
+            usage.documents = usage.documents.saturating_sub(1);
+            usage.remove(row_size(&key.document, &stale));
+
+            let mut devices = write_txn
+                .open_table(DEVICE_PROGRESS_TABLE)
+                .map_err(ServiceError::db)?;
+            remove_device_positions(&mut devices, &key.user, &key.document, usage)?;
+            Ok(true)
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(stale)
+    }
+
+    fn list_device_progress(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Vec<Progress>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let aliases = read_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        let document = resolve_alias(&aliases, &user, document)?;
+
+        let table = read_txn
+            .open_table(DEVICE_PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+        let mut positions = device_positions(&table, &user, &document)?;
+        positions.sort_by_key(|p| std::cmp::Reverse(p.reported_at()));
+
+        Ok(positions)
+    }
+
+    fn list_progress(&self, user: String) -> Result<Vec<(String, Progress)>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        user_progress(&table, &user)
+    }
+
+    fn list_progress_history(&self, user: String) -> Result<Vec<(String, Progress)>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(HISTORY_TABLE)
+            .map_err(ServiceError::db)?;
+        let start = HistoryKey {
+            user: user.clone(),
+            ..Default::default()
+        };
+
+        let mut history = Vec::new();
+        for entry in table.range(start..).map_err(ServiceError::db)? {
+            let (key, value) = entry.map_err(ServiceError::db)?;
+            let key = key.value();
+            if key.user != user {
+                break;
+            }
+            history.push((key.document, value.value()));
+        }
+        Ok(history)
+    }
+
+    fn get_statistics(
+        &self,
+        user: String,
+    ) -> Result<Option<(StatisticsFile, Vec<u8>)>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let files = read_txn
+            .open_table(STATISTICS_FILES_TABLE)
+            .map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(STATISTICS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let Some(file) = files.get(user.as_str()).map_err(ServiceError::db)? else {
+            return Ok(None);
+        };
+        Ok(table
+            .get(user.as_str())
+            .map_err(ServiceError::db)?
+            .map(|data| (file.value(), data.value().to_vec())))
+    }
+
+    fn get_statistics_file(&self, user: String) -> Result<Option<StatisticsFile>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(STATISTICS_FILES_TABLE)
+            .map_err(ServiceError::db)?;
+
+        Ok(table
+            .get(user.as_str())
+            .map_err(ServiceError::db)?
+            .map(|file| file.value()))
+    }
+
+    fn put_statistics(
+        &self,
+        user: String,
+        data: Vec<u8>,
+        default_quota: Quota,
+    ) -> Result<StatisticsFile, ServiceError> {
+        let file = StatisticsFile::of(&data);
+
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        Self::account_usage(&write_txn, &user, Some(default_quota), |usage| {
+            let mut table = write_txn
+                .open_table(STATISTICS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .insert(user.as_str(), data.as_slice())
+                .map_err(ServiceError::db)?;
+
+            let mut files = write_txn
+                .open_table(STATISTICS_FILES_TABLE)
+                .map_err(ServiceError::db)?;
+            let replaced = files
+                .insert(user.as_str(), &file)
+                .map_err(ServiceError::db)?
+                .map(|replaced| replaced.value().size);
+            usage.store(replaced, file.size);
+            Ok(())
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(file)
+    }
+
+    fn list_annotations(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Vec<Annotation>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let aliases = read_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        let document = resolve_alias(&aliases, &user, document)?;
+
+        let table = read_txn
+            .open_table(ANNOTATIONS_TABLE)
+            .map_err(ServiceError::db)?;
+        document_annotations(&table, &user, &document)
+    }
+
+    fn merge_annotations(
+        &self,
+        user: String,
+        document: String,
+        annotations: Vec<Annotation>,
+        default_quota: Quota,
+    ) -> Result<Vec<Annotation>, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let merged = Self::account_usage(&write_txn, &user, Some(default_quota), |usage| {
+            let aliases = write_txn
+                .open_table(ALIASES_TABLE)
+                .map_err(ServiceError::db)?;
+            let document = resolve_alias(&aliases, &user, document)?;
+
+            let mut table = write_txn
+                .open_table(ANNOTATIONS_TABLE)
+                .map_err(ServiceError::db)?;
+            write_annotations(&mut table, &user, &document, annotations, usage)?;
+            document_annotations(&table, &user, &document)
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(merged)
+    }
+
+    fn list_bookmarks(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Vec<Bookmark>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let aliases = read_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+        let document = resolve_alias(&aliases, &user, document)?;
+
+        let table = read_txn
+            .open_table(BOOKMARKS_TABLE)
+            .map_err(ServiceError::db)?;
+        document_bookmarks(&table, &user, &document)
+    }
+
+    fn put_bookmark(
+        &self,
+        user: String,
+        document: String,
+        bookmark: Bookmark,
+        default_quota: Quota,
+    ) -> Result<Bookmark, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let stored = Self::account_usage(&write_txn, &user, Some(default_quota), |usage| {
+            let aliases = write_txn
+                .open_table(ALIASES_TABLE)
+                .map_err(ServiceError::db)?;
+            let document = resolve_alias(&aliases, &user, document)?;
+
+            let mut table = write_txn
+                .open_table(BOOKMARKS_TABLE)
+                .map_err(ServiceError::db)?;
+            write_bookmark(&mut table, &user, &document, bookmark, usage)
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(stored)
+    }
+
+    fn get_goals(&self, user: String) -> Result<Option<Goals>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn.open_table(GOALS_TABLE).map_err(ServiceError::db)?;
+
+        Ok(table
+            .get(user.as_str())
+            .map_err(ServiceError::db)?
+            .map(|goals| goals.value()))
+    }
+
+    fn set_goals(
+        &self,
+        user: String,
+        goals: Goals,
+        default_quota: Quota,
+    ) -> Result<Goals, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        Self::account_usage(&write_txn, &user, Some(default_quota), |usage| {
+            let mut table = write_txn
+                .open_table(GOALS_TABLE)
+                .map_err(ServiceError::db)?;
+            let replaced = table
+                .insert(user.as_str(), &goals)
+                .map_err(ServiceError::db)?
+                .map(|replaced| replaced.value().stored_size());
+            usage.store(replaced, goals.stored_size());
+            Ok(())
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(goals)
+    }
+
+    fn set_shelf(
+        &self,
+        user: String,
+        document: String,
+        shelf: Shelf,
+        at: u64,
+        default_quota: Quota,
+    ) -> Result<ShelfEntry, ServiceError> {
+        self.modify_shelf(user, document, default_quota, |entry| {
+            entry.set_shelf(shelf, at)
+        })
+    }
+
+    fn list_shelves(&self, user: String) -> Result<Vec<(String, ShelfEntry)>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(SHELVES_TABLE)
+            .map_err(ServiceError::db)?;
+        let start = ShelfKey {
+            user: user.clone(),
+            document: String::new(),
+        };
+
+        let mut shelves = Vec::new();
+        for entry in table.range(start..).map_err(ServiceError::db)? {
+            let (key, value) = entry.map_err(ServiceError::db)?;
+            let key = key.value();
+            if key.user != user {
+                break;
+            }
+            shelves.push((key.document, value.value()));
+        }
+        Ok(shelves)
+    }
+
+    fn list_users(&self) -> Result<Vec<User>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn.open_table(USERS_TABLE).map_err(ServiceError::db)?;
+
+        let mut users = Vec::new();
+        for entry in table.iter().map_err(ServiceError::db)? {
+            let (_key, value) = entry.map_err(ServiceError::db)?;
+            users.push(value.value());
+        }
+        Ok(users)
+    }
+
+    fn get_quota(&self, user: String) -> Result<Option<Quota>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(QUOTAS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        Ok(table
+            .get(user.as_str())
+            .map_err(ServiceError::db)?
+            .map(|quota| quota.value()))
+    }
+
+    fn set_quota(&self, user: String, quota: Option<Quota>) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = {
+            let mut table = write_txn
+                .open_table(QUOTAS_TABLE)
+                .map_err(ServiceError::db)?;
+            match quota {
+                Some(quota) => table.insert(user.as_str(), &quota),
+                None => table.remove(user.as_str()),
+            }
+            .map_err(ServiceError::db)?
+            .is_some()
+        };
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn get_usage(&self, user: String) -> Result<QuotaUsage, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn.open_table(USAGE_TABLE).map_err(ServiceError::db)?;
+
+        Ok(table
+            .get(user.as_str())
+            .map_err(ServiceError::db)?
+            .map(|usage| usage.value())
+            .unwrap_or_default())
+    }
+
+    fn delete_user(&self, name: String) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = Self::remove_user(&write_txn, &name)?;
+        write_txn.commit().map_err(ServiceError::db)?;
+        Ok(existed)
+    }
+
+    fn delete_user_if_inactive(&self, name: String, before: i64) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let inactive = write_txn
+            .open_table(USERS_TABLE)
+            .map_err(ServiceError::db)?
+            .get(name.as_str())
+            .map_err(ServiceError::db)?
+            .and_then(|user| user.value().last_activity())
+            .is_some_and(|last_activity| last_activity < before);
+        if inactive {
+            Self::remove_user(&write_txn, &name)?;
+        }
+        write_txn.commit().map_err(ServiceError::db)?;
+        Ok(inactive)
+    }
+
+    fn flag_inactive_user(&self, name: String, at: i64) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let flagged = {
+            let mut table = write_txn
+                .open_table(INACTIVE_USERS_TABLE)
+                .map_err(ServiceError::db)?;
+            let flagged = table
+                .get(name.as_str())
+                .map_err(ServiceError::db)?
+                .is_none();
+            if flagged {
+                table.insert(name.as_str(), at).map_err(ServiceError::db)?;
+            }
+            flagged
+        };
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(flagged)
+    }
+
+    fn unflag_inactive_user(&self, name: String) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = {
+            let mut table = write_txn
+                .open_table(INACTIVE_USERS_TABLE)
+                .map_err(ServiceError::db)?;
+            table
+                .remove(name.as_str())
+                .map_err(ServiceError::db)?
+                .is_some()
+        };
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn list_inactive_users(&self) -> Result<Vec<(String, i64)>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(INACTIVE_USERS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let mut users = Vec::new();
+        for entry in table.iter().map_err(ServiceError::db)? {
+            let (key, value) = entry.map_err(ServiceError::db)?;
+            users.push((key.value().to_string(), value.value()));
+        }
+        Ok(users)
+    }
+
+    fn list_devices(&self, user: String) -> Result<Vec<Device>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(DEVICES_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let start = DeviceKey {
+            user: user.clone(),
+            device_id: String::new(),
+        };
+
+        let mut devices = Vec::new();
+        for entry in table.range(start..).map_err(ServiceError::db)? {
+            let (key, value) = entry.map_err(ServiceError::db)?;
+            if key.value().user != user {
+                break;
+            }
+            devices.push(value.value());
+        }
+        Ok(devices)
+    }
+
+    fn rename_device(
+        &self,
+        user: String,
+        device_id: String,
+        name: Option<String>,
+    ) -> Result<bool, ServiceError> {
+        let key = DeviceKey { user, device_id };
+
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = Self::account_usage(&write_txn, &key.user, None, |usage| {
+            let mut table = write_txn
+                .open_table(DEVICES_TABLE)
+                .map_err(ServiceError::db)?;
+            let existing = table
+                .get(&key)
+                .map_err(ServiceError::db)?
+                .map(|device| device.value());
+            Ok(match existing {
+                Some(mut device) => {
+                    let replaced = device.stored_size();
+                    device.name = name;
+                    table.insert(&key, &device).map_err(ServiceError::db)?;
+                    usage.store(Some(replaced), device.stored_size());
+                    true
+                }
+                None => false,
+            })
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn forget_device(&self, user: String, device_id: String) -> Result<bool, ServiceError> {
+        let key = DeviceKey { user, device_id };
+
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = Self::account_usage(&write_txn, &key.user, None, |usage| {
+            let mut table = write_txn
+                .open_table(DEVICES_TABLE)
+                .map_err(ServiceError::db)?;
+            let removed = table
+                .remove(&key)
+                .map_err(ServiceError::db)?
+                .map(|removed| removed.value().stored_size());
+            if let Some(removed) = removed {
+                usage.remove(removed);
+            }
+            Ok(removed.is_some())
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn record_event(&self, event: AuditEvent) -> Result<(), ServiceError> {
+        let key = AuditKey {
+            timestamp: event.timestamp,
+            id: uuid::Uuid::new_v4().as_u64_pair().0,
+        };
+
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        {
+            let mut table = write_txn
+                .open_table(AUDIT_TABLE)
+                .map_err(ServiceError::db)?;
+            table.insert(&key, &event).map_err(ServiceError::db)?;
+        }
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(())
+    }
+
+    fn list_events(
+        &self,
+        user: Option<String>,
+        since: Option<i64>,
+    ) -> Result<Vec<AuditEvent>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn.open_table(AUDIT_TABLE).map_err(ServiceError::db)?;
+
+        let start = AuditKey {
+            timestamp: since.unwrap_or(i64::MIN),
+            id: 0,
+        };
+
+        let mut events = Vec::new();
+        for entry in table.range(start..).map_err(ServiceError::db)? {
+            let (_key, value) = entry.map_err(ServiceError::db)?;
+            let event = value.value();
+            if user.as_ref().is_none_or(|u| *u == event.username) {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    fn prune_events(&self, before: i64) -> Result<usize, ServiceError> {
+        let end = AuditKey {
+            timestamp: before,
+            id: 0,
+        };
+
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let mut removed = 0;
+        {
+            let mut table = write_txn
+                .open_table(AUDIT_TABLE)
+                .map_err(ServiceError::db)?;
+            for entry in table
+                .extract_from_if(..end, |_, _| true)
+                .map_err(ServiceError::db)?
+            {
+                entry.map_err(ServiceError::db)?;
+                removed += 1;
+            }
+        }
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(removed)
+    }
+
+    fn create_or_update_group(&self, group: Group) -> Result<Group, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        {
+            let mut table = write_txn
+                .open_table(GROUPS_TABLE)
+                .map_err(ServiceError::db)?;
+            table.insert(&*group.id, &group).map_err(ServiceError::db)?;
+        }
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(group)
+    }
+
+    fn get_group(&self, id: String) -> Result<Option<Group>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(GROUPS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let group = table
+            .get(&*id)
+            .map_err(ServiceError::db)?
+            .map(|group| group.value());
+
+        Ok(group)
+    }
+
+    fn list_groups(&self, user: Option<String>) -> Result<Vec<Group>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(GROUPS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let mut groups = Vec::new();
+        for entry in table.iter().map_err(ServiceError::db)? {
+            let (_key, value) = entry.map_err(ServiceError::db)?;
+            let group = value.value();
+            if user.as_ref().is_none_or(|u| group.is_member(u)) {
+                groups.push(group);
+            }
+        }
+        Ok(groups)
+    }
+
+    fn delete_group(&self, id: String) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = {
+            let mut table = write_txn
+                .open_table(GROUPS_TABLE)
+                .map_err(ServiceError::db)?;
+            table.remove(&*id).map_err(ServiceError::db)?.is_some()
+        };
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn join_group(&self, invite_code: String, user: String) -> Result<Option<Group>, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let joined = {
+            let mut table = write_txn
+                .open_table(GROUPS_TABLE)
+                .map_err(ServiceError::db)?;
+            let mut found = None;
+            for entry in table.iter().map_err(ServiceError::db)? {
+                let (_key, value) = entry.map_err(ServiceError::db)?;
+                let group = value.value();
+                if group.invite_code == invite_code {
+                    found = Some(group);
+                    break;
+                }
+            }
+            match found {
+                Some(mut group) => {
+                    if group.add_member(user) {
+                        table.insert(&*group.id, &group).map_err(ServiceError::db)?;
+                    }
+                    Some(group)
+                }
+                None => None,
+            }
+        };
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(joined)
+    }
+
+    fn leave_group(&self, id: String, user: String) -> Result<bool, ServiceError> {
+        let left = self.modify_group(&id, |group| group.remove_member(&user))?;
+        Ok(left.unwrap_or(false))
+    }
+
+    fn set_group_visibility(
+        &self,
+        id: String,
+        user: String,
+        visibility: Visibility,
+    ) -> Result<bool, ServiceError> {
+        let updated = self.modify_group(&id, |group| {
+            match group.members.iter_mut().find(|m| m.username == user) {
+                Some(member) => {
+                    member.visibility = visibility;
+                    true
+                }
+                None => false,
+            }
+        })?;
+        Ok(updated.unwrap_or(false))
+    }
+
+    fn rotate_group_invite(&self, id: String) -> Result<Option<String>, ServiceError> {
+        self.modify_group(&id, |group| {
+            group.rotate_invite_code();
+            group.invite_code.clone()
+        })
+    }
+
+    fn create_share(&self, share: Share, default_quota: Quota) -> Result<Share, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        Self::account_usage(&write_txn, &share.user, Some(default_quota), |usage| {
+            let mut table = write_txn
+                .open_table(SHARES_TABLE)
+                .map_err(ServiceError::db)?;
+            let replaced = table
+                .insert(&*share.token, &share)
+                .map_err(ServiceError::db)?
+                .map(|replaced| replaced.value().stored_size());
+            usage.store(replaced, share.stored_size());
+            Ok(())
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(share)
+    }
+
+    fn get_share(&self, token: String) -> Result<Option<Share>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(SHARES_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let share = table
+            .get(&*token)
+            .map_err(ServiceError::db)?
+            .map(|share| share.value());
+
+        Ok(share)
+    }
+
+    fn list_shares(&self, user: String) -> Result<Vec<Share>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(SHARES_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let mut shares = Vec::new();
+        for entry in table.iter().map_err(ServiceError::db)? {
+            let (_key, value) = entry.map_err(ServiceError::db)?;
+            let share = value.value();
+            if share.user == user {
+                shares.push(share);
+            }
+        }
+        shares.sort_by_key(|share| share.created_at);
+        Ok(shares)
+    }
+
+    fn revoke_share(&self, user: String, token: String) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let revoked = Self::account_usage(&write_txn, &user, None, |usage| {
+            let mut table = write_txn
+                .open_table(SHARES_TABLE)
+                .map_err(ServiceError::db)?;
+            let owned = table
+                .get(&*token)
+                .map_err(ServiceError::db)?
+                .map(|share| share.value())
+                .filter(|share| share.user == user);
+            if let Some(share) = &owned {
+                table.remove(&*token).map_err(ServiceError::db)?;
+                usage.remove(share.stored_size());
+            }
+            Ok(owned.is_some())
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(revoked)
+    }
+
+    fn set_document(
+        &self,
+        user: Option<String>,
+        document: String,
+        metadata: DocumentMetadata,
+        default_quota: Quota,
+    ) -> Result<DocumentMetadata, ServiceError> {
+        let key = DocumentKey { user, document };
+
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let write = |usage: &mut QuotaUsage| {
+            let mut table = write_txn
+                .open_table(DOCUMENTS_TABLE)
+                .map_err(ServiceError::db)?;
+            let replaced = table
+                .insert(&key, &metadata)
+                .map_err(ServiceError::db)?
+                .map(|replaced| row_size(&key.document, &replaced.value()));
+            usage.store(replaced, row_size(&key.document, &metadata));
+            Ok(())
+        };
+        match &key.user {
+            // Global metadata belongs to no user
+            None => write(&mut QuotaUsage::default())?,
+            Some(user) => Self::account_usage(&write_txn, user, Some(default_quota), write)?,
+        }
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(metadata)
+    }
+
+    fn get_document(
+        &self,
+        user: String,
+        document: String,
+    ) -> Result<Option<DocumentMetadata>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(DOCUMENTS_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let mut key = DocumentKey {
+            user: Some(user),
+            document,
+        };
+        if let Some(metadata) = table.get(&key).map_err(ServiceError::db)? {
+            return Ok(Some(metadata.value()));
+        }
+
+        key.user = None;
+        let metadata = table
+            .get(&key)
+            .map_err(ServiceError::db)?
+            .map(|metadata| metadata.value());
+
+        Ok(metadata)
+    }
+
+    fn link_document(
+        &self,
+        user: String,
+        alias: String,
+        document: String,
+        default_quota: Quota,
+    ) -> Result<String, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let canonical = Self::account_usage(&write_txn, &user, Some(default_quota), |usage| {
+            let mut aliases = write_txn
+                .open_table(ALIASES_TABLE)
+                .map_err(ServiceError::db)?;
+            let alias = resolve_alias(&aliases, &user, alias)?;
+            let canonical = resolve_alias(&aliases, &user, document)?;
+
+            if alias != canonical {
+                // Everything pointing to the former canonical document now follows it
+                let start = AliasKey {
+                    user: user.clone(),
+                    alias: String::new(),
+                };
+                let mut repointed = Vec::new();
+                for entry in aliases.range(start..).map_err(ServiceError::db)? {
+                    let (key, value) = entry.map_err(ServiceError::db)?;
+                    let key = key.value();
+                    if key.user != user {
+                        break;
+                    }
+                    if value.value() == alias {
+                        repointed.push(key);
+                    }
+                }
+                for key in repointed {
+                    aliases
+                        .insert(&key, canonical.as_str())
+                        .map_err(ServiceError::db)?;
+                    usage.store(
+                        Some(alias_size(&key.alias, &alias)),
+                        alias_size(&key.alias, &canonical),
+                    );
+                }
+                let replaced = aliases
+                    .insert(
+                        &AliasKey {
+                            user: user.clone(),
+                            alias: alias.clone(),
+                        },
+                        canonical.as_str(),
+                    )
+                    .map_err(ServiceError::db)?
+                    .map(|replaced| alias_size(&alias, replaced.value()));
+                usage.store(replaced, alias_size(&alias, &canonical));
+
+                // Keep the most recent progress of both documents
+                let mut progress = write_txn
+                    .open_table(PROGRESS_TABLE)
+                    .map_err(ServiceError::db)?;
+                let aliased = progress
+                    .remove(&ProgressKey {
+                        user: user.clone(),
+                        document: alias.clone(),
+                    })
+                    .map_err(ServiceError::db)?
+                    .map(|progress| progress.value());
+                if let Some(aliased) = aliased {
+                    usage.documents = usage.documents.saturating_sub(1);
+                    usage.remove(row_size(&alias, &aliased));
+                    let key = ProgressKey {
+                        user: user.clone(),
+                        document: canonical.clone(),
+                    };
+                    let current = progress
+                        .get(&key)
+                        .map_err(ServiceError::db)?
+                        .map(|current| current.value());
+                    match current {
+                        Some(current) if current.reported_at() >= aliased.reported_at() => {}
+                        current => {
+                            progress.insert(&key, &aliased).map_err(ServiceError::db)?;
+                            if current.is_none() {
+                                usage.documents += 1;
+                            }
+                            usage.store(
+                                current.map(|current| row_size(&canonical, &current)),
+                                row_size(&canonical, &aliased),
+                            );
+                        }
+                    }
+                }
+
+                // Same for the position of each device
+                let mut devices = write_txn
+                    .open_table(DEVICE_PROGRESS_TABLE)
+                    .map_err(ServiceError::db)?;
+                for aliased in device_positions(&devices, &user, &alias)? {
+                    devices
+                        .remove(&DeviceProgressKey {
+                            user: user.clone(),
+                            document: alias.clone(),
+                            device_id: aliased.device_id.clone(),
+                        })
+                        .map_err(ServiceError::db)?;
+                    usage.remove(row_size(&alias, &aliased));
+                    let key = DeviceProgressKey {
+                        user: user.clone(),
+                        document: canonical.clone(),
+                        device_id: aliased.device_id.clone(),
+                    };
+                    let current = devices
+                        .get(&key)
+                        .map_err(ServiceError::db)?
+                        .map(|current| current.value());
+                    match current {
+                        Some(current) if current.reported_at() >= aliased.reported_at() => {}
+                        current => {
+                            devices.insert(&key, &aliased).map_err(ServiceError::db)?;
+                            usage.store(
+                                current.map(|current| row_size(&canonical, &current)),
+                                row_size(&canonical, &aliased),
+                            );
+                        }
+                    }
+                }
+
+                // The history of the alias becomes part of the canonical document's
+                let mut history = write_txn
+                    .open_table(HISTORY_TABLE)
+                    .map_err(ServiceError::db)?;
+                for key in document_history_keys(&history, &user, &alias)? {
+                    let value = history.remove(&key).map_err(ServiceError::db)?;
+                    if let Some(value) = value.map(|value| value.value()) {
+                        let key = HistoryKey {
+                            document: canonical.clone(),
+                            ..key
+                        };
+                        history.insert(&key, &value).map_err(ServiceError::db)?;
+                    }
+                }
+
+                // Annotations of both documents are merged
+                let mut annotations = write_txn
+                    .open_table(ANNOTATIONS_TABLE)
+                    .map_err(ServiceError::db)?;
+                let aliased = document_annotations(&annotations, &user, &alias)?;
+                for annotation in &aliased {
+                    usage.remove(row_size(&alias, annotation));
+                    annotations
+                        .remove(&AnnotationKey {
+                            user: user.clone(),
+                            document: alias.clone(),
+                            id: annotation.id.clone(),
+                        })
+                        .map_err(ServiceError::db)?;
+                }
+                write_annotations(&mut annotations, &user, &canonical, aliased, usage)?;
+
+                // And so are their bookmarks
+                let mut bookmarks = write_txn
+                    .open_table(BOOKMARKS_TABLE)
+                    .map_err(ServiceError::db)?;
+                for bookmark in document_bookmarks(&bookmarks, &user, &alias)? {
+                    usage.remove(row_size(&alias, &bookmark));
+                    bookmarks
+                        .remove(&BookmarkKey {
+                            user: user.clone(),
+                            document: alias.clone(),
+                            id: bookmark.id.clone(),
+                        })
+                        .map_err(ServiceError::db)?;
+                    write_bookmark(&mut bookmarks, &user, &canonical, bookmark, usage)?;
+                }
+
+                // The shelf entry keeps the read-throughs of both
+                let mut shelves = write_txn
+                    .open_table(SHELVES_TABLE)
+                    .map_err(ServiceError::db)?;
+                let aliased = shelves
+                    .remove(&ShelfKey {
+                        user: user.clone(),
+                        document: alias.clone(),
+                    })
+                    .map_err(ServiceError::db)?
+                    .map(|entry| entry.value());
+                if let Some(aliased) = aliased {
+                    usage.remove(row_size(&alias, &aliased));
+                    let key = ShelfKey {
+                        user: user.clone(),
+                        document: canonical.clone(),
+                    };
+                    let current = shelves
+                        .get(&key)
+                        .map_err(ServiceError::db)?
+                        .map(|current| current.value());
+                    let replaced = current
+                        .as_ref()
+                        .map(|current| row_size(&canonical, current));
+                    let merged = match current {
+                        Some(current) => current.merge(aliased),
+                        None => aliased,
+                    };
+                    shelves.insert(&key, &merged).map_err(ServiceError::db)?;
+                    usage.store(replaced, row_size(&canonical, &merged));
+                }
+            }
+            Ok(canonical)
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(canonical)
+    }
+
+    fn unlink_document(&self, user: String, alias: String) -> Result<bool, ServiceError> {
+        let write_txn = self.db.begin_write().map_err(ServiceError::db)?;
+        let existed = Self::account_usage(&write_txn, &user, None, |usage| {
+            let mut table = write_txn
+                .open_table(ALIASES_TABLE)
+                .map_err(ServiceError::db)?;
+            let removed = table
+                .remove(&AliasKey {
+                    user: user.clone(),
+                    alias: alias.clone(),
+                })
+                .map_err(ServiceError::db)?
+                .map(|document| alias_size(&alias, document.value()));
+            if let Some(removed) = removed {
+                usage.remove(removed);
+            }
+            Ok(removed.is_some())
+        })?;
+        write_txn.commit().map_err(ServiceError::db)?;
+
+        Ok(existed)
+    }
+
+    fn list_aliases(&self, user: String) -> Result<Vec<(String, String)>, ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        let table = read_txn
+            .open_table(ALIASES_TABLE)
+            .map_err(ServiceError::db)?;
+
+        let start = AliasKey {
+            user: user.clone(),
+            alias: String::new(),
+        };
+
+        let mut aliases = Vec::new();
+        for entry in table.range(start..).map_err(ServiceError::db)? {
+            let (key, value) = entry.map_err(ServiceError::db)?;
+            let key = key.value();
+            if key.user != user {
+                break;
+            }
+            aliases.push((key.alias, value.value().to_string()));
+        }
+        Ok(aliases)
+    }
+
+    fn ping(&self) -> Result<(), ServiceError> {
+        let read_txn = self.db.begin_read().map_err(ServiceError::db)?;
+        read_txn.open_table(USERS_TABLE).map_err(ServiceError::db)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::model::AuditKind;
+    use tempfile::{NamedTempFile, TempDir};
+
+    // === Test Helper Functions ===
+
+    fn create_test_service() -> (TempDir, impl KorrosyncService) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let service = KorrosyncServiceRedb::new(db_path).expect("Failed to create service");
+        (temp_dir, service)
+    }
+
+    fn create_test_user(username: &str) -> User {
+        User::new(username, "test_password").expect("Failed to create user")
+    }
+
+    fn create_test_progress() -> Progress {
+        Progress {
+            device_id: "device-123".to_string(),
+            device: "Kindle".to_string(),
+            percentage: 45.5,
+            progress: "Page 91 of 200".to_string(),
+            timestamp: 1609459200000,
+            client_timestamp: None,
+        }
+    }
+
+    // === Service Initialization Tests ===
+
+    #[test]
+    fn test_new_creates_service_with_simple_path() {
+        let db = NamedTempFile::new().expect("Failed to create temp file");
+        let service = KorrosyncServiceRedb::new(db.path());
+        assert!(service.is_ok(), "Service creation should succeed");
+    }
+
+    #[test]
+    fn test_new_creates_parent_directories() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let db_path = temp_dir.path().join("nested/dirs/korrosync.db");
+
+        let service = KorrosyncServiceRedb::new(&db_path);
+        assert!(
+            service.is_ok(),
+            "Service should create parent directories automatically"
+        );
+        assert!(
+            db_path.parent().unwrap().exists(),
+            "Parent directories should exist"
+        );
+    }
+
+    #[test]
+    fn test_new_opens_existing_database() {
+        let db = NamedTempFile::new().expect("Failed to create temp file");
+        let db_path = db.path().to_path_buf();
+
+        // Create first service and add a user
+        {
+            let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to create service");
+            let user = create_test_user("alice");
+            service
+                .create_or_update_user(user)
+                .expect("Failed to add user");
+        }
+
+        // Reopen the same database
+        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to reopen database");
+        let retrieved = service
+            .get_user("alice".into())
+            .expect("Failed to get user")
+            .expect("User should exist");
+
+        assert_eq!(retrieved.username(), "alice");
+    }
+
+    #[test]
+    fn test_ping_reads_storage() {
+        let (_temp, service) = create_test_service();
+
+        service.ping().expect("Storage should be readable");
+    }
+
+    // === User CRUD Operation Tests ===
+
+    #[test]
+    fn test_add_and_get_user() {
+        let (_temp, service) = create_test_service();
+        let user = create_test_user("alice");
+
+        service
+            .create_or_update_user(user)
+            .expect("Failed to add user");
+
+        let retrieved = service
+            .get_user("alice".into())
+            .expect("Failed to get user")
+            .expect("User not found");
+
+        assert_eq!(retrieved.username(), "alice");
+    }
+
+    #[test]
+    fn test_get_user_returns_none_when_not_exists() {
+        let (_temp, service) = create_test_service();
+
+        let result = service
+            .get_user("nonexistent".into())
+            .expect("Query should not fail");
+
+        assert!(result.is_none(), "Should return None for non-existent user");
+    }
+
+    #[test]
+    fn test_add_user_overwrites_existing() {
+        let (_temp, service) = create_test_service();
+        let user1 = User::new("alice", "password1").expect("Failed to create user1");
+        let user2 = User::new("alice", "password2").expect("Failed to create user2");
+
+        service
+            .create_or_update_user(user1)
+            .expect("Failed to add user1");
+        service
+            .create_or_update_user(user2)
+            .expect("Failed to add user2");
+
+        let retrieved = service
+            .get_user("alice".into())
+            .expect("Failed to get user")
+            .expect("User not found");
+
+        // Verify the second password works (overwrote the first)
+        assert!(
+            retrieved
+                .check("password2")
+                .expect("Error checking password"),
+            "Should verify with second password"
+        );
+        assert!(
+            !retrieved
+                .check("password1")
+                .expect("Error checking password"),
+            "Should not verify with first password"
+        );
+    }
+
+    #[test]
+    fn test_username_verification() {
+        let (_temp, service) = create_test_service();
+        let user = create_test_user("alice");
+
+        service
+            .create_or_update_user(user)
+            .expect("Failed to add user");
+
+        let retrieved = service
+            .get_user("alice".into())
+            .expect("Failed to get user")
+            .expect("User not found");
+
+        assert_eq!(
+            retrieved.username(),
+            "alice",
+            "Username should match exactly"
+        );
+    }
+
+    #[test]
+    fn test_username_case_sensitive() {
+        let (_temp, service) = create_test_service();
+        let user = create_test_user("Alice");
+
+        service
+            .create_or_update_user(user)
+            .expect("Failed to add user");
+
+        let result = service
+            .get_user("alice".into())
+            .expect("Query should not fail");
+        assert!(result.is_none(), "Username lookup should be case-sensitive");
+
+        let result = service
+            .get_user("Alice".into())
+            .expect("Query should not fail");
+        assert!(result.is_some(), "Exact case should match");
+    }
+
+    #[test]
+    fn test_change_password_keeps_activity() {
+        let (_temp, service) = create_test_service();
+        let mut user = create_test_user("alice");
+        user.set_last_activity(1000);
+        service
+            .create_or_update_user(user)
+            .expect("Failed to add user");
+
+        assert!(
+            service
+                .change_password("alice".into(), "new_password".into())
+                .unwrap()
+        );
+
+        let user = service.get_user("alice".into()).unwrap().unwrap();
+        assert!(user.check("new_password").unwrap());
+        assert!(!user.check("test_password").unwrap());
+        assert_eq!(user.last_activity(), Some(1000));
+    }
+
+    #[test]
+    fn test_change_password_and_touch_do_not_create_users() {
+        let (_temp, service) = create_test_service();
+        service
+            .create_or_update_user(create_test_user("alice"))
+            .expect("Failed to add user");
+        service.delete_user("alice".into()).unwrap();
+
+        assert!(
+            !service
+                .change_password("alice".into(), "new_password".into())
+                .unwrap()
+        );
+        assert!(service.touch_user("alice".into()).unwrap().is_none());
+        assert!(service.get_user("alice".into()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_touch_user_keeps_password() {
+        let (_temp, service) = create_test_service();
+        service
+            .create_or_update_user(create_test_user("alice"))
+            .expect("Failed to add user");
+
+        let touched = service.touch_user("alice".into()).unwrap().unwrap();
+        assert!(touched.last_activity().is_some());
+
+        let user = service.get_user("alice".into()).unwrap().unwrap();
+        assert_eq!(user.last_activity(), touched.last_activity());
+        assert!(user.check("test_password").unwrap());
+    }
+
+    // === Progress CRUD Operation Tests ===
+
+    #[test]
+    fn test_update_and_get_progress() {
+        let (_temp, service) = create_test_service();
+        let progress = create_test_progress();
+
+        service
+            .update_progress("alice".into(), "book.epub".into(), progress)
+            .expect("Failed to update progress");
+
+        let retrieved = service
+            .get_progress("alice".to_string(), "book.epub".to_string())
+            .expect("Failed to get progress");
+
+        assert!(retrieved.is_some());
+        let retrieved = retrieved.unwrap();
+        assert_eq!(retrieved.device_id, "device-123");
+        assert_eq!(retrieved.device, "Kindle");
+        assert_eq!(retrieved.percentage, 45.5);
+        assert_eq!(retrieved.progress, "Page 91 of 200");
+        assert_eq!(retrieved.timestamp, 1609459200000);
+    }
+
+    #[test]
+    fn test_update_progress_returns_document_and_timestamp() {
+        let (_temp, service) = create_test_service();
+        let progress = create_test_progress();
+
+        let (doc, ts) = service
+            .update_progress("alice".into(), "book.epub".into(), progress)
+            .expect("Failed to update progress");
+
+        assert_eq!(doc, "book.epub");
+        assert_eq!(ts, 1609459200000);
+    }
+
+    #[test]
+    fn test_update_progress_overwrites_existing() {
+        let (_temp, service) = create_test_service();
+
+        let progress1 = Progress {
+            device_id: "device-1".to_string(),
+            device: "Kindle".to_string(),
+            percentage: 30.0,
+            progress: "Page 60".to_string(),
+            timestamp: 1000000,
+            client_timestamp: None,
+        };
+
+        let progress2 = Progress {
+            device_id: "device-2".to_string(),
+            device: "Kobo".to_string(),
+            percentage: 70.0,
+            progress: "Page 140".to_string(),
+            timestamp: 2000000,
+            client_timestamp: None,
+        };
+
+        service
+            .update_progress("alice".into(), "book.epub".into(), progress1)
+            .expect("Failed to update progress first time");
+
+        service
+            .update_progress("alice".into(), "book.epub".into(), progress2)
+            .expect("Failed to update progress second time");
+
+        let retrieved = service
+            .get_progress("alice".to_string(), "book.epub".to_string())
+            .expect("Failed to get progress");
+
+        assert!(retrieved.is_some());
+        let retrieved = retrieved.unwrap();
+        assert_eq!(retrieved.device_id, "device-2");
+        assert_eq!(retrieved.percentage, 70.0);
+        assert_eq!(retrieved.timestamp, 2000000);
+    }
+
+    #[test]
+    fn test_update_progress_is_a_single_sync() {
+        let (_temp, service) = create_test_service();
+
+        service
+            .update_progress("alice".into(), "book.epub".into(), progress_at(1000, 1.0))
+            .expect("Failed to update progress");
+
+        let devices = service.list_devices("alice".into()).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].device_id, "device-123");
+        let shelves = service.list_shelves("alice".into()).unwrap();
+        assert_eq!(shelves.len(), 1);
+        assert_eq!(shelves[0].1.shelf(), Shelf::Finished);
+
+        service
+            .set_quota(
+                "alice".into(),
+                Some(Quota {
+                    max_documents: Some(1),
+                    max_bytes: None,
+                }),
+            )
+            .unwrap();
+        let rejected =
+            service.update_progress("alice".into(), "other.epub".into(), progress_at(2000, 0.5));
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
+    }
+
+    #[test]
+    fn test_get_progress_not_found_error() {
+        let (_temp, service) = create_test_service();
+
+        let result = service.get_progress("alice".to_string(), "nonexistent.epub".to_string());
+
+        assert!(
+            result.is_ok(),
+            "Should return Ok(None) for non-existent progress"
+        );
+        match result {
+            Ok(None) => {} // Expected
+            _ => panic!("Expected NotFound error"),
+        }
+    }
+
+    #[test]
+    fn test_progress_is_user_specific() {
+        let (_temp, service) = create_test_service();
+        let progress = create_test_progress();
+
+        // Same document, different users
+        service
+            .update_progress("alice".into(), "book.epub".into(), progress.clone())
+            .expect("Failed to update alice's progress");
+
+        let mut bob_progress = progress;
+        bob_progress.percentage = 80.0;
+        service
+            .update_progress("bob".into(), "book.epub".into(), bob_progress)
+            .expect("Failed to update bob's progress");
+
+        // Verify each user has their own progress
+        let alice_retrieved = service
+            .get_progress("alice".to_string(), "book.epub".to_string())
+            .expect("Failed to get alice's progress");
+
+        let bob_retrieved = service
+            .get_progress("bob".to_string(), "book.epub".to_string())
+            .expect("Failed to get bob's progress");
+
+        assert!(alice_retrieved.is_some());
+        assert!(bob_retrieved.is_some());
+        let alice_retrieved = alice_retrieved.unwrap();
+        let bob_retrieved = bob_retrieved.unwrap();
+        assert_eq!(alice_retrieved.percentage, 45.5);
+        assert_eq!(bob_retrieved.percentage, 80.0);
+    }
+
+    #[test]
+    fn test_progress_is_document_specific() {
+        let (_temp, service) = create_test_service();
+
+        let progress1 = Progress {
+            device_id: "device-1".to_string(),
+            device: "Kindle".to_string(),
+            percentage: 30.0,
+            progress: "Page 60".to_string(),
+            timestamp: 1000000,
+            client_timestamp: None,
+        };
+
+        let progress2 = Progress {
+            device_id: "device-1".to_string(),
+            device: "Kindle".to_string(),
+            percentage: 70.0,
+            progress: "Page 140".to_string(),
+            timestamp: 2000000,
+            client_timestamp: None,
+        };
+
+        // Same user, different documents
+        service
+            .update_progress("alice".into(), "book1.epub".into(), progress1)
+            .expect("Failed to update progress for book1");
+
+        service
+            .update_progress("alice".into(), "book2.epub".into(), progress2)
+            .expect("Failed to update progress for book2");
+
+        // Verify each document has separate progress
+        let book1_retrieved = service
+            .get_progress("alice".to_string(), "book1.epub".to_string())
+            .expect("Failed to get book1 progress");
+
+        let book2_retrieved = service
+            .get_progress("alice".to_string(), "book2.epub".to_string())
+            .expect("Failed to get book2 progress");
+
+        assert!(book1_retrieved.is_some());
+        assert!(book2_retrieved.is_some());
+        assert_eq!(book1_retrieved.unwrap().percentage, 30.0);
+        assert_eq!(book2_retrieved.unwrap().percentage, 70.0);
+    }
+
+    #[test]
+    fn test_progress_all_fields_stored_correctly() {
+        let (_temp, service) = create_test_service();
+
+        let progress = Progress {
+            device_id: "unique-device-id-123".to_string(),
+            device: "Kindle Paperwhite 11th Gen".to_string(),
+            percentage: 67.89,
+            progress: "Chapter 12, Page 345 of 512".to_string(),
+            timestamp: 1704067200000,
+            client_timestamp: None,
+        };
+
+        service
+            .update_progress("testuser".into(), "detailed-book.pdf".into(), progress)
+            .expect("Failed to update progress");
+
+        let retrieved = service
+            .get_progress("testuser".to_string(), "detailed-book.pdf".to_string())
+            .expect("Failed to get progress");
+
+        assert!(retrieved.is_some());
+        let retrieved = retrieved.unwrap();
+        assert_eq!(retrieved.device_id, "unique-device-id-123");
+        assert_eq!(retrieved.device, "Kindle Paperwhite 11th Gen");
+        assert_eq!(retrieved.percentage, 67.89);
+        assert_eq!(retrieved.progress, "Chapter 12, Page 345 of 512");
+        assert_eq!(retrieved.timestamp, 1704067200000);
+    }
+
+    // === Thread Safety and Concurrency Tests ===
+
+    #[tokio::test]
+    async fn test_service_clone_is_thread_safe() {
+        let (_temp, svc) = create_test_service();
+        let service = Arc::new(svc);
+        let user = create_test_user("alice");
+        service
+            .create_or_update_user(user)
+            .expect("Failed to add user");
+
+        let service_clone = service.clone();
+
+        let handle = tokio::spawn(async move {
+            service_clone
+                .get_user("alice".into())
+                .expect("Failed to get user")
+                .expect("User not found")
+                .username()
+                .to_string()
+        });
+
+        let username = handle.await.expect("Task failed");
+        assert_eq!(username, "alice");
+    }
 
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
+    #[tokio::test]
+    async fn test_concurrent_reads() {
+        let (_temp, svc) = create_test_service();
+        let service = Arc::new(svc);
+        let user = create_test_user("alice");
+        service
+            .create_or_update_user(user)
+            .expect("Failed to add user");
+
+        let mut handles = vec![];
+
+        // Spawn 10 concurrent read tasks
+        for _ in 0..10 {
+            let service_clone = service.clone();
+            let handle = tokio::spawn(async move {
+                service_clone
+                    .get_user("alice".into())
+                    .expect("Failed to get user")
+                    .expect("User not found")
+                    .username()
+                    .to_string()
+            });
+            handles.push(handle);
+        }
+
+        // All reads should succeed
+        for handle in handles {
+            let username = handle.await.expect("Task failed");
+            assert_eq!(username, "alice");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes() {
+        let (_temp, svc) = create_test_service();
+        let service = Arc::new(svc);
+
+        let mut handles = vec![];
+
+        // Spawn 10 concurrent write tasks for different users
+        for i in 0..10 {
+            let username = format!("user{}", i);
+            let service = service.clone();
+            let handle = tokio::spawn(async move {
+                let user = User::new(&username, "password").expect("Failed to create user");
+                service
+                    .create_or_update_user(user)
+                    .expect("Failed to add user");
+                username
+            });
+            handles.push(handle);
+        }
+
+        // Wait for all writes to complete
+        for handle in handles {
+            handle.await.expect("Task failed");
+        }
+
+        // Verify all users were created
+        for i in 0..10 {
+            let username = format!("user{}", i);
+            let result = service
+                .get_user(username.clone())
+                .expect("Failed to get user");
+            assert!(result.is_some(), "User {} should exist", username);
+        }
+    }
+
+    // === Edge Case Tests ===
+
+    #[test]
+    fn test_empty_username_or_document() {
+        let (_temp, service) = create_test_service();
+        let progress = create_test_progress();
+
+        let result = service.update_progress("".into(), "book.epub".into(), progress.clone());
+        assert!(result.is_ok(), "Empty username should be allowed");
+
+        let result = service.update_progress("alice".into(), "".into(), progress);
+        assert!(result.is_ok(), "Empty document should be allowed");
+    }
+
+    #[test]
+    fn test_special_characters_in_identifiers() {
+        let (_temp, service) = create_test_service();
+        let progress = create_test_progress();
+
+        let special_user = "user@example.com";
+        let special_doc = "book-title_v2.0 [final] (2024).epub";
+
+        service
+            .update_progress(special_user.into(), special_doc.into(), progress)
+            .expect("Should handle special characters");
+
+        let retrieved = service
+            .get_progress(special_user.into(), special_doc.into())
+            .expect("Should retrieve with special characters");
+
+        assert!(retrieved.is_some());
+        assert_eq!(retrieved.unwrap().device_id, "device-123");
+    }
+
+    #[test]
+    fn test_boundary_values() {
+        let (_temp, service) = create_test_service();
+
+        let progress_0 = Progress {
+            device_id: "device-1".to_string(),
+            device: "Test".to_string(),
+            percentage: 0.0,
+            progress: "Start".to_string(),
+            timestamp: 0,
+            client_timestamp: None,
+        };
+
+        let progress_100 = Progress {
+            device_id: "device-1".to_string(),
+            device: "Test".to_string(),
+            percentage: 100.0,
+            progress: "End".to_string(),
+            timestamp: u64::MAX,
+            client_timestamp: None,
+        };
+
+        service
+            .update_progress("alice".into(), "doc1".into(), progress_0)
+            .expect("Should handle 0% and timestamp 0");
+
+        service
+            .update_progress("alice".into(), "doc2".into(), progress_100)
+            .expect("Should handle 100% and max timestamp");
+
+        let retrieved_0 = service
+            .get_progress("alice".to_string(), "doc1".to_string())
+            .expect("Should retrieve 0%");
+
+        assert!(retrieved_0.is_some());
+        let retrieved_0 = retrieved_0.unwrap();
+
+        assert_eq!(retrieved_0.percentage, 0.0);
+        assert_eq!(retrieved_0.timestamp, 0);
+
+        let retrieved_100 = service
+            .get_progress("alice".to_string(), "doc2".to_string())
+            .expect("Should retrieve 100%");
+
+        assert!(retrieved_100.is_some());
+        let retrieved_100 = retrieved_100.unwrap();
+        assert_eq!(retrieved_100.percentage, 100.0);
+        assert_eq!(retrieved_100.timestamp, u64::MAX);
+    }
+
+    #[test]
+    fn test_very_long_identifiers() {
+        let (_temp, service) = create_test_service();
+        let progress = create_test_progress();
+
+        let long_username = "a".repeat(1000);
+        let long_document = "b".repeat(1000);
+
+        service
+            .update_progress(long_username.clone(), long_document.clone(), progress)
+            .expect("Should handle very long identifiers");
+
+        let retrieved = service
+            .get_progress(long_username.clone(), long_document.clone())
+            .expect("Should retrieve with long identifiers");
+
+        assert!(retrieved.is_some());
+        assert_eq!(retrieved.unwrap().device_id, "device-123");
+    }
+
+    // === List Users Tests ===
+
+    #[test]
+    fn test_list_users_empty() {
+        let (_temp, service) = create_test_service();
+        let users = service.list_users().expect("Failed to list users");
+        assert!(users.is_empty());
+    }
+
+    #[test]
+    fn test_list_users_returns_all() {
+        let (_temp, service) = create_test_service();
+
+        service
+            .create_or_update_user(create_test_user("alice"))
+            .expect("Failed to add alice");
+        service
+            .create_or_update_user(create_test_user("bob"))
+            .expect("Failed to add bob");
+        service
+            .create_or_update_user(create_test_user("charlie"))
+            .expect("Failed to add charlie");
+
+        let users = service.list_users().expect("Failed to list users");
+        assert_eq!(users.len(), 3);
+
+        let mut usernames: Vec<&str> = users.iter().map(|u| u.username()).collect();
+        usernames.sort();
+        assert_eq!(usernames, vec!["alice", "bob", "charlie"]);
+    }
+
+    // === Delete User Tests ===
+
+    #[test]
+    fn test_delete_user_existing() {
+        let (_temp, service) = create_test_service();
+        service
+            .create_or_update_user(create_test_user("alice"))
+            .expect("Failed to add user");
+
+        let deleted = service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
+        assert!(deleted, "Should return true for existing user");
+
+        let user = service
+            .get_user("alice".into())
+            .expect("Failed to get user");
+        assert!(user.is_none(), "User should no longer exist");
+    }
+
+    #[test]
+    fn test_delete_user_nonexistent() {
+        let (_temp, service) = create_test_service();
+
+        let deleted = service
+            .delete_user("nonexistent".into())
+            .expect("Failed to delete user");
+        assert!(!deleted, "Should return false for non-existent user");
+    }
+
+    #[test]
+    fn test_delete_user_if_inactive() {
+        let (_temp, service) = create_test_service();
+        let mut user = create_test_user("alice");
+        user.set_last_activity(2000);
+        service.create_or_update_user(user).unwrap();
+        service
+            .create_or_update_user(create_test_user("never"))
+            .unwrap();
+
+        assert!(
+            !service
+                .delete_user_if_inactive("alice".into(), 2000)
+                .unwrap(),
+            "Users active since the cutoff must be kept"
+        );
+        assert!(
+            !service
+                .delete_user_if_inactive("never".into(), 2000)
+                .unwrap(),
+            "Users that never logged in must be kept"
+        );
+        assert!(
+            !service
+                .delete_user_if_inactive("nobody".into(), 2000)
+                .unwrap()
+        );
+        assert!(
+            service
+                .delete_user_if_inactive("alice".into(), 2001)
+                .unwrap()
+        );
+        assert!(service.get_user("alice".into()).unwrap().is_none());
+        assert!(service.get_user("never".into()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_delete_user_removes_progress() {
+        let (_temp, service) = create_test_service();
+        service
+            .create_or_update_user(create_test_user("alice"))
+            .expect("Failed to add user");
+        service
+            .update_progress("alice".into(), "book.epub".into(), create_test_progress())
+            .expect("Failed to update alice's progress");
+        service
+            .update_progress("bob".into(), "book.epub".into(), create_test_progress())
+            .expect("Failed to update bob's progress");
 
-    use super::*;
-    use tempfile::{NamedTempFile, TempDir};
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
 
-    // === Test Helper Functions ===
+        let alice_progress = service
+            .get_progress("alice".into(), "book.epub".into())
+            .expect("Failed to get progress");
+        assert!(
+            alice_progress.is_none(),
+            "Alice's progress should be removed"
+        );
 
-    fn create_test_service() -> (TempDir, impl KorrosyncService) {
-        let temp_dir = TempDir::new().expect("Failed to create temp dir");
-        let db_path = temp_dir.path().join("test.db");
-        let service = KorrosyncServiceRedb::new(db_path).expect("Failed to create service");
-        (temp_dir, service)
+        let bob_progress = service
+            .get_progress("bob".into(), "book.epub".into())
+            .expect("Failed to get progress");
+        assert!(bob_progress.is_some(), "Other users' progress must be kept");
     }
 
-    fn create_test_user(username: &str) -> User {
-        User::new(username, "test_password").expect("Failed to create user")
-    }
+    // === Device Registry Tests ===
 
-    fn create_test_progress() -> Progress {
-        Progress {
-            device_id: "device-123".to_string(),
-            device: "Kindle".to_string(),
-            percentage: 45.5,
-            progress: "Page 91 of 200".to_string(),
-            timestamp: 1609459200000,
-        }
+    /// Reports progress of `document` from a device, which records the device
+    fn report_from_device(
+        service: &impl KorrosyncService,
+        user: &str,
+        device_id: &str,
+        seen: u64,
+        document: &str,
+    ) {
+        let progress = Progress {
+            device_id: device_id.to_string(),
+            timestamp: seen,
+            client_timestamp: None,
+            ..create_test_progress()
+        };
+        service
+            .sync_progress(
+                user.into(),
+                vec![(document.to_string(), progress)],
+                &SyncContext::default(),
+                None,
+            )
+            .expect("Failed to sync progress");
     }
 
-    // === Service Initialization Tests ===
+    #[test]
+    fn test_reporting_device_keeps_first_seen_and_name() {
+        let (_temp, service) = create_test_service();
+
+        report_from_device(&service, "alice", "k1", 1000, "a.epub");
+        service
+            .rename_device("alice".into(), "k1".into(), Some("Bedroom".into()))
+            .expect("Failed to rename device");
+
+        report_from_device(&service, "alice", "k1", 2000, "b.epub");
+        let stored = service
+            .list_devices("alice".into())
+            .expect("Failed to list devices")
+            .remove(0);
+
+        assert_eq!(stored.first_seen, 1000);
+        assert_eq!(stored.last_seen, 2000);
+        assert_eq!(stored.last_document, "b.epub");
+        assert_eq!(stored.display_name(), "Bedroom");
+    }
 
     #[test]
-    fn test_new_creates_service_with_simple_path() {
-        let db = NamedTempFile::new().expect("Failed to create temp file");
-        let service = KorrosyncServiceRedb::new(db.path());
-        assert!(service.is_ok(), "Service creation should succeed");
+    fn test_list_devices_is_user_specific() {
+        let (_temp, service) = create_test_service();
+
+        for (user, device_id) in [
+            ("alice", "k2"),
+            ("alice", "k1"),
+            ("bob", "k3"),
+            ("al", "k4"),
+        ] {
+            report_from_device(&service, user, device_id, 1000, "a.epub");
+        }
+
+        let ids: Vec<String> = service
+            .list_devices("alice".into())
+            .expect("Failed to list devices")
+            .into_iter()
+            .map(|d| d.device_id)
+            .collect();
+        assert_eq!(ids, vec!["k1", "k2"]);
     }
 
     #[test]
-    fn test_new_creates_parent_directories() {
-        let temp_dir = TempDir::new().expect("Failed to create temp dir");
-        let db_path = temp_dir.path().join("nested/dirs/korrosync.db");
+    fn test_rename_and_forget_unknown_device() {
+        let (_temp, service) = create_test_service();
 
-        let service = KorrosyncServiceRedb::new(&db_path);
         assert!(
-            service.is_ok(),
-            "Service should create parent directories automatically"
+            !service
+                .rename_device("alice".into(), "nope".into(), Some("x".into()))
+                .expect("Failed to rename device")
         );
         assert!(
-            db_path.parent().unwrap().exists(),
-            "Parent directories should exist"
+            !service
+                .forget_device("alice".into(), "nope".into())
+                .expect("Failed to forget device")
         );
     }
 
     #[test]
-    fn test_new_opens_existing_database() {
-        let db = NamedTempFile::new().expect("Failed to create temp file");
-        let db_path = db.path().to_path_buf();
-
-        // Create first service and add a user
-        {
-            let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to create service");
-            let user = create_test_user("alice");
-            service
-                .create_or_update_user(user)
-                .expect("Failed to add user");
+    fn test_forget_device_and_delete_user() {
+        let (_temp, service) = create_test_service();
+        for device_id in ["k1", "k2"] {
+            report_from_device(&service, "alice", device_id, 1000, "a.epub");
         }
 
-        // Reopen the same database
-        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to reopen database");
-        let retrieved = service
-            .get_user("alice".into())
-            .expect("Failed to get user")
-            .expect("User should exist");
+        assert!(
+            service
+                .forget_device("alice".into(), "k1".into())
+                .expect("Failed to forget device")
+        );
+        assert_eq!(service.list_devices("alice".into()).unwrap().len(), 1);
 
-        assert_eq!(retrieved.username(), "alice");
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
+        assert!(service.list_devices("alice".into()).unwrap().is_empty());
     }
 
-    // === User CRUD Operation Tests ===
+    // === Audit Log Tests ===
+
+    fn create_test_event(kind: AuditKind, username: &str, timestamp: i64) -> AuditEvent {
+        AuditEvent {
+            timestamp,
+            ..AuditEvent::new(kind, username)
+        }
+    }
 
     #[test]
-    fn test_add_and_get_user() {
+    fn test_list_events_filters_by_user_and_since() {
         let (_temp, service) = create_test_service();
-        let user = create_test_user("alice");
 
-        service
-            .create_or_update_user(user)
-            .expect("Failed to add user");
+        for (kind, user, ts) in [
+            (AuditKind::Registration, "alice", 1000),
+            (AuditKind::AuthFailure, "bob", 2000),
+            (AuditKind::AuthFailure, "alice", 2000),
+            (AuditKind::PasswordChange, "alice", 3000),
+        ] {
+            service
+                .record_event(create_test_event(kind, user, ts))
+                .expect("Failed to record event");
+        }
 
-        let retrieved = service
-            .get_user("alice".into())
-            .expect("Failed to get user")
-            .expect("User not found");
+        let all = service.list_events(None, None).expect("Failed to list");
+        assert_eq!(all.len(), 4);
+        assert!(
+            all.windows(2).all(|w| w[0].timestamp <= w[1].timestamp),
+            "Events should be sorted chronologically"
+        );
 
-        assert_eq!(retrieved.username(), "alice");
+        let alice = service
+            .list_events(Some("alice".into()), None)
+            .expect("Failed to list");
+        assert_eq!(alice.len(), 3);
+        assert!(alice.iter().all(|e| e.username == "alice"));
+
+        let recent = service
+            .list_events(Some("alice".into()), Some(2000))
+            .expect("Failed to list");
+        let kinds: Vec<AuditKind> = recent.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![AuditKind::AuthFailure, AuditKind::PasswordChange]
+        );
     }
 
     #[test]
-    fn test_get_user_returns_none_when_not_exists() {
+    fn test_prune_events_removes_only_older_events() {
         let (_temp, service) = create_test_service();
 
-        let result = service
-            .get_user("nonexistent".into())
-            .expect("Query should not fail");
+        for ts in [1000, 2000, 3000] {
+            service
+                .record_event(create_test_event(AuditKind::AdminAction, "alice", ts))
+                .expect("Failed to record event");
+        }
 
-        assert!(result.is_none(), "Should return None for non-existent user");
+        let removed = service.prune_events(2000).expect("Failed to prune");
+        assert_eq!(removed, 1);
+
+        let remaining: Vec<i64> = service
+            .list_events(None, None)
+            .expect("Failed to list")
+            .iter()
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(remaining, vec![2000, 3000]);
     }
 
     #[test]
-    fn test_add_user_overwrites_existing() {
+    fn test_audit_events_survive_user_deletion() {
         let (_temp, service) = create_test_service();
-        let user1 = User::new("alice", "password1").expect("Failed to create user1");
-        let user2 = User::new("alice", "password2").expect("Failed to create user2");
-
         service
-            .create_or_update_user(user1)
-            .expect("Failed to add user1");
+            .create_or_update_user(create_test_user("alice"))
+            .expect("Failed to add user");
         service
-            .create_or_update_user(user2)
-            .expect("Failed to add user2");
+            .record_event(AuditEvent::new(AuditKind::Registration, "alice"))
+            .expect("Failed to record event");
 
-        let retrieved = service
-            .get_user("alice".into())
-            .expect("Failed to get user")
-            .expect("User not found");
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
+
+        let events = service
+            .list_events(Some("alice".into()), None)
+            .expect("Failed to list");
+        assert_eq!(events.len(), 1);
+    }
+
+    // === Group Tests ===
+
+    #[test]
+    fn test_join_and_list_groups() {
+        let (_temp, service) = create_test_service();
+        let group = service
+            .create_or_update_group(Group::new("Book club", "alice"))
+            .expect("Failed to create group");
 
-        // Verify the second password works (overwrote the first)
         assert!(
-            retrieved
-                .check("password2")
-                .expect("Error checking password"),
-            "Should verify with second password"
+            service
+                .join_group("wrong".into(), "bob".into())
+                .expect("Failed to join group")
+                .is_none()
         );
+        let joined = service
+            .join_group(group.invite_code.clone(), "bob".into())
+            .expect("Failed to join group")
+            .expect("Group should exist");
+        assert!(joined.is_member("bob"));
+
+        assert_eq!(service.list_groups(None).unwrap().len(), 1);
+        assert_eq!(service.list_groups(Some("bob".into())).unwrap().len(), 1);
         assert!(
-            !retrieved
-                .check("password1")
-                .expect("Error checking password"),
-            "Should not verify with first password"
+            service
+                .list_groups(Some("carol".into()))
+                .unwrap()
+                .is_empty()
         );
     }
 
     #[test]
-    fn test_username_verification() {
+    fn test_set_group_visibility() {
         let (_temp, service) = create_test_service();
-        let user = create_test_user("alice");
+        let group = service
+            .create_or_update_group(Group::new("Book club", "alice"))
+            .expect("Failed to create group");
 
-        service
-            .create_or_update_user(user)
-            .expect("Failed to add user");
+        assert!(
+            service
+                .set_group_visibility(group.id.clone(), "alice".into(), Visibility::Hidden)
+                .expect("Failed to set visibility")
+        );
+        assert!(
+            !service
+                .set_group_visibility(group.id.clone(), "bob".into(), Visibility::Hidden)
+                .expect("Failed to set visibility")
+        );
 
-        let retrieved = service
-            .get_user("alice".into())
-            .expect("Failed to get user")
-            .expect("User not found");
+        let stored = service.get_group(group.id).unwrap().unwrap();
+        assert_eq!(stored.members[0].visibility, Visibility::Hidden);
+    }
 
-        assert_eq!(
-            retrieved.username(),
-            "alice",
-            "Username should match exactly"
+    #[test]
+    fn test_last_member_leaving_deletes_group() {
+        let (_temp, service) = create_test_service();
+        let group = service
+            .create_or_update_group(Group::new("Book club", "alice"))
+            .expect("Failed to create group");
+
+        assert!(
+            service
+                .leave_group(group.id.clone(), "alice".into())
+                .expect("Failed to leave group")
+        );
+        assert!(service.get_group(group.id.clone()).unwrap().is_none());
+        assert!(
+            !service
+                .leave_group(group.id, "alice".into())
+                .expect("Failed to leave group")
         );
     }
 
     #[test]
-    fn test_username_case_sensitive() {
+    fn test_delete_user_leaves_groups() {
         let (_temp, service) = create_test_service();
-        let user = create_test_user("Alice");
-
         service
-            .create_or_update_user(user)
+            .create_or_update_user(create_test_user("alice"))
             .expect("Failed to add user");
+        let shared = service
+            .create_or_update_group(Group::new("Book club", "alice"))
+            .expect("Failed to create group");
+        let solo = service
+            .create_or_update_group(Group::new("Solo", "alice"))
+            .expect("Failed to create group");
+        service
+            .join_group(shared.invite_code.clone(), "bob".into())
+            .expect("Failed to join group");
 
-        let result = service
-            .get_user("alice".into())
-            .expect("Query should not fail");
-        assert!(result.is_none(), "Username lookup should be case-sensitive");
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
 
-        let result = service
-            .get_user("Alice".into())
-            .expect("Query should not fail");
-        assert!(result.is_some(), "Exact case should match");
+        let shared = service.get_group(shared.id).unwrap().unwrap();
+        assert_eq!(shared.owner, "bob");
+        assert!(!shared.is_member("alice"));
+        assert!(service.get_group(solo.id).unwrap().is_none());
     }
 
-    // === Progress CRUD Operation Tests ===
+    // === Share Tests ===
 
     #[test]
-    fn test_update_and_get_progress() {
+    fn test_share_lifecycle() {
         let (_temp, service) = create_test_service();
-        let progress = create_test_progress();
-
-        service
-            .update_progress("alice".into(), "book.epub".into(), progress)
-            .expect("Failed to update progress");
-
-        let retrieved = service
-            .get_progress("alice".to_string(), "book.epub".to_string())
-            .expect("Failed to get progress");
+        let share = service
+            .create_share(Share::new("alice", "book.epub", None), Quota::default())
+            .expect("Failed to create share");
+
+        let stored = service
+            .get_share(share.token.clone())
+            .expect("Failed to get share")
+            .expect("Share should exist");
+        assert_eq!(stored.user, "alice");
+        assert_eq!(stored.document, "book.epub");
+        assert_eq!(service.list_shares("alice".into()).unwrap().len(), 1);
+        assert!(service.list_shares("bob".into()).unwrap().is_empty());
 
-        assert!(retrieved.is_some());
-        let retrieved = retrieved.unwrap();
-        assert_eq!(retrieved.device_id, "device-123");
-        assert_eq!(retrieved.device, "Kindle");
-        assert_eq!(retrieved.percentage, 45.5);
-        assert_eq!(retrieved.progress, "Page 91 of 200");
-        assert_eq!(retrieved.timestamp, 1609459200000);
+        assert!(
+            !service
+                .revoke_share("bob".into(), share.token.clone())
+                .expect("Failed to revoke share"),
+            "Only the owner can revoke a share"
+        );
+        assert!(
+            service
+                .revoke_share("alice".into(), share.token.clone())
+                .expect("Failed to revoke share")
+        );
+        assert!(service.get_share(share.token).unwrap().is_none());
     }
 
     #[test]
-    fn test_update_progress_returns_document_and_timestamp() {
+    fn test_delete_user_removes_shares() {
         let (_temp, service) = create_test_service();
-        let progress = create_test_progress();
+        let alice = service
+            .create_share(Share::new("alice", "book.epub", None), Quota::default())
+            .expect("Failed to create share");
+        let bob = service
+            .create_share(Share::new("bob", "book.epub", None), Quota::default())
+            .expect("Failed to create share");
 
-        let (doc, ts) = service
-            .update_progress("alice".into(), "book.epub".into(), progress)
-            .expect("Failed to update progress");
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
 
-        assert_eq!(doc, "book.epub");
-        assert_eq!(ts, 1609459200000);
+        assert!(service.get_share(alice.token).unwrap().is_none());
+        assert!(service.get_share(bob.token).unwrap().is_some());
     }
 
-    #[test]
-    fn test_update_progress_overwrites_existing() {
-        let (_temp, service) = create_test_service();
-
-        let progress1 = Progress {
-            device_id: "device-1".to_string(),
-            device: "Kindle".to_string(),
-            percentage: 30.0,
-            progress: "Page 60".to_string(),
-            timestamp: 1000000,
-        };
+    // === Annotation Tests ===
 
-        let progress2 = Progress {
-            device_id: "device-2".to_string(),
-            device: "Kobo".to_string(),
-            percentage: 70.0,
-            progress: "Page 140".to_string(),
-            timestamp: 2000000,
-        };
+    fn annotation(id: &str, note: &str, updated_at: u64) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            note: Some(note.to_string()),
+            pos0: "pos0".to_string(),
+            pos1: "pos1".to_string(),
+            updated_at,
+            ..Default::default()
+        }
+    }
 
-        service
-            .update_progress("alice".into(), "book.epub".into(), progress1)
-            .expect("Failed to update progress first time");
+    #[test]
+    fn test_merge_annotations_keeps_latest_versions() {
+        let (_temp, service) = create_test_service();
 
         service
-            .update_progress("alice".into(), "book.epub".into(), progress2)
-            .expect("Failed to update progress second time");
-
-        let retrieved = service
-            .get_progress("alice".to_string(), "book.epub".to_string())
-            .expect("Failed to get progress");
+            .merge_annotations(
+                "alice".into(),
+                "book.epub".into(),
+                vec![annotation("b", "old", 10), annotation("a", "kept", 10)],
+                Quota::default(),
+            )
+            .expect("Failed to merge annotations");
+        let merged = service
+            .merge_annotations(
+                "alice".into(),
+                "book.epub".into(),
+                vec![annotation("b", "new", 20), annotation("a", "stale", 5)],
+                Quota::default(),
+            )
+            .expect("Failed to merge annotations");
+
+        let notes: Vec<_> = merged.iter().map(|a| a.note.as_deref().unwrap()).collect();
+        assert_eq!(notes, vec!["kept", "new"]);
+        assert!(
+            service
+                .list_annotations("bob".into(), "book.epub".into())
+                .unwrap()
+                .is_empty()
+        );
+    }
 
-        assert!(retrieved.is_some());
-        let retrieved = retrieved.unwrap();
-        assert_eq!(retrieved.device_id, "device-2");
-        assert_eq!(retrieved.percentage, 70.0);
-        assert_eq!(retrieved.timestamp, 2000000);
+    #[test]
+    fn test_link_document_merges_annotations() {
+        let (_temp, service) = create_test_service();
+        service
+            .merge_annotations(
+                "alice".into(),
+                "old.epub".into(),
+                vec![
+                    annotation("a", "from alias", 20),
+                    annotation("b", "only alias", 1),
+                ],
+                Quota::default(),
+            )
+            .unwrap();
+        service
+            .merge_annotations(
+                "alice".into(),
+                "new.epub".into(),
+                vec![annotation("a", "from canonical", 10)],
+                Quota::default(),
+            )
+            .unwrap();
+
+        service
+            .link_document(
+                "alice".into(),
+                "old.epub".into(),
+                "new.epub".into(),
+                Quota::default(),
+            )
+            .expect("Failed to link document");
+
+        let annotations = service
+            .list_annotations("alice".into(), "old.epub".into())
+            .unwrap();
+        let notes: Vec<_> = annotations
+            .iter()
+            .map(|a| a.note.as_deref().unwrap())
+            .collect();
+        assert_eq!(notes, vec!["from alias", "only alias"]);
     }
 
     #[test]
-    fn test_get_progress_not_found_error() {
+    fn test_delete_user_removes_annotations() {
         let (_temp, service) = create_test_service();
+        for user in ["alice", "bob"] {
+            service
+                .merge_annotations(
+                    user.into(),
+                    "book.epub".into(),
+                    vec![annotation("a", "x", 1)],
+                    Quota::default(),
+                )
+                .unwrap();
+        }
 
-        let result = service.get_progress("alice".to_string(), "nonexistent.epub".to_string());
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
 
         assert!(
-            result.is_ok(),
-            "Should return Ok(None) for non-existent progress"
+            service
+                .list_annotations("alice".into(), "book.epub".into())
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            service
+                .list_annotations("bob".into(), "book.epub".into())
+                .unwrap()
+                .len(),
+            1
         );
-        match result {
-            Ok(None) => {} // Expected
-            _ => panic!("Expected NotFound error"),
-        }
     }
 
+    // === Bookmark Tests ===
+
     #[test]
-    fn test_progress_is_user_specific() {
+    fn test_put_bookmark_merges_and_deletes() {
         let (_temp, service) = create_test_service();
-        let progress = create_test_progress();
+        let bookmark = Bookmark::new("Chapter 3", "/body/DocFragment[7].0", 10);
 
-        // Same document, different users
         service
-            .update_progress("alice".into(), "book.epub".into(), progress.clone())
-            .expect("Failed to update alice's progress");
+            .put_bookmark(
+                "alice".into(),
+                "book.epub".into(),
+                bookmark.clone(),
+                Quota::default(),
+            )
+            .expect("Failed to put bookmark");
+        let stale = Bookmark {
+            label: "Outdated".to_string(),
+            updated_at: 5,
+            ..bookmark.clone()
+        };
+        let stored = service
+            .put_bookmark("alice".into(), "book.epub".into(), stale, Quota::default())
+            .expect("Failed to put bookmark");
+        assert_eq!(stored.label, "Chapter 3");
 
-        let mut bob_progress = progress;
-        bob_progress.percentage = 80.0;
         service
-            .update_progress("bob".into(), "book.epub".into(), bob_progress)
-            .expect("Failed to update bob's progress");
+            .put_bookmark(
+                "alice".into(),
+                "book.epub".into(),
+                bookmark.delete(20),
+                Quota::default(),
+            )
+            .expect("Failed to delete bookmark");
+        let bookmarks = service
+            .list_bookmarks("alice".into(), "book.epub".into())
+            .expect("Failed to list bookmarks");
+        assert_eq!(bookmarks.len(), 1);
+        assert!(bookmarks[0].deleted);
+    }
 
-        // Verify each user has their own progress
-        let alice_retrieved = service
-            .get_progress("alice".to_string(), "book.epub".to_string())
-            .expect("Failed to get alice's progress");
+    #[test]
+    fn test_delete_user_removes_bookmarks() {
+        let (_temp, service) = create_test_service();
+        for user in ["alice", "bob"] {
+            service
+                .put_bookmark(
+                    user.into(),
+                    "book.epub".into(),
+                    Bookmark::new("x", "1", 1),
+                    Quota::default(),
+                )
+                .unwrap();
+        }
 
-        let bob_retrieved = service
-            .get_progress("bob".to_string(), "book.epub".to_string())
-            .expect("Failed to get bob's progress");
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
 
-        assert!(alice_retrieved.is_some());
-        assert!(bob_retrieved.is_some());
-        let alice_retrieved = alice_retrieved.unwrap();
-        let bob_retrieved = bob_retrieved.unwrap();
-        assert_eq!(alice_retrieved.percentage, 45.5);
-        assert_eq!(bob_retrieved.percentage, 80.0);
+        assert!(
+            service
+                .list_bookmarks("alice".into(), "book.epub".into())
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            service
+                .list_bookmarks("bob".into(), "book.epub".into())
+                .unwrap()
+                .len(),
+            1
+        );
     }
 
+    // === Goal Tests ===
+
     #[test]
-    fn test_progress_is_document_specific() {
+    fn test_set_and_get_goals() {
         let (_temp, service) = create_test_service();
+        assert!(service.get_goals("alice".into()).unwrap().is_none());
 
-        let progress1 = Progress {
-            device_id: "device-1".to_string(),
-            device: "Kindle".to_string(),
-            percentage: 30.0,
-            progress: "Page 60".to_string(),
-            timestamp: 1000000,
-        };
-
-        let progress2 = Progress {
-            device_id: "device-1".to_string(),
-            device: "Kindle".to_string(),
-            percentage: 70.0,
-            progress: "Page 140".to_string(),
-            timestamp: 2000000,
+        let goals = Goals {
+            books_per_year: Some(24),
+            minutes_per_day: None,
+            updated_at: 1000,
         };
-
-        // Same user, different documents
         service
-            .update_progress("alice".into(), "book1.epub".into(), progress1)
-            .expect("Failed to update progress for book1");
+            .set_goals("alice".into(), goals.clone(), Quota::default())
+            .expect("Failed to set goals");
+        assert_eq!(service.get_goals("alice".into()).unwrap(), Some(goals));
 
         service
-            .update_progress("alice".into(), "book2.epub".into(), progress2)
-            .expect("Failed to update progress for book2");
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
+        assert!(service.get_goals("alice".into()).unwrap().is_none());
+    }
 
-        // Verify each document has separate progress
-        let book1_retrieved = service
-            .get_progress("alice".to_string(), "book1.epub".to_string())
-            .expect("Failed to get book1 progress");
+    #[test]
+    fn test_set_and_clear_quota() {
+        let (_temp, service) = create_test_service();
+        assert!(service.get_quota("alice".into()).unwrap().is_none());
 
-        let book2_retrieved = service
-            .get_progress("alice".to_string(), "book2.epub".to_string())
-            .expect("Failed to get book2 progress");
+        let quota = Quota {
+            max_documents: Some(10),
+            max_bytes: None,
+        };
+        assert!(!service.set_quota("alice".into(), Some(quota)).unwrap());
+        assert_eq!(service.get_quota("alice".into()).unwrap(), Some(quota));
 
-        assert!(book1_retrieved.is_some());
-        assert!(book2_retrieved.is_some());
-        assert_eq!(book1_retrieved.unwrap().percentage, 30.0);
-        assert_eq!(book2_retrieved.unwrap().percentage, 70.0);
+        assert!(service.set_quota("alice".into(), None).unwrap());
+        assert!(service.get_quota("alice".into()).unwrap().is_none());
+
+        service.set_quota("alice".into(), Some(quota)).unwrap();
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
+        assert!(service.get_quota("alice".into()).unwrap().is_none());
     }
 
     #[test]
-    fn test_progress_all_fields_stored_correctly() {
+    fn test_sync_progress_enforces_quota() {
         let (_temp, service) = create_test_service();
-
-        let progress = Progress {
-            device_id: "unique-device-id-123".to_string(),
-            device: "Kindle Paperwhite 11th Gen".to_string(),
-            percentage: 67.89,
-            progress: "Chapter 12, Page 345 of 512".to_string(),
-            timestamp: 1704067200000,
+        let context = SyncContext {
+            default_quota: Quota {
+                max_documents: Some(1),
+                max_bytes: None,
+            },
+            ..Default::default()
+        };
+        let sync = |document: &str, at: u64, context: &SyncContext| {
+            service.sync_progress(
+                "alice".into(),
+                vec![(document.to_string(), progress_at(at, 0.5))],
+                context,
+                None,
+            )
         };
 
-        service
-            .update_progress("testuser".into(), "detailed-book.pdf".into(), progress)
-            .expect("Failed to update progress");
+        let stored = sync("a.epub", 1000, &context).expect("Failed to sync progress");
+        assert_eq!(stored, Some(vec![("a.epub".to_string(), 1000)]));
+        // Updating a stored document does not grow the document count
+        sync("a.epub", 2000, &context).expect("Failed to sync progress");
 
-        let retrieved = service
-            .get_progress("testuser".to_string(), "detailed-book.pdf".to_string())
-            .expect("Failed to get progress");
+        let rejected = sync("b.epub", 3000, &context);
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
+        assert!(
+            service
+                .get_progress("alice".into(), "b.epub".into())
+                .unwrap()
+                .is_none(),
+            "A rejected update must not store anything"
+        );
+        assert_eq!(
+            service.list_progress_history("alice".into()).unwrap().len(),
+            2
+        );
 
-        assert!(retrieved.is_some());
-        let retrieved = retrieved.unwrap();
-        assert_eq!(retrieved.device_id, "unique-device-id-123");
-        assert_eq!(retrieved.device, "Kindle Paperwhite 11th Gen");
-        assert_eq!(retrieved.percentage, 67.89);
-        assert_eq!(retrieved.progress, "Chapter 12, Page 345 of 512");
-        assert_eq!(retrieved.timestamp, 1704067200000);
+        // The quota of the user takes precedence over the default
+        service
+            .set_quota("alice".into(), Some(Quota::default()))
+            .unwrap();
+        sync("b.epub", 3000, &context).expect("Failed to sync progress");
+        assert_eq!(service.list_progress("alice".into()).unwrap().len(), 2);
     }
 
-    // === Thread Safety and Concurrency Tests ===
+    #[test]
+    fn test_quota_covers_annotations_bookmarks_and_statistics() {
+        let (_temp, service) = create_test_service();
+        let quota = Quota {
+            max_documents: None,
+            max_bytes: Some(200),
+        };
 
-    #[tokio::test]
-    async fn test_service_clone_is_thread_safe() {
-        let (_temp, svc) = create_test_service();
-        let service = Arc::new(svc);
-        let user = create_test_user("alice");
         service
-            .create_or_update_user(user)
-            .expect("Failed to add user");
+            .merge_annotations(
+                "alice".into(),
+                "book.epub".into(),
+                vec![annotation("a", "note", 10)],
+                quota,
+            )
+            .expect("Failed to merge annotations");
+        let rejected = service.merge_annotations(
+            "alice".into(),
+            "book.epub".into(),
+            vec![annotation("b", &"x".repeat(200), 10)],
+            quota,
+        );
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
+        assert_eq!(
+            service
+                .list_annotations("alice".into(), "book.epub".into())
+                .unwrap()
+                .len(),
+            1
+        );
 
-        let service_clone = service.clone();
+        let bookmark = Bookmark::new("x".repeat(200), "1", 1);
+        let rejected = service.put_bookmark("alice".into(), "book.epub".into(), bookmark, quota);
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
 
-        let handle = tokio::spawn(async move {
-            service_clone
-                .get_user("alice".into())
-                .expect("Failed to get user")
-                .expect("User not found")
-                .username()
-                .to_string()
-        });
+        let rejected = service.put_statistics("alice".into(), vec![0; 200], quota);
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
+        assert!(
+            service
+                .get_statistics_file("alice".into())
+                .unwrap()
+                .is_none()
+        );
 
-        let username = handle.await.expect("Task failed");
-        assert_eq!(username, "alice");
+        // Deleting never grows the usage, even past the limit
+        let tombstone = annotation("a", "", 20);
+        service
+            .merge_annotations(
+                "alice".into(),
+                "book.epub".into(),
+                vec![tombstone],
+                Quota {
+                    max_documents: None,
+                    max_bytes: Some(0),
+                },
+            )
+            .expect("Failed to delete annotation");
     }
 
-    #[tokio::test]
-    async fn test_concurrent_reads() {
-        let (_temp, svc) = create_test_service();
-        let service = Arc::new(svc);
-        let user = create_test_user("alice");
+    #[test]
+    fn test_quota_covers_metadata_shares_and_aliases() {
+        let (_temp, service) = create_test_service();
+        let quota = Quota {
+            max_documents: None,
+            max_bytes: Some(100),
+        };
+
+        let metadata = DocumentMetadata {
+            title: Some("x".repeat(200)),
+            ..Default::default()
+        };
+        let rejected = service.set_document(
+            Some("alice".into()),
+            "book.epub".into(),
+            metadata.clone(),
+            quota,
+        );
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
+        // Global metadata belongs to no user
         service
-            .create_or_update_user(user)
-            .expect("Failed to add user");
+            .set_document(None, "book.epub".into(), metadata, quota)
+            .expect("Failed to set global metadata");
+
+        let share = Share::new("alice", "x".repeat(200), None);
+        let rejected = service.create_share(share, quota);
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
+        assert!(service.list_shares("alice".into()).unwrap().is_empty());
+
+        let rejected =
+            service.link_document("alice".into(), "x".repeat(200), "book.epub".into(), quota);
+        assert!(matches!(rejected, Err(ServiceError::QuotaExceeded(_))));
+        assert!(service.list_aliases("alice".into()).unwrap().is_empty());
+    }
 
-        let mut handles = vec![];
+    #[test]
+    fn test_usage_follows_every_change() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let service =
+            KorrosyncServiceRedb::new(temp_dir.path().join("test.db")).expect("Failed to open");
+        let usage = || {
+            service
+                .get_usage("alice".into())
+                .expect("Failed to get usage")
+        };
+        // The usage kept up to date must match the usage measured from scratch
+        let assert_measured = || {
+            let write_txn = service.db.begin_write().expect("Failed to begin write");
+            let measured = migration::stored_usage(&write_txn)
+                .expect("Failed to measure usage")
+                .remove("alice")
+                .unwrap_or_default();
+            assert_eq!(usage(), measured);
+        };
+        let sync = |document: &str, at: u64| {
+            service
+                .sync_progress(
+                    "alice".into(),
+                    vec![(document.to_string(), progress_at(at, 0.5))],
+                    &SyncContext::default(),
+                    None,
+                )
+                .expect("Failed to sync progress");
+        };
 
-        // Spawn 10 concurrent read tasks
-        for _ in 0..10 {
-            let service_clone = service.clone();
-            let handle = tokio::spawn(async move {
-                service_clone
-                    .get_user("alice".into())
-                    .expect("Failed to get user")
-                    .expect("User not found")
-                    .username()
-                    .to_string()
-            });
-            handles.push(handle);
-        }
+        sync("a.epub", 1000);
+        let first = usage();
+        assert_eq!(first.documents, 1);
+        assert_measured();
 
-        // All reads should succeed
-        for handle in handles {
-            let username = handle.await.expect("Task failed");
-            assert_eq!(username, "alice");
-        }
-    }
+        // Updating a document grows its history, which is not counted
+        sync("a.epub", 2000);
+        assert_eq!(usage(), first);
+        assert_measured();
 
-    #[tokio::test]
-    async fn test_concurrent_writes() {
-        let (_temp, svc) = create_test_service();
-        let service = Arc::new(svc);
+        sync("b.epub", 3000);
+        service
+            .merge_annotations(
+                "alice".into(),
+                "a.epub".into(),
+                vec![annotation("a", "note", 10)],
+                Quota::default(),
+            )
+            .unwrap();
+        service
+            .put_bookmark(
+                "alice".into(),
+                "a.epub".into(),
+                Bookmark::new("Start", "1", 10),
+                Quota::default(),
+            )
+            .unwrap();
+        service
+            .rename_device("alice".into(), "device-123".into(), Some("Bedside".into()))
+            .unwrap();
+        let before = usage();
+        service
+            .put_statistics("alice".into(), vec![0; 100], Quota::default())
+            .unwrap();
+        assert_eq!(usage().bytes - before.bytes, 100);
+        assert_measured();
 
-        let mut handles = vec![];
+        service
+            .link_document(
+                "alice".into(),
+                "a.epub".into(),
+                "book.epub".into(),
+                Quota::default(),
+            )
+            .unwrap();
+        assert_measured();
 
-        // Spawn 10 concurrent write tasks for different users
-        for i in 0..10 {
-            let username = format!("user{}", i);
-            let service = service.clone();
-            let handle = tokio::spawn(async move {
-                let user = User::new(&username, "password").expect("Failed to create user");
-                service
-                    .create_or_update_user(user)
-                    .expect("Failed to add user");
-                username
-            });
-            handles.push(handle);
-        }
+        service
+            .set_shelf(
+                "alice".into(),
+                "b.epub".into(),
+                Shelf::Abandoned,
+                4000,
+                Quota::default(),
+            )
+            .unwrap();
+        service
+            .set_goals(
+                "alice".into(),
+                Goals {
+                    books_per_year: Some(12),
+                    minutes_per_day: None,
+                    updated_at: 4000,
+                },
+                Quota::default(),
+            )
+            .unwrap();
+        let share = service
+            .create_share(Share::new("alice", "b.epub", None), Quota::default())
+            .unwrap();
+        let metadata = DocumentMetadata {
+            title: Some("Book".to_string()),
+            updated_at: 4000,
+            ..Default::default()
+        };
+        service
+            .set_document(
+                Some("alice".into()),
+                "b.epub".into(),
+                metadata.clone(),
+                Quota::default(),
+            )
+            .unwrap();
+        // Global metadata is not charged to anyone
+        let before = usage();
+        service
+            .set_document(None, "b.epub".into(), metadata, Quota::default())
+            .unwrap();
+        assert_eq!(usage(), before);
+        assert_measured();
 
-        // Wait for all writes to complete
-        for handle in handles {
-            handle.await.expect("Task failed");
-        }
+        service.revoke_share("alice".into(), share.token).unwrap();
+        service
+            .unlink_document("alice".into(), "a.epub".into())
+            .unwrap();
+        assert_measured();
+        service
+            .link_document(
+                "alice".into(),
+                "a.epub".into(),
+                "book.epub".into(),
+                Quota::default(),
+            )
+            .unwrap();
+        service
+            .delete_progress_if_older("alice".into(), "book.epub".into(), 5000)
+            .unwrap();
+        assert_measured();
+        service
+            .delete_progress("alice".into(), "b.epub".into())
+            .unwrap();
+        assert_eq!(usage().documents, 0);
+        service
+            .forget_device("alice".into(), "device-123".into())
+            .unwrap();
+        assert_measured();
 
-        // Verify all users were created
-        for i in 0..10 {
-            let username = format!("user{}", i);
-            let result = service
-                .get_user(username.clone())
-                .expect("Failed to get user");
-            assert!(result.is_some(), "User {} should exist", username);
-        }
+        service.delete_user("alice".into()).unwrap();
+        assert_eq!(usage(), QuotaUsage::default());
+    }
+
+    #[test]
+    fn test_sync_progress_records_devices() {
+        let (_temp, service) = create_test_service();
+        report_from_device(&service, "alice", "kobo-1", 500, "old.epub");
+        service
+            .rename_device("alice".into(), "kobo-1".into(), Some("Bedside".into()))
+            .unwrap();
+
+        let mut kobo = progress_at(1000, 0.1);
+        kobo.device_id = "kobo-1".to_string();
+        let mut kindle = progress_at(2000, 0.2);
+        kindle.device_id = "kindle-1".to_string();
+        let context = SyncContext {
+            ip: Some("10.0.0.1".to_string()),
+            ..Default::default()
+        };
+        service
+            .sync_progress(
+                "alice".into(),
+                vec![("a.epub".into(), kobo), ("b.epub".into(), kindle)],
+                &context,
+                None,
+            )
+            .expect("Failed to sync progress");
+
+        let devices = service.list_devices("alice".into()).unwrap();
+        assert_eq!(devices.len(), 2);
+        let kindle = &devices[0];
+        assert_eq!(kindle.device_id, "kindle-1");
+        assert_eq!(kindle.first_seen, 2000);
+        assert_eq!(kindle.last_document, "b.epub");
+        let kobo = &devices[1];
+        assert_eq!(kobo.name.as_deref(), Some("Bedside"));
+        assert_eq!(kobo.first_seen, 500);
+        assert_eq!(kobo.last_seen, 1000);
+        assert_eq!(kobo.last_ip.as_deref(), Some("10.0.0.1"));
     }
 
-    // === Edge Case Tests ===
-
     #[test]
-    fn test_empty_username_or_document() {
+    fn test_sync_progress_moves_shelves() {
         let (_temp, service) = create_test_service();
-        let progress = create_test_progress();
+        let sync = |document: &str, percentage: f32, finished_threshold: Option<f32>| {
+            let context = SyncContext {
+                default_quota: Quota {
+                    max_documents: Some(1),
+                    max_bytes: None,
+                },
+                finished_threshold,
+                ..Default::default()
+            };
+            service.sync_progress(
+                "alice".into(),
+                vec![(document.to_string(), progress_at(1000, percentage))],
+                &context,
+                None,
+            )
+        };
 
-        let result = service.update_progress("".into(), "book.epub".into(), progress.clone());
-        assert!(result.is_ok(), "Empty username should be allowed");
+        sync("a.epub", 0.5, None).expect("Failed to sync progress");
+        assert!(service.list_shelves("alice".into()).unwrap().is_empty());
 
-        let result = service.update_progress("alice".into(), "".into(), progress);
-        assert!(result.is_ok(), "Empty document should be allowed");
+        sync("a.epub", 1.0, Some(0.99)).expect("Failed to sync progress");
+        // Rejected by the quota, so the shelves must not change either
+        assert!(sync("b.epub", 0.5, Some(0.99)).is_err());
+
+        let shelves = service.list_shelves("alice".into()).unwrap();
+        let shelves: Vec<_> = shelves
+            .iter()
+            .map(|(document, entry)| (document.as_str(), entry.shelf()))
+            .collect();
+        assert_eq!(shelves, vec![("a.epub", Shelf::Finished)]);
     }
 
     #[test]
-    fn test_special_characters_in_identifiers() {
+    fn test_sync_progress_condition_covers_every_document() {
         let (_temp, service) = create_test_service();
-        let progress = create_test_progress();
+        service
+            .update_progress("alice".into(), "a.epub".into(), progress_at(1000, 0.1))
+            .unwrap();
+
+        let exists = |current: Option<&Progress>| current.is_some();
+        let updates = vec![
+            ("a.epub".to_string(), progress_at(2000, 0.2)),
+            ("b.epub".to_string(), progress_at(2000, 0.2)),
+        ];
+        let stored = service
+            .sync_progress(
+                "alice".into(),
+                updates,
+                &SyncContext::default(),
+                Some(&exists),
+            )
+            .expect("Failed to sync progress");
+        assert_eq!(stored, None);
+        let current = service
+            .get_progress("alice".into(), "a.epub".into())
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.percentage, 0.1);
+    }
 
-        let special_user = "user@example.com";
-        let special_doc = "book-title_v2.0 [final] (2024).epub";
+    // === Shelf Tests ===
 
+    #[test]
+    fn test_shelves_follow_progress_and_aliases() {
+        let (_temp, service) = create_test_service();
         service
-            .update_progress(special_user.into(), special_doc.into(), progress)
-            .expect("Should handle special characters");
+            .link_document("alice".into(), "old".into(), "new".into(), Quota::default())
+            .expect("Failed to link document");
+        let sync = |user: &str, document: &str, percentage: f32, timestamp: u64| {
+            let context = SyncContext {
+                finished_threshold: Some(0.99),
+                ..Default::default()
+            };
+            service
+                .sync_progress(
+                    user.into(),
+                    vec![(document.into(), progress_at(timestamp, percentage))],
+                    &context,
+                    None,
+                )
+                .expect("Failed to sync progress");
+        };
 
-        let retrieved = service
-            .get_progress(special_user.into(), special_doc.into())
-            .expect("Should retrieve with special characters");
+        sync("alice", "old", 0.5, 1000);
+        sync("alice", "new", 1.0, 2000);
+        let entry = service
+            .set_shelf(
+                "alice".into(),
+                "other".into(),
+                Shelf::Abandoned,
+                3000,
+                Quota::default(),
+            )
+            .expect("Failed to set shelf");
+        assert_eq!(entry.shelf(), Shelf::Abandoned);
+        sync("bob", "new", 0.1, 1000);
+
+        let shelves = service.list_shelves("alice".into()).unwrap();
+        let shelves: Vec<_> = shelves
+            .iter()
+            .map(|(document, entry)| (document.as_str(), entry.shelf(), entry.finished_at()))
+            .collect();
+        assert_eq!(
+            shelves,
+            vec![
+                ("new", Shelf::Finished, Some(2000)),
+                ("other", Shelf::Abandoned, None),
+            ]
+        );
 
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap().device_id, "device-123");
+        service
+            .delete_progress("alice".into(), "new".into())
+            .expect("Failed to delete progress");
+        assert_eq!(service.list_shelves("alice".into()).unwrap().len(), 1);
+
+        service
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
+        assert!(service.list_shelves("alice".into()).unwrap().is_empty());
+        assert_eq!(service.list_shelves("bob".into()).unwrap().len(), 1);
     }
 
     #[test]
-    fn test_boundary_values() {
+    fn test_link_document_merges_shelves() {
         let (_temp, service) = create_test_service();
+        service
+            .set_shelf(
+                "alice".into(),
+                "old".into(),
+                Shelf::Finished,
+                1000,
+                Quota::default(),
+            )
+            .unwrap();
+        service
+            .set_shelf(
+                "alice".into(),
+                "new".into(),
+                Shelf::Reading,
+                2000,
+                Quota::default(),
+            )
+            .unwrap();
 
-        let progress_0 = Progress {
-            device_id: "device-1".to_string(),
-            device: "Test".to_string(),
-            percentage: 0.0,
-            progress: "Start".to_string(),
-            timestamp: 0,
-        };
+        service
+            .link_document("alice".into(), "old".into(), "new".into(), Quota::default())
+            .expect("Failed to link document");
+
+        let shelves = service.list_shelves("alice".into()).unwrap();
+        assert_eq!(shelves.len(), 1, "The alias must not keep its own entry");
+        let (document, entry) = &shelves[0];
+        assert_eq!(document, "new");
+        assert_eq!(entry.shelf(), Shelf::Reading);
+        assert_eq!(entry.finished_at(), Some(1000));
+    }
 
-        let progress_100 = Progress {
-            device_id: "device-1".to_string(),
-            device: "Test".to_string(),
-            percentage: 100.0,
-            progress: "End".to_string(),
-            timestamp: u64::MAX,
+    // === Document Metadata Tests ===
+
+    #[test]
+    fn test_user_document_metadata_overrides_global() {
+        let (_temp, service) = create_test_service();
+        let global = DocumentMetadata {
+            title: Some("Dune".to_string()),
+            ..Default::default()
+        };
+        let mine = DocumentMetadata {
+            title: Some("Dune (annotated)".to_string()),
+            ..Default::default()
         };
 
         service
-            .update_progress("alice".into(), "doc1".into(), progress_0)
-            .expect("Should handle 0% and timestamp 0");
+            .set_document(None, "abc".into(), global, Quota::default())
+            .expect("Failed to set document");
+        service
+            .set_document(Some("alice".into()), "abc".into(), mine, Quota::default())
+            .expect("Failed to set document");
+
+        let title = |user: &str| {
+            service
+                .get_document(user.into(), "abc".into())
+                .expect("Failed to get document")
+                .and_then(|metadata| metadata.title)
+        };
+        assert_eq!(title("alice").as_deref(), Some("Dune (annotated)"));
+        assert_eq!(title("bob").as_deref(), Some("Dune"));
 
         service
-            .update_progress("alice".into(), "doc2".into(), progress_100)
-            .expect("Should handle 100% and max timestamp");
+            .delete_user("alice".into())
+            .expect("Failed to delete user");
+        assert_eq!(title("alice").as_deref(), Some("Dune"));
+        assert!(
+            service
+                .get_document("alice".into(), "unknown".into())
+                .unwrap()
+                .is_none()
+        );
+    }
 
-        let retrieved_0 = service
-            .get_progress("alice".to_string(), "doc1".to_string())
-            .expect("Should retrieve 0%");
+    #[test]
+    fn test_list_progress_is_user_specific() {
+        let (_temp, service) = create_test_service();
+        for (user, document) in [("alice", "b.epub"), ("bob", "a.epub"), ("alice", "a.epub")] {
+            service
+                .update_progress(user.into(), document.into(), create_test_progress())
+                .expect("Failed to update progress");
+        }
 
-        assert!(retrieved_0.is_some());
-        let retrieved_0 = retrieved_0.unwrap();
+        let documents: Vec<String> = service
+            .list_progress("alice".into())
+            .expect("Failed to list progress")
+            .into_iter()
+            .map(|(document, _)| document)
+            .collect();
+        assert_eq!(documents, vec!["a.epub", "b.epub"]);
+    }
 
-        assert_eq!(retrieved_0.percentage, 0.0);
-        assert_eq!(retrieved_0.timestamp, 0);
+    // === Document Alias Tests ===
 
-        let retrieved_100 = service
-            .get_progress("alice".to_string(), "doc2".to_string())
-            .expect("Should retrieve 100%");
+    fn progress_at(timestamp: u64, percentage: f32) -> Progress {
+        Progress {
+            percentage,
+            timestamp,
+            client_timestamp: None,
+            ..create_test_progress()
+        }
+    }
 
-        assert!(retrieved_100.is_some());
-        let retrieved_100 = retrieved_100.unwrap();
-        assert_eq!(retrieved_100.percentage, 100.0);
-        assert_eq!(retrieved_100.timestamp, u64::MAX);
+    #[test]
+    fn test_progress_follows_aliases() {
+        let (_temp, service) = create_test_service();
+        let canonical = service
+            .link_document("alice".into(), "old".into(), "new".into(), Quota::default())
+            .expect("Failed to link document");
+        assert_eq!(canonical, "new");
+
+        let (document, _) = service
+            .update_progress("alice".into(), "old".into(), progress_at(1000, 0.3))
+            .expect("Failed to update progress");
+        assert_eq!(document, "old", "The requested document is echoed back");
+
+        let stored = service
+            .get_progress("alice".into(), "new".into())
+            .unwrap()
+            .expect("Progress should be stored under the canonical document");
+        assert_eq!(stored.percentage, 0.3);
+        assert!(
+            service
+                .get_progress("bob".into(), "new".into())
+                .unwrap()
+                .is_none(),
+            "Aliases are per user"
+        );
+
+        assert!(
+            service
+                .unlink_document("alice".into(), "old".into())
+                .unwrap()
+        );
+        assert!(
+            service
+                .get_progress("alice".into(), "old".into())
+                .unwrap()
+                .is_none()
+        );
     }
 
     #[test]
-    fn test_very_long_identifiers() {
+    fn test_sync_progress_if() {
         let (_temp, service) = create_test_service();
-        let progress = create_test_progress();
+        let sync_if = |progress: Progress, condition: &ProgressCondition<'_>| {
+            service
+                .sync_progress(
+                    "alice".into(),
+                    vec![("book.epub".into(), progress)],
+                    &SyncContext::default(),
+                    Some(condition),
+                )
+                .expect("Failed to update progress")
+        };
+        let unchanged_since = |timestamp: u64| {
+            move |current: Option<&Progress>| current.map(|p| p.timestamp) == Some(timestamp)
+        };
 
-        let long_username = "a".repeat(1000);
-        let long_document = "b".repeat(1000);
+        let stored = sync_if(progress_at(1000, 0.1), &|current| current.is_none());
+        assert_eq!(stored, Some(vec![("book.epub".to_string(), 1000)]));
+
+        let stored = sync_if(progress_at(2000, 0.2), &unchanged_since(500));
+        assert_eq!(stored, None, "A stale condition must not store anything");
+        let current = service
+            .get_progress("alice".into(), "book.epub".into())
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.percentage, 0.1);
+
+        let stored = sync_if(progress_at(2000, 0.2), &unchanged_since(1000));
+        assert_eq!(stored, Some(vec![("book.epub".to_string(), 2000)]));
+    }
 
+    #[test]
+    fn test_delete_progress() {
+        let (_temp, service) = create_test_service();
         service
-            .update_progress(long_username.clone(), long_document.clone(), progress)
-            .expect("Should handle very long identifiers");
+            .link_document("alice".into(), "old".into(), "new".into(), Quota::default())
+            .expect("Failed to link document");
+        for (user, document) in [("alice", "new"), ("alice", "other"), ("bob", "new")] {
+            service
+                .update_progress(user.into(), document.into(), progress_at(1000, 0.5))
+                .expect("Failed to update progress");
+        }
 
-        let retrieved = service
-            .get_progress(long_username.clone(), long_document.clone())
-            .expect("Should retrieve with long identifiers");
+        assert!(
+            service
+                .delete_progress("alice".into(), "old".into())
+                .expect("Failed to delete progress"),
+            "Deleting through an alias removes the canonical document's progress"
+        );
+        assert!(
+            !service
+                .delete_progress("alice".into(), "new".into())
+                .unwrap()
+        );
 
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap().device_id, "device-123");
+        assert!(
+            service
+                .get_progress("alice".into(), "new".into())
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            service
+                .list_device_progress("alice".into(), "new".into())
+                .unwrap()
+                .is_empty()
+        );
+        let history = service.list_progress_history("alice".into()).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].0, "other");
+        assert!(
+            service
+                .get_progress("bob".into(), "new".into())
+                .unwrap()
+                .is_some(),
+            "Other users keep their progress"
+        );
+        assert_eq!(
+            service
+                .list_device_progress("bob".into(), "new".into())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            service.list_progress_history("bob".into()).unwrap().len(),
+            1
+        );
     }
 
-    // === List Users Tests ===
-
     #[test]
-    fn test_list_users_empty() {
+    fn test_delete_progress_if_older() {
         let (_temp, service) = create_test_service();
-        let users = service.list_users().expect("Failed to list users");
-        assert!(users.is_empty());
+        service
+            .set_shelf(
+                "alice".into(),
+                "book".into(),
+                Shelf::Finished,
+                1000,
+                Quota::default(),
+            )
+            .unwrap();
+        service
+            .update_progress("alice".into(), "book".into(), progress_at(2000, 1.0))
+            .unwrap();
+
+        assert!(
+            !service
+                .delete_progress_if_older("alice".into(), "book".into(), 2000)
+                .unwrap(),
+            "Progress updated since the cutoff must be kept"
+        );
+        assert!(
+            service
+                .delete_progress_if_older("alice".into(), "book".into(), 2001)
+                .unwrap()
+        );
+        assert!(
+            !service
+                .delete_progress_if_older("alice".into(), "book".into(), 2001)
+                .unwrap()
+        );
+
+        assert!(service.list_progress("alice".into()).unwrap().is_empty());
+        assert!(
+            service
+                .list_device_progress("alice".into(), "book".into())
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            service.list_progress_history("alice".into()).unwrap().len(),
+            1
+        );
+        assert_eq!(service.list_shelves("alice".into()).unwrap().len(), 1);
     }
 
     #[test]
-    fn test_list_users_returns_all() {
+    fn test_progress_batch() {
         let (_temp, service) = create_test_service();
-
         service
-            .create_or_update_user(create_test_user("alice"))
-            .expect("Failed to add alice");
+            .link_document("alice".into(), "old".into(), "new".into(), Quota::default())
+            .expect("Failed to link document");
+
+        let results = service
+            .sync_progress(
+                "alice".into(),
+                vec![
+                    ("a.epub".into(), progress_at(1000, 0.1)),
+                    ("old".into(), progress_at(2000, 0.2)),
+                    ("a.epub".into(), progress_at(3000, 0.3)),
+                ],
+                &SyncContext::default(),
+                None,
+            )
+            .expect("Failed to update progress")
+            .expect("Unconditional updates are always stored");
+        assert_eq!(
+            results,
+            vec![
+                ("a.epub".to_string(), 1000),
+                ("old".to_string(), 2000),
+                ("a.epub".to_string(), 3000),
+            ]
+        );
+        assert_eq!(
+            service.list_progress_history("alice".into()).unwrap().len(),
+            3
+        );
+
+        let progress = service
+            .get_progress_batch(
+                "alice".into(),
+                vec!["old".into(), "unknown.epub".into(), "a.epub".into()],
+            )
+            .expect("Failed to get progress");
+        let found: Vec<_> = progress
+            .iter()
+            .map(|(document, progress)| (document.as_str(), progress.percentage))
+            .collect();
+        assert_eq!(found, vec![("old", 0.2), ("a.epub", 0.3)]);
+    }
+
+    #[test]
+    fn test_link_keeps_most_recent_progress() {
+        let (_temp, service) = create_test_service();
         service
-            .create_or_update_user(create_test_user("bob"))
-            .expect("Failed to add bob");
+            .update_progress("alice".into(), "a".into(), progress_at(2000, 0.6))
+            .unwrap();
         service
-            .create_or_update_user(create_test_user("charlie"))
-            .expect("Failed to add charlie");
+            .update_progress("alice".into(), "b".into(), progress_at(1000, 0.2))
+            .unwrap();
 
-        let users = service.list_users().expect("Failed to list users");
-        assert_eq!(users.len(), 3);
+        service
+            .link_document("alice".into(), "a".into(), "b".into(), Quota::default())
+            .expect("Failed to link document");
 
-        let mut usernames: Vec<&str> = users.iter().map(|u| u.username()).collect();
-        usernames.sort();
-        assert_eq!(usernames, vec!["alice", "bob", "charlie"]);
+        let progress = service.list_progress("alice".into()).unwrap();
+        assert_eq!(progress.len(), 1);
+        assert_eq!(progress[0].0, "b");
+        assert_eq!(progress[0].1.percentage, 0.6);
     }
 
-    // === Delete User Tests ===
-
     #[test]
-    fn test_delete_user_existing() {
+    fn test_link_merges_alias_groups() {
         let (_temp, service) = create_test_service();
         service
-            .create_or_update_user(create_test_user("alice"))
-            .expect("Failed to add user");
+            .link_document("alice".into(), "a".into(), "b".into(), Quota::default())
+            .unwrap();
+        service
+            .link_document("alice".into(), "c".into(), "d".into(), Quota::default())
+            .unwrap();
 
-        let deleted = service
-            .delete_user("alice".into())
-            .expect("Failed to delete user");
-        assert!(deleted, "Should return true for existing user");
+        // Linking to an alias links to its canonical document; "b"'s aliases follow it
+        let canonical = service
+            .link_document("alice".into(), "a".into(), "c".into(), Quota::default())
+            .unwrap();
+        assert_eq!(canonical, "d");
 
-        let user = service
-            .get_user("alice".into())
-            .expect("Failed to get user");
-        assert!(user.is_none(), "User should no longer exist");
+        let aliases = service.list_aliases("alice".into()).unwrap();
+        assert_eq!(
+            aliases,
+            vec![
+                ("a".to_string(), "d".to_string()),
+                ("b".to_string(), "d".to_string()),
+                ("c".to_string(), "d".to_string()),
+            ]
+        );
+
+        service.delete_user("alice".into()).unwrap();
+        assert!(service.list_aliases("alice".into()).unwrap().is_empty());
     }
 
+    // === Per-Device Progress Tests ===
+
     #[test]
-    fn test_delete_user_nonexistent() {
+    fn test_device_progress_keeps_each_device() {
         let (_temp, service) = create_test_service();
+        for (device_id, timestamp, percentage) in [
+            ("phone", 1000, 0.1),
+            ("kobo", 2000, 0.2),
+            ("phone", 3000, 0.3),
+        ] {
+            let progress = Progress {
+                device_id: device_id.to_string(),
+                ..progress_at(timestamp, percentage)
+            };
+            service
+                .update_progress("alice".into(), "book".into(), progress)
+                .expect("Failed to update progress");
+        }
 
-        let deleted = service
-            .delete_user("nonexistent".into())
-            .expect("Failed to delete user");
-        assert!(!deleted, "Should return false for non-existent user");
+        let positions = service
+            .list_device_progress("alice".into(), "book".into())
+            .expect("Failed to list device progress");
+        let summary: Vec<(&str, f32)> = positions
+            .iter()
+            .map(|p| (p.device_id.as_str(), p.percentage))
+            .collect();
+        assert_eq!(summary, vec![("phone", 0.3), ("kobo", 0.2)]);
+
+        service
+            .link_document(
+                "alice".into(),
+                "book".into(),
+                "other".into(),
+                Quota::default(),
+            )
+            .expect("Failed to link document");
+        assert_eq!(
+            service
+                .list_device_progress("alice".into(), "other".into())
+                .unwrap()
+                .len(),
+            2,
+            "Device positions follow the canonical document"
+        );
+
+        service.delete_user("alice".into()).unwrap();
+        assert!(
+            service
+                .list_device_progress("alice".into(), "other".into())
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[test]
@@ -983,6 +4716,7 @@ mod tests {
             percentage: 50.0,
             progress: "".to_string(),
             timestamp: 1000000,
+            client_timestamp: None,
         };
 
         service
@@ -996,4 +4730,66 @@ mod tests {
         assert!(retrieved.is_some());
         assert_eq!(retrieved.unwrap().progress, "");
     }
+
+    // === Progress History Tests ===
+
+    #[test]
+    fn test_progress_history_keeps_every_update() {
+        let (_temp, service) = create_test_service();
+        for (document, timestamp, percentage) in
+            [("a", 3000, 0.3), ("b", 2000, 0.1), ("a", 1000, 0.2)]
+        {
+            service
+                .update_progress(
+                    "alice".into(),
+                    document.into(),
+                    progress_at(timestamp, percentage),
+                )
+                .unwrap();
+        }
+        service
+            .update_progress("bob".into(), "a".into(), progress_at(1500, 0.9))
+            .unwrap();
+
+        let history = service.list_progress_history("alice".into()).unwrap();
+        let entries: Vec<_> = history
+            .iter()
+            .map(|(document, progress)| (document.as_str(), progress.timestamp))
+            .collect();
+        assert_eq!(entries, vec![("a", 1000), ("b", 2000), ("a", 3000)]);
+
+        assert!(service.delete_user("alice".into()).is_ok());
+        assert!(
+            service
+                .list_progress_history("alice".into())
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            service.list_progress_history("bob".into()).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_progress_history_follows_links() {
+        let (_temp, service) = create_test_service();
+        service
+            .update_progress("alice".into(), "old".into(), progress_at(1000, 0.1))
+            .unwrap();
+        service
+            .update_progress("alice".into(), "new".into(), progress_at(2000, 0.2))
+            .unwrap();
+
+        service
+            .link_document("alice".into(), "old".into(), "new".into(), Quota::default())
+            .unwrap();
+        service
+            .update_progress("alice".into(), "old".into(), progress_at(3000, 0.3))
+            .unwrap();
+
+        let history = service.list_progress_history("alice".into()).unwrap();
+        assert_eq!(history.len(), 3);
+        assert!(history.iter().all(|(document, _)| document == "new"));
+    }
 }
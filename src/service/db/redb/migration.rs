@@ -0,0 +1,604 @@
+//! Migrations of tables whose key or value layout changed.
+//!
+//! Tables are versioned by name. When the layout of a stored type changes, the table is
+//! bumped to a new version and the rows of the previous one are converted the next time
+//! the database is opened, after which the old table is deleted.
+
+use std::collections::BTreeMap;
+
+use redb::{Key, ReadableTable, TableDefinition, TableHandle, WriteTransaction};
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::{
+    ALIASES_TABLE, ANNOTATIONS_TABLE, BOOKMARKS_TABLE, DEVICE_PROGRESS_TABLE, DEVICES_TABLE,
+    DOCUMENTS_TABLE, DeviceProgressKey, GOALS_TABLE, PROGRESS_TABLE, ProgressKey, SHARES_TABLE,
+    SHELVES_TABLE, STATISTICS_FILES_TABLE, STATISTICS_TABLE, USAGE_TABLE, alias_size, row_size,
+};
+use crate::{
+    model::{Progress, QuotaUsage, StatisticsFile, StoredSize},
+    service::{
+        error::ServiceError,
+        serialization::{Legacy, LegacyType, Rkyv},
+    },
+};
+
+const PROGRESS_TABLE_V2: TableDefinition<Legacy<ProgressKeyV3>, Legacy<ProgressV2>> =
+    TableDefinition::new("progress-v2");
+const PROGRESS_TABLE_V3: TableDefinition<Legacy<ProgressKeyV3>, Rkyv<Progress>> =
+    TableDefinition::new("progress-v3");
+const DEVICE_PROGRESS_TABLE_V1: TableDefinition<Rkyv<DeviceProgressKey>, Legacy<ProgressV2>> =
+    TableDefinition::new("device-progress-v1");
+/// Usage measured before it covered shelves, goals, shares, aliases and document metadata,
+/// and while it still counted the progress history
+const USAGE_TABLE_V1: TableDefinition<&str, Rkyv<QuotaUsage>> = TableDefinition::new("usage-v1");
+
+/// [`Progress`] as stored before it recorded the client-reported timestamp.
+#[derive(Debug, Archive, Serialize, Deserialize, Default)]
+struct ProgressV2 {
+    device_id: String,
+    device: String,
+    percentage: f32,
+    progress: String,
+    timestamp: u64,
+}
+
+impl LegacyType for ProgressV2 {
+    const TYPE_NAME: &'static str = "korrosync::model::progress::Progress";
+}
+
+impl From<ProgressV2> for Progress {
+    fn from(value: ProgressV2) -> Self {
+        Self {
+            device_id: value.device_id,
+            device: value.device,
+            percentage: value.percentage,
+            progress: value.progress,
+            timestamp: value.timestamp,
+            client_timestamp: None,
+        }
+    }
+}
+
+/// [`ProgressKey`] as stored before it was ordered by user.
+#[derive(Debug, Archive, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct ProgressKeyV3 {
+    document: String,
+    user: String,
+}
+
+impl LegacyType for ProgressKeyV3 {
+    const TYPE_NAME: &'static str = "korrosync::service::db::redb::ProgressKey";
+}
+
+impl From<ProgressKeyV3> for ProgressKey {
+    fn from(value: ProgressKeyV3) -> Self {
+        Self {
+            user: value.user,
+            document: value.document,
+        }
+    }
+}
+
+/// Runs every pending migration within the given transaction.
+pub(super) fn migrate(write_txn: &WriteTransaction) -> Result<(), ServiceError> {
+    migrate_progress(write_txn, PROGRESS_TABLE_V2, PROGRESS_TABLE)?;
+    migrate_progress_keys(write_txn)?;
+    migrate_progress(write_txn, DEVICE_PROGRESS_TABLE_V1, DEVICE_PROGRESS_TABLE)?;
+    describe_statistics(write_txn)?;
+    measure_usage(write_txn)?;
+    Ok(())
+}
+
+/// Stores the storage used by each user, measured from the data stored before it was kept
+/// up to date.
+fn measure_usage(write_txn: &WriteTransaction) -> Result<(), ServiceError> {
+    if table_exists(write_txn, USAGE_TABLE.name())? {
+        return Ok(());
+    }
+    if table_exists(write_txn, USAGE_TABLE_V1.name())? {
+        write_txn
+            .delete_table(USAGE_TABLE_V1)
+            .map_err(ServiceError::db)?;
+    }
+
+    let usage = stored_usage(write_txn)?;
+    let mut table = write_txn
+        .open_table(USAGE_TABLE)
+        .map_err(ServiceError::db)?;
+    for (user, usage) in &usage {
+        table
+            .insert(user.as_str(), usage)
+            .map_err(ServiceError::db)?;
+    }
+
+    if !usage.is_empty() {
+        tracing::info!(
+            "Measured the storage used by {} users in {}",
+            usage.len(),
+            USAGE_TABLE.name()
+        );
+    }
+    Ok(())
+}
+
+/// Measures the storage used by every user from the data they store.
+pub(super) fn stored_usage(
+    write_txn: &WriteTransaction,
+) -> Result<BTreeMap<String, QuotaUsage>, ServiceError> {
+    let mut usage = BTreeMap::<String, QuotaUsage>::new();
+
+    let table = write_txn
+        .open_table(PROGRESS_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        let user = usage.entry(key.user).or_default();
+        user.documents += 1;
+        user.store(None, row_size(&key.document, &value.value()));
+    }
+
+    let table = write_txn
+        .open_table(DEVICE_PROGRESS_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        let size = row_size(&key.document, &value.value());
+        usage.entry(key.user).or_default().store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(DEVICES_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let size = value.value().stored_size();
+        usage.entry(key.value().user).or_default().store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(ANNOTATIONS_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        let size = row_size(&key.document, &value.value());
+        usage.entry(key.user).or_default().store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(BOOKMARKS_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        let size = row_size(&key.document, &value.value());
+        usage.entry(key.user).or_default().store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(STATISTICS_FILES_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (user, file) = entry.map_err(ServiceError::db)?;
+        let size = file.value().size;
+        usage
+            .entry(user.value().to_string())
+            .or_default()
+            .store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(SHELVES_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        let size = row_size(&key.document, &value.value());
+        usage.entry(key.user).or_default().store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(GOALS_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (user, goals) = entry.map_err(ServiceError::db)?;
+        let size = goals.value().stored_size();
+        usage
+            .entry(user.value().to_string())
+            .or_default()
+            .store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(SHARES_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (_, share) = entry.map_err(ServiceError::db)?;
+        let share = share.value();
+        let size = share.stored_size();
+        usage.entry(share.user).or_default().store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(ALIASES_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, document) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        let size = alias_size(&key.alias, document.value());
+        usage.entry(key.user).or_default().store(None, size);
+    }
+
+    let table = write_txn
+        .open_table(DOCUMENTS_TABLE)
+        .map_err(ServiceError::db)?;
+    for entry in table.iter().map_err(ServiceError::db)? {
+        let (key, value) = entry.map_err(ServiceError::db)?;
+        let key = key.value();
+        // Global metadata belongs to no user
+        if let Some(user) = key.user {
+            let size = row_size(&key.document, &value.value());
+            usage.entry(user).or_default().store(None, size);
+        }
+    }
+
+    Ok(usage)
+}
+
+/// Stores the metadata of the statistics databases stored before it was kept apart.
+fn describe_statistics(write_txn: &WriteTransaction) -> Result<(), ServiceError> {
+    let statistics = write_txn
+        .open_table(STATISTICS_TABLE)
+        .map_err(ServiceError::db)?;
+    let mut files = write_txn
+        .open_table(STATISTICS_FILES_TABLE)
+        .map_err(ServiceError::db)?;
+
+    let mut described = 0;
+    for entry in statistics.iter().map_err(ServiceError::db)? {
+        let (user, data) = entry.map_err(ServiceError::db)?;
+        if files.get(user.value()).map_err(ServiceError::db)?.is_some() {
+            continue;
+        }
+        files
+            .insert(user.value(), StatisticsFile::of(data.value()))
+            .map_err(ServiceError::db)?;
+        described += 1;
+    }
+
+    if described > 0 {
+        tracing::info!(
+            "Stored the metadata of {} statistics databases in {}",
+            described,
+            STATISTICS_FILES_TABLE.name()
+        );
+    }
+    Ok(())
+}
+
+/// Whether a table exists in the database.
+fn table_exists(write_txn: &WriteTransaction, name: &str) -> Result<bool, ServiceError> {
+    Ok(write_txn
+        .list_tables()
+        .map_err(ServiceError::db)?
+        .any(|table| table.name() == name))
+}
+
+/// Copies the rows of a legacy progress table into its current version and deletes it.
+fn migrate_progress<L, K>(
+    write_txn: &WriteTransaction,
+    legacy: TableDefinition<L, Legacy<ProgressV2>>,
+    current: TableDefinition<K, Rkyv<Progress>>,
+) -> Result<(), ServiceError>
+where
+    L: Key + 'static,
+    K: Key + 'static,
+    for<'a> K::SelfType<'a>: From<L::SelfType<'a>>,
+{
+    if !table_exists(write_txn, legacy.name())? {
+        return Ok(());
+    }
+
+    let migrated = {
+        let source = write_txn.open_table(legacy).map_err(ServiceError::db)?;
+        let mut target = write_txn.open_table(current).map_err(ServiceError::db)?;
+        let mut migrated = 0;
+        for entry in source.iter().map_err(ServiceError::db)? {
+            let (key, value) = entry.map_err(ServiceError::db)?;
+            target
+                .insert(
+                    K::SelfType::from(key.value()),
+                    Progress::from(value.value()),
+                )
+                .map_err(ServiceError::db)?;
+            migrated += 1;
+        }
+        migrated
+    };
+    write_txn.delete_table(legacy).map_err(ServiceError::db)?;
+
+    tracing::info!(
+        "Migrated {} rows from {} to {}",
+        migrated,
+        legacy.name(),
+        current.name()
+    );
+    Ok(())
+}
+
+/// Rekeys the progress stored before it was ordered by user and deletes the old table.
+fn migrate_progress_keys(write_txn: &WriteTransaction) -> Result<(), ServiceError> {
+    if !table_exists(write_txn, PROGRESS_TABLE_V3.name())? {
+        return Ok(());
+    }
+
+    let migrated = {
+        let source = write_txn
+            .open_table(PROGRESS_TABLE_V3)
+            .map_err(ServiceError::db)?;
+        let mut target = write_txn
+            .open_table(PROGRESS_TABLE)
+            .map_err(ServiceError::db)?;
+        let mut migrated = 0;
+        for entry in source.iter().map_err(ServiceError::db)? {
+            let (key, value) = entry.map_err(ServiceError::db)?;
+            target
+                .insert(ProgressKey::from(key.value()), value.value())
+                .map_err(ServiceError::db)?;
+            migrated += 1;
+        }
+        migrated
+    };
+    write_txn
+        .delete_table(PROGRESS_TABLE_V3)
+        .map_err(ServiceError::db)?;
+
+    tracing::info!(
+        "Migrated {} rows from {} to {}",
+        migrated,
+        PROGRESS_TABLE_V3.name(),
+        PROGRESS_TABLE.name()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use redb::{Database, ReadableDatabase};
+    use tempfile::NamedTempFile;
+
+    use super::*;
+    use crate::service::db::redb::AliasKey;
+    use crate::service::db::{KorrosyncService, KorrosyncServiceRedb};
+
+    fn legacy_progress(device_id: &str, timestamp: u64) -> ProgressV2 {
+        ProgressV2 {
+            device_id: device_id.to_string(),
+            device: "Kobo".to_string(),
+            percentage: 0.5,
+            progress: "/body/DocFragment[5]".to_string(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_migrate_progress_tables() {
+        let db_path = NamedTempFile::new().expect("Failed to create temp file");
+
+        {
+            let db = Database::create(db_path.path()).expect("Failed to create database");
+            let write_txn = db.begin_write().expect("Failed to begin write");
+            {
+                let mut progress = write_txn
+                    .open_table(PROGRESS_TABLE_V2)
+                    .expect("Failed to open legacy table");
+                progress
+                    .insert(
+                        ProgressKeyV3 {
+                            document: "book.epub".to_string(),
+                            user: "alice".to_string(),
+                        },
+                        legacy_progress("kobo-1", 2000),
+                    )
+                    .expect("Failed to insert legacy progress");
+
+                let mut devices = write_txn
+                    .open_table(DEVICE_PROGRESS_TABLE_V1)
+                    .expect("Failed to open legacy table");
+                for (device_id, timestamp) in [("kobo-1", 2000), ("kindle-1", 1000)] {
+                    devices
+                        .insert(
+                            DeviceProgressKey {
+                                user: "alice".to_string(),
+                                document: "book.epub".to_string(),
+                                device_id: device_id.to_string(),
+                            },
+                            legacy_progress(device_id, timestamp),
+                        )
+                        .expect("Failed to insert legacy device progress");
+                }
+            }
+            write_txn.commit().expect("Failed to commit");
+        }
+
+        let service = KorrosyncServiceRedb::new(db_path.path()).expect("Failed to open service");
+
+        let progress = service
+            .get_progress("alice".into(), "book.epub".into())
+            .expect("Failed to get progress")
+            .expect("Progress should have been migrated");
+        assert_eq!(progress.device_id, "kobo-1");
+        assert_eq!(progress.timestamp, 2000);
+        assert_eq!(progress.client_timestamp, None);
+
+        let positions = service
+            .list_device_progress("alice".into(), "book.epub".into())
+            .expect("Failed to list device progress");
+        let devices: Vec<_> = positions.iter().map(|p| p.device_id.as_str()).collect();
+        assert_eq!(devices, vec!["kobo-1", "kindle-1"]);
+
+        let read_txn = service.db.begin_read().expect("Failed to begin read");
+        let tables: Vec<_> = read_txn
+            .list_tables()
+            .expect("Failed to list tables")
+            .map(|table| table.name().to_string())
+            .collect();
+        assert!(!tables.contains(&"progress-v2".to_string()));
+        assert!(!tables.contains(&"progress-v3".to_string()));
+        assert!(!tables.contains(&"device-progress-v1".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_progress_keys() {
+        let db_path = NamedTempFile::new().expect("Failed to create temp file");
+
+        {
+            let db = Database::create(db_path.path()).expect("Failed to create database");
+            let write_txn = db.begin_write().expect("Failed to begin write");
+            {
+                let mut progress = write_txn
+                    .open_table(PROGRESS_TABLE_V3)
+                    .expect("Failed to open legacy table");
+                for (document, user, timestamp) in [
+                    ("a.epub", "bob", 1000),
+                    ("b.epub", "alice", 2000),
+                    ("c.epub", "alice", 3000),
+                ] {
+                    let progress_v3 = Progress {
+                        timestamp,
+                        ..Default::default()
+                    };
+                    progress
+                        .insert(
+                            ProgressKeyV3 {
+                                document: document.to_string(),
+                                user: user.to_string(),
+                            },
+                            progress_v3,
+                        )
+                        .expect("Failed to insert legacy progress");
+                }
+            }
+            write_txn.commit().expect("Failed to commit");
+        }
+
+        let service = KorrosyncServiceRedb::new(db_path.path()).expect("Failed to open service");
+
+        let progress = service
+            .list_progress("alice".into())
+            .expect("Failed to list progress");
+        let documents: Vec<_> = progress
+            .iter()
+            .map(|(document, progress)| (document.as_str(), progress.timestamp))
+            .collect();
+        assert_eq!(documents, vec![("b.epub", 2000), ("c.epub", 3000)]);
+        assert_eq!(service.list_progress("bob".into()).unwrap().len(), 1);
+
+        let read_txn = service.db.begin_read().expect("Failed to begin read");
+        let exists = read_txn
+            .list_tables()
+            .expect("Failed to list tables")
+            .any(|table| table.name() == "progress-v3");
+        assert!(!exists);
+    }
+
+    #[test]
+    fn test_describe_existing_statistics() {
+        let db_path = NamedTempFile::new().expect("Failed to create temp file");
+
+        {
+            let db = Database::create(db_path.path()).expect("Failed to create database");
+            let write_txn = db.begin_write().expect("Failed to begin write");
+            {
+                let mut statistics = write_txn
+                    .open_table(STATISTICS_TABLE)
+                    .expect("Failed to open statistics table");
+                statistics
+                    .insert("alice", b"SQLite format 3\0".as_slice())
+                    .expect("Failed to insert statistics");
+            }
+            write_txn.commit().expect("Failed to commit");
+        }
+
+        let service = KorrosyncServiceRedb::new(db_path.path()).expect("Failed to open service");
+
+        let file = service
+            .get_statistics_file("alice".into())
+            .expect("Failed to get statistics file")
+            .expect("Statistics should have been described");
+        assert_eq!(file, StatisticsFile::of(b"SQLite format 3\0"));
+    }
+
+    #[test]
+    fn test_measure_existing_usage() {
+        let db_path = NamedTempFile::new().expect("Failed to create temp file");
+        let progress = Progress {
+            device_id: "kobo-1".to_string(),
+            ..Default::default()
+        };
+
+        {
+            let db = Database::create(db_path.path()).expect("Failed to create database");
+            let write_txn = db.begin_write().expect("Failed to begin write");
+            {
+                let mut table = write_txn
+                    .open_table(PROGRESS_TABLE)
+                    .expect("Failed to open progress table");
+                table
+                    .insert(
+                        ProgressKey {
+                            user: "alice".to_string(),
+                            document: "book.epub".to_string(),
+                        },
+                        &progress,
+                    )
+                    .expect("Failed to insert progress");
+
+                let mut statistics = write_txn
+                    .open_table(STATISTICS_TABLE)
+                    .expect("Failed to open statistics table");
+                statistics
+                    .insert("alice", b"SQLite format 3\0".as_slice())
+                    .expect("Failed to insert statistics");
+
+                let mut aliases = write_txn
+                    .open_table(ALIASES_TABLE)
+                    .expect("Failed to open aliases table");
+                aliases
+                    .insert(
+                        AliasKey {
+                            user: "alice".to_string(),
+                            alias: "old.epub".to_string(),
+                        },
+                        "book.epub",
+                    )
+                    .expect("Failed to insert alias");
+
+                // Measured before aliases were counted
+                let mut stale = write_txn
+                    .open_table(USAGE_TABLE_V1)
+                    .expect("Failed to open legacy usage table");
+                stale
+                    .insert("alice", &QuotaUsage::default())
+                    .expect("Failed to insert usage");
+            }
+            write_txn.commit().expect("Failed to commit");
+        }
+
+        let service = KorrosyncServiceRedb::new(db_path.path()).expect("Failed to open service");
+
+        let usage = service
+            .get_usage("alice".into())
+            .expect("Failed to get usage");
+        assert_eq!(usage.documents, 1);
+        assert_eq!(
+            usage.bytes,
+            row_size("book.epub", &progress) + 16 + alias_size("old.epub", "book.epub")
+        );
+
+        let read_txn = service.db.begin_read().expect("Failed to begin read");
+        assert!(read_txn.open_table(USAGE_TABLE_V1).is_err());
+    }
+}
@@ -16,11 +16,15 @@
 //!     Ok(service) => println!("Service created successfully"),
 //!     Err(ServiceError::Io(e)) => eprintln!("I/O error: {}", e),
 //!     Err(ServiceError::DB(e)) => eprintln!("Database error: {}", e),
+//!     Err(ServiceError::QuotaExceeded(e)) => eprintln!("Quota exceeded: {}", e),
+//!     Err(ServiceError::Model(e)) => eprintln!("Model error: {}", e),
 //! }
 //! ```
 
 use thiserror::Error;
 
+use crate::model;
+
 #[derive(Debug, Error)]
 pub enum ServiceError {
     // I/O errors that occur during file operations, such as:
@@ -37,6 +41,15 @@ pub enum ServiceError {
     // - Table creation or access failures
     #[error(transparent)]
     DB(Box<dyn std::error::Error + Send + Sync>),
+
+    // A write was rejected because it would grow a user's storage past their quota,
+    // described by the exceeded limit
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    // Errors from the model layer, such as a failure to hash a password
+    #[error(transparent)]
+    Model(#[from] model::Error),
 }
 
 impl ServiceError {
@@ -20,6 +20,15 @@
 //! runtime polymorphism and future support for alternative storage backends
 //! (e.g., PostgreSQL, SQLite, or cloud storage).
 //!
+//! ### [`retention`]
+//!
+//! Background tasks that remove data past its retention period: audit events, stale
+//! progress and inactive accounts.
+//!
+//! ### [`statistics`]
+//!
+//! Validation and merging of KOReader statistics databases (`statistics-sync` feature).
+//!
 //! # Usage Example
 //!
 //! ```no_run
@@ -45,4 +54,7 @@
 
 pub mod db;
 pub mod error;
+pub mod retention;
 pub mod serialization;
+#[cfg(feature = "statistics-sync")]
+pub mod statistics;
@@ -0,0 +1,398 @@
+//! Background retention tasks.
+//!
+//! This module contains long-running tasks that periodically remove data that is past its
+//! configured retention period: expired audit events, progress that was not updated for
+//! too long and accounts that have been inactive (see [`collect_garbage`]).
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{Audit, Retention},
+    model::{AuditEvent, AuditKind},
+    service::{db::KorrosyncService, error::ServiceError},
+};
+
+const AUDIT_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const GARBAGE_COLLECTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// What a garbage collection pass removed or flagged (or would have, in a dry run).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GarbageReport {
+    /// Progress not updated within the retention period, as (user, document, last update)
+    pub stale_progress: Vec<(String, String, u64)>,
+    /// Users that became inactive and were flagged, with their last activity
+    pub flagged_users: Vec<(String, i64)>,
+    /// Flagged users removed after the grace period, with their last activity
+    pub removed_users: Vec<(String, i64)>,
+    /// Flagged users that became active again and were unflagged
+    pub reactivated_users: Vec<String>,
+}
+
+impl GarbageReport {
+    /// Whether the pass found nothing to do
+    pub fn is_empty(&self) -> bool {
+        self.stale_progress.is_empty()
+            && self.flagged_users.is_empty()
+            && self.removed_users.is_empty()
+            && self.reactivated_users.is_empty()
+    }
+}
+
+/// Applies the retention rules at `now` (Unix milliseconds).
+///
+/// - Progress not updated for `progress_days` is deleted, with its device positions. Its
+///   history and shelf entry are kept, so finished books still count in the statistics,
+///   goals and shelves (see [`KorrosyncService::delete_progress_if_older`]).
+/// - Users without activity for `inactive_user_days` are flagged as inactive. Once they
+///   have been flagged for `inactive_user_grace_days`, they are removed with all their
+///   data and the removal is audited. Flagged users that log in again are unflagged.
+///   Users that never logged in are left alone.
+///
+/// With `dry_run`, nothing is written and the report tells what would have happened.
+pub fn collect_garbage(
+    sync: &dyn KorrosyncService,
+    config: &Retention,
+    now: i64,
+    dry_run: bool,
+) -> Result<GarbageReport, ServiceError> {
+    let mut report = GarbageReport::default();
+    let users = sync.list_users()?;
+
+    if config.progress_days > 0 {
+        let cutoff = now.saturating_sub(days_to_millis(config.progress_days));
+        let cutoff = u64::try_from(cutoff).unwrap_or_default();
+        for user in &users {
+            for (document, progress) in sync.list_progress(user.username().to_string())? {
+                if progress.timestamp < cutoff {
+                    // The progress may have been updated since it was listed
+                    let deleted = dry_run
+                        || sync.delete_progress_if_older(
+                            user.username().to_string(),
+                            document.clone(),
+                            cutoff,
+                        )?;
+                    if !deleted {
+                        continue;
+                    }
+                    report.stale_progress.push((
+                        user.username().to_string(),
+                        document,
+                        progress.timestamp,
+                    ));
+                }
+            }
+        }
+    }
+
+    if config.inactive_user_days > 0 {
+        let cutoff = now.saturating_sub(days_to_millis(config.inactive_user_days));
+        let flagged: HashMap<String, i64> = sync.list_inactive_users()?.into_iter().collect();
+        for user in &users {
+            let name = user.username().to_string();
+            let Some(last_activity) = user.last_activity() else {
+                continue;
+            };
+
+            if last_activity >= cutoff {
+                if flagged.contains_key(&name) {
+                    if !dry_run {
+                        sync.unflag_inactive_user(name.clone())?;
+                    }
+                    report.reactivated_users.push(name);
+                }
+                continue;
+            }
+
+            let flagged_at = flagged.get(&name).copied();
+            let expired = config.inactive_user_grace_days.is_some_and(|grace| {
+                flagged_at.unwrap_or(now) <= now.saturating_sub(days_to_millis(grace))
+            });
+            if expired {
+                if !dry_run {
+                    // The user may have logged in since they were listed
+                    if !sync.delete_user_if_inactive(name.clone(), cutoff)? {
+                        continue;
+                    }
+                    sync.record_event(
+                        AuditEvent::new(AuditKind::UserDeleted, &name).with_detail(format!(
+                            "inactive for {} days",
+                            config.inactive_user_days
+                        )),
+                    )?;
+                }
+                report.removed_users.push((name, last_activity));
+            } else if flagged_at.is_none() {
+                if !dry_run {
+                    sync.flag_inactive_user(name.clone(), now)?;
+                }
+                report.flagged_users.push((name, last_activity));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Spawns a task that applies the retention rules for progress and inactive accounts.
+///
+/// Collection runs once at startup and then every hour until `shutdown_token` is
+/// cancelled. When every rule is disabled, the task exits immediately.
+pub fn garbage_collection_task(
+    sync: Arc<dyn KorrosyncService + Send + Sync>,
+    config: &Retention,
+    shutdown_token: CancellationToken,
+) -> JoinHandle<()> {
+    let config = config.clone();
+
+    tokio::spawn(async move {
+        if !config.is_enabled() {
+            tracing::debug!("Retention rules disabled, keeping progress and accounts forever");
+            return;
+        }
+
+        loop {
+            let now = Utc::now().timestamp_millis();
+            match collect_garbage(sync.as_ref(), &config, now, false) {
+                Ok(report) if report.is_empty() => {}
+                Ok(report) => tracing::info!(
+                    "Garbage collection removed {} stale progress entries and {} inactive users, flagged {} users",
+                    report.stale_progress.len(),
+                    report.removed_users.len(),
+                    report.flagged_users.len()
+                ),
+                Err(e) => tracing::warn!("Failed to collect garbage: {e}"),
+            }
+
+            tokio::select! {
+                _ = shutdown_token.cancelled() => {
+                    tracing::info!("Garbage collection task shutting down");
+                    break;
+                }
+                _ = tokio::time::sleep(GARBAGE_COLLECTION_INTERVAL) => {}
+            }
+        }
+    })
+}
+
+fn days_to_millis(days: u64) -> i64 {
+    i64::try_from(days)
+        .unwrap_or(i64::MAX)
+        .saturating_mul(MILLIS_PER_DAY)
+}
+
+/// Spawns a task that prunes audit events older than the configured retention.
+///
+/// Pruning runs once at startup and then every hour until `shutdown_token` is cancelled.
+/// When the retention is `0`, events are kept forever and the task exits immediately.
+pub fn audit_retention_task(
+    sync: Arc<dyn KorrosyncService + Send + Sync>,
+    config: &Audit,
+    shutdown_token: CancellationToken,
+) -> JoinHandle<()> {
+    let retention_days = config.retention_days;
+
+    tokio::spawn(async move {
+        if retention_days == 0 {
+            tracing::debug!("Audit retention disabled, keeping events forever");
+            return;
+        }
+
+        let retention_millis = days_to_millis(retention_days);
+
+        loop {
+            let before = Utc::now()
+                .timestamp_millis()
+                .saturating_sub(retention_millis);
+            match sync.prune_events(before) {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("Pruned {removed} expired audit events"),
+                Err(e) => tracing::warn!("Failed to prune audit events: {e}"),
+            }
+
+            tokio::select! {
+                _ = shutdown_token.cancelled() => {
+                    tracing::info!("Audit retention task shutting down");
+                    break;
+                }
+                _ = tokio::time::sleep(AUDIT_PRUNE_INTERVAL) => {}
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::NamedTempFile;
+
+    use super::*;
+    use crate::{
+        model::{Progress, Shelf, User},
+        service::db::{KorrosyncServiceRedb, SyncContext},
+    };
+
+    const DAY: i64 = MILLIS_PER_DAY;
+    /// 2024-03-10T00:00:00Z
+    const NOW: i64 = 1_710_028_800_000;
+
+    fn service() -> (NamedTempFile, KorrosyncServiceRedb) {
+        let file = NamedTempFile::new().expect("Failed to create temp file");
+        let service = KorrosyncServiceRedb::new(file.path()).expect("Failed to create service");
+        (file, service)
+    }
+
+    fn add_user(service: &KorrosyncServiceRedb, name: &str, last_activity: Option<i64>) {
+        let mut user = User::new(name, "secret").expect("Failed to create user");
+        if let Some(at) = last_activity {
+            user.set_last_activity(at);
+        }
+        service
+            .create_or_update_user(user)
+            .expect("Failed to save user");
+    }
+
+    fn add_progress(service: &KorrosyncServiceRedb, user: &str, document: &str, at: i64) {
+        let progress = Progress {
+            device_id: "kobo-1".to_string(),
+            timestamp: at as u64,
+            ..Default::default()
+        };
+        service
+            .update_progress(user.into(), document.into(), progress)
+            .expect("Failed to update progress");
+    }
+
+    #[test]
+    fn test_stale_progress_is_deleted() {
+        let (_file, service) = service();
+        add_user(&service, "alice", Some(NOW));
+        add_progress(&service, "alice", "old.epub", NOW - 40 * DAY);
+        add_progress(&service, "alice", "new.epub", NOW - DAY);
+        let config = Retention {
+            progress_days: 30,
+            ..Default::default()
+        };
+
+        let report = collect_garbage(&service, &config, NOW, true).unwrap();
+        let stale = vec![(
+            "alice".to_string(),
+            "old.epub".to_string(),
+            (NOW - 40 * DAY) as u64,
+        )];
+        assert_eq!(report.stale_progress, stale);
+        assert_eq!(service.list_progress("alice".into()).unwrap().len(), 2);
+
+        let report = collect_garbage(&service, &config, NOW, false).unwrap();
+        assert_eq!(report.stale_progress, stale);
+        let progress = service.list_progress("alice".into()).unwrap();
+        assert_eq!(progress.len(), 1);
+        assert_eq!(progress[0].0, "new.epub");
+    }
+
+    #[test]
+    fn test_finished_books_survive() {
+        let (_file, service) = service();
+        add_user(&service, "alice", Some(NOW));
+        let progress = Progress {
+            device_id: "kobo-1".to_string(),
+            percentage: 1.0,
+            timestamp: (NOW - 40 * DAY) as u64,
+            ..Default::default()
+        };
+        let context = SyncContext {
+            finished_threshold: Some(0.99),
+            ..Default::default()
+        };
+        service
+            .sync_progress(
+                "alice".into(),
+                vec![("done.epub".into(), progress)],
+                &context,
+                None,
+            )
+            .expect("Failed to sync progress");
+        let config = Retention {
+            progress_days: 30,
+            ..Default::default()
+        };
+
+        let report = collect_garbage(&service, &config, NOW, false).unwrap();
+        assert_eq!(report.stale_progress.len(), 1);
+        assert!(service.list_progress("alice".into()).unwrap().is_empty());
+
+        let shelves = service.list_shelves("alice".into()).unwrap();
+        assert_eq!(shelves.len(), 1);
+        assert_eq!(shelves[0].1.shelf(), Shelf::Finished);
+        assert_eq!(
+            service.list_progress_history("alice".into()).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_inactive_users_are_flagged_then_removed() {
+        let (_file, service) = service();
+        add_user(&service, "alice", Some(NOW - 100 * DAY));
+        add_user(&service, "bob", Some(NOW - DAY));
+        add_user(&service, "carol", None);
+        let config = Retention {
+            inactive_user_days: 90,
+            inactive_user_grace_days: Some(7),
+            ..Default::default()
+        };
+
+        let report = collect_garbage(&service, &config, NOW, false).unwrap();
+        assert_eq!(
+            report.flagged_users,
+            vec![("alice".to_string(), NOW - 100 * DAY)]
+        );
+        assert!(report.removed_users.is_empty());
+        assert_eq!(
+            service.list_inactive_users().unwrap(),
+            vec![("alice".to_string(), NOW)]
+        );
+
+        // Still within the grace period: nothing changes
+        let report = collect_garbage(&service, &config, NOW + 6 * DAY, false).unwrap();
+        assert!(report.is_empty());
+
+        let report = collect_garbage(&service, &config, NOW + 7 * DAY, true).unwrap();
+        assert_eq!(report.removed_users.len(), 1);
+        assert!(service.get_user("alice".into()).unwrap().is_some());
+
+        collect_garbage(&service, &config, NOW + 7 * DAY, false).unwrap();
+        assert!(service.get_user("alice".into()).unwrap().is_none());
+        assert!(service.list_inactive_users().unwrap().is_empty());
+        let events = service.list_events(Some("alice".into()), None).unwrap();
+        assert_eq!(events.last().map(|e| e.kind), Some(AuditKind::UserDeleted));
+        // Users that never logged in are left alone
+        assert!(service.get_user("carol".into()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_flag_only_and_reactivation() {
+        let (_file, service) = service();
+        add_user(&service, "alice", Some(NOW - 100 * DAY));
+        let config = Retention {
+            inactive_user_days: 90,
+            ..Default::default()
+        };
+
+        collect_garbage(&service, &config, NOW, false).unwrap();
+        let report = collect_garbage(&service, &config, NOW + 365 * DAY, false).unwrap();
+        assert!(
+            report.removed_users.is_empty(),
+            "Without grace period users are only flagged"
+        );
+        assert_eq!(service.list_inactive_users().unwrap().len(), 1);
+
+        add_user(&service, "alice", Some(NOW + 365 * DAY));
+        let report = collect_garbage(&service, &config, NOW + 365 * DAY, false).unwrap();
+        assert_eq!(report.reactivated_users, vec!["alice".to_string()]);
+        assert!(service.list_inactive_users().unwrap().is_empty());
+    }
+}
@@ -90,3 +90,70 @@ where
         Self::from_bytes(data1).cmp(&Self::from_bytes(data2))
     }
 }
+
+/// Type whose archived layout has since changed, kept only to migrate stored data.
+///
+/// redb records the type name of the values of every table, so the legacy type must
+/// report the name the original type had when the table was written.
+pub(crate) trait LegacyType {
+    const TYPE_NAME: &'static str;
+}
+
+/// Like [`Rkyv`], but for keys and values stored with an older layout of a type.
+#[derive(Debug)]
+pub(crate) struct Legacy<T>(T);
+
+impl<T> Value for Legacy<T>
+where
+    T: std::fmt::Debug + Default + Archive + LegacyType,
+    T::Archived: RkyvDeserialize<T, HighDeserializer<Error>>
+        + rkyv::Portable
+        + for<'a> rkyv::bytecheck::CheckBytes<rkyv::api::high::HighValidator<'a, Error>>,
+    for<'a> T: RkyvSerialize<HighSerializer<AlignedVec, ArenaHandle<'a>, Error>>,
+{
+    type SelfType<'a>
+        = T
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = AlignedVec
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        Rkyv::<T>::from_bytes(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'a,
+        Self: 'b,
+    {
+        Rkyv::<T>::as_bytes(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new(&format!("Rkyv<{}>", T::TYPE_NAME))
+    }
+}
+
+impl<T> Key for Legacy<T>
+where
+    T: std::fmt::Debug + Default + Archive + LegacyType + Ord,
+    T::Archived: RkyvDeserialize<T, HighDeserializer<Error>>
+        + rkyv::Portable
+        + for<'a> rkyv::bytecheck::CheckBytes<rkyv::api::high::HighValidator<'a, Error>>,
+    for<'a> T: RkyvSerialize<HighSerializer<AlignedVec, ArenaHandle<'a>, Error>>,
+{
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        Self::from_bytes(data1).cmp(&Self::from_bytes(data2))
+    }
+}
@@ -0,0 +1,379 @@
+//! Merging of KOReader statistics databases.
+//!
+//! KOReader's statistics plugin records every page turn in a SQLite database
+//! (`statistics.sqlite3`) and can synchronize it through cloud storage: each device
+//! downloads the remote copy, merges it into its own and uploads the result. This module
+//! performs the same merge on the server, so the stored copy always holds the reading
+//! history of every device even when two of them upload at the same time.
+//!
+//! The merge follows KOReader's: books are matched by `(title, authors, md5)` and their
+//! counters keep the highest value, page statistics are matched by
+//! `(book, page, start_time)` and keep the longest duration, and the per-book totals are
+//! recomputed afterwards.
+//!
+//! The databases come from clients, so they are opened defensively: the schema is not
+//! trusted, triggers are disabled and files declaring triggers or views are rejected
+//! before anything runs against them.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{Connection, OpenFlags, config::DbConfig};
+use thiserror::Error;
+
+/// Tables a file must contain to be accepted as a statistics database
+const REQUIRED_TABLES: [&str; 2] = ["book", "page_stat_data"];
+
+/// Schema objects a statistics database must not contain, as they would run code
+/// during the merge
+const FORBIDDEN_OBJECTS: [&str; 2] = ["trigger", "view"];
+
+const MERGE_SQL: &str = r#"
+    INSERT INTO book (
+        title, authors, notes, last_open, highlights, pages, series, language, md5,
+        total_read_time, total_read_pages
+    )
+    SELECT
+        title, authors, notes, last_open, highlights, pages, series, language, md5,
+        total_read_time, total_read_pages
+    FROM income.book WHERE true
+    ON CONFLICT (title, authors, md5) DO UPDATE SET
+        notes = max(ifnull(notes, 0), ifnull(excluded.notes, 0)),
+        last_open = max(ifnull(last_open, 0), ifnull(excluded.last_open, 0)),
+        highlights = max(ifnull(highlights, 0), ifnull(excluded.highlights, 0)),
+        pages = max(ifnull(pages, 0), ifnull(excluded.pages, 0)),
+        series = ifnull(series, excluded.series),
+        language = ifnull(language, excluded.language),
+        total_read_time = max(ifnull(total_read_time, 0), ifnull(excluded.total_read_time, 0)),
+        total_read_pages = max(ifnull(total_read_pages, 0), ifnull(excluded.total_read_pages, 0));
+
+    CREATE TEMP TABLE book_id_map AS
+    SELECT m.id AS mid, i.id AS iid
+    FROM main.book m
+    JOIN income.book i ON (m.title, m.authors, m.md5) IS (i.title, i.authors, i.md5);
+
+    INSERT INTO page_stat_data (id_book, page, start_time, duration, total_pages)
+    SELECT map.mid, page, start_time, duration, total_pages
+    FROM income.page_stat_data
+    JOIN book_id_map AS map ON id_book = map.iid
+    WHERE true
+    ON CONFLICT (id_book, page, start_time) DO UPDATE SET
+        duration = max(duration, excluded.duration);
+
+    DROP TABLE book_id_map;
+
+    UPDATE book SET (total_read_pages, total_read_time) = (
+        SELECT count(DISTINCT page), sum(duration)
+        FROM page_stat_data
+        WHERE id_book = book.id
+    )
+    WHERE id IN (SELECT id_book FROM page_stat_data);
+"#;
+
+/// Errors that can occur while validating or merging statistics databases
+#[derive(Debug, Error)]
+pub enum StatisticsError {
+    #[error("Invalid statistics database: {0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// A statistics database uploaded by a client.
+///
+/// The upload is received into a temporary file, removed on drop, so it is never held in
+/// memory while it is validated or merged.
+pub struct Upload(TempDb);
+
+impl Upload {
+    /// Creates an empty upload, for the client's database to be written to [`Upload::path`].
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(Self(TempDb::write(&[])?))
+    }
+
+    /// Path of the temporary file holding the upload
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+
+    /// Checks that the upload is a KOReader statistics database.
+    pub fn validate(&self) -> Result<(), StatisticsError> {
+        check_tables(self.path())
+    }
+
+    /// Merges the upload into the `canonical` statistics database and returns the result.
+    ///
+    /// Both databases are validated first; an invalid one yields [`StatisticsError::Invalid`].
+    pub fn merge_into(&self, canonical: &[u8]) -> Result<Vec<u8>, StatisticsError> {
+        let canonical = TempDb::write(canonical)?;
+        check_tables(canonical.path())?;
+        self.validate()?;
+
+        {
+            let mut conn = open(canonical.path(), OpenFlags::default())?;
+            conn.execute(
+                "ATTACH DATABASE ?1 AS income",
+                [self.path().to_string_lossy()],
+            )?;
+            let tx = conn.transaction()?;
+            tx.execute_batch(MERGE_SQL)
+                .map_err(|e| StatisticsError::Invalid(e.to_string()))?;
+            tx.commit()?;
+            conn.execute("DETACH DATABASE income", [])?;
+        }
+
+        Ok(fs::read(canonical.path())?)
+    }
+
+    /// Reads the uploaded database, to store it as is.
+    pub fn read(&self) -> Result<Vec<u8>, std::io::Error> {
+        fs::read(self.path())
+    }
+}
+
+/// Opens a database without trusting its schema: defensive mode forbids writing to the
+/// schema directly, and neither triggers nor SQL functions declared by it run.
+fn open(path: &Path, flags: OpenFlags) -> Result<Connection, StatisticsError> {
+    let conn = Connection::open_with_flags(path, flags)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_DEFENSIVE, true)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_TRUSTED_SCHEMA, false)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_ENABLE_TRIGGER, false)?;
+    conn.pragma_update(None, "trusted_schema", "OFF")?;
+    Ok(conn)
+}
+
+fn check_tables(path: &Path) -> Result<(), StatisticsError> {
+    let invalid = |e: rusqlite::Error| StatisticsError::Invalid(e.to_string());
+
+    let conn = open(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut statement = conn
+        .prepare("SELECT type, name FROM sqlite_master")
+        .map_err(invalid)?;
+    let objects = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(invalid)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid)?;
+
+    if let Some((kind, name)) = objects
+        .iter()
+        .find(|(kind, _)| FORBIDDEN_OBJECTS.contains(&kind.as_str()))
+    {
+        return Err(StatisticsError::Invalid(format!(
+            "unexpected {kind} '{name}'"
+        )));
+    }
+
+    let tables: Vec<_> = objects
+        .iter()
+        .filter(|(kind, _)| kind == "table")
+        .map(|(_, name)| name)
+        .collect();
+    match REQUIRED_TABLES
+        .iter()
+        .find(|table| !tables.iter().any(|t| t == *table))
+    {
+        Some(missing) => Err(StatisticsError::Invalid(format!(
+            "missing table '{missing}'"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// A database file in the temporary directory, removed (with its journals) on drop.
+struct TempDb(PathBuf);
+
+impl TempDb {
+    fn write(data: &[u8]) -> Result<Self, std::io::Error> {
+        let path = std::env::temp_dir().join(format!(
+            "korrosync-statistics-{}.sqlite3",
+            uuid::Uuid::new_v4().simple()
+        ));
+        fs::write(&path, data)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-journal", "-wal", "-shm"] {
+            let mut path = self.0.clone().into_os_string();
+            path.push(suffix);
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+        CREATE TABLE book (
+            id integer PRIMARY KEY autoincrement, title text, authors text, notes integer,
+            last_open integer, highlights integer, pages integer, series text, language text,
+            md5 text, total_read_time integer, total_read_pages integer
+        );
+        CREATE UNIQUE INDEX book_title_author_md5 ON book(title, authors, md5);
+        CREATE TABLE page_stat_data (
+            id_book integer, page integer NOT NULL DEFAULT 0,
+            start_time integer NOT NULL DEFAULT 0, duration integer NOT NULL DEFAULT 0,
+            total_pages integer NOT NULL DEFAULT 0,
+            UNIQUE (id_book, page, start_time),
+            FOREIGN KEY(id_book) REFERENCES book(id)
+        );
+    "#;
+
+    /// Builds a statistics database with the given books and (book index, page, start, duration) rows.
+    fn statistics_db(books: &[&str], pages: &[(usize, i64, i64, i64)]) -> Vec<u8> {
+        let file = TempDb::write(&[]).expect("Failed to create temp file");
+        {
+            let conn = Connection::open(file.path()).expect("Failed to open database");
+            conn.execute_batch(SCHEMA).expect("Failed to create schema");
+            let mut ids = Vec::new();
+            for title in books {
+                conn.execute(
+                    "INSERT INTO book (title, authors, md5, last_open) VALUES (?1, 'Author', ?1, 1)",
+                    [title],
+                )
+                .expect("Failed to insert book");
+                ids.push(conn.last_insert_rowid());
+            }
+            for (book, page, start_time, duration) in pages {
+                conn.execute(
+                    "INSERT INTO page_stat_data (id_book, page, start_time, duration, total_pages) \
+                     VALUES (?1, ?2, ?3, ?4, 100)",
+                    [ids[*book], *page, *start_time, *duration],
+                )
+                .expect("Failed to insert page stat");
+            }
+        }
+        fs::read(file.path()).expect("Failed to read database")
+    }
+
+    fn upload(data: &[u8]) -> Upload {
+        let upload = Upload::new().expect("Failed to create upload");
+        fs::write(upload.path(), data).expect("Failed to write upload");
+        upload
+    }
+
+    fn query(data: &[u8], sql: &str) -> Vec<(String, i64, i64)> {
+        let file = TempDb::write(data).expect("Failed to create temp file");
+        let conn = Connection::open(file.path()).expect("Failed to open database");
+        let mut statement = conn.prepare(sql).expect("Failed to prepare query");
+        statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .expect("Failed to run query")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read rows")
+    }
+
+    #[test]
+    fn test_validate_rejects_other_files() {
+        assert!(upload(&statistics_db(&["Dune"], &[])).validate().is_ok());
+        assert!(matches!(
+            upload(b"not a database").validate(),
+            Err(StatisticsError::Invalid(_))
+        ));
+
+        let file = TempDb::write(&[]).unwrap();
+        Connection::open(file.path())
+            .unwrap()
+            .execute_batch("CREATE TABLE book (id integer)")
+            .unwrap();
+        let data = fs::read(file.path()).unwrap();
+        assert!(matches!(
+            upload(&data).validate(),
+            Err(StatisticsError::Invalid(message)) if message.contains("page_stat_data")
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_triggers_and_views() {
+        for object in [
+            "CREATE TRIGGER wipe AFTER INSERT ON book BEGIN DELETE FROM page_stat_data; END",
+            "CREATE VIEW books AS SELECT title FROM book",
+        ] {
+            let file = TempDb::write(&statistics_db(&["Dune"], &[])).unwrap();
+            Connection::open(file.path())
+                .unwrap()
+                .execute_batch(object)
+                .unwrap();
+            let data = fs::read(file.path()).unwrap();
+
+            assert!(matches!(
+                upload(&data).validate(),
+                Err(StatisticsError::Invalid(message)) if message.contains("unexpected")
+            ));
+            let canonical = statistics_db(&["Emma"], &[]);
+            assert!(matches!(
+                upload(&data).merge_into(&canonical),
+                Err(StatisticsError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_merge_combines_books_and_page_stats() {
+        // Book ids differ between devices: "Emma" is 1 on one and 2 on the other
+        let canonical = statistics_db(&["Dune", "Emma"], &[(0, 1, 100, 30), (1, 1, 200, 10)]);
+        let incoming = statistics_db(
+            &["Emma", "Ulysses"],
+            &[(0, 1, 200, 25), (0, 2, 300, 40), (1, 1, 400, 60)],
+        );
+
+        let merged = upload(&incoming)
+            .merge_into(&canonical)
+            .expect("Failed to merge");
+
+        let books = query(
+            &merged,
+            "SELECT title, total_read_pages, total_read_time FROM book ORDER BY title",
+        );
+        assert_eq!(
+            books,
+            vec![
+                ("Dune".to_string(), 1, 30),
+                ("Emma".to_string(), 2, 65),
+                ("Ulysses".to_string(), 1, 60),
+            ]
+        );
+
+        let pages = query(
+            &merged,
+            "SELECT b.title, p.page, p.duration FROM page_stat_data p \
+             JOIN book b ON b.id = p.id_book ORDER BY b.title, p.page",
+        );
+        assert_eq!(pages.len(), 4);
+        assert!(pages.contains(&("Emma".to_string(), 1, 25)));
+    }
+
+    #[test]
+    fn test_merge_is_idempotent() {
+        let canonical = statistics_db(&["Dune"], &[(0, 1, 100, 30)]);
+        let incoming = statistics_db(&["Dune"], &[(0, 2, 200, 20)]);
+
+        let once = upload(&incoming)
+            .merge_into(&canonical)
+            .expect("Failed to merge");
+        let twice = upload(&incoming)
+            .merge_into(&once)
+            .expect("Failed to merge");
+
+        let sql = "SELECT title, total_read_pages, total_read_time FROM book";
+        assert_eq!(query(&once, sql), query(&twice, sql));
+        assert_eq!(query(&twice, sql), vec![("Dune".to_string(), 2, 50)]);
+    }
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use axum::{Router, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn send(app: &Router, request: axum::http::Request<axum::body::Body>) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body).expect("Invalid JSON response")
+    };
+
+    (status, body)
+}
+
+async fn put_progress(app: &Router, document: &str, percentage: f32) {
+    let request_body = json!({
+        "device_id": "device123",
+        "device": "Kobo",
+        "document": document,
+        "percentage": percentage,
+        "progress": "/body/DocFragment[3]"
+    })
+    .to_string();
+
+    let (status, body) = send(
+        app,
+        AuthenticatedRequestBuilder::put("/syncs/progress")
+            .json_body(&request_body)
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["document"], document);
+}
+
+#[tokio::test]
+async fn progress_follows_linked_documents() {
+    let app = spawn_app();
+    put_progress(&app, "digest-v1", 0.4).await;
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::put("/aliases/digest-v2")
+            .json_body(&json!({"document": "digest-v1"}).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body, json!({"alias": "digest-v2", "document": "digest-v1"}));
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/progress/digest-v2").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["percentage"], 0.4);
+
+    put_progress(&app, "digest-v2", 0.5).await;
+
+    let (_, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/progress/digest-v1").build(),
+    )
+    .await;
+    assert_eq!(body["percentage"], 0.5);
+
+    let (status, body) = send(&app, AuthenticatedRequestBuilder::get("/aliases").build()).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(
+        body,
+        json!([{"alias": "digest-v2", "document": "digest-v1"}])
+    );
+}
+
+#[tokio::test]
+async fn unlink_document_alias() {
+    let app = spawn_app();
+
+    send(
+        &app,
+        AuthenticatedRequestBuilder::put("/aliases/digest-v2")
+            .json_body(&json!({"document": "digest-v1"}).to_string())
+            .build(),
+    )
+    .await;
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete("/aliases/digest-v2").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete("/aliases/digest-v2").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
+
+#[tokio::test]
+async fn document_cannot_alias_itself() {
+    let app = spawn_app();
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::put("/aliases/digest-v1")
+            .json_body(&json!({"document": "digest-v1"}).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["code"], "invalid_input");
+}
+
+#[tokio::test]
+async fn oversized_alias_and_document_are_rejected() {
+    let app = spawn_app();
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::put(&format!("/aliases/{}", "a".repeat(513)))
+            .json_body(&json!({"document": "d".repeat(513)}).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "alias");
+    assert_eq!(body["errors"][1]["field"], "document");
+}
@@ -0,0 +1,261 @@
+mod common;
+
+use axum::{Router, body::Body, http::Request, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+const XPOINTER: &str = "/body/DocFragment[3]/body/p[1]/text()";
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, String) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    (
+        status,
+        String::from_utf8(body.to_vec()).expect("Invalid UTF-8"),
+    )
+}
+
+async fn merge(app: &Router, annotations: Value) -> (StatusCode, Value) {
+    let (status, body) = send(
+        app,
+        AuthenticatedRequestBuilder::post("/annotations/test.epub")
+            .json_body(&json!({ "annotations": annotations }).to_string())
+            .build(),
+    )
+    .await;
+    (
+        status,
+        serde_json::from_str(&body).expect("Invalid JSON response"),
+    )
+}
+
+async fn list(app: &Router) -> Value {
+    let (status, body) = send(
+        app,
+        AuthenticatedRequestBuilder::get("/annotations/test.epub").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    serde_json::from_str(&body).expect("Invalid JSON response")
+}
+
+fn highlight(id: &str, note: &str, updated_at: u64) -> Value {
+    json!({
+        "id": id,
+        "text": "It was a pleasure to burn.",
+        "note": note,
+        "pos0": format!("{XPOINTER}.0"),
+        "pos1": format!("{XPOINTER}.26"),
+        "chapter": "The Hearth and the Salamander",
+        "color": "yellow",
+        "created_at": 1_700_000_000_000u64,
+        "updated_at": updated_at,
+    })
+}
+
+#[tokio::test]
+async fn annotations_from_several_devices_are_merged() {
+    let app = spawn_app();
+
+    let (status, body) = merge(&app, json!([highlight("a1", "first", 1_700_000_001_000)])).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["document"], "test.epub");
+    assert_eq!(body["annotations"].as_array().unwrap().len(), 1);
+
+    // Another device edits the note and adds a highlight
+    let (_, body) = merge(
+        &app,
+        json!([
+            highlight("a1", "edited", 1_700_000_002_000),
+            highlight("a2", "second", 1_700_000_002_000)
+        ]),
+    )
+    .await;
+    assert_eq!(body["annotations"].as_array().unwrap().len(), 2);
+
+    // A stale copy of the first device does not overwrite the edit
+    let (_, body) = merge(&app, json!([highlight("a1", "first", 1_700_000_001_000)])).await;
+    assert_eq!(body["annotations"][0]["id"], "a1");
+    assert_eq!(body["annotations"][0]["note"], "edited");
+    assert_eq!(body["annotations"][0]["color"], "yellow");
+    assert_eq!(body["annotations"][0]["deleted"], false);
+
+    assert_eq!(list(&app).await["annotations"], body["annotations"]);
+}
+
+#[tokio::test]
+async fn deleted_annotations_are_kept_as_tombstones() {
+    let app = spawn_app();
+    merge(&app, json!([highlight("a1", "note", 1_700_000_001_000)])).await;
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete("/annotations/test.epub/a1").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let body = list(&app).await;
+    assert_eq!(body["annotations"][0]["deleted"], true);
+    assert!(body["annotations"][0].get("text").is_none());
+
+    // A device that missed the deletion can't resurrect the annotation
+    let (_, body) = merge(&app, json!([highlight("a1", "note", 1_700_000_001_000)])).await;
+    assert_eq!(body["annotations"][0]["deleted"], true);
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete("/annotations/test.epub/a1").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
+
+#[tokio::test]
+async fn timestamps_ahead_of_the_server_are_capped() {
+    let app = spawn_app();
+
+    let (_, body) = merge(&app, json!([highlight("a1", "note", u64::MAX)])).await;
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+    let updated_at = body["annotations"][0]["updated_at"].as_u64().unwrap();
+    assert!(
+        updated_at <= now,
+        "Timestamp should be capped to the server clock"
+    );
+}
+
+#[tokio::test]
+async fn invalid_annotations_are_rejected() {
+    let app = spawn_app();
+
+    let (status, body) = merge(
+        &app,
+        json!([{ "id": "", "pos0": "", "pos1": "x", "updated_at": 1 }]),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["field"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(fields, vec!["annotations[0].id", "annotations[0].pos0"]);
+
+    // Tombstones only need an id
+    let (status, _) = merge(
+        &app,
+        json!([{ "id": "a1", "updated_at": 1, "deleted": true }]),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[tokio::test]
+async fn oversized_paths_and_payloads_are_rejected() {
+    let app = spawn_app();
+
+    let doc = "x".repeat(513);
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get(&format!("/annotations/{doc}")).build(),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["errors"][0]["field"], "doc");
+
+    let id = "x".repeat(1025);
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete(&format!("/annotations/test.epub/{id}")).build(),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+
+    let annotations: Vec<_> = (0..1001)
+        .map(|i| highlight(&format!("a{i}"), "", 1_700_000_001_000))
+        .collect();
+    let (status, body) = merge(&app, json!(annotations)).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "annotations");
+}
+
+#[tokio::test]
+async fn annotations_follow_document_aliases() {
+    let app = spawn_app();
+    merge(&app, json!([highlight("a1", "note", 1_700_000_001_000)])).await;
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::put("/aliases/test.epub")
+            .json_body(&json!({ "document": "canonical.epub" }).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+
+    let (_, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/annotations/canonical.epub").build(),
+    )
+    .await;
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["annotations"][0]["id"], "a1");
+}
+
+#[tokio::test]
+async fn annotations_export_as_markdown_and_json() {
+    let app = spawn_app();
+    merge(
+        &app,
+        json!([
+            highlight("a1", "Opening line", 1_700_000_001_000),
+            { "id": "a2", "pos0": "x", "pos1": "y", "updated_at": 1, "deleted": true }
+        ]),
+    )
+    .await;
+    send(
+        &app,
+        AuthenticatedRequestBuilder::put("/documents/test.epub")
+            .json_body(&json!({ "title": "Fahrenheit 451", "author": "Ray Bradbury" }).to_string())
+            .build(),
+    )
+    .await;
+
+    let (status, markdown) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/annotations/test.epub/export/markdown").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(markdown.starts_with("# Fahrenheit 451\n\n*Ray Bradbury*\n"));
+    assert!(markdown.contains("## The Hearth and the Salamander\n"));
+    assert!(markdown.contains("> It was a pleasure to burn.\n\nOpening line\n"));
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/annotations/test.epub/export/json").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["title"], "Fahrenheit 451");
+    assert_eq!(body["annotations"].as_array().unwrap().len(), 1);
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/annotations/test.epub/export/pdf").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{AuthenticatedRequestBuilder, UnauthenticatedRequestBuilder, spawn_app_with_service};
+use korrosync::model::AuditKind;
+use korrosync::service::db::KorrosyncService;
+use serde_json::json;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn auth_failures_are_audited() {
+    let (app, sync) = spawn_app_with_service();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::get("/users/auth")
+                .credentials("test", "wrong")
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::get("/users/auth")
+                .credentials("ghost", "test")
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let events = sync.list_events(None, None).expect("Failed to list events");
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.kind == AuditKind::AuthFailure));
+    assert_eq!(events[0].username, "test");
+    assert_eq!(events[0].detail, "invalid password");
+    assert_eq!(events[1].username, "ghost");
+    assert_eq!(events[1].detail, "unknown user");
+}
+
+#[tokio::test]
+async fn logins_are_audited_once_per_session() {
+    let (app, sync) = spawn_app_with_service();
+
+    for _ in 0..3 {
+        let response = app
+            .clone()
+            .oneshot(AuthenticatedRequestBuilder::get("/users/auth").build())
+            .await
+            .expect("Failed to send request");
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    let events = sync.list_events(None, None).expect("Failed to list events");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, AuditKind::Login);
+    assert_eq!(events[0].username, "test");
+}
+
+#[tokio::test]
+async fn unknown_user_failures_are_audited_once_per_window() {
+    let (app, sync) = spawn_app_with_service();
+
+    for username in ["ghost", "phantom", "ghost"] {
+        let response = app
+            .clone()
+            .oneshot(
+                AuthenticatedRequestBuilder::get("/users/auth")
+                    .credentials(username, "test")
+                    .build(),
+            )
+            .await
+            .expect("Failed to send request");
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+
+    let events = sync.list_events(None, None).expect("Failed to list events");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].username, "ghost");
+    assert_eq!(events[0].detail, "unknown user");
+}
+
+#[tokio::test]
+async fn account_lifecycle_is_audited() {
+    let (app, sync) = spawn_app_with_service();
+
+    let response = app
+        .clone()
+        .oneshot(
+            UnauthenticatedRequestBuilder::post("/users/create")
+                .json_body(&json!({"username": "alice", "password": "secret"}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::CREATED, response.status());
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/users/password")
+                .credentials("alice", "secret")
+                .json_body(
+                    &json!({"current_password": "secret", "new_password": "other"}).to_string(),
+                )
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::delete("/users/me")
+                .credentials("alice", "other")
+                .json_body(&json!({"confirm": "alice"}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+    let kinds: Vec<AuditKind> = sync
+        .list_events(Some("alice".into()), None)
+        .expect("Failed to list events")
+        .iter()
+        .map(|e| e.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            AuditKind::Registration,
+            AuditKind::Login,
+            AuditKind::PasswordChange,
+            AuditKind::UserDeleted
+        ]
+    );
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use axum::{Router, body::Body, http::Request, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body).expect("Invalid JSON response")
+    };
+    (status, body)
+}
+
+async fn put_bookmark(app: &Router, id: &str, body: Value) -> (StatusCode, Value) {
+    send(
+        app,
+        AuthenticatedRequestBuilder::put(&format!("/syncs/bookmarks/test.epub/{id}"))
+            .json_body(&body.to_string())
+            .build(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn create_get_and_list_bookmarks() {
+    let app = spawn_app();
+
+    let (status, created) = send(
+        &app,
+        AuthenticatedRequestBuilder::post("/syncs/bookmarks/test.epub")
+            .json_body(
+                &json!({ "label": "Chapter 3", "position": "/body/DocFragment[7].0" }).to_string(),
+            )
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::CREATED, status);
+    assert_eq!(created["label"], "Chapter 3");
+    assert_eq!(created["deleted"], false);
+    let id = created["id"].as_str().expect("Expected an id");
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get(&format!("/syncs/bookmarks/test.epub/{id}")).build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body, created);
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/bookmarks/test.epub").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["document"], "test.epub");
+    assert_eq!(body["bookmarks"], json!([created]));
+
+    // Progress is untouched by bookmarks
+    let (_, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/progress/test.epub").build(),
+    )
+    .await;
+    assert_eq!(body, json!({}));
+}
+
+#[tokio::test]
+async fn most_recent_bookmark_change_wins() {
+    let app = spawn_app();
+
+    let (status, body) = put_bookmark(
+        &app,
+        "b1",
+        json!({ "label": "new", "position": "12", "timestamp": 2_000 }),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["created_at"], 2_000);
+
+    let (_, body) = put_bookmark(
+        &app,
+        "b1",
+        json!({ "label": "stale", "position": "10", "timestamp": 1_000 }),
+    )
+    .await;
+    assert_eq!(body["label"], "new");
+    assert_eq!(body["position"], "12");
+    assert_eq!(body["updated_at"], 2_000);
+}
+
+#[tokio::test]
+async fn deleted_bookmarks_are_kept_as_tombstones() {
+    let app = spawn_app();
+    put_bookmark(
+        &app,
+        "b1",
+        json!({ "label": "x", "position": "12", "timestamp": 1_000 }),
+    )
+    .await;
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete("/syncs/bookmarks/test.epub/b1").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let (_, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/bookmarks/test.epub").build(),
+    )
+    .await;
+    assert_eq!(body["bookmarks"][0]["id"], "b1");
+    assert_eq!(body["bookmarks"][0]["deleted"], true);
+
+    // A device that missed the deletion can't resurrect it with an older change
+    let (_, body) = put_bookmark(
+        &app,
+        "b1",
+        json!({ "label": "x", "position": "12", "timestamp": 1_000 }),
+    )
+    .await;
+    assert_eq!(body["deleted"], true);
+
+    for request in [
+        AuthenticatedRequestBuilder::get("/syncs/bookmarks/test.epub/b1").build(),
+        AuthenticatedRequestBuilder::delete("/syncs/bookmarks/test.epub/b1").build(),
+    ] {
+        let (status, _) = send(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, status);
+    }
+}
+
+#[tokio::test]
+async fn invalid_bookmarks_are_rejected() {
+    let app = spawn_app();
+
+    let (status, body) = put_bookmark(&app, "b1", json!({ "label": "x", "position": " " })).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "position");
+
+    let id = "x".repeat(1025);
+    let (status, body) = put_bookmark(&app, &id, json!({ "position": "1" })).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "id");
+
+    let doc = "x".repeat(513);
+    for request in [
+        AuthenticatedRequestBuilder::get(&format!("/syncs/bookmarks/{doc}")),
+        AuthenticatedRequestBuilder::get(&format!("/syncs/bookmarks/{doc}/b1")),
+        AuthenticatedRequestBuilder::delete(&format!("/syncs/bookmarks/{doc}/b1")),
+    ] {
+        let (status, body) = send(&app, request.build()).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+        assert_eq!(body["errors"][0]["field"], "doc");
+    }
+}
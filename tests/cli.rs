@@ -62,3 +62,420 @@ async fn assert_server() {
         Ok(response) => assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND),
     };
 }
+
+#[test]
+fn cli_user_create_stores_koreader_key() {
+    let path = NamedTempFile::new().expect("Creating temp file");
+    let db_path = path.path().to_string_lossy().to_string();
+
+    let run = |args: &[&str]| {
+        cargo_bin_cmd!("korrosync")
+            .arg("--db-path")
+            .arg(&db_path)
+            .args(args)
+            .assert()
+    };
+
+    run(&["user", "create", "-u", "alice", "-p", "secret"]).success();
+
+    run(&["user", "verify", "-u", "alice", "-p", "secret"]).success();
+    run(&[
+        "user",
+        "verify",
+        "-u",
+        "alice",
+        "-p",
+        "5ebe2294ecd0e0f08eab7690d2a6ee69",
+        "--raw-key",
+    ])
+    .success();
+    run(&["user", "verify", "-u", "alice", "-p", "secret", "--raw-key"]).failure();
+    run(&["user", "verify", "-u", "alice", "-p", "wrong"]).failure();
+    run(&["user", "verify", "-u", "bob", "-p", "secret"]).failure();
+
+    run(&[
+        "user",
+        "reset-password",
+        "-u",
+        "alice",
+        "-p",
+        "plain",
+        "--raw-key",
+    ])
+    .success();
+    run(&["user", "verify", "-u", "alice", "-p", "plain", "--raw-key"]).success();
+}
+
+#[test]
+fn cli_progress_list_shows_document_metadata() {
+    use korrosync::model::Progress;
+    use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+
+    let path = NamedTempFile::new().expect("Creating temp file");
+    let db_path = path.path().to_string_lossy().to_string();
+
+    {
+        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+        service
+            .update_progress(
+                "alice".into(),
+                "0b2f7c".into(),
+                Progress {
+                    device_id: "kobo-1".to_string(),
+                    device: "Kobo".to_string(),
+                    percentage: 0.42,
+                    progress: "/body/DocFragment[3]".to_string(),
+                    timestamp: 1704067200000,
+                    client_timestamp: None,
+                },
+            )
+            .expect("Failed to update progress");
+    }
+
+    let run = |args: &[&str]| {
+        cargo_bin_cmd!("korrosync")
+            .arg("--db-path")
+            .arg(&db_path)
+            .args(args)
+            .assert()
+    };
+
+    run(&[
+        "document",
+        "set",
+        "-d",
+        "0b2f7c",
+        "-t",
+        "Dune",
+        "-a",
+        "Frank Herbert",
+    ])
+    .success();
+
+    let output = run(&["progress", "list", "-u", "alice"]).success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(stdout.contains("Dune"), "Missing title in:\n{stdout}");
+    assert!(
+        stdout.contains("Frank Herbert"),
+        "Missing author in:\n{stdout}"
+    );
+    assert!(stdout.contains("42.0%"), "Missing percentage in:\n{stdout}");
+
+    let output = run(&["progress", "delete", "-u", "alice", "-d", "0b2f7c"]).success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("deleted successfully"),
+        "Unexpected output:\n{stdout}"
+    );
+    let output = run(&["progress", "list", "-u", "alice"]).success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("No progress found"),
+        "Unexpected output:\n{stdout}"
+    );
+}
+
+#[test]
+fn cli_stats_shows_sessions_and_finished_documents() {
+    use korrosync::model::Progress;
+    use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+
+    let path = NamedTempFile::new().expect("Creating temp file");
+    let db_path = path.path().to_string_lossy().to_string();
+
+    {
+        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+        for (minutes, percentage) in [(0, 0.5), (30, 1.0)] {
+            service
+                .update_progress(
+                    "alice".into(),
+                    "0b2f7c".into(),
+                    Progress {
+                        device_id: "kobo-1".to_string(),
+                        device: "Kobo".to_string(),
+                        percentage,
+                        progress: "/body/DocFragment[3]".to_string(),
+                        timestamp: 1704067200000 + minutes * 60_000,
+                        client_timestamp: None,
+                    },
+                )
+                .expect("Failed to update progress");
+        }
+    }
+
+    let output = cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["stats", "-u", "alice"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("2024-01-01 00:00:00 UTC"),
+        "Missing session start in:\n{stdout}"
+    );
+    assert!(stdout.contains("0h 30m"), "Missing duration in:\n{stdout}");
+    assert!(
+        stdout.contains("50.0%"),
+        "Missing points read in:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("2024-01"),
+        "Missing finished month in:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Average pace: 100.0% per hour"),
+        "Missing pace in:\n{stdout}"
+    );
+}
+
+#[test]
+fn cli_goals_set_and_show() {
+    use korrosync::model::{Progress, User};
+    use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+
+    let path = NamedTempFile::new().expect("Creating temp file");
+    let db_path = path.path().to_string_lossy().to_string();
+
+    {
+        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+        service
+            .create_or_update_user(User::new("alice", "secret").expect("Failed to create user"))
+            .expect("Failed to insert user");
+        service
+            .update_progress(
+                "alice".into(),
+                "0b2f7c".into(),
+                Progress {
+                    device_id: "kobo-1".to_string(),
+                    percentage: 0.5,
+                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
+                    ..Default::default()
+                },
+            )
+            .expect("Failed to update progress");
+    }
+
+    cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["goals", "set", "-u", "alice", "-b", "12", "-m", "20"])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["goals", "set", "-u", "bob", "-b", "12"])
+        .assert()
+        .failure();
+
+    let output = cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["goals", "show", "-u", "alice"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("Reading streak: 1 day(s) (longest: 1)"),
+        "Missing streak in:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Today: 0/20 minutes"),
+        "Missing daily goal in:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("This year: 0/12 books"),
+        "Missing yearly goal in:\n{stdout}"
+    );
+}
+
+#[test]
+fn cli_shelf_set_and_list() {
+    use korrosync::model::{Progress, User};
+    use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb, SyncContext};
+
+    let path = NamedTempFile::new().expect("Creating temp file");
+    let db_path = path.path().to_string_lossy().to_string();
+
+    {
+        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+        service
+            .create_or_update_user(User::new("alice", "secret").expect("Failed to create user"))
+            .expect("Failed to insert user");
+        service
+            .sync_progress(
+                "alice".into(),
+                vec![(
+                    "0b2f7c".into(),
+                    Progress {
+                        device_id: "kobo-1".to_string(),
+                        device: "Kobo".to_string(),
+                        percentage: 0.5,
+                        progress: "/body/DocFragment[3]".to_string(),
+                        timestamp: 1000,
+                        client_timestamp: None,
+                    },
+                )],
+                &SyncContext {
+                    finished_threshold: Some(0.99),
+                    ..Default::default()
+                },
+                None,
+            )
+            .expect("Failed to sync progress");
+    }
+
+    cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args([
+            "shelf", "set", "-u", "alice", "-d", "9c1e4a", "-s", "finished",
+        ])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args([
+            "shelf", "set", "-u", "alice", "-d", "9c1e4a", "-s", "wishlist",
+        ])
+        .assert()
+        .failure();
+
+    let output = cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["shelf", "list", "-u", "alice", "-s", "finished"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("9c1e4a"),
+        "Missing finished document in:\n{stdout}"
+    );
+    assert!(
+        !stdout.contains("0b2f7c"),
+        "Unexpected document being read in:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Total: 1 document(s)"),
+        "Unexpected total in:\n{stdout}"
+    );
+}
+
+#[test]
+fn cli_db_gc_dry_run_reports_without_removing() {
+    use korrosync::model::{Progress, User};
+    use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+
+    let path = NamedTempFile::new().expect("Creating temp file");
+    let db_path = path.path().to_string_lossy().to_string();
+
+    {
+        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+        let mut user = User::new("alice", "secret").expect("Failed to create user");
+        user.set_last_activity(1_000);
+        service
+            .create_or_update_user(user)
+            .expect("Failed to insert user");
+        service
+            .update_progress(
+                "alice".into(),
+                "0b2f7c".into(),
+                Progress {
+                    device_id: "kobo-1".to_string(),
+                    timestamp: 1_000,
+                    ..Default::default()
+                },
+            )
+            .expect("Failed to update progress");
+    }
+
+    let output = cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["db", "gc", "--dry-run"])
+        .env("KORROSYNC_PROGRESS_RETENTION_DAYS", "30")
+        .env("KORROSYNC_INACTIVE_USER_DAYS", "90")
+        .env("KORROSYNC_INACTIVE_USER_GRACE_DAYS", "0")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("Would remove 1 stale progress entries"),
+        "Unexpected output:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Would remove 1 inactive user(s)"),
+        "Unexpected output:\n{stdout}"
+    );
+
+    let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+    assert!(service.get_user("alice".into()).unwrap().is_some());
+    assert_eq!(service.list_progress("alice".into()).unwrap().len(), 1);
+}
+
+#[test]
+fn cli_user_quota_overrides_and_resets() {
+    use korrosync::model::Quota;
+    use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
+
+    let path = NamedTempFile::new().expect("Creating temp file");
+    let db_path = path.path().to_string_lossy().to_string();
+
+    cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["user", "create", "-u", "alice", "-p", "secret"])
+        .assert()
+        .success();
+
+    let output = cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["user", "quota", "-u", "alice", "--max-documents", "50"])
+        .env("KORROSYNC_QUOTA_MAX_BYTES", "4096")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("Documents: 0/50"),
+        "Unexpected output:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Bytes: 0/4096"),
+        "Unexpected output:\n{stdout}"
+    );
+
+    {
+        let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+        assert_eq!(
+            service.get_quota("alice".into()).unwrap(),
+            Some(Quota {
+                max_documents: Some(50),
+                max_bytes: Some(4096),
+            })
+        );
+    }
+
+    let output = cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["user", "list"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(stdout.contains("0/50"), "Unexpected output:\n{stdout}");
+
+    cargo_bin_cmd!("korrosync")
+        .arg("--db-path")
+        .arg(&db_path)
+        .args(["user", "quota", "-u", "alice", "--reset"])
+        .assert()
+        .success();
+    let service = KorrosyncServiceRedb::new(&db_path).expect("Failed to open database");
+    assert!(service.get_quota("alice".into()).unwrap().is_none());
+}
@@ -6,9 +6,22 @@ use axum::Router;
 use axum::body::Body;
 use axum::http::{Method, Request};
 use korrosync::api::{router::app, state::AppState};
+use korrosync::config::{Quotas, Shelves, Validation};
 use korrosync::model::User;
 use korrosync::service::db::{KorrosyncService, KorrosyncServiceRedb};
 use tempfile::NamedTempFile;
+use tokio_util::sync::CancellationToken;
+
+/// Builds the application state around a service, using the default validation limits
+pub(crate) fn test_state(sync: Arc<KorrosyncServiceRedb>) -> AppState {
+    AppState {
+        sync,
+        validation: Validation::default(),
+        shelves: Shelves::default(),
+        quotas: Quotas::default(),
+        shutdown: CancellationToken::new(),
+    }
+}
 
 /// Creates a test application with a single test user (username: "test", password: "test")
 pub(crate) fn spawn_app() -> Router {
@@ -19,7 +32,20 @@ pub(crate) fn spawn_app() -> Router {
     sync.create_or_update_user(User::new("test", "test").expect("Error instantiating test user"))
         .expect("Error inserting user");
 
-    app(AppState { sync })
+    app(test_state(sync))
+}
+
+/// Creates a test application with a single test user (username: "test", password: "test"),
+/// also returning the underlying service so tests can inspect stored data
+pub(crate) fn spawn_app_with_service() -> (Router, Arc<KorrosyncServiceRedb>) {
+    let db_path = NamedTempFile::new().expect("Creating temp file");
+    let sync =
+        Arc::new(KorrosyncServiceRedb::new(db_path).expect("Failed to create KorrosyncService"));
+
+    sync.create_or_update_user(User::new("test", "test").expect("Error instantiating test user"))
+        .expect("Error inserting user");
+
+    (app(test_state(sync.clone())), sync)
 }
 
 /// Creates a test application with multiple users
@@ -35,7 +61,40 @@ pub(crate) fn spawn_app_with_users(users: Vec<(&str, &str)>) -> Router {
         .expect("Error inserting user");
     }
 
-    app(AppState { sync })
+    app(test_state(sync))
+}
+
+/// Creates a test application with a single test user (username: "test", password: "test")
+/// and custom validation limits
+pub(crate) fn spawn_app_with_validation(validation: Validation) -> Router {
+    let db_path = NamedTempFile::new().expect("Creating temp file");
+    let sync =
+        Arc::new(KorrosyncServiceRedb::new(db_path).expect("Failed to create KorrosyncService"));
+
+    sync.create_or_update_user(User::new("test", "test").expect("Error instantiating test user"))
+        .expect("Error inserting user");
+
+    app(AppState {
+        validation,
+        ..test_state(sync)
+    })
+}
+
+/// Creates a test application with a single test user (username: "test", password: "test")
+/// and a default storage quota, also returning the underlying service
+pub(crate) fn spawn_app_with_quotas(quotas: Quotas) -> (Router, Arc<KorrosyncServiceRedb>) {
+    let db_path = NamedTempFile::new().expect("Creating temp file");
+    let sync =
+        Arc::new(KorrosyncServiceRedb::new(db_path).expect("Failed to create KorrosyncService"));
+
+    sync.create_or_update_user(User::new("test", "test").expect("Error instantiating test user"))
+        .expect("Error inserting user");
+
+    let state = AppState {
+        quotas,
+        ..test_state(sync.clone())
+    };
+    (app(state), sync)
 }
 
 /// Creates a test application without any users
@@ -44,7 +103,7 @@ pub(crate) fn spawn_app_empty() -> Router {
     let sync =
         Arc::new(KorrosyncServiceRedb::new(db_path).expect("Failed to create KorrosyncService"));
 
-    app(AppState { sync })
+    app(test_state(sync))
 }
 
 /// Helper to create a User instance for testing
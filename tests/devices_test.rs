@@ -0,0 +1,90 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app};
+use serde_json::json;
+use tower::ServiceExt;
+
+async fn put_progress(app: &axum::Router, device_id: &str, device: &str, document: &str) {
+    let request_body = json!({
+        "device_id": device_id,
+        "device": device,
+        "document": document,
+        "percentage": 0.5,
+        "progress": "Page 10"
+    })
+    .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/syncs/progress")
+                .json_body(&request_body)
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+async fn get_devices(app: &axum::Router) -> serde_json::Value {
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::get("/devices").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    serde_json::from_slice(&body).expect("Invalid JSON response")
+}
+
+#[tokio::test]
+async fn devices_empty_without_progress() {
+    let app = spawn_app();
+
+    assert_eq!(get_devices(&app).await, json!([]));
+}
+
+#[tokio::test]
+async fn devices_are_registered_from_progress_updates() {
+    let app = spawn_app();
+
+    put_progress(&app, "kindle-1", "Kindle", "a.epub").await;
+    put_progress(&app, "kobo-1", "Kobo", "b.epub").await;
+    put_progress(&app, "kindle-1", "Kindle", "c.epub").await;
+
+    let devices = get_devices(&app).await;
+    let devices = devices.as_array().expect("Expected an array");
+    assert_eq!(devices.len(), 2);
+
+    let kindle = &devices[0];
+    assert_eq!(kindle["device_id"], "kindle-1");
+    assert_eq!(kindle["name"], "Kindle");
+    assert_eq!(kindle["last_document"], "c.epub");
+    assert!(kindle["first_seen"].as_u64() <= kindle["last_seen"].as_u64());
+
+    assert_eq!(devices[1]["device_id"], "kobo-1");
+    assert_eq!(devices[1]["last_document"], "b.epub");
+}
+
+#[tokio::test]
+async fn devices_fails_with_invalid_http_methods() {
+    let app = spawn_app();
+
+    for method in [Method::POST, Method::PUT, Method::DELETE, Method::PATCH] {
+        let response = app
+            .clone()
+            .oneshot(AuthenticatedRequestBuilder::new(method.clone(), "/devices").build())
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(
+            StatusCode::METHOD_NOT_ALLOWED,
+            response.status(),
+            "Method {method:?} should return 405"
+        );
+    }
+}
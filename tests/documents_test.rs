@@ -0,0 +1,154 @@
+mod common;
+
+use axum::{Router, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn put_document(app: &Router, document: &str, body: Value) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put(&format!("/documents/{document}"))
+                .json_body(&body.to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    (
+        status,
+        serde_json::from_slice(&body).expect("Invalid JSON response"),
+    )
+}
+
+async fn get_json(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::get(uri).build())
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    (
+        status,
+        serde_json::from_slice(&body).expect("Invalid JSON response"),
+    )
+}
+
+#[tokio::test]
+async fn set_and_get_document_metadata() {
+    let app = spawn_app();
+
+    let (status, body) = put_document(
+        &app,
+        "0b2f7c",
+        json!({
+            "title": "Dune",
+            "author": "Frank Herbert",
+            "series": "",
+            "cover_url": "https://example.com/dune.jpg"
+        }),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["document"], "0b2f7c");
+    assert!(body.get("series").is_none(), "Empty fields are cleared");
+
+    let (status, body) = get_json(&app, "/documents/0b2f7c").await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["title"], "Dune");
+    assert_eq!(body["author"], "Frank Herbert");
+    assert_eq!(body["cover_url"], "https://example.com/dune.jpg");
+    assert!(body["updated_at"].is_u64());
+}
+
+#[tokio::test]
+async fn unknown_document_returns_not_found() {
+    let app = spawn_app();
+
+    let (status, body) = get_json(&app, "/documents/unknown").await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+    assert_eq!(body["code"], "not_found");
+}
+
+#[tokio::test]
+async fn cover_url_must_be_http() {
+    let app = spawn_app();
+
+    let (status, body) = put_document(
+        &app,
+        "0b2f7c",
+        json!({"title": "Dune", "cover_url": "javascript:alert(1)"}),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["code"], "invalid_input");
+}
+
+#[tokio::test]
+async fn oversized_document_and_metadata_are_rejected() {
+    let app = spawn_app();
+
+    let (status, body) = put_document(
+        &app,
+        &"d".repeat(513),
+        json!({"title": "t".repeat(1025), "author": "Frank Herbert"}),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["code"], "invalid_input");
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .expect("Expected an errors array")
+        .iter()
+        .map(|e| e["field"].as_str().expect("Missing field name"))
+        .collect();
+    assert_eq!(fields, vec!["doc", "title"]);
+}
+
+#[tokio::test]
+async fn document_listing_includes_progress_and_metadata() {
+    let app = spawn_app();
+
+    for document in ["0b2f7c", "9a1e44"] {
+        let response = app
+            .clone()
+            .oneshot(
+                AuthenticatedRequestBuilder::put("/syncs/progress")
+                    .json_body(
+                        &json!({
+                            "device_id": "device123",
+                            "device": "Kobo",
+                            "document": document,
+                            "percentage": 0.5,
+                            "progress": "/body/DocFragment[3]"
+                        })
+                        .to_string(),
+                    )
+                    .build(),
+            )
+            .await
+            .expect("Failed to send request");
+        assert_eq!(StatusCode::OK, response.status());
+    }
+    put_document(&app, "0b2f7c", json!({"title": "Dune"})).await;
+
+    let (status, body) = get_json(&app, "/documents").await;
+    assert_eq!(StatusCode::OK, status);
+
+    let items = body.as_array().expect("Expected an array");
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["document"], "0b2f7c");
+    assert_eq!(items[0]["progress"]["percentage"], 0.5);
+    assert_eq!(items[0]["metadata"]["title"], "Dune");
+    assert_eq!(items[1]["document"], "9a1e44");
+    assert!(items[1].get("metadata").is_none());
+}
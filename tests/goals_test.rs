@@ -0,0 +1,120 @@
+mod common;
+
+use axum::{Router, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app, spawn_app_with_validation};
+use korrosync::config::Validation;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+const DAY: u64 = 24 * 60 * 60 * 1000;
+
+async fn send(app: &Router, request: axum::http::Request<axum::body::Body>) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    (
+        status,
+        serde_json::from_slice(&body).expect("Invalid JSON response"),
+    )
+}
+
+async fn put_goals(app: &Router, goals: Value) -> (StatusCode, Value) {
+    send(
+        app,
+        AuthenticatedRequestBuilder::put("/goals")
+            .json_body(&goals.to_string())
+            .build(),
+    )
+    .await
+}
+
+async fn put_progress(app: &Router, percentage: f32, timestamp: u64) {
+    let (status, _) = send(
+        app,
+        AuthenticatedRequestBuilder::put("/syncs/progress")
+            .json_body(
+                &json!({
+                    "device_id": "kobo-1",
+                    "device": "Kobo",
+                    "document": "dune.epub",
+                    "percentage": percentage,
+                    "progress": "/body/DocFragment[1]",
+                    "timestamp": timestamp,
+                })
+                .to_string(),
+            )
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[tokio::test]
+async fn goals_are_empty_by_default() {
+    let app = spawn_app();
+
+    let (status, body) = send(&app, AuthenticatedRequestBuilder::get("/goals").build()).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(
+        body,
+        json!({
+            "streak": { "current": 0, "longest": 0 },
+            "today": { "done": 0 },
+            "year": { "done": 0 },
+        })
+    );
+}
+
+#[tokio::test]
+async fn goals_are_evaluated_against_progress() {
+    let app = spawn_app_with_validation(Validation {
+        max_clock_skew_secs: 4 * 86_400,
+        ..Validation::default()
+    });
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+
+    // Read yesterday and finished the book today
+    put_progress(&app, 0.5, now - DAY).await;
+    put_progress(&app, 1.0, now).await;
+
+    let (status, body) =
+        put_goals(&app, json!({ "books_per_year": 1, "minutes_per_day": 30 })).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["books_per_year"], 1);
+    assert_eq!(body["minutes_per_day"], 30);
+    assert_eq!(body["streak"], json!({ "current": 2, "longest": 2 }));
+    assert_eq!(
+        body["today"],
+        json!({ "done": 0, "target": 30, "completed": false })
+    );
+    assert_eq!(
+        body["year"],
+        json!({ "done": 1, "target": 1, "completed": true })
+    );
+
+    let (_, stored) = send(&app, AuthenticatedRequestBuilder::get("/goals").build()).await;
+    assert_eq!(stored, body);
+
+    // Omitted goals are cleared
+    let (_, body) = put_goals(&app, json!({ "minutes_per_day": 10 })).await;
+    assert!(body.get("books_per_year").is_none());
+    assert_eq!(body["year"], json!({ "done": 1 }));
+}
+
+#[tokio::test]
+async fn invalid_goals_are_rejected() {
+    let app = spawn_app();
+
+    let (status, body) =
+        put_goals(&app, json!({ "books_per_year": 0, "minutes_per_day": 0 })).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"].as_array().unwrap().len(), 2);
+    assert_eq!(body["errors"][0]["field"], "books_per_year");
+}
@@ -0,0 +1,313 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+};
+use common::{AuthenticatedRequestBuilder, spawn_app_with_users};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+fn spawn_club() -> Router {
+    spawn_app_with_users(vec![("alice", "alice"), ("bob", "bob"), ("carol", "carol")])
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body).expect("Invalid JSON response")
+    };
+
+    (status, body)
+}
+
+/// Creates a group owned by alice and returns (id, invite_code)
+async fn create_group(app: &Router) -> (String, String) {
+    let (status, body) = send(
+        app,
+        AuthenticatedRequestBuilder::post("/groups")
+            .credentials("alice", "alice")
+            .json_body(&json!({"name": "Book club"}).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::CREATED, status);
+
+    (
+        body["id"].as_str().expect("Missing id").to_string(),
+        body["invite_code"]
+            .as_str()
+            .expect("Missing invite code")
+            .to_string(),
+    )
+}
+
+async fn join(app: &Router, user: &str, invite_code: &str) -> StatusCode {
+    let (status, _) = send(
+        app,
+        AuthenticatedRequestBuilder::post("/groups/join")
+            .credentials(user, user)
+            .json_body(&json!({"invite_code": invite_code}).to_string())
+            .build(),
+    )
+    .await;
+    status
+}
+
+async fn put_progress(app: &Router, user: &str, document: &str, percentage: f32) {
+    let (status, _) = send(
+        app,
+        AuthenticatedRequestBuilder::put("/syncs/progress")
+            .credentials(user, user)
+            .json_body(
+                &json!({
+                    "device_id": format!("{user}-device"),
+                    "device": "Kobo",
+                    "document": document,
+                    "percentage": percentage,
+                    "progress": "/body/DocFragment[10]"
+                })
+                .to_string(),
+            )
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[tokio::test]
+async fn create_and_join_group() {
+    let app = spawn_club();
+    let (id, invite_code) = create_group(&app).await;
+
+    assert_eq!(StatusCode::OK, join(&app, "bob", &invite_code).await);
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get(&format!("/groups/{id}"))
+            .credentials("bob", "bob")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["owner"], "alice");
+    assert!(
+        body.get("invite_code").is_none(),
+        "Invite code is only shown to the owner"
+    );
+    assert_eq!(body["members"].as_array().map(Vec::len), Some(2));
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get("/groups")
+            .credentials("carol", "carol")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body, json!([]));
+}
+
+#[tokio::test]
+async fn join_with_invalid_invite_code() {
+    let app = spawn_club();
+    create_group(&app).await;
+
+    assert_eq!(StatusCode::NOT_FOUND, join(&app, "bob", "nope").await);
+}
+
+#[tokio::test]
+async fn group_is_hidden_from_non_members() {
+    let app = spawn_club();
+    let (id, _) = create_group(&app).await;
+    put_progress(&app, "alice", "book.epub", 0.5).await;
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::get(&format!("/groups/{id}/progress/book.epub"))
+            .credentials("carol", "carol")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
+
+#[tokio::test]
+async fn group_progress_respects_visibility() {
+    let app = spawn_club();
+    let (id, invite_code) = create_group(&app).await;
+    join(&app, "bob", &invite_code).await;
+    join(&app, "carol", &invite_code).await;
+
+    put_progress(&app, "alice", "book.epub", 0.25).await;
+    put_progress(&app, "bob", "book.epub", 0.5).await;
+    put_progress(&app, "carol", "book.epub", 0.75).await;
+
+    for (user, visibility) in [("bob", "percentage"), ("carol", "hidden")] {
+        let (status, _) = send(
+            &app,
+            AuthenticatedRequestBuilder::put(&format!("/groups/{id}/settings"))
+                .credentials(user, user)
+                .json_body(&json!({"visibility": visibility}).to_string())
+                .build(),
+        )
+        .await;
+        assert_eq!(StatusCode::NO_CONTENT, status);
+    }
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get(&format!("/groups/{id}/progress/book.epub"))
+            .credentials("bob", "bob")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["document"], "book.epub");
+
+    let members = body["members"].as_array().expect("Expected an array");
+    assert_eq!(members.len(), 2, "Hidden members are omitted");
+
+    assert_eq!(members[0]["username"], "alice");
+    assert_eq!(members[0]["percentage"], 0.25);
+    assert_eq!(members[0]["progress"], "/body/DocFragment[10]");
+    assert_eq!(members[0]["device"], "Kobo");
+
+    assert_eq!(members[1]["username"], "bob");
+    assert_eq!(members[1]["percentage"], 0.5);
+    assert!(members[1].get("progress").is_none());
+    assert!(members[1].get("device").is_none());
+}
+
+#[tokio::test]
+async fn invalid_visibility_is_rejected() {
+    let app = spawn_club();
+    let (id, _) = create_group(&app).await;
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::put(&format!("/groups/{id}/settings"))
+            .credentials("alice", "alice")
+            .json_body(&json!({"visibility": "public"}).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["code"], "invalid_input");
+}
+
+#[tokio::test]
+async fn oversized_group_name_is_rejected() {
+    let app = spawn_club();
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::post("/groups")
+            .credentials("alice", "alice")
+            .json_body(&json!({"name": "n".repeat(1025)}).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "name");
+}
+
+#[tokio::test]
+async fn only_owner_can_rotate_invite_and_delete() {
+    let app = spawn_club();
+    let (id, invite_code) = create_group(&app).await;
+    join(&app, "bob", &invite_code).await;
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::post(&format!("/groups/{id}/invite"))
+            .credentials("bob", "bob")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::FORBIDDEN, status);
+    assert_eq!(body["code"], "forbidden");
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete(&format!("/groups/{id}"))
+            .credentials("bob", "bob")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::FORBIDDEN, status);
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::post(&format!("/groups/{id}/invite"))
+            .credentials("alice", "alice")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    let new_code = body["invite_code"].as_str().expect("Missing invite code");
+    assert_ne!(new_code, invite_code);
+
+    assert_eq!(
+        StatusCode::NOT_FOUND,
+        join(&app, "carol", &invite_code).await
+    );
+    assert_eq!(StatusCode::OK, join(&app, "carol", new_code).await);
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::delete(&format!("/groups/{id}"))
+            .credentials("alice", "alice")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::get(&format!("/groups/{id}"))
+            .credentials("bob", "bob")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
+
+#[tokio::test]
+async fn owner_leaving_transfers_ownership() {
+    let app = spawn_club();
+    let (id, invite_code) = create_group(&app).await;
+    join(&app, "bob", &invite_code).await;
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::post(&format!("/groups/{id}/leave"))
+            .credentials("alice", "alice")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::get(&format!("/groups/{id}"))
+            .credentials("bob", "bob")
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["owner"], "bob");
+    assert!(body["invite_code"].is_string());
+}
@@ -1,10 +1,25 @@
 mod common;
 
+use std::sync::Arc;
+
 use axum::body::HttpBody;
 use axum::http::{Method, StatusCode};
+use korrosync::api::router::app;
+use korrosync::service::db::KorrosyncServiceRedb;
+use serde_json::Value;
+use tempfile::NamedTempFile;
 use tower::ServiceExt;
 
-use crate::common::{AuthenticatedRequestBuilder, spawn_app};
+use crate::common::{
+    AuthenticatedRequestBuilder, UnauthenticatedRequestBuilder, spawn_app, test_state,
+};
+
+async fn json_body(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    serde_json::from_slice(&body).expect("Invalid JSON response")
+}
 
 #[tokio::test]
 async fn health_check_works() {
@@ -47,3 +62,73 @@ async fn health_check_fails_with_invalid_verb() {
         assert_eq!(Some(0), response.into_body().size_hint().exact());
     }
 }
+
+#[tokio::test]
+async fn health_check_requires_authentication() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(UnauthenticatedRequestBuilder::get("/healthcheck").build())
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
+
+#[tokio::test]
+async fn liveness_probe_needs_no_credentials() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(UnauthenticatedRequestBuilder::get("/livez").build())
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[tokio::test]
+async fn readiness_probe_reports_storage_latency() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(UnauthenticatedRequestBuilder::get("/readyz").build())
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::OK, response.status());
+    let body = json_body(response).await;
+    assert_eq!("ready", body["status"]);
+    assert!(body["storage_latency_ms"].as_f64().unwrap() >= 0.0);
+}
+
+#[tokio::test]
+async fn readiness_probe_fails_during_shutdown() {
+    let db_path = NamedTempFile::new().expect("Creating temp file");
+    let sync =
+        Arc::new(KorrosyncServiceRedb::new(db_path).expect("Failed to create KorrosyncService"));
+    let state = test_state(sync);
+    let shutdown = state.shutdown.clone();
+    let app = app(state);
+
+    shutdown.cancel();
+
+    let response = app
+        .clone()
+        .oneshot(UnauthenticatedRequestBuilder::get("/readyz").build())
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+    let body = json_body(response).await;
+    assert_eq!("shutting_down", body["status"]);
+    assert!(body.get("storage_latency_ms").is_none());
+
+    // Liveness is unaffected, so the orchestrator doesn't kill the draining server
+    let response = app
+        .oneshot(UnauthenticatedRequestBuilder::get("/livez").build())
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::OK, response.status());
+}
@@ -230,6 +230,38 @@ async fn auth_middleware_applies_to_all_protected_routes() {
         ("/users/auth", Method::GET),
         ("/syncs/progress", Method::PUT),
         ("/syncs/progress/test.epub", Method::GET),
+        ("/syncs/progress/test.epub", Method::DELETE),
+        ("/syncs/progress/batch", Method::POST),
+        ("/syncs/progress/batch", Method::PUT),
+        ("/syncs/progress/test.epub/devices", Method::GET),
+        ("/users/password", Method::PUT),
+        ("/users/me", Method::DELETE),
+        ("/devices", Method::GET),
+        ("/groups", Method::GET),
+        ("/groups", Method::POST),
+        ("/groups/join", Method::POST),
+        ("/groups/some-id/progress/test.epub", Method::GET),
+        ("/shares", Method::GET),
+        ("/shares", Method::POST),
+        ("/shares/some-token", Method::DELETE),
+        ("/stats", Method::GET),
+        ("/goals", Method::GET),
+        ("/goals", Method::PUT),
+        ("/shelves", Method::GET),
+        ("/shelves/test.epub", Method::PUT),
+        ("/aliases", Method::GET),
+        ("/aliases/test.epub", Method::PUT),
+        ("/documents", Method::GET),
+        ("/documents/test.epub", Method::GET),
+        ("/documents/test.epub", Method::PUT),
+        ("/syncs/bookmarks/test.epub", Method::GET),
+        ("/syncs/bookmarks/test.epub", Method::POST),
+        ("/syncs/bookmarks/test.epub/b1", Method::PUT),
+        ("/syncs/bookmarks/test.epub/b1", Method::DELETE),
+        ("/annotations/test.epub", Method::GET),
+        ("/annotations/test.epub", Method::POST),
+        ("/annotations/test.epub/a1", Method::DELETE),
+        ("/annotations/test.epub/export/markdown", Method::GET),
     ];
 
     for (route, method) in protected_routes {
@@ -331,3 +363,129 @@ async fn public_routes_work_even_with_invalid_auth_headers() {
 
     assert_eq!(StatusCode::OK, response.status());
 }
+
+// ==================== KOREADER MIDDLEWARE TESTS ====================
+
+/// Marks a request as sent by the KOReader sync plugin
+fn from_koreader(mut request: Request<Body>) -> Request<Body> {
+    request.headers_mut().insert(
+        "accept",
+        "application/vnd.koreader.v1+json".parse().unwrap(),
+    );
+    request
+}
+
+async fn json_body(response: axum::response::Response) -> serde_json::Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    serde_json::from_slice(&body).expect("Invalid JSON response")
+}
+
+#[tokio::test]
+async fn koreader_middleware_sends_reference_unauthorized_error() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(from_koreader(
+            AuthenticatedRequestBuilder::get("/users/auth")
+                .credentials("test", "wrong")
+                .build(),
+        ))
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    let body = json_body(response).await;
+    assert_eq!(2001, body["code"]);
+    assert!(body["message"].is_string());
+}
+
+#[tokio::test]
+async fn koreader_middleware_sends_reference_existing_user_error() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(from_koreader(
+            UnauthenticatedRequestBuilder::post("/users/create")
+                .json_body(&json!({ "username": "test", "password": "test" }).to_string())
+                .build(),
+        ))
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::PAYMENT_REQUIRED, response.status());
+    assert_eq!(2002, json_body(response).await["code"]);
+}
+
+#[tokio::test]
+async fn koreader_middleware_sends_reference_invalid_request_errors() {
+    let app = spawn_app();
+
+    let response = app
+        .clone()
+        .oneshot(from_koreader(
+            AuthenticatedRequestBuilder::put("/syncs/progress")
+                .json_body(
+                    &json!({
+                        "device_id": "device",
+                        "device": "Kobo",
+                        "document": "",
+                        "percentage": 0.5,
+                        "progress": "/body/p[1]",
+                    })
+                    .to_string(),
+                )
+                .build(),
+        ))
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let body = json_body(response).await;
+    assert_eq!(2004, body["code"]);
+    assert!(body.get("errors").is_none());
+
+    let response = app
+        .oneshot(from_koreader(
+            AuthenticatedRequestBuilder::put("/syncs/progress")
+                .json_body("not json")
+                .build(),
+        ))
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    assert_eq!(2003, json_body(response).await["code"]);
+}
+
+#[tokio::test]
+async fn koreader_middleware_keeps_regular_errors_for_other_clients() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::get("/users/auth")
+                .credentials("test", "wrong")
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    assert_eq!("unauthorized", json_body(response).await["code"]);
+}
+
+#[tokio::test]
+async fn koreader_middleware_keeps_successful_responses() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(from_koreader(
+            AuthenticatedRequestBuilder::get("/users/auth").build(),
+        ))
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::OK, response.status());
+}
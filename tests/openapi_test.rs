@@ -0,0 +1,219 @@
+mod common;
+
+use std::collections::BTreeSet;
+
+use axum::{
+    Router,
+    http::{Method, StatusCode},
+};
+use common::{AuthenticatedRequestBuilder, UnauthenticatedRequestBuilder, spawn_app};
+use serde_json::Value;
+use tower::ServiceExt;
+
+/// Every route of the router described by the OpenAPI document
+const ROUTES: &[(&str, &str)] = &[
+    ("get", "/robots.txt"),
+    ("get", "/share/{token}"),
+    ("post", "/users/create"),
+    ("get", "/livez"),
+    ("get", "/readyz"),
+    ("get", "/users/auth"),
+    ("put", "/users/password"),
+    ("delete", "/users/me"),
+    ("put", "/syncs/progress"),
+    ("get", "/syncs/progress/{doc}"),
+    ("delete", "/syncs/progress/{doc}"),
+    ("post", "/syncs/progress/batch"),
+    ("put", "/syncs/progress/batch"),
+    ("get", "/syncs/progress/{doc}/devices"),
+    ("get", "/syncs/bookmarks/{doc}"),
+    ("post", "/syncs/bookmarks/{doc}"),
+    ("get", "/syncs/bookmarks/{doc}/{id}"),
+    ("put", "/syncs/bookmarks/{doc}/{id}"),
+    ("delete", "/syncs/bookmarks/{doc}/{id}"),
+    ("get", "/devices"),
+    ("get", "/documents"),
+    ("get", "/documents/{doc}"),
+    ("put", "/documents/{doc}"),
+    ("get", "/aliases"),
+    ("put", "/aliases/{alias}"),
+    ("delete", "/aliases/{alias}"),
+    ("get", "/annotations/{doc}"),
+    ("post", "/annotations/{doc}"),
+    ("delete", "/annotations/{doc}/{id}"),
+    ("get", "/annotations/{doc}/export/{format}"),
+    ("post", "/groups"),
+    ("get", "/groups"),
+    ("get", "/groups/{id}"),
+    ("delete", "/groups/{id}"),
+    ("post", "/groups/join"),
+    ("post", "/groups/{id}/invite"),
+    ("put", "/groups/{id}/settings"),
+    ("post", "/groups/{id}/leave"),
+    ("get", "/groups/{id}/progress/{doc}"),
+    ("post", "/shares"),
+    ("get", "/shares"),
+    ("delete", "/shares/{token}"),
+    ("get", "/stats"),
+    ("get", "/goals"),
+    ("put", "/goals"),
+    ("get", "/shelves"),
+    ("put", "/shelves/{doc}"),
+    ("get", "/healthcheck"),
+];
+
+/// Routes reachable without credentials
+const PUBLIC_PATHS: &[&str] = &[
+    "/robots.txt",
+    "/share/{token}",
+    "/users/create",
+    "/livez",
+    "/readyz",
+];
+
+const METHODS: &[&str] = &["get", "put", "post", "delete"];
+
+async fn get_spec(app: &Router) -> Value {
+    let response = app
+        .clone()
+        .oneshot(UnauthenticatedRequestBuilder::get("/openapi.json").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    serde_json::from_slice(&body).expect("Invalid JSON response")
+}
+
+/// Documented operations as (method, path) pairs
+fn operations(spec: &Value) -> BTreeSet<(String, String)> {
+    spec["paths"]
+        .as_object()
+        .expect("Missing paths")
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .expect("Invalid path item")
+                .keys()
+                .filter(|method| METHODS.contains(&method.as_str()))
+                .map(move |method| (method.clone(), path.clone()))
+        })
+        .collect()
+}
+
+/// Sends an authenticated request to a documented path, with placeholders filled in
+async fn send(app: &Router, method: &str, path: &str) -> (StatusCode, usize) {
+    let uri = path.replace("{format}", "json").replace(['{', '}'], "");
+    let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::new(method, &uri).build())
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    (status, body.len())
+}
+
+#[tokio::test]
+async fn spec_documents_every_route() {
+    let app = spawn_app();
+    let spec = get_spec(&app).await;
+
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    let expected: BTreeSet<(String, String)> = ROUTES
+        .iter()
+        .map(|(method, path)| (method.to_string(), path.to_string()))
+        .collect();
+    assert_eq!(expected, operations(&spec));
+}
+
+#[tokio::test]
+async fn every_documented_operation_is_routed() {
+    let app = spawn_app();
+    let spec = get_spec(&app).await;
+
+    for (method, path) in operations(&spec) {
+        let (status, body_len) = send(&app, &method, &path).await;
+        assert_ne!(
+            StatusCode::METHOD_NOT_ALLOWED,
+            status,
+            "{method} {path} is documented but not routed"
+        );
+        // Unmatched routes get an empty 404 from the fallback handler
+        assert!(
+            status != StatusCode::NOT_FOUND || body_len > 0,
+            "{method} {path} is documented but not routed"
+        );
+    }
+}
+
+#[tokio::test]
+async fn documented_paths_have_no_undocumented_methods() {
+    let app = spawn_app();
+    let spec = get_spec(&app).await;
+    let operations = operations(&spec);
+
+    for path in spec["paths"].as_object().unwrap().keys() {
+        for method in METHODS {
+            if operations.contains(&(method.to_string(), path.clone())) {
+                continue;
+            }
+            let (status, _) = send(&app, method, path).await;
+            assert_eq!(
+                StatusCode::METHOD_NOT_ALLOWED,
+                status,
+                "{method} {path} is routed but not documented"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn protected_operations_require_authentication() {
+    let app = spawn_app();
+    let spec = get_spec(&app).await;
+
+    for (method, path) in operations(&spec) {
+        let operation = &spec["paths"][&path][&method];
+        let public = PUBLIC_PATHS.contains(&path.as_str());
+        assert_eq!(
+            public,
+            operation.get("security").is_none(),
+            "Unexpected security of {method} {path}"
+        );
+        assert_eq!(
+            public,
+            operation["responses"].get("401").is_none(),
+            "Unexpected 401 response of {method} {path}"
+        );
+    }
+    let schemes = &spec["components"]["securitySchemes"];
+    assert_eq!(schemes["auth_user"]["name"], "x-auth-user");
+    assert_eq!(schemes["auth_key"]["name"], "x-auth-key");
+}
+
+#[tokio::test]
+async fn spec_describes_the_progress_payloads() {
+    let app = spawn_app();
+    let spec = get_spec(&app).await;
+    let schemas = &spec["components"]["schemas"];
+
+    for schema in [
+        "UpdateProgressRequest",
+        "ProgressResponse",
+        "AuthResponse",
+        "ApiErrorPayload",
+    ] {
+        assert!(schemas.get(schema).is_some(), "Missing schema {schema}");
+    }
+    let required = schemas["UpdateProgressRequest"]["required"]
+        .as_array()
+        .unwrap();
+    assert!(required.contains(&Value::from("document")));
+}
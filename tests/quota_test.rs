@@ -0,0 +1,180 @@
+mod common;
+
+use axum::{Router, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app_with_quotas};
+use korrosync::{config::Quotas, model::Quota, service::db::KorrosyncService};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn send(app: &Router, request: axum::http::Request<axum::body::Body>) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    (
+        status,
+        serde_json::from_slice(&body).expect("Invalid JSON response"),
+    )
+}
+
+fn progress(document: &str, position: &str) -> Value {
+    json!({
+        "device_id": "kobo-1",
+        "device": "Kobo",
+        "document": document,
+        "percentage": 0.5,
+        "progress": position,
+    })
+}
+
+async fn put_progress(app: &Router, document: &str, position: &str) -> (StatusCode, Value) {
+    send(
+        app,
+        AuthenticatedRequestBuilder::put("/syncs/progress")
+            .json_body(&progress(document, position).to_string())
+            .build(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn document_limit_rejects_new_documents() {
+    let (app, _) = spawn_app_with_quotas(Quotas {
+        max_documents: 2,
+        max_bytes: 0,
+    });
+
+    for document in ["a.epub", "b.epub"] {
+        let (status, _) = put_progress(&app, document, "/body/1").await;
+        assert_eq!(StatusCode::OK, status);
+    }
+
+    let (status, body) = put_progress(&app, "c.epub", "/body/1").await;
+    assert_eq!(StatusCode::INSUFFICIENT_STORAGE, status);
+    assert_eq!(body["code"], "quota_exceeded");
+
+    // Documents already stored can still be synced
+    let (status, _) = put_progress(&app, "a.epub", "/body/2").await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[tokio::test]
+async fn byte_limit_rejects_growing_progress() {
+    let (app, _) = spawn_app_with_quotas(Quotas {
+        max_documents: 0,
+        max_bytes: 256,
+    });
+
+    // Stores the progress, the device position and the device
+    let (status, _) = put_progress(&app, "a.epub", "/body/1").await;
+    assert_eq!(StatusCode::OK, status);
+
+    let (status, body) = put_progress(&app, "a.epub", &"/body/1".repeat(20)).await;
+    assert_eq!(StatusCode::INSUFFICIENT_STORAGE, status);
+    assert_eq!(body["code"], "quota_exceeded");
+}
+
+#[tokio::test]
+async fn documents_at_the_byte_limit_keep_syncing() {
+    let (app, service) = spawn_app_with_quotas(Quotas::default());
+    let (status, _) = put_progress(&app, "a.epub", "/body/1").await;
+    assert_eq!(StatusCode::OK, status);
+
+    let usage = service.get_usage("test".into()).unwrap();
+    service
+        .set_quota(
+            "test".into(),
+            Some(Quota {
+                max_documents: None,
+                max_bytes: Some(usage.bytes),
+            }),
+        )
+        .unwrap();
+
+    // Every sync adds to the history, which must not use up the quota
+    for position in ["/body/2", "/body/3", "/body/4"] {
+        let (status, _) = put_progress(&app, "a.epub", position).await;
+        assert_eq!(StatusCode::OK, status);
+    }
+    assert_eq!(usage, service.get_usage("test".into()).unwrap());
+}
+
+#[tokio::test]
+async fn batch_over_quota_is_rejected_as_a_whole() {
+    let (app, service) = spawn_app_with_quotas(Quotas {
+        max_documents: 2,
+        max_bytes: 0,
+    });
+
+    let (status, _) = send(
+        &app,
+        AuthenticatedRequestBuilder::put("/syncs/progress/batch")
+            .json_body(
+                &json!({
+                    "progress": [
+                        progress("a.epub", "/body/1"),
+                        progress("b.epub", "/body/1"),
+                        progress("c.epub", "/body/1"),
+                    ]
+                })
+                .to_string(),
+            )
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::INSUFFICIENT_STORAGE, status);
+    assert!(service.list_progress("test".into()).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn user_quota_overrides_the_default() {
+    let (app, service) = spawn_app_with_quotas(Quotas {
+        max_documents: 1,
+        max_bytes: 0,
+    });
+    service
+        .set_quota(
+            "test".into(),
+            Some(Quota {
+                max_documents: Some(2),
+                max_bytes: None,
+            }),
+        )
+        .unwrap();
+
+    for document in ["a.epub", "b.epub"] {
+        let (status, _) = put_progress(&app, document, "/body/1").await;
+        assert_eq!(StatusCode::OK, status);
+    }
+    let (status, _) = put_progress(&app, "c.epub", "/body/1").await;
+    assert_eq!(StatusCode::INSUFFICIENT_STORAGE, status);
+
+    // Going back to the default keeps the stored progress, but blocks new documents
+    service.set_quota("test".into(), None).unwrap();
+    let (status, _) = put_progress(&app, "b.epub", "/body/2").await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[tokio::test]
+async fn byte_limit_covers_document_metadata() {
+    let (app, _) = spawn_app_with_quotas(Quotas {
+        max_documents: 0,
+        max_bytes: 64,
+    });
+
+    let (status, body) = send(
+        &app,
+        AuthenticatedRequestBuilder::put("/documents/a.epub")
+            .json_body(&json!({ "title": "x".repeat(100) }).to_string())
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::INSUFFICIENT_STORAGE, status);
+    assert_eq!(body["code"], "quota_exceeded");
+}
@@ -0,0 +1,221 @@
+mod common;
+
+use axum::{
+    Router,
+    http::{StatusCode, header},
+};
+use common::{AuthenticatedRequestBuilder, UnauthenticatedRequestBuilder, spawn_app};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn create_share(app: &Router, body: Value) -> Value {
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::post("/shares")
+                .json_body(&body.to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::CREATED, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    serde_json::from_slice(&body).expect("Invalid JSON response")
+}
+
+async fn put_progress(app: &Router, document: &str) {
+    let request_body = json!({
+        "device_id": "device123",
+        "device": "Kobo <Libra>",
+        "document": document,
+        "percentage": 0.63,
+        "progress": "/body/DocFragment[12]"
+    })
+    .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/syncs/progress")
+                .json_body(&request_body)
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[tokio::test]
+async fn share_link_serves_progress_as_json() {
+    let app = spawn_app();
+    put_progress(&app, "book.epub").await;
+
+    let share = create_share(&app, json!({"document": "book.epub"})).await;
+    assert!(share.get("expires_at").is_none());
+    let url = share["url"].as_str().expect("Missing url");
+
+    let response = app
+        .oneshot(UnauthenticatedRequestBuilder::get(url).build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body: Value = serde_json::from_slice(&body).expect("Invalid JSON response");
+
+    assert_eq!(body["document"], "book.epub");
+    assert_eq!(body["device"], "Kobo <Libra>");
+    assert!((body["percentage"].as_f64().unwrap() - 0.63).abs() < 1e-6);
+    assert!(body["timestamp"].is_u64());
+    assert!(body.get("progress").is_none());
+}
+
+#[tokio::test]
+async fn share_link_serves_escaped_html() {
+    let app = spawn_app();
+    put_progress(&app, "<script>.epub").await;
+
+    let share = create_share(&app, json!({"document": "<script>.epub"})).await;
+    let url = share["url"].as_str().expect("Missing url");
+
+    let mut request = UnauthenticatedRequestBuilder::get(url).build();
+    request.headers_mut().insert(
+        header::ACCEPT,
+        "text/html,application/xhtml+xml".parse().unwrap(),
+    );
+
+    let response = app.oneshot(request).await.expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+    assert!(
+        response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let html = String::from_utf8(body.to_vec()).expect("Invalid UTF-8");
+
+    assert!(html.contains("63%"));
+    assert!(html.contains("&lt;script&gt;.epub"));
+    assert!(html.contains("Kobo &lt;Libra&gt;"));
+    assert!(!html.contains("<script>"));
+}
+
+#[tokio::test]
+async fn revoked_share_link_returns_not_found() {
+    let app = spawn_app();
+    let share = create_share(&app, json!({"document": "book.epub"})).await;
+    let token = share["token"].as_str().expect("Missing token");
+
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::delete(&format!("/shares/{token}")).build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+    let response = app
+        .clone()
+        .oneshot(UnauthenticatedRequestBuilder::get(&format!("/share/{token}")).build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    let response = app
+        .oneshot(AuthenticatedRequestBuilder::delete(&format!("/shares/{token}")).build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[tokio::test]
+async fn expired_share_link_returns_not_found() {
+    let app = spawn_app();
+    let share = create_share(&app, json!({"document": "book.epub", "expires_in": 1})).await;
+    assert!(share["expires_at"].is_u64());
+    let url = share["url"].as_str().expect("Missing url");
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    let response = app
+        .oneshot(UnauthenticatedRequestBuilder::get(url).build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[tokio::test]
+async fn share_without_progress_and_listing() {
+    let app = spawn_app();
+    let share = create_share(&app, json!({"document": "unread.epub"})).await;
+    let url = share["url"].as_str().expect("Missing url");
+
+    let response = app
+        .clone()
+        .oneshot(UnauthenticatedRequestBuilder::get(url).build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body: Value = serde_json::from_slice(&body).expect("Invalid JSON response");
+    assert_eq!(body, json!({"document": "unread.epub"}));
+
+    let response = app
+        .oneshot(AuthenticatedRequestBuilder::get("/shares").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body: Value = serde_json::from_slice(&body).expect("Invalid JSON response");
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["token"], share["token"]);
+}
+
+#[tokio::test]
+async fn share_requires_document() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::post("/shares")
+                .json_body(&json!({"document": ""}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+}
+
+#[tokio::test]
+async fn share_rejects_oversized_document() {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::post("/shares")
+                .json_body(&json!({"document": "d".repeat(513), "expires_in": 0}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body: Value = serde_json::from_slice(&body).expect("Invalid JSON response");
+    assert_eq!(body["errors"][0]["field"], "document");
+    assert_eq!(body["errors"][1]["field"], "expires_in");
+}
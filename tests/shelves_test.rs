@@ -0,0 +1,137 @@
+mod common;
+
+use axum::{Router, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn send(app: &Router, request: axum::http::Request<axum::body::Body>) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    (
+        status,
+        serde_json::from_slice(&body).expect("Invalid JSON response"),
+    )
+}
+
+async fn put_progress(app: &Router, document: &str, percentage: f32) {
+    let (status, _) = send(
+        app,
+        AuthenticatedRequestBuilder::put("/syncs/progress")
+            .json_body(
+                &json!({
+                    "device_id": "kobo-1",
+                    "device": "Kobo",
+                    "document": document,
+                    "percentage": percentage,
+                    "progress": "/body/DocFragment[1]",
+                })
+                .to_string(),
+            )
+            .build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+async fn get_shelves(app: &Router) -> Value {
+    let (status, body) = send(app, AuthenticatedRequestBuilder::get("/shelves").build()).await;
+    assert_eq!(StatusCode::OK, status);
+    body
+}
+
+async fn put_shelf(app: &Router, document: &str, shelf: &str) -> (StatusCode, Value) {
+    send(
+        app,
+        AuthenticatedRequestBuilder::put(&format!("/shelves/{document}"))
+            .json_body(&json!({ "shelf": shelf }).to_string())
+            .build(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn shelves_are_empty_by_default() {
+    let app = spawn_app();
+
+    assert_eq!(
+        get_shelves(&app).await,
+        json!({ "reading": [], "finished": [], "abandoned": [] })
+    );
+}
+
+#[tokio::test]
+async fn progress_moves_documents_between_shelves() {
+    let app = spawn_app();
+
+    put_progress(&app, "dune.epub", 0.5).await;
+    put_progress(&app, "emma.epub", 0.2).await;
+    let shelves = get_shelves(&app).await;
+    assert_eq!(shelves["reading"].as_array().unwrap().len(), 2);
+    assert_eq!(shelves["reading"][0]["document"], "dune.epub");
+
+    // Reaching the threshold finishes the book
+    put_progress(&app, "dune.epub", 1.0).await;
+    let shelves = get_shelves(&app).await;
+    let finished = &shelves["finished"][0];
+    assert_eq!(finished["document"], "dune.epub");
+    assert_eq!(finished["times_finished"], 1);
+    let finished_at = finished["finished_at"]
+        .as_u64()
+        .expect("Missing finish date");
+    assert_eq!(finished["reads"][0]["ended_at"], finished_at);
+
+    // Reading it again starts a new read-through and keeps the finish date
+    put_progress(&app, "dune.epub", 0.01).await;
+    let shelves = get_shelves(&app).await;
+    assert!(shelves["finished"].as_array().unwrap().is_empty());
+    let dune = &shelves["reading"][0];
+    assert_eq!(dune["document"], "dune.epub");
+    assert_eq!(dune["finished_at"], finished_at);
+    assert_eq!(dune["reads"].as_array().unwrap().len(), 2);
+    assert_eq!(dune["reads"][0]["shelf"], "finished");
+    assert_eq!(dune["reads"][1]["shelf"], "reading");
+}
+
+#[tokio::test]
+async fn documents_can_be_shelved_explicitly() {
+    let app = spawn_app();
+    put_progress(&app, "dune.epub", 0.3).await;
+
+    let (status, body) = put_shelf(&app, "dune.epub", "abandoned").await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["shelf"], "abandoned");
+    assert!(body.get("finished_at").is_none());
+
+    let (status, body) = put_shelf(&app, "emma.epub", "finished").await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["times_finished"], 1);
+
+    let shelves = get_shelves(&app).await;
+    assert_eq!(shelves["abandoned"][0]["document"], "dune.epub");
+    assert_eq!(shelves["finished"][0]["document"], "emma.epub");
+    assert!(shelves["reading"].as_array().unwrap().is_empty());
+
+    // Picking an abandoned book up again resumes the same read-through
+    put_progress(&app, "dune.epub", 0.35).await;
+    let shelves = get_shelves(&app).await;
+    assert_eq!(shelves["reading"][0]["document"], "dune.epub");
+    assert_eq!(shelves["reading"][0]["reads"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn put_shelf_rejects_unknown_shelves() {
+    let app = spawn_app();
+
+    let (status, body) = put_shelf(&app, "dune.epub", "wishlist").await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["code"], "invalid_input");
+}
@@ -0,0 +1,347 @@
+#![cfg(feature = "statistics-sync")]
+
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use common::{spawn_app_with_service, test_state};
+use korrosync::{
+    api::{router::app, state::AppState},
+    config::{Quotas, Validation},
+    model::{User, koreader_key},
+    service::db::KorrosyncService,
+};
+use rusqlite::Connection;
+use tempfile::NamedTempFile;
+use tower::ServiceExt;
+
+const SCHEMA: &str = r#"
+    CREATE TABLE book (
+        id integer PRIMARY KEY autoincrement, title text, authors text, notes integer,
+        last_open integer, highlights integer, pages integer, series text, language text,
+        md5 text, total_read_time integer, total_read_pages integer
+    );
+    CREATE UNIQUE INDEX book_title_author_md5 ON book(title, authors, md5);
+    CREATE TABLE page_stat_data (
+        id_book integer, page integer NOT NULL DEFAULT 0,
+        start_time integer NOT NULL DEFAULT 0, duration integer NOT NULL DEFAULT 0,
+        total_pages integer NOT NULL DEFAULT 0,
+        UNIQUE (id_book, page, start_time),
+        FOREIGN KEY(id_book) REFERENCES book(id)
+    );
+"#;
+
+/// Creates an app with user "alice" whose KOReader password is "secret"
+fn spawn_dav_app() -> Router {
+    let (app, service) = spawn_app_with_service();
+    service
+        .create_or_update_user(
+            User::new("alice", koreader_key("secret")).expect("Error instantiating user"),
+        )
+        .expect("Error inserting user");
+    app
+}
+
+/// Creates an app with user "alice" under an upload size limit and a default quota
+fn spawn_limited_dav_app(max_statistics_size: usize, quotas: Quotas) -> Router {
+    let (_, service) = spawn_app_with_service();
+    service
+        .create_or_update_user(
+            User::new("alice", koreader_key("secret")).expect("Error instantiating user"),
+        )
+        .expect("Error inserting user");
+    app(AppState {
+        validation: Validation {
+            max_statistics_size,
+            ..Validation::default()
+        },
+        quotas,
+        ..test_state(service)
+    })
+}
+
+/// Builds a statistics database where each book has one page read for a minute
+fn statistics_db(books: &[&str]) -> Vec<u8> {
+    let file = NamedTempFile::new().expect("Creating temp file");
+    {
+        let conn = Connection::open(file.path()).expect("Failed to open database");
+        conn.execute_batch(SCHEMA).expect("Failed to create schema");
+        for title in books {
+            conn.execute(
+                "INSERT INTO book (title, authors, md5) VALUES (?1, 'Author', ?1)",
+                [title],
+            )
+            .expect("Failed to insert book");
+            conn.execute(
+                "INSERT INTO page_stat_data (id_book, page, start_time, duration, total_pages) \
+                 VALUES (?1, 1, 1700000000, 60, 100)",
+                [conn.last_insert_rowid()],
+            )
+            .expect("Failed to insert page stat");
+        }
+    }
+    std::fs::read(file.path()).expect("Failed to read database")
+}
+
+fn book_titles(data: &[u8]) -> Vec<String> {
+    let file = NamedTempFile::new().expect("Creating temp file");
+    std::fs::write(file.path(), data).expect("Failed to write database");
+    let conn = Connection::open(file.path()).expect("Failed to open database");
+    let mut statement = conn
+        .prepare("SELECT title FROM book ORDER BY title")
+        .expect("Failed to prepare query");
+    statement
+        .query_map([], |row| row.get(0))
+        .expect("Failed to run query")
+        .collect::<Result<_, _>>()
+        .expect("Failed to read rows")
+}
+
+fn dav_request(method: &str, uri: &str, password: &str) -> axum::http::request::Builder {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    Request::builder().method(method).uri(uri).header(
+        header::AUTHORIZATION,
+        format!("Basic {}", STANDARD.encode(format!("alice:{password}"))),
+    )
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, header::HeaderMap, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+
+    (status, headers, body.to_vec())
+}
+
+async fn upload(app: &Router, data: Vec<u8>, if_match: Option<&str>) -> (StatusCode, String) {
+    let mut request = dav_request("PUT", "/dav/statistics.sqlite3", "secret");
+    if let Some(etag) = if_match {
+        request = request.header(header::IF_MATCH, etag);
+    }
+    let (status, headers, _) = send(app, request.body(Body::from(data)).unwrap()).await;
+    let etag = headers
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    (status, etag)
+}
+
+async fn download(app: &Router) -> (StatusCode, Vec<u8>) {
+    let (status, _, body) = send(
+        app,
+        dav_request("GET", "/dav/statistics.sqlite3", "secret")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    (status, body)
+}
+
+#[tokio::test]
+async fn dav_requires_basic_credentials() {
+    let app = spawn_dav_app();
+
+    let (status, headers, _) = send(
+        &app,
+        Request::builder()
+            .uri("/dav/statistics.sqlite3")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, status);
+    assert_eq!(
+        headers[header::WWW_AUTHENTICATE],
+        "Basic realm=\"korrosync\""
+    );
+
+    let (status, _, _) = send(
+        &app,
+        dav_request("GET", "/dav/statistics.sqlite3", "wrong")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, status);
+}
+
+#[tokio::test]
+async fn dav_upload_and_download() {
+    let app = spawn_dav_app();
+
+    assert_eq!(StatusCode::NOT_FOUND, download(&app).await.0);
+
+    let data = statistics_db(&["Dune"]);
+    let (status, etag) = upload(&app, data.clone(), None).await;
+    assert_eq!(StatusCode::CREATED, status);
+    assert!(etag.starts_with('"'));
+
+    let (status, body) = download(&app).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body, data);
+
+    let (status, _, body) = send(
+        &app,
+        dav_request("PROPFIND", "/dav/", "secret")
+            .header("depth", "1")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(StatusCode::MULTI_STATUS, status);
+    let body = String::from_utf8(body).expect("Invalid UTF-8");
+    assert!(body.contains("<d:href>/dav/</d:href>"));
+    assert!(body.contains("<d:href>/dav/statistics.sqlite3</d:href>"));
+}
+
+#[tokio::test]
+async fn dav_describes_the_stored_file() {
+    let app = spawn_dav_app();
+
+    let data = statistics_db(&["Dune"]);
+    let (_, etag) = upload(&app, data.clone(), None).await;
+
+    let (status, headers, body) = send(
+        &app,
+        dav_request("HEAD", "/dav/statistics.sqlite3", "secret")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.is_empty());
+    assert_eq!(etag, headers[header::ETAG].to_str().unwrap());
+    assert_eq!(
+        data.len().to_string(),
+        headers[header::CONTENT_LENGTH].to_str().unwrap()
+    );
+
+    let (status, _, body) = send(
+        &app,
+        dav_request("PROPFIND", "/dav/statistics.sqlite3", "secret")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(StatusCode::MULTI_STATUS, status);
+    let body = String::from_utf8(body).expect("Invalid UTF-8");
+    assert!(body.contains(&format!(
+        "<d:getcontentlength>{}</d:getcontentlength>",
+        data.len()
+    )));
+    assert!(body.contains(&format!(
+        "<d:getetag>{}</d:getetag>",
+        etag.replace('"', "&quot;")
+    )));
+}
+
+#[tokio::test]
+async fn dav_merges_uploads_from_several_devices() {
+    let app = spawn_dav_app();
+
+    upload(&app, statistics_db(&["Dune", "Emma"]), None).await;
+    let (status, _) = upload(&app, statistics_db(&["Ulysses"]), None).await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let (_, body) = download(&app).await;
+    assert_eq!(book_titles(&body), vec!["Dune", "Emma", "Ulysses"]);
+}
+
+#[tokio::test]
+async fn dav_serializes_concurrent_uploads_of_a_user() {
+    let app = spawn_dav_app();
+
+    upload(&app, statistics_db(&["Dune"]), None).await;
+    let (first, second) = tokio::join!(
+        upload(&app, statistics_db(&["Emma"]), None),
+        upload(&app, statistics_db(&["Ulysses"]), None),
+    );
+    assert_eq!(StatusCode::NO_CONTENT, first.0);
+    assert_eq!(StatusCode::NO_CONTENT, second.0);
+
+    let (_, body) = download(&app).await;
+    assert_eq!(book_titles(&body), vec!["Dune", "Emma", "Ulysses"]);
+}
+
+#[tokio::test]
+async fn dav_replaces_upload_based_on_latest_copy() {
+    let app = spawn_dav_app();
+
+    let (_, etag) = upload(&app, statistics_db(&["Dune", "Emma"]), None).await;
+
+    // The device deleted "Emma" after merging the latest copy
+    let (status, _) = upload(&app, statistics_db(&["Dune"]), Some(&etag)).await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let (_, body) = download(&app).await;
+    assert_eq!(book_titles(&body), vec!["Dune"]);
+}
+
+#[tokio::test]
+async fn dav_rejects_invalid_uploads_and_other_files() {
+    let app = spawn_dav_app();
+
+    let (status, _) = upload(&app, b"not a database".to_vec(), None).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+
+    let (status, _, _) = send(
+        &app,
+        dav_request("PUT", "/dav/notes.txt", "secret")
+            .body(Body::from("hello"))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
+
+#[tokio::test]
+async fn dav_rejects_uploads_over_the_size_limit() {
+    let data = statistics_db(&["Dune"]);
+    let app = spawn_limited_dav_app(data.len() - 1, Quotas::default());
+
+    let (status, _) = upload(&app, data.clone(), None).await;
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, status);
+
+    // Announced sizes are rejected before reading the body
+    let (status, _, _) = send(
+        &app,
+        dav_request("PUT", "/dav/statistics.sqlite3", "secret")
+            .header(header::CONTENT_LENGTH, data.len())
+            .body(Body::from(data.clone()))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, status);
+    assert_eq!(StatusCode::NOT_FOUND, download(&app).await.0);
+
+    let app = spawn_limited_dav_app(data.len(), Quotas::default());
+    let (status, _) = upload(&app, data, None).await;
+    assert_eq!(StatusCode::CREATED, status);
+}
+
+#[tokio::test]
+async fn dav_counts_uploads_against_the_quota() {
+    let data = statistics_db(&["Dune"]);
+    let quotas = Quotas {
+        max_bytes: data.len() as u64 - 1,
+        ..Quotas::default()
+    };
+    let app = spawn_limited_dav_app(Validation::default().max_statistics_size, quotas);
+
+    let (status, _) = upload(&app, data, None).await;
+    assert_eq!(StatusCode::INSUFFICIENT_STORAGE, status);
+    assert_eq!(StatusCode::NOT_FOUND, download(&app).await.0);
+}
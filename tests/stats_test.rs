@@ -0,0 +1,93 @@
+mod common;
+
+use axum::{Router, http::StatusCode};
+use common::{AuthenticatedRequestBuilder, spawn_app, spawn_app_with_validation};
+use korrosync::config::Validation;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+const MINUTE: u64 = 60 * 1000;
+
+async fn get_stats(app: &Router) -> Value {
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::get("/stats").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    serde_json::from_slice(&body).expect("Invalid JSON response")
+}
+
+async fn put_progress(app: &Router, document: &str, percentage: f32, timestamp: u64) {
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/syncs/progress")
+                .json_body(
+                    &json!({
+                        "device_id": "kobo-1",
+                        "device": "Kobo",
+                        "document": document,
+                        "percentage": percentage,
+                        "progress": "/body/DocFragment[1]",
+                        "timestamp": timestamp,
+                    })
+                    .to_string(),
+                )
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[tokio::test]
+async fn stats_are_empty_without_history() {
+    let app = spawn_app();
+
+    let body = get_stats(&app).await;
+    assert_eq!(body["total_reading_secs"], 0);
+    assert_eq!(body["average_pace"], Value::Null);
+    assert_eq!(body["sessions"], json!([]));
+    assert_eq!(body["finished_per_month"], json!([]));
+}
+
+#[tokio::test]
+async fn stats_are_derived_from_progress_history() {
+    let app = spawn_app_with_validation(Validation {
+        max_clock_skew_secs: 86_400,
+        ..Validation::default()
+    });
+    let start = chrono::Utc::now().timestamp_millis() as u64 - 6 * 60 * MINUTE;
+
+    // One hour reading a book to the end, then a short look at another one later on
+    put_progress(&app, "dune.epub", 0.5, start).await;
+    put_progress(&app, "dune.epub", 0.75, start + 30 * MINUTE).await;
+    put_progress(&app, "dune.epub", 1.0, start + 60 * MINUTE).await;
+    put_progress(&app, "emma.epub", 0.1, start + 180 * MINUTE).await;
+
+    let body = get_stats(&app).await;
+
+    let sessions = body["sessions"].as_array().expect("Expected sessions");
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0]["document"], "dune.epub");
+    assert_eq!(sessions[0]["device_id"], "kobo-1");
+    assert_eq!(sessions[0]["start"], start);
+    assert_eq!(sessions[0]["duration_secs"], 3600);
+    assert_eq!(sessions[0]["percentage_points"], 50.0);
+    assert_eq!(sessions[1]["document"], "emma.epub");
+    assert_eq!(sessions[1]["duration_secs"], 0);
+
+    assert_eq!(body["total_reading_secs"], 3600);
+    assert_eq!(body["average_pace"], 50.0);
+
+    let finished = body["finished_per_month"]
+        .as_array()
+        .expect("Expected finished documents");
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0]["count"], 1);
+}
@@ -2,7 +2,8 @@ mod common;
 
 use axum::body::Body;
 use axum::http::{Method, Request, StatusCode};
-use common::{AuthenticatedRequestBuilder, spawn_app};
+use common::{AuthenticatedRequestBuilder, spawn_app, spawn_app_with_validation};
+use korrosync::config::Validation;
 use serde_json::json;
 use tower::ServiceExt;
 
@@ -223,7 +224,6 @@ async fn get_syncs_progress_fails_with_invalid_http_methods() {
     let methods = [
         Method::POST,
         Method::PUT,
-        Method::DELETE,
         Method::PATCH,
         Method::OPTIONS,
         Method::TRACE,
@@ -251,3 +251,512 @@ async fn get_syncs_progress_fails_with_invalid_http_methods() {
         );
     }
 }
+
+#[tokio::test]
+async fn get_syncs_progress_devices_lists_each_device_position() {
+    let app = spawn_app();
+
+    for (device_id, device, percentage) in [
+        ("phone-1", "Android", 0.25),
+        ("kobo-1", "Kobo Libra", 0.5),
+        ("phone-1", "Android", 0.3),
+    ] {
+        let request_body = json!({
+            "device_id": device_id,
+            "device": device,
+            "document": "test_doc.epub",
+            "percentage": percentage,
+            "progress": "Chapter 5"
+        })
+        .to_string();
+
+        let response = app
+            .clone()
+            .oneshot(
+                AuthenticatedRequestBuilder::put("/syncs/progress")
+                    .json_body(&request_body)
+                    .build(),
+            )
+            .await
+            .expect("Failed to send request");
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    let response = app
+        .oneshot(AuthenticatedRequestBuilder::get("/syncs/progress/test_doc.epub/devices").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body_json: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+    let positions = body_json.as_array().expect("Expected an array");
+
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[0]["device_id"], "phone-1");
+    assert_eq!(positions[0]["percentage"], 0.3);
+    assert_eq!(positions[0]["name"], "Android");
+    assert_eq!(positions[1]["device_id"], "kobo-1");
+    assert_eq!(positions[1]["percentage"], 0.5);
+}
+
+async fn put_invalid_progress(body: serde_json::Value) -> serde_json::Value {
+    let app = spawn_app();
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/syncs/progress")
+                .json_body(&body.to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body_json: serde_json::Value =
+        serde_json::from_slice(&body).expect("Invalid JSON response");
+    assert_eq!(body_json["code"], "invalid_input");
+
+    body_json
+}
+
+fn error_fields(body: &serde_json::Value) -> Vec<&str> {
+    body["errors"]
+        .as_array()
+        .expect("Expected an errors array")
+        .iter()
+        .map(|e| e["field"].as_str().expect("Missing field name"))
+        .collect()
+}
+
+#[tokio::test]
+async fn put_syncs_progress_rejects_out_of_range_percentage() {
+    for percentage in [-0.1, 1.5, 1e30] {
+        let body = put_invalid_progress(json!({
+            "device_id": "device123",
+            "device": "MyDevice",
+            "document": "test_doc.epub",
+            "percentage": percentage,
+            "progress": "Chapter 5"
+        }))
+        .await;
+
+        assert_eq!(error_fields(&body), vec!["percentage"]);
+        assert_eq!(
+            body["errors"][0]["message"],
+            "must be a finite number between 0 and 1"
+        );
+    }
+}
+
+#[tokio::test]
+async fn put_syncs_progress_rejects_empty_identifiers() {
+    let body = put_invalid_progress(json!({
+        "device_id": " ",
+        "device": "MyDevice",
+        "document": "",
+        "percentage": 0.5,
+        "progress": "Chapter 5"
+    }))
+    .await;
+
+    assert_eq!(error_fields(&body), vec!["document", "device_id"]);
+    assert_eq!(body["message"], "Invalid input: Invalid progress payload");
+}
+
+#[tokio::test]
+async fn put_syncs_progress_rejects_oversized_fields() {
+    let body = put_invalid_progress(json!({
+        "device_id": "device123",
+        "device": "d".repeat(1025),
+        "document": "x".repeat(513),
+        "percentage": 0.5,
+        "progress": "p".repeat(1025)
+    }))
+    .await;
+
+    assert_eq!(error_fields(&body), vec!["document", "device", "progress"]);
+    assert_eq!(
+        body["errors"][0]["message"],
+        "must be at most 512 bytes long"
+    );
+}
+
+#[tokio::test]
+async fn put_syncs_progress_accepts_boundary_values() {
+    let app = spawn_app();
+
+    for percentage in [0.0, 1.0] {
+        let request_body = json!({
+            "device_id": "device123",
+            "device": "d".repeat(1024),
+            "document": "x".repeat(512),
+            "percentage": percentage,
+            "progress": ""
+        })
+        .to_string();
+
+        let response = app
+            .clone()
+            .oneshot(
+                AuthenticatedRequestBuilder::put("/syncs/progress")
+                    .json_body(&request_body)
+                    .build(),
+            )
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(StatusCode::OK, response.status());
+    }
+}
+
+async fn send_json(app: &axum::Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to send request");
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body_json = serde_json::from_slice(&body).expect("Invalid JSON response");
+
+    (status, body_json)
+}
+
+fn progress_request(timestamp: Option<u64>) -> Request<Body> {
+    let mut body = json!({
+        "device_id": "device123",
+        "device": "MyDevice",
+        "document": "offline.epub",
+        "percentage": 0.3,
+        "progress": "Chapter 2"
+    });
+    if let Some(timestamp) = timestamp {
+        body["timestamp"] = json!(timestamp);
+    }
+
+    AuthenticatedRequestBuilder::put("/syncs/progress")
+        .json_body(&body.to_string())
+        .build()
+}
+
+fn one_day_skew() -> Validation {
+    Validation {
+        max_clock_skew_secs: 86_400,
+        ..Validation::default()
+    }
+}
+
+fn now_millis() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+#[tokio::test]
+async fn put_syncs_progress_stores_client_timestamp_within_skew() {
+    let app = spawn_app_with_validation(one_day_skew());
+    let reported = now_millis() - 3_600_000;
+
+    let (status, _) = send_json(&app, progress_request(Some(reported))).await;
+    assert_eq!(StatusCode::OK, status);
+
+    let (status, body) = send_json(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/progress/offline.epub").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body["client_timestamp"], reported);
+    assert!(body["timestamp"].as_u64().expect("Missing timestamp") > reported);
+}
+
+#[tokio::test]
+async fn put_syncs_progress_accepts_client_timestamp_header() {
+    let app = spawn_app_with_validation(one_day_skew());
+    let reported = now_millis() - 60_000;
+
+    let mut request = progress_request(None);
+    request.headers_mut().insert(
+        "x-client-timestamp",
+        reported.to_string().parse().expect("Invalid header value"),
+    );
+    let (status, _) = send_json(&app, request).await;
+    assert_eq!(StatusCode::OK, status);
+
+    let (_, body) = send_json(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/progress/offline.epub").build(),
+    )
+    .await;
+    assert_eq!(body["client_timestamp"], reported);
+}
+
+#[tokio::test]
+async fn put_syncs_progress_ignores_client_timestamp_outside_skew() {
+    let week = 7 * 86_400_000;
+
+    for (validation, reported) in [
+        (Validation::default(), now_millis() - 1_000),
+        (one_day_skew(), now_millis() - week),
+        (one_day_skew(), now_millis() + week),
+    ] {
+        let app = spawn_app_with_validation(validation);
+
+        let (status, _) = send_json(&app, progress_request(Some(reported))).await;
+        assert_eq!(StatusCode::OK, status);
+
+        let (_, body) = send_json(
+            &app,
+            AuthenticatedRequestBuilder::get("/syncs/progress/offline.epub").build(),
+        )
+        .await;
+        assert!(body.get("client_timestamp").is_none());
+        assert!(body["timestamp"].is_number());
+    }
+}
+
+#[tokio::test]
+async fn put_syncs_progress_rejects_invalid_client_timestamp_header() {
+    let app = spawn_app_with_validation(one_day_skew());
+
+    let mut request = progress_request(None);
+    request.headers_mut().insert(
+        "x-client-timestamp",
+        "yesterday".parse().expect("Invalid header value"),
+    );
+    let (status, body) = send_json(&app, request).await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "x-client-timestamp");
+}
+
+fn batch_update(progress: serde_json::Value) -> Request<Body> {
+    AuthenticatedRequestBuilder::put("/syncs/progress/batch")
+        .json_body(&json!({ "progress": progress }).to_string())
+        .build()
+}
+
+fn batch_get(documents: serde_json::Value) -> Request<Body> {
+    AuthenticatedRequestBuilder::post("/syncs/progress/batch")
+        .json_body(&json!({ "documents": documents }).to_string())
+        .build()
+}
+
+#[tokio::test]
+async fn batch_progress_round_trip() {
+    let app = spawn_app_with_validation(one_day_skew());
+    let now = now_millis();
+    let update = |document: &str, percentage: f32, timestamp: u64| {
+        json!({
+            "device_id": "device123",
+            "device": "MyDevice",
+            "document": document,
+            "percentage": percentage,
+            "progress": "Chapter 1",
+            "timestamp": timestamp,
+        })
+    };
+
+    // Queued offline, sent out of order: the most recent position wins
+    let (status, body) = send_json(
+        &app,
+        batch_update(json!([
+            update("a.epub", 0.4, now - 1_000),
+            update("b.epub", 0.2, now - 3_000),
+            update("a.epub", 0.1, now - 2_000),
+        ])),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    let applied: Vec<_> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|stored| stored["document"].as_str().unwrap())
+        .collect();
+    assert_eq!(applied, vec!["b.epub", "a.epub", "a.epub"]);
+
+    let (status, body) = send_json(&app, batch_get(json!(["a.epub", "none.epub", "b.epub"]))).await;
+    assert_eq!(StatusCode::OK, status);
+    let progress = body.as_array().unwrap();
+    assert_eq!(progress.len(), 2);
+    assert_eq!(progress[0]["document"], "a.epub");
+    assert_eq!(progress[0]["percentage"], 0.4);
+    assert_eq!(progress[0]["client_timestamp"], now - 1_000);
+    assert_eq!(progress[1]["document"], "b.epub");
+    assert_eq!(progress[1]["percentage"], 0.2);
+
+    let (_, devices) = send_json(&app, AuthenticatedRequestBuilder::get("/devices").build()).await;
+    assert_eq!(devices.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn batch_progress_rejects_invalid_payloads() {
+    let app = spawn_app_with_validation(Validation {
+        max_batch_size: 2,
+        ..Validation::default()
+    });
+
+    let (status, body) = send_json(&app, batch_get(json!([]))).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "documents");
+
+    let (status, body) = send_json(&app, batch_get(json!(["a", "b", "c"]))).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!(body["errors"][0]["field"], "documents");
+
+    // A single invalid update rejects the whole batch
+    let (status, body) = send_json(
+        &app,
+        batch_update(json!([
+            { "device_id": "d", "device": "D", "document": "a.epub", "percentage": 0.5, "progress": "p" },
+            { "device_id": "d", "device": "D", "document": "", "percentage": 2.0, "progress": "p" },
+        ])),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        fields,
+        vec!["progress[1].document", "progress[1].percentage"]
+    );
+
+    let (_, body) = send_json(&app, batch_get(json!(["a.epub"]))).await;
+    assert_eq!(body, json!([]));
+}
+
+fn conditional_get(header: &'static str, value: &str) -> Request<Body> {
+    let mut request = AuthenticatedRequestBuilder::get("/syncs/progress/offline.epub").build();
+    request
+        .headers_mut()
+        .insert(header, value.parse().expect("Invalid header value"));
+    request
+}
+
+#[tokio::test]
+async fn get_syncs_progress_supports_conditional_requests() {
+    let app = spawn_app();
+
+    let response = app.clone().oneshot(progress_request(None)).await.unwrap();
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::get("/syncs/progress/offline.epub").build())
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(response.headers()["etag"], etag.as_str());
+    let last_modified = response.headers()["last-modified"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(last_modified.ends_with(" GMT"));
+
+    for (header, value, expected) in [
+        ("if-none-match", etag.as_str(), StatusCode::NOT_MODIFIED),
+        (
+            "if-none-match",
+            &format!("W/{etag}"),
+            StatusCode::NOT_MODIFIED,
+        ),
+        ("if-none-match", "\"1\", *", StatusCode::NOT_MODIFIED),
+        ("if-none-match", "\"1\"", StatusCode::OK),
+        (
+            "if-modified-since",
+            last_modified.as_str(),
+            StatusCode::NOT_MODIFIED,
+        ),
+        (
+            "if-modified-since",
+            "Thu, 01 Jan 1970 00:00:00 GMT",
+            StatusCode::OK,
+        ),
+        ("if-modified-since", "not a date", StatusCode::OK),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(conditional_get(header, value))
+            .await
+            .unwrap();
+        assert_eq!(expected, response.status(), "{header}: {value}");
+        assert_eq!(response.headers()["etag"], etag.as_str());
+    }
+}
+
+#[tokio::test]
+async fn put_syncs_progress_with_if_match() {
+    let app = spawn_app();
+    let with_if_match = |value: &str| {
+        let mut request = progress_request(None);
+        request
+            .headers_mut()
+            .insert("if-match", value.parse().expect("Invalid header value"));
+        request
+    };
+
+    // Nothing stored yet: no tag can match
+    let (status, body) = send_json(&app, with_if_match("*")).await;
+    assert_eq!(StatusCode::PRECONDITION_FAILED, status);
+    assert_eq!(body["code"], "precondition_failed");
+
+    let response = app.clone().oneshot(progress_request(None)).await.unwrap();
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+
+    let (status, _) = send_json(&app, with_if_match("\"1\"")).await;
+    assert_eq!(StatusCode::PRECONDITION_FAILED, status);
+    let (status, _) = send_json(&app, with_if_match(&format!("W/{etag}"))).await;
+    assert_eq!(StatusCode::PRECONDITION_FAILED, status);
+
+    let response = app.clone().oneshot(with_if_match(&etag)).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+    let new_etag = response.headers()["etag"].to_str().unwrap().to_string();
+
+    let (status, _) = send_json(&app, with_if_match(&format!("\"1\", {new_etag}"))).await;
+    assert_eq!(StatusCode::OK, status);
+    let (status, _) = send_json(&app, with_if_match("*")).await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[tokio::test]
+async fn delete_syncs_progress_removes_document_progress() {
+    let app = spawn_app();
+
+    let (status, _) = send_json(&app, progress_request(None)).await;
+    assert_eq!(StatusCode::OK, status);
+
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::delete("/syncs/progress/offline.epub").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+    let (status, body) = send_json(
+        &app,
+        AuthenticatedRequestBuilder::get("/syncs/progress/offline.epub").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(body, json!({}));
+
+    let (status, body) = send_json(
+        &app,
+        AuthenticatedRequestBuilder::delete("/syncs/progress/offline.epub").build(),
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+    assert_eq!(body["code"], "not_found");
+}
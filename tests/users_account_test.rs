@@ -0,0 +1,234 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{AuthenticatedRequestBuilder, UnauthenticatedRequestBuilder, spawn_app};
+use serde_json::json;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn change_password_rotates_credentials() {
+    let app = spawn_app();
+
+    let request_body = json!({
+        "current_password": "test",
+        "new_password": "new-secret"
+    })
+    .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/users/password")
+                .json_body(&request_body)
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::get("/users/auth").build())
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        response.status(),
+        "Old password should no longer be accepted"
+    );
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::get("/users/auth")
+                .credentials("test", "new-secret")
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[tokio::test]
+async fn change_password_fails_with_wrong_current_password() {
+    let app = spawn_app();
+
+    let request_body = json!({
+        "current_password": "wrong",
+        "new_password": "new-secret"
+    })
+    .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/users/password")
+                .json_body(&request_body)
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let response = app
+        .oneshot(AuthenticatedRequestBuilder::get("/users/auth").build())
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(
+        StatusCode::OK,
+        response.status(),
+        "Password should be unchanged"
+    );
+}
+
+#[tokio::test]
+async fn change_password_fails_with_empty_new_password() {
+    let app = spawn_app();
+
+    let request_body = json!({
+        "current_password": "test",
+        "new_password": ""
+    })
+    .to_string();
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/users/password")
+                .json_body(&request_body)
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body_json: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+
+    assert_eq!(body_json["code"], "invalid_input");
+}
+
+#[tokio::test]
+async fn delete_account_removes_user_and_progress() {
+    let app = spawn_app();
+
+    let progress_body = json!({
+        "device_id": "device123",
+        "device": "MyDevice",
+        "document": "test_doc.epub",
+        "percentage": 0.75,
+        "progress": "Chapter 5"
+    })
+    .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/syncs/progress")
+                .json_body(&progress_body)
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::delete("/users/me")
+                .json_body(&json!({"confirm": "test"}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+    let response = app
+        .clone()
+        .oneshot(AuthenticatedRequestBuilder::get("/users/auth").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    // Re-register the same username: the previous progress must be gone
+    let response = app
+        .clone()
+        .oneshot(
+            UnauthenticatedRequestBuilder::post("/users/create")
+                .json_body(&json!({"username": "test", "password": "test"}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::CREATED, response.status());
+
+    let response = app
+        .oneshot(AuthenticatedRequestBuilder::get("/syncs/progress/test_doc.epub").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body_json: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+    assert_eq!(body_json, json!({}));
+}
+
+#[tokio::test]
+async fn delete_account_requires_confirmation() {
+    let app = spawn_app();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::delete("/users/me")
+                .json_body(&json!({"confirm": "someone-else"}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = app
+        .oneshot(AuthenticatedRequestBuilder::get("/users/auth").build())
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::OK, response.status(), "User should still exist");
+}
+
+#[tokio::test]
+async fn account_routes_require_auth() {
+    let app = spawn_app();
+
+    let response = app
+        .clone()
+        .oneshot(
+            AuthenticatedRequestBuilder::delete("/users/me")
+                .credentials("test", "wrong")
+                .json_body(&json!({"confirm": "test"}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let response = app
+        .oneshot(
+            AuthenticatedRequestBuilder::put("/users/password")
+                .credentials("nobody", "test")
+                .json_body(&json!({"current_password": "test", "new_password": "x"}).to_string())
+                .build(),
+        )
+        .await
+        .expect("Failed to send request");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}